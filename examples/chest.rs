@@ -200,37 +200,8 @@ impl Config for Game {
                             client.send_message(format!("Chest: {:?}", server.state.chest));
                         }
                     }
-                    ClientEvent::ClickContainer {
-                        window_id,
-                        state_id,
-                        slot_id,
-                        mode,
-                        slot_changes,
-                        carried_item,
-                    } => {
-                        println!(
-                            "window_id: {:?}, state_id: {:?}, slot_id: {:?}, mode: {:?}, \
-                             slot_changes: {:?}, carried_item: {:?}",
-                            window_id, state_id, slot_id, mode, slot_changes, carried_item
-                        );
-                        client.cursor_held_item = carried_item;
-                        if let Some(window) = client.open_inventory.as_mut() {
-                            if let Some(obj_inv) =
-                                server.inventories.get_mut(window.object_inventory)
-                            {
-                                for (slot_id, slot) in slot_changes {
-                                    if slot_id < obj_inv.slot_count() as SlotId {
-                                        obj_inv.set_slot(slot_id, slot);
-                                    } else {
-                                        let offset = obj_inv.slot_count() as SlotId;
-                                        client.inventory.set_slot(
-                                            slot_id - offset + PlayerInventory::GENERAL_SLOTS.start,
-                                            slot,
-                                        );
-                                    }
-                                }
-                            }
-                        }
+                    ClientEvent::ClickContainer(click) => {
+                        println!("{click:?}");
                     }
                     ClientEvent::StartSneaking => {
                         let slot_id: SlotId = PlayerInventory::HOTBAR_SLOTS.start;
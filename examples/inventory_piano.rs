@@ -189,7 +189,7 @@ impl Config for Game {
                         // This is not possible to fix :(
                         play_note(client, player, slot_id);
                     }
-                    ClientEvent::ClickContainer { slot_id, mode, .. } => {
+                    ClientEvent::ClickContainer(ContainerClick { slot_id, mode, .. }) => {
                         client.send_message(format!("{:#?}", event));
                         if mode != ClickContainerMode::Click {
                             // Prevent notes from being played twice if the user clicks quickly
@@ -3,9 +3,13 @@ package rs.valence.extractor.extractors;
 import com.google.gson.JsonArray;
 import com.google.gson.JsonElement;
 import com.google.gson.JsonObject;
+import net.minecraft.entity.EquipmentSlot;
+import net.minecraft.entity.mob.MobEntity;
 import net.minecraft.util.registry.Registry;
 import rs.valence.extractor.Main;
 
+import java.util.Locale;
+
 public class Items implements Main.Extractor {
     public Items() {
     }
@@ -28,6 +32,16 @@ public class Items implements Main.Extractor {
             itemJson.addProperty("max_durability", item.getMaxDamage());
             itemJson.addProperty("enchantability", item.getEnchantability());
             itemJson.addProperty("fireproof", item.isFireproof());
+            itemJson.addProperty("rarity", item.getRarity(item.getDefaultStack()).name().toLowerCase(Locale.ROOT));
+
+            var equipmentSlot = MobEntity.getPreferredEquipmentSlot(item.getDefaultStack());
+            if (equipmentSlot != EquipmentSlot.MAINHAND) {
+                itemJson.addProperty("equipment_slot", equipmentSlot.getName());
+            }
+
+            if (item.hasRecipeRemainder()) {
+                itemJson.addProperty("recipe_remainder", Registry.ITEM.getId(item.getRecipeRemainder()).getPath());
+            }
 
             if (item.getFoodComponent() != null) {
                 var foodJson = new JsonObject();
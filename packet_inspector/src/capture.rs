@@ -0,0 +1,141 @@
+//! Recording proxied packets to capture files and reading them back.
+//!
+//! A capture file begins with [`MAGIC`] and a version byte, followed by a
+//! sequence of [`Record`]s encoded back to back using the protocol's own
+//! [`Encode`] and [`Decode`] implementations.
+
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use anyhow::{ensure, Context};
+use chrono::Local;
+use serde::Serialize;
+use valence_protocol::{Decode, Encode};
+
+/// The bytes every capture file starts with.
+const MAGIC: &[u8; 8] = b"VLNCCAP\0";
+/// The version of the capture format. Bump this whenever [`Record`] changes.
+const VERSION: u8 = 1;
+
+/// The direction a packet was sent in.
+#[derive(
+    Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Encode, Decode, Serialize, clap::ValueEnum,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    /// Client to server.
+    C2s,
+    /// Server to client.
+    S2c,
+}
+
+impl Direction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Direction::C2s => "c2s",
+            Direction::S2c => "s2c",
+        }
+    }
+}
+
+/// The protocol state a packet was sent in.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Encode, Decode, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PacketState {
+    Handshaking,
+    Status,
+    Login,
+    Play,
+}
+
+/// A single packet in a capture file.
+#[derive(Clone, Debug, Encode, Decode)]
+pub struct Record<'a> {
+    /// Milliseconds since the start of the session.
+    pub millis: u64,
+    pub direction: Direction,
+    pub state: PacketState,
+    /// The name of the packet as it was decoded by the proxy.
+    pub name: &'a str,
+    /// The uncompressed packet data, starting with the packet ID.
+    pub data: &'a [u8],
+}
+
+/// Writes the packets of one proxied session to a capture file.
+pub struct CaptureWriter {
+    writer: BufWriter<File>,
+    path: PathBuf,
+    start: Instant,
+}
+
+impl CaptureWriter {
+    /// Creates a new capture file in `dir` named after the current time and
+    /// the client's port.
+    pub fn create(dir: &Path, remote_addr: SocketAddr) -> anyhow::Result<Self> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("creating capture directory {}", dir.display()))?;
+
+        let now = Local::now().format("%Y-%m-%d_%H-%M-%S%.3f");
+        let path = dir.join(format!("{now}_{}.vcap", remote_addr.port()));
+
+        let file = File::create(&path).with_context(|| format!("creating {}", path.display()))?;
+
+        let mut writer = BufWriter::new(file);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+
+        Ok(Self {
+            writer,
+            path,
+            start: Instant::now(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends a packet to the capture file.
+    pub fn write(
+        &mut self,
+        direction: Direction,
+        state: PacketState,
+        name: &str,
+        data: &[u8],
+    ) -> anyhow::Result<()> {
+        Record {
+            millis: self.start.elapsed().as_millis() as u64,
+            direction,
+            state,
+            name,
+            data,
+        }
+        .encode(&mut self.writer)
+    }
+}
+
+/// Reads all records from the contents of a capture file.
+pub fn read_capture(mut bytes: &[u8]) -> anyhow::Result<Vec<Record<'_>>> {
+    ensure!(bytes.starts_with(MAGIC), "not a capture file");
+    bytes = &bytes[MAGIC.len()..];
+
+    let version = u8::decode(&mut bytes)?;
+    ensure!(
+        version == VERSION,
+        "unsupported capture version {version} (expected {VERSION})"
+    );
+
+    let mut records = vec![];
+
+    while !bytes.is_empty() {
+        let record = Record::decode(&mut bytes)
+            .with_context(|| format!("decoding record {}", records.len()))?;
+
+        records.push(record);
+    }
+
+    Ok(records)
+}
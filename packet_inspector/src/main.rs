@@ -1,7 +1,8 @@
 use std::error::Error;
 use std::io::ErrorKind;
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use std::{fmt, io};
 
@@ -9,6 +10,7 @@ use anyhow::bail;
 use chrono::{DateTime, Utc};
 use clap::Parser;
 use regex::Regex;
+use serde_json::{json, Value};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::net::{TcpListener, TcpStream};
@@ -23,7 +25,15 @@ use valence_protocol::packets::s2c::login::{LoginSuccess, S2cLoginPacket};
 use valence_protocol::packets::s2c::play::S2cPlayPacket;
 use valence_protocol::packets::s2c::status::{PingResponse, StatusResponse};
 use valence_protocol::types::HandshakeNextState;
-use valence_protocol::{Decode, Encode, Packet, PacketDecoder, PacketEncoder};
+use valence_protocol::{ByteCounter, Decode, Encode, Packet, PacketDecoder, PacketEncoder};
+
+use crate::capture::{CaptureWriter, Direction, PacketState};
+use crate::replay::ReplayTarget;
+use crate::stats::Stats;
+
+mod capture;
+mod replay;
+mod stats;
 
 #[derive(Parser, Clone, Debug)]
 #[clap(author, version, about)]
@@ -39,6 +49,12 @@ struct Cli {
     ///
     /// If no regex is provided, all packets are considered matching.
     regex: Option<Regex>,
+    /// Only print packets sent in this direction.
+    #[clap(short, long, value_enum)]
+    direction: Option<Direction>,
+    /// The format packets are printed in.
+    #[clap(short, long, value_enum, default_value = "debug")]
+    format: OutputFormat,
     /// The maximum number of connections allowed to the proxy. By default,
     /// there is no limit.
     #[clap(short, long)]
@@ -46,10 +62,72 @@ struct Cli {
     /// Print a timestamp before each packet.
     #[clap(short, long)]
     timestamp: bool,
+    /// Record every proxied session to a timestamped capture file in this
+    /// directory.
+    #[clap(long, value_name = "DIR")]
+    capture: Option<PathBuf>,
+    /// Replay a capture file instead of proxying connections.
+    #[clap(long, value_name = "FILE")]
+    replay: Option<PathBuf>,
+    /// The side of the connection to replay the capture file to. The server
+    /// is connected to at the server address, while the client is waited for
+    /// at the client address.
+    #[clap(long, value_enum, default_value = "server", requires = "replay")]
+    replay_to: ReplayTarget,
+}
+
+/// The format packets and session summaries are printed in.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, clap::ValueEnum)]
+enum OutputFormat {
+    /// The pretty-printed `Debug` representation of each packet.
+    Debug,
+    /// One JSON object per line for each packet, containing the packet's
+    /// `Debug` representation along with its direction, state, name, and size.
+    Json,
+}
+
+impl Cli {
+    /// Returns `true` if packets with this name sent in this direction are
+    /// printed and counted in the session summary.
+    fn is_shown(&self, direction: Direction, name: &str) -> bool {
+        self.regex.as_ref().is_none_or(|r| r.is_match(name))
+            && self.direction.is_none_or(|d| d == direction)
+    }
+}
+
+/// Returns the line printed for a packet in the JSON format.
+fn packet_json<P>(
+    direction: Direction,
+    state: PacketState,
+    pkt: &P,
+    bytes: usize,
+    time: Option<DateTime<Utc>>,
+) -> Value
+where
+    P: Packet + fmt::Debug,
+{
+    let mut line = json!({
+        "type": "packet",
+        "direction": direction,
+        "state": state,
+        "name": pkt.packet_name(),
+        "bytes": bytes,
+        "packet": format!("{pkt:?}"),
+    });
+
+    if let Some(time) = time {
+        line["time"] = time.to_rfc3339().into();
+    }
+
+    line
 }
 
 struct State {
     cli: Arc<Cli>,
+    direction: Direction,
+    state: PacketState,
+    capture: Option<Arc<Mutex<CaptureWriter>>>,
+    stats: Arc<Mutex<Stats>>,
     enc: PacketEncoder,
     dec: PacketDecoder,
     read: OwnedReadHalf,
@@ -77,22 +155,50 @@ impl State {
 
             let pkt: P = self.dec.try_next_packet()?.unwrap();
 
+            if let Some(capture) = &self.capture {
+                let mut data = vec![];
+                pkt.encode(&mut data)?;
+
+                capture.lock().unwrap().write(
+                    self.direction,
+                    self.state,
+                    pkt.packet_name(),
+                    &data,
+                )?;
+            }
+
             self.enc.append_packet(&pkt)?;
 
             let bytes = self.enc.take();
             self.write.write_all(&bytes).await?;
 
-            if let Some(r) = &self.cli.regex {
-                if !r.is_match(pkt.packet_name()) {
-                    return Ok(pkt);
-                }
+            if !self.cli.is_shown(self.direction, pkt.packet_name()) {
+                return Ok(pkt);
             }
 
-            if self.cli.timestamp {
-                let now: DateTime<Utc> = Utc::now();
-                println!("{now} {pkt:#?}");
-            } else {
-                println!("{pkt:#?}");
+            let mut counter = ByteCounter::new();
+            pkt.encode(&mut counter)?;
+
+            self.stats
+                .lock()
+                .unwrap()
+                .record(self.direction, pkt.packet_name(), counter.0);
+
+            match self.cli.format {
+                OutputFormat::Debug => {
+                    if self.cli.timestamp {
+                        let now: DateTime<Utc> = Utc::now();
+                        println!("{now} {pkt:#?}");
+                    } else {
+                        println!("{pkt:#?}");
+                    }
+                }
+                OutputFormat::Json => {
+                    let time = self.cli.timestamp.then(Utc::now);
+                    let line = packet_json(self.direction, self.state, &pkt, counter.0, time);
+
+                    println!("{line}");
+                }
             }
 
             Ok(pkt)
@@ -105,6 +211,11 @@ impl State {
 async fn main() -> Result<(), Box<dyn Error>> {
     let cli = Arc::new(Cli::parse());
 
+    if let Some(path) = &cli.replay {
+        replay::replay(&cli, path, cli.replay_to).await?;
+        return Ok(());
+    }
+
     let sema = Arc::new(Semaphore::new(cli.max_connections.unwrap_or(100_000)));
 
     eprintln!("Waiting for connections on {}", cli.client);
@@ -120,11 +231,17 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
         let cli = cli.clone();
         tokio::spawn(async move {
-            if let Err(e) = handle_connection(client, cli).await {
+            let stats = Arc::new(Mutex::new(Stats::default()));
+
+            if let Err(e) =
+                handle_connection(client, remote_client_addr, cli.clone(), stats.clone()).await
+            {
                 eprintln!("Connection to {remote_client_addr} ended with: {e:#}");
             } else {
                 eprintln!("Connection to {remote_client_addr} ended.");
             }
+
+            stats.lock().unwrap().print(cli.format);
             drop(permit);
         });
     }
@@ -132,7 +249,12 @@ async fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-async fn handle_connection(client: TcpStream, cli: Arc<Cli>) -> anyhow::Result<()> {
+async fn handle_connection(
+    client: TcpStream,
+    remote_client_addr: SocketAddr,
+    cli: Arc<Cli>,
+    stats: Arc<Mutex<Stats>>,
+) -> anyhow::Result<()> {
     eprintln!("Connecting to {}", cli.server);
 
     let server = TcpStream::connect(cli.server).await?;
@@ -141,11 +263,24 @@ async fn handle_connection(client: TcpStream, cli: Arc<Cli>) -> anyhow::Result<(
         eprintln!("Failed to set TCP_NODELAY: {e}");
     }
 
+    let capture = match &cli.capture {
+        Some(dir) => {
+            let capture = CaptureWriter::create(dir, remote_client_addr)?;
+            eprintln!("Recording session to {}", capture.path().display());
+            Some(Arc::new(Mutex::new(capture)))
+        }
+        None => None,
+    };
+
     let (client_read, client_write) = client.into_split();
     let (server_read, server_write) = server.into_split();
 
     let mut s2c = State {
         cli: cli.clone(),
+        direction: Direction::S2c,
+        state: PacketState::Handshaking,
+        capture: capture.clone(),
+        stats: stats.clone(),
         enc: PacketEncoder::new(),
         dec: PacketDecoder::new(),
         read: server_read,
@@ -154,6 +289,10 @@ async fn handle_connection(client: TcpStream, cli: Arc<Cli>) -> anyhow::Result<(
 
     let mut c2s = State {
         cli,
+        direction: Direction::C2s,
+        state: PacketState::Handshaking,
+        capture,
+        stats,
         enc: PacketEncoder::new(),
         dec: PacketDecoder::new(),
         read: client_read,
@@ -164,6 +303,9 @@ async fn handle_connection(client: TcpStream, cli: Arc<Cli>) -> anyhow::Result<(
 
     match handshake.next_state {
         HandshakeNextState::Status => {
+            c2s.state = PacketState::Status;
+            s2c.state = PacketState::Status;
+
             c2s.rw_packet::<StatusRequest>().await?;
             s2c.rw_packet::<StatusResponse>().await?;
             c2s.rw_packet::<PingRequest>().await?;
@@ -172,6 +314,9 @@ async fn handle_connection(client: TcpStream, cli: Arc<Cli>) -> anyhow::Result<(
             Ok(())
         }
         HandshakeNextState::Login => {
+            c2s.state = PacketState::Login;
+            s2c.state = PacketState::Login;
+
             c2s.rw_packet::<LoginStart>().await?;
 
             match s2c.rw_packet::<S2cLoginPacket>().await? {
@@ -205,6 +350,9 @@ async fn handle_connection(client: TcpStream, cli: Arc<Cli>) -> anyhow::Result<(
                 }
             }
 
+            c2s.state = PacketState::Play;
+            s2c.state = PacketState::Play;
+
             let c2s_fut: JoinHandle<anyhow::Result<()>> = tokio::spawn(async move {
                 loop {
                     c2s.rw_packet::<C2sPlayPacket>().await?;
@@ -239,3 +387,63 @@ async fn passthrough(mut read: OwnedReadHalf, mut write: OwnedWriteHalf) -> anyh
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use valence_protocol::packets::c2s::play::KeepAliveC2s;
+
+    use super::*;
+
+    fn cli(args: &[&str]) -> Cli {
+        Cli::parse_from(
+            ["packet_inspector", "127.0.0.1:25566", "127.0.0.1:25565"]
+                .iter()
+                .chain(args),
+        )
+    }
+
+    #[test]
+    fn direction_filter() {
+        let cli = cli(&["--direction", "s2c"]);
+
+        assert!(cli.is_shown(Direction::S2c, "KeepAliveS2c"));
+        assert!(!cli.is_shown(Direction::C2s, "KeepAliveC2s"));
+    }
+
+    #[test]
+    fn regex_and_direction_filter() {
+        let cli = cli(&["KeepAlive", "--direction", "c2s"]);
+
+        assert!(cli.is_shown(Direction::C2s, "KeepAliveC2s"));
+        assert!(!cli.is_shown(Direction::C2s, "SetPlayerPosition"));
+        assert!(!cli.is_shown(Direction::S2c, "KeepAliveS2c"));
+    }
+
+    #[test]
+    fn packet_json_line() {
+        let time = DateTime::parse_from_rfc3339("2022-10-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let line = packet_json(
+            Direction::C2s,
+            PacketState::Play,
+            &KeepAliveC2s { id: 5 },
+            9,
+            Some(time),
+        );
+
+        assert_eq!(
+            line,
+            json!({
+                "type": "packet",
+                "direction": "c2s",
+                "state": "play",
+                "name": "KeepAliveC2s",
+                "bytes": 9,
+                "packet": "KeepAliveC2s { id: 5 }",
+                "time": "2022-10-01T12:00:00+00:00",
+            })
+        );
+    }
+}
@@ -0,0 +1,184 @@
+//! Feeding a capture file back to a server or client.
+//!
+//! The replayer plays one side of the recorded session. Packets sent by that
+//! side are sent again with their original timing, while packets from the
+//! other side are read and discarded. Until the play state is reached, the
+//! replayer waits for each recorded packet from the other side before
+//! continuing, so that compression is enabled at the right moment.
+
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::Duration;
+use std::{io, mem};
+
+use anyhow::bail;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::OwnedReadHalf;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::{sleep_until, timeout, Instant};
+use valence_protocol::packets::s2c::login::S2cLoginPacket;
+use valence_protocol::{Decode, Encode, Packet, PacketDecoder, PacketEncoder};
+
+use crate::capture::{read_capture, Direction, PacketState};
+use crate::{Cli, TIMEOUT};
+
+/// The side of the connection a capture is replayed to.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, clap::ValueEnum)]
+pub enum ReplayTarget {
+    /// Connect to the server address and send the recorded client packets.
+    Server,
+    /// Wait for a client on the listening address and send the recorded
+    /// server packets.
+    Client,
+}
+
+/// A packet whose contents are passed along without being interpreted.
+struct RawPacket<'a>(&'a [u8]);
+
+impl Encode for RawPacket<'_> {
+    fn encode(&self, mut w: impl io::Write) -> anyhow::Result<()> {
+        Ok(w.write_all(self.0)?)
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl<'a> Decode<'a> for RawPacket<'a> {
+    fn decode(r: &mut &'a [u8]) -> anyhow::Result<Self> {
+        Ok(Self(mem::take(r)))
+    }
+}
+
+impl Packet for RawPacket<'_> {
+    fn packet_name(&self) -> &'static str {
+        "RawPacket"
+    }
+}
+
+struct Receiver {
+    dec: PacketDecoder,
+    read: OwnedReadHalf,
+}
+
+impl Receiver {
+    /// Reads the next packet from the peer, passing it to `f`.
+    async fn recv<'a, P, T>(&'a mut self, f: impl FnOnce(P) -> T) -> anyhow::Result<T>
+    where
+        P: Decode<'a> + Packet,
+    {
+        while !self.dec.has_next_packet()? {
+            self.dec.reserve(4096);
+            let mut buf = self.dec.take_capacity();
+
+            if self.read.read_buf(&mut buf).await? == 0 {
+                return Err(io::Error::from(ErrorKind::UnexpectedEof).into());
+            }
+
+            self.dec.queue_bytes(buf);
+        }
+
+        Ok(f(self.dec.try_next_packet()?.unwrap()))
+    }
+}
+
+pub async fn replay(cli: &Cli, path: &Path, target: ReplayTarget) -> anyhow::Result<()> {
+    let bytes = tokio::fs::read(path).await?;
+    let records = read_capture(&bytes)?;
+
+    let stream = match target {
+        ReplayTarget::Server => {
+            eprintln!("Connecting to {}", cli.server);
+            TcpStream::connect(cli.server).await?
+        }
+        ReplayTarget::Client => {
+            eprintln!("Waiting for a connection on {}", cli.client);
+            let (stream, remote_addr) = TcpListener::bind(cli.client).await?.accept().await?;
+            eprintln!("Accepted connection to {remote_addr}");
+            stream
+        }
+    };
+
+    if let Err(e) = stream.set_nodelay(true) {
+        eprintln!("Failed to set TCP_NODELAY: {e}");
+    }
+
+    let send_direction = match target {
+        ReplayTarget::Server => Direction::C2s,
+        ReplayTarget::Client => Direction::S2c,
+    };
+
+    let (read, mut write) = stream.into_split();
+
+    let mut enc = PacketEncoder::new();
+    let mut receiver = Some(Receiver {
+        dec: PacketDecoder::new(),
+        read,
+    });
+
+    let start = Instant::now();
+    let mut sent = 0;
+
+    for record in &records {
+        if record.direction == send_direction {
+            sleep_until(start + Duration::from_millis(record.millis)).await;
+
+            enc.append_packet(&RawPacket(record.data))?;
+            write.write_all(&enc.take()).await?;
+            sent += 1;
+
+            // When acting as the server, compression begins right after the
+            // recorded set compression packet is sent.
+            if target == ReplayTarget::Client && record.state == PacketState::Login {
+                if let Ok(S2cLoginPacket::SetCompression(pkt)) =
+                    S2cLoginPacket::decode(&mut &record.data[..])
+                {
+                    enc.set_compression(Some(pkt.threshold.0 as u32));
+                    if let Some(receiver) = &mut receiver {
+                        receiver.dec.set_compression(true);
+                    }
+                }
+            }
+        } else if record.state != PacketState::Play {
+            let Some(receiver) = &mut receiver else {
+                continue;
+            };
+
+            if target == ReplayTarget::Server && record.state == PacketState::Login {
+                let threshold = timeout(
+                    TIMEOUT,
+                    receiver.recv(|pkt: S2cLoginPacket| match pkt {
+                        S2cLoginPacket::EncryptionRequest(_) => {
+                            bail!("got encryption request. Disable online_mode to replay.")
+                        }
+                        S2cLoginPacket::SetCompression(pkt) => Ok(Some(pkt.threshold.0 as u32)),
+                        _ => Ok(None),
+                    }),
+                )
+                .await???;
+
+                if let Some(threshold) = threshold {
+                    enc.set_compression(Some(threshold));
+                    receiver.dec.set_compression(true);
+                }
+            } else {
+                timeout(TIMEOUT, receiver.recv(|_: RawPacket| ())).await??;
+            }
+        } else if let Some(mut receiver) = receiver.take() {
+            // Packets in the play state are not sent in lockstep, so discard
+            // everything the peer sends from here on in the background.
+            tokio::spawn(async move { while receiver.recv(|_: RawPacket| ()).await.is_ok() {} });
+        }
+    }
+
+    write.shutdown().await?;
+
+    eprintln!(
+        "Replayed {sent} packets from {} over {:.1}s",
+        path.display(),
+        start.elapsed().as_secs_f64()
+    );
+
+    Ok(())
+}
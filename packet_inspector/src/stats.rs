@@ -0,0 +1,182 @@
+//! Packet counts and byte totals collected over a session.
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use serde_json::{json, Value};
+
+use crate::capture::Direction;
+use crate::OutputFormat;
+
+#[derive(Default, Debug)]
+pub struct Stats {
+    packets: BTreeMap<(Direction, &'static str), PacketStats>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug)]
+struct PacketStats {
+    count: u64,
+    /// Total size of the packets before compression.
+    bytes: u64,
+}
+
+impl Stats {
+    pub fn record(&mut self, direction: Direction, name: &'static str, bytes: usize) {
+        let stats = self.packets.entry((direction, name)).or_default();
+        stats.count += 1;
+        stats.bytes += bytes as u64;
+    }
+
+    /// Returns the stats of every packet, grouped by direction and sorted by
+    /// the number of bytes sent in descending order.
+    fn sorted(&self) -> Vec<(Direction, &'static str, PacketStats)> {
+        let mut packets: Vec<_> = self
+            .packets
+            .iter()
+            .map(|(&(direction, name), &stats)| (direction, name, stats))
+            .collect();
+
+        packets.sort_by_key(|&(direction, _, stats)| (direction, Reverse(stats.bytes)));
+        packets
+    }
+
+    /// Returns the summary as one JSON object per packet.
+    fn json_lines(&self) -> Vec<Value> {
+        self.sorted()
+            .into_iter()
+            .map(|(direction, name, stats)| {
+                json!({
+                    "type": "summary",
+                    "direction": direction,
+                    "name": name,
+                    "count": stats.count,
+                    "bytes": stats.bytes,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the summary as a table with a row for each packet and a final
+    /// row with the totals.
+    fn table(&self) -> String {
+        let mut table = format!(
+            "{:<9} {:<40} {:>10} {:>14}\n",
+            "Direction", "Packet", "Count", "Bytes"
+        );
+
+        let mut total = PacketStats::default();
+
+        for (direction, name, stats) in self.sorted() {
+            table += &format!(
+                "{:<9} {name:<40} {:>10} {:>14}\n",
+                direction.as_str(),
+                stats.count,
+                stats.bytes
+            );
+
+            total.count += stats.count;
+            total.bytes += stats.bytes;
+        }
+
+        table += &format!(
+            "{:<9} {:<40} {:>10} {:>14}",
+            "Total", "", total.count, total.bytes
+        );
+
+        table
+    }
+
+    /// Prints the summary of the session. The summary goes to `stdout` as JSON
+    /// lines in the JSON format, and to `stderr` as a table otherwise so that
+    /// it stays visible when `stdout` is redirected.
+    pub fn print(&self, format: OutputFormat) {
+        match format {
+            OutputFormat::Debug => eprintln!("{}", self.table()),
+            OutputFormat::Json => {
+                for line in self.json_lines() {
+                    println!("{line}");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> Stats {
+        let mut stats = Stats::default();
+        stats.record(Direction::S2c, "ChunkDataAndUpdateLight", 5000);
+        stats.record(Direction::C2s, "KeepAliveC2s", 9);
+        stats.record(Direction::S2c, "KeepAliveS2c", 9);
+        stats.record(Direction::C2s, "SetPlayerPosition", 26);
+        stats.record(Direction::C2s, "SetPlayerPosition", 26);
+        stats
+    }
+
+    #[test]
+    fn summary_counts_packets() {
+        let stats = session().sorted();
+
+        assert_eq!(
+            stats,
+            [
+                (
+                    Direction::C2s,
+                    "SetPlayerPosition",
+                    PacketStats {
+                        count: 2,
+                        bytes: 52
+                    }
+                ),
+                (
+                    Direction::C2s,
+                    "KeepAliveC2s",
+                    PacketStats { count: 1, bytes: 9 }
+                ),
+                (
+                    Direction::S2c,
+                    "ChunkDataAndUpdateLight",
+                    PacketStats {
+                        count: 1,
+                        bytes: 5000
+                    }
+                ),
+                (
+                    Direction::S2c,
+                    "KeepAliveS2c",
+                    PacketStats { count: 1, bytes: 9 }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn summary_json_lines() {
+        let lines = session().json_lines();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[0],
+            json!({
+                "type": "summary",
+                "direction": "c2s",
+                "name": "SetPlayerPosition",
+                "count": 2,
+                "bytes": 52,
+            })
+        );
+    }
+
+    #[test]
+    fn summary_table_totals() {
+        let table = session().table();
+        let total = table.lines().last().unwrap();
+
+        assert_eq!(
+            total.split_whitespace().collect::<Vec<_>>(),
+            ["Total", "5", "5070"]
+        );
+    }
+}
@@ -44,6 +44,10 @@ impl Config for Game {
         ConnectionMode::Offline
     }
 
+    fn metrics_address(&self) -> Option<SocketAddr> {
+        Some(([127, 0, 0, 1], 9225).into())
+    }
+
     fn outgoing_capacity(&self) -> usize {
         usize::MAX
     }
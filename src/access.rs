@@ -0,0 +1,799 @@
+//! Ban lists, the whitelist, and operators.
+//!
+//! When an [access control directory] is configured, the server loads its
+//! lists from that directory at startup and checks every client against them
+//! before [`Config::login`] is called. The lists are stored in the same files
+//! and format as the vanilla server, so an existing vanilla server's
+//! directory can be used as is:
+//!
+//! - `banned-players.json`
+//! - `banned-ips.json`
+//! - `whitelist.json`
+//! - `ops.json`
+//!
+//! Missing files are treated as empty lists. Every change made through
+//! [`AccessControl`] is written back to disk immediately.
+//!
+//! [access control directory]: crate::config::Config::access_control_dir
+//! [`Config::login`]: crate::config::Config::login
+
+use std::fs;
+use std::io::ErrorKind;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+use anyhow::Context;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use time::format_description::FormatItem;
+use time::macros::format_description;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use valence_protocol::Text;
+
+use crate::server::NewClientData;
+
+const BANNED_PLAYERS_FILE: &str = "banned-players.json";
+const BANNED_IPS_FILE: &str = "banned-ips.json";
+const WHITELIST_FILE: &str = "whitelist.json";
+const OPS_FILE: &str = "ops.json";
+
+/// The format of timestamps in the ban lists.
+const TIMESTAMP_FORMAT: &[FormatItem] = format_description!(
+    "[year]-[month]-[day] [hour]:[minute]:[second] [offset_hour sign:mandatory][offset_minute]"
+);
+
+/// The ban lists, whitelist, and operator list of a server.
+///
+/// The lists can be read and modified from any thread.
+pub struct AccessControl {
+    dir: PathBuf,
+    lists: RwLock<Lists>,
+}
+
+struct Lists {
+    banned_players: Vec<PlayerBan>,
+    banned_ips: Vec<IpBan>,
+    whitelist: Vec<WhitelistEntry>,
+    ops: Vec<Operator>,
+    whitelist_enabled: bool,
+}
+
+/// An entry in `banned-players.json`.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct PlayerBan {
+    /// The UUID of the banned player. If this is `None`, the ban applies to
+    /// every player with a matching name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<Uuid>,
+    /// The username of the banned player. Names are compared ignoring case.
+    pub name: String,
+    #[serde(flatten)]
+    pub info: BanInfo,
+}
+
+/// An entry in `banned-ips.json`.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct IpBan {
+    /// The banned address.
+    pub ip: IpAddr,
+    #[serde(flatten)]
+    pub info: BanInfo,
+}
+
+/// The details shared by player and IP bans.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct BanInfo {
+    /// When the ban was made.
+    #[serde(with = "timestamp", default = "SystemTime::now")]
+    pub created: SystemTime,
+    /// Who made the ban.
+    #[serde(default = "default_source")]
+    pub source: String,
+    /// When the ban ends, or `None` if the ban is permanent.
+    #[serde(with = "expiry", default)]
+    pub expires: Option<SystemTime>,
+    /// The reason shown to the banned player.
+    #[serde(default = "default_reason")]
+    pub reason: String,
+}
+
+/// An entry in `whitelist.json`.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct WhitelistEntry {
+    pub uuid: Uuid,
+    pub name: String,
+}
+
+/// An entry in `ops.json`.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Operator {
+    pub uuid: Uuid,
+    pub name: String,
+    /// The operator's permission level, from 1 to 4.
+    pub level: u8,
+    /// Whether the operator can join when the server is full.
+    #[serde(rename = "bypassesPlayerLimit", default)]
+    pub bypasses_player_limit: bool,
+}
+
+impl BanInfo {
+    /// Creates a permanent ban made now by the server with the given reason.
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self {
+            created: SystemTime::now(),
+            source: default_source(),
+            expires: None,
+            reason: reason.into(),
+        }
+    }
+
+    /// Returns `true` if the ban has ended at the given time.
+    pub fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+
+    /// Builds the message shown to the player when they are disconnected.
+    fn disconnect_message(&self, header: &str) -> Text {
+        let mut msg = format!("{header}\nReason: {}", self.reason);
+
+        if let Some(expires) = self.expires {
+            let expires = timestamp::format(expires);
+
+            if expires != timestamp::FALLBACK {
+                msg.push_str("\nYour ban will be removed on ");
+                msg.push_str(&expires);
+            }
+        }
+
+        msg.into()
+    }
+}
+
+fn default_source() -> String {
+    "Server".into()
+}
+
+fn default_reason() -> String {
+    "Banned by an operator.".into()
+}
+
+impl AccessControl {
+    /// Loads the lists from the files in `dir`.
+    ///
+    /// `whitelist_enabled` determines if the whitelist is enforced. It can be
+    /// changed later with [`AccessControl::set_whitelist_enabled`].
+    pub fn load(dir: impl Into<PathBuf>, whitelist_enabled: bool) -> anyhow::Result<Self> {
+        let dir = dir.into();
+        let lists = Lists::load(&dir, whitelist_enabled)?;
+
+        Ok(Self {
+            dir,
+            lists: RwLock::new(lists),
+        })
+    }
+
+    /// Gets the directory the lists are stored in.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Discards the lists in memory and loads them from disk again.
+    pub fn reload(&self) -> anyhow::Result<()> {
+        let mut lists = self.lists.write().unwrap();
+        *lists = Lists::load(&self.dir, lists.whitelist_enabled)?;
+        Ok(())
+    }
+
+    /// Decides if a client is allowed to join. If they aren't, the reason
+    /// they should be disconnected with is returned.
+    ///
+    /// The checks are made in the same order as the vanilla server: player
+    /// bans, then the whitelist, then IP bans. Operators are always
+    /// considered to be on the whitelist. Expired bans are ignored.
+    pub fn check_login(&self, ncd: &NewClientData) -> Result<(), Text> {
+        let lists = self.lists.read().unwrap();
+        let now = SystemTime::now();
+
+        if let Some(ban) = lists.player_ban(ncd.uuid, ncd.username.as_str(), now) {
+            return Err(ban
+                .info
+                .disconnect_message("You are banned from this server."));
+        }
+
+        if lists.whitelist_enabled
+            && !lists.whitelist.iter().any(|e| e.uuid == ncd.uuid)
+            && !lists.ops.iter().any(|op| op.uuid == ncd.uuid)
+        {
+            return Err("You are not white-listed on this server!".into());
+        }
+
+        if let Some(ban) = lists.ip_ban(ncd.remote_addr, now) {
+            return Err(ban
+                .info
+                .disconnect_message("Your IP address is banned from this server."));
+        }
+
+        Ok(())
+    }
+
+    /// Returns all player bans, including expired ones.
+    pub fn banned_players(&self) -> Vec<PlayerBan> {
+        self.lists.read().unwrap().banned_players.clone()
+    }
+
+    /// Gets the ban in effect for the player with the given UUID and name,
+    /// if any.
+    pub fn player_ban(&self, uuid: Uuid, name: &str) -> Option<PlayerBan> {
+        self.lists
+            .read()
+            .unwrap()
+            .player_ban(uuid, name, SystemTime::now())
+            .cloned()
+    }
+
+    /// Bans a player, replacing any existing ban which applies to the same
+    /// player.
+    ///
+    /// Players which are already on the server are not disconnected.
+    pub fn ban_player(&self, ban: PlayerBan) -> anyhow::Result<()> {
+        let mut lists = self.lists.write().unwrap();
+
+        lists
+            .banned_players
+            .retain(|b| !b.matches(ban.uuid, &ban.name));
+        lists.banned_players.push(ban);
+
+        self.save(BANNED_PLAYERS_FILE, &lists.banned_players)
+    }
+
+    /// Removes all bans of players with the given name. Returns `true` if a
+    /// ban was removed.
+    pub fn pardon_player(&self, name: &str) -> anyhow::Result<bool> {
+        let mut lists = self.lists.write().unwrap();
+
+        let len = lists.banned_players.len();
+        lists
+            .banned_players
+            .retain(|b| !b.name.eq_ignore_ascii_case(name));
+
+        if lists.banned_players.len() == len {
+            return Ok(false);
+        }
+
+        self.save(BANNED_PLAYERS_FILE, &lists.banned_players)?;
+        Ok(true)
+    }
+
+    /// Returns all IP bans, including expired ones.
+    pub fn banned_ips(&self) -> Vec<IpBan> {
+        self.lists.read().unwrap().banned_ips.clone()
+    }
+
+    /// Gets the ban in effect for the given address, if any.
+    pub fn ip_ban(&self, ip: IpAddr) -> Option<IpBan> {
+        self.lists
+            .read()
+            .unwrap()
+            .ip_ban(ip, SystemTime::now())
+            .cloned()
+    }
+
+    /// Bans an IP address, replacing any existing ban of the same address.
+    ///
+    /// Players which are already on the server are not disconnected.
+    pub fn ban_ip(&self, ban: IpBan) -> anyhow::Result<()> {
+        let mut lists = self.lists.write().unwrap();
+
+        lists.banned_ips.retain(|b| b.ip != ban.ip);
+        lists.banned_ips.push(ban);
+
+        self.save(BANNED_IPS_FILE, &lists.banned_ips)
+    }
+
+    /// Removes the ban of an IP address. Returns `true` if the address was
+    /// banned.
+    pub fn pardon_ip(&self, ip: IpAddr) -> anyhow::Result<bool> {
+        let mut lists = self.lists.write().unwrap();
+
+        let len = lists.banned_ips.len();
+        lists.banned_ips.retain(|b| b.ip != ip);
+
+        if lists.banned_ips.len() == len {
+            return Ok(false);
+        }
+
+        self.save(BANNED_IPS_FILE, &lists.banned_ips)?;
+        Ok(true)
+    }
+
+    /// Returns `true` if clients must be on the whitelist to join.
+    pub fn whitelist_enabled(&self) -> bool {
+        self.lists.read().unwrap().whitelist_enabled
+    }
+
+    /// Sets whether clients must be on the whitelist to join. This is not
+    /// saved to disk.
+    pub fn set_whitelist_enabled(&self, enabled: bool) {
+        self.lists.write().unwrap().whitelist_enabled = enabled;
+    }
+
+    /// Returns every player on the whitelist.
+    pub fn whitelist(&self) -> Vec<WhitelistEntry> {
+        self.lists.read().unwrap().whitelist.clone()
+    }
+
+    /// Returns `true` if the player with the given UUID is on the whitelist.
+    pub fn is_whitelisted(&self, uuid: Uuid) -> bool {
+        self.lists
+            .read()
+            .unwrap()
+            .whitelist
+            .iter()
+            .any(|e| e.uuid == uuid)
+    }
+
+    /// Adds a player to the whitelist. Returns `false` if the player was
+    /// already on it.
+    pub fn add_to_whitelist(&self, uuid: Uuid, name: impl Into<String>) -> anyhow::Result<bool> {
+        let mut lists = self.lists.write().unwrap();
+
+        if lists.whitelist.iter().any(|e| e.uuid == uuid) {
+            return Ok(false);
+        }
+
+        lists.whitelist.push(WhitelistEntry {
+            uuid,
+            name: name.into(),
+        });
+
+        self.save(WHITELIST_FILE, &lists.whitelist)?;
+        Ok(true)
+    }
+
+    /// Removes the player with the given name from the whitelist. Returns
+    /// `true` if the player was on it.
+    pub fn remove_from_whitelist(&self, name: &str) -> anyhow::Result<bool> {
+        let mut lists = self.lists.write().unwrap();
+
+        let len = lists.whitelist.len();
+        lists
+            .whitelist
+            .retain(|e| !e.name.eq_ignore_ascii_case(name));
+
+        if lists.whitelist.len() == len {
+            return Ok(false);
+        }
+
+        self.save(WHITELIST_FILE, &lists.whitelist)?;
+        Ok(true)
+    }
+
+    /// Returns every operator.
+    pub fn ops(&self) -> Vec<Operator> {
+        self.lists.read().unwrap().ops.clone()
+    }
+
+    /// Gets the operator with the given UUID, if they are one.
+    pub fn operator(&self, uuid: Uuid) -> Option<Operator> {
+        self.lists
+            .read()
+            .unwrap()
+            .ops
+            .iter()
+            .find(|op| op.uuid == uuid)
+            .cloned()
+    }
+
+    /// Makes a player an operator, replacing their previous entry if they
+    /// already were one.
+    pub fn op(&self, op: Operator) -> anyhow::Result<()> {
+        let mut lists = self.lists.write().unwrap();
+
+        lists.ops.retain(|o| o.uuid != op.uuid);
+        lists.ops.push(op);
+
+        self.save(OPS_FILE, &lists.ops)
+    }
+
+    /// Removes the operator with the given name. Returns `true` if the player
+    /// was an operator.
+    pub fn deop(&self, name: &str) -> anyhow::Result<bool> {
+        let mut lists = self.lists.write().unwrap();
+
+        let len = lists.ops.len();
+        lists.ops.retain(|o| !o.name.eq_ignore_ascii_case(name));
+
+        if lists.ops.len() == len {
+            return Ok(false);
+        }
+
+        self.save(OPS_FILE, &lists.ops)?;
+        Ok(true)
+    }
+
+    /// Writes a list to a temporary file which then replaces the old file, so
+    /// that the old list is kept intact if writing fails partway through.
+    fn save<T: Serialize>(&self, file: &str, list: &[T]) -> anyhow::Result<()> {
+        let path = self.dir.join(file);
+        let tmp_path = self.dir.join(format!("{file}.tmp"));
+
+        let json = serde_json::to_string_pretty(list)?;
+
+        fs::write(&tmp_path, json)
+            .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+
+        fs::rename(&tmp_path, &path)
+            .with_context(|| format!("failed to replace {}", path.display()))
+    }
+}
+
+impl Lists {
+    fn load(dir: &Path, whitelist_enabled: bool) -> anyhow::Result<Self> {
+        Ok(Self {
+            banned_players: load_list(&dir.join(BANNED_PLAYERS_FILE))?,
+            banned_ips: load_list(&dir.join(BANNED_IPS_FILE))?,
+            whitelist: load_list(&dir.join(WHITELIST_FILE))?,
+            ops: load_list(&dir.join(OPS_FILE))?,
+            whitelist_enabled,
+        })
+    }
+
+    fn player_ban(&self, uuid: Uuid, name: &str, now: SystemTime) -> Option<&PlayerBan> {
+        self.banned_players
+            .iter()
+            .find(|b| b.matches(Some(uuid), name) && !b.info.is_expired(now))
+    }
+
+    fn ip_ban(&self, ip: IpAddr, now: SystemTime) -> Option<&IpBan> {
+        self.banned_ips
+            .iter()
+            .find(|b| b.ip == ip && !b.info.is_expired(now))
+    }
+}
+
+impl PlayerBan {
+    /// Returns `true` if this ban applies to a player with the given UUID
+    /// and name. Bans with a UUID only match that UUID, since names can be
+    /// changed and reused by other players.
+    fn matches(&self, uuid: Option<Uuid>, name: &str) -> bool {
+        match self.uuid {
+            Some(ban_uuid) => uuid == Some(ban_uuid),
+            None => self.name.eq_ignore_ascii_case(name),
+        }
+    }
+}
+
+fn load_list<T: DeserializeOwned>(path: &Path) -> anyhow::Result<Vec<T>> {
+    let json = match fs::read_to_string(path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+    };
+
+    serde_json::from_str(&json).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+mod timestamp {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::*;
+
+    /// Returned by [`format`] for times which cannot be represented.
+    pub const FALLBACK: &str = "forever";
+
+    /// Formats a timestamp. Times beyond the year 9999 are formatted as
+    /// `"forever"` rather than panicking.
+    pub fn format(time: SystemTime) -> String {
+        let time = match time.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(since) => time::Duration::try_from(since)
+                .ok()
+                .and_then(|d| OffsetDateTime::UNIX_EPOCH.checked_add(d)),
+            Err(e) => time::Duration::try_from(e.duration())
+                .ok()
+                .and_then(|d| OffsetDateTime::UNIX_EPOCH.checked_sub(d)),
+        };
+
+        time.and_then(|time| time.format(TIMESTAMP_FORMAT).ok())
+            .unwrap_or_else(|| FALLBACK.to_owned())
+    }
+
+    pub fn parse(s: &str) -> Result<SystemTime, time::error::Parse> {
+        OffsetDateTime::parse(s, TIMESTAMP_FORMAT).map(SystemTime::from)
+    }
+
+    pub fn serialize<S: Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format(*time))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SystemTime, D::Error> {
+        parse(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Ban expiry times, which are either a timestamp or `"forever"`.
+mod expiry {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::*;
+
+    const FOREVER: &str = "forever";
+
+    pub fn serialize<S: Serializer>(
+        time: &Option<SystemTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match time {
+            Some(time) => serializer.serialize_str(&timestamp::format(*time)),
+            None => serializer.serialize_str(FOREVER),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<SystemTime>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+
+        if s == FOREVER {
+            return Ok(None);
+        }
+
+        timestamp::parse(&s)
+            .map(Some)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use valence_protocol::Username;
+
+    use super::*;
+
+    /// A fresh, empty directory for a test's lists.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+            let path = std::env::temp_dir().join(format!(
+                "valence-access-test-{}-{}",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn ncd(name: &str, uuid: u128, ip: Ipv4Addr) -> NewClientData {
+        NewClientData {
+            uuid: Uuid::from_u128(uuid),
+            username: Username::new(name.to_owned()).unwrap(),
+            textures: None,
+            remote_addr: ip.into(),
+        }
+    }
+
+    #[test]
+    fn reads_vanilla_files() {
+        let dir = TempDir::new();
+
+        fs::write(
+            dir.0.join(BANNED_PLAYERS_FILE),
+            r#"[
+              {
+                "uuid": "069a79f4-44e9-4726-a5be-fca90e38aaf5",
+                "name": "Notch",
+                "created": "2022-10-01 12:30:00 +0200",
+                "source": "Server",
+                "expires": "forever",
+                "reason": "Banned by an operator."
+              }
+            ]"#,
+        )
+        .unwrap();
+
+        fs::write(
+            dir.0.join(BANNED_IPS_FILE),
+            r#"[
+              {
+                "ip": "10.0.0.1",
+                "created": "2022-10-01 12:30:00 +0000",
+                "source": "jeb_",
+                "expires": "2999-01-01 00:00:00 +0000",
+                "reason": "griefing"
+              }
+            ]"#,
+        )
+        .unwrap();
+
+        fs::write(
+            dir.0.join(OPS_FILE),
+            r#"[
+              {
+                "uuid": "853c80ef-3c37-49fd-aa49-938b674adae6",
+                "name": "jeb_",
+                "level": 4,
+                "bypassesPlayerLimit": false
+              }
+            ]"#,
+        )
+        .unwrap();
+
+        let access = AccessControl::load(&dir.0, false).unwrap();
+
+        let ban = &access.banned_players()[0];
+        assert_eq!(ban.name, "Notch");
+        assert_eq!(ban.info.expires, None);
+        assert_eq!(
+            ban.info.created,
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1664620200)
+        );
+
+        assert_eq!(access.banned_ips()[0].info.source, "jeb_");
+        assert_eq!(access.ops()[0].level, 4);
+        assert!(access.whitelist().is_empty());
+
+        // Saving writes the same format back.
+        access.ban_player(ban.clone()).unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.0.join(BANNED_PLAYERS_FILE)).unwrap())
+                .unwrap();
+
+        assert_eq!(json[0]["created"], "2022-10-01 10:30:00 +0000");
+        assert_eq!(json[0]["expires"], "forever");
+        assert_eq!(json[0]["uuid"], "069a79f4-44e9-4726-a5be-fca90e38aaf5");
+    }
+
+    #[test]
+    fn login_checks() {
+        let dir = TempDir::new();
+        let access = AccessControl::load(&dir.0, false).unwrap();
+
+        let ip = Ipv4Addr::new(10, 0, 0, 1);
+        let steve = ncd("Steve", 1, ip);
+
+        assert!(access.check_login(&steve).is_ok());
+
+        // Name bans apply regardless of UUID and case.
+        access
+            .ban_player(PlayerBan {
+                uuid: None,
+                name: "steve".into(),
+                info: BanInfo::new("testing"),
+            })
+            .unwrap();
+
+        let reason = access.check_login(&steve).unwrap_err();
+        assert!(format!("{reason:?}").contains("testing"));
+
+        assert!(access.pardon_player("STEVE").unwrap());
+        assert!(access.check_login(&steve).is_ok());
+
+        // UUID bans do not apply to other players using the same name.
+        access
+            .ban_player(PlayerBan {
+                uuid: Some(Uuid::from_u128(3)),
+                name: "Steve".into(),
+                info: BanInfo::new("old account"),
+            })
+            .unwrap();
+
+        assert!(access.check_login(&steve).is_ok());
+        assert!(access.check_login(&ncd("Alex", 3, ip)).is_err());
+
+        assert!(access.pardon_player("Steve").unwrap());
+
+        // Expired bans are ignored.
+        access
+            .ban_ip(IpBan {
+                ip: ip.into(),
+                info: BanInfo {
+                    expires: Some(SystemTime::now() - Duration::from_secs(1)),
+                    ..BanInfo::new("expired")
+                },
+            })
+            .unwrap();
+
+        assert!(access.check_login(&steve).is_ok());
+
+        access
+            .ban_ip(IpBan {
+                ip: ip.into(),
+                info: BanInfo::new("bad address"),
+            })
+            .unwrap();
+
+        assert_eq!(access.banned_ips().len(), 1);
+        assert!(access.check_login(&steve).is_err());
+        assert!(access
+            .check_login(&ncd("Alex", 2, Ipv4Addr::LOCALHOST))
+            .is_ok());
+
+        assert!(access.pardon_ip(ip.into()).unwrap());
+        assert!(!access.pardon_ip(ip.into()).unwrap());
+
+        // Operators bypass the whitelist.
+        access.set_whitelist_enabled(true);
+        assert!(access.check_login(&steve).is_err());
+
+        access
+            .op(Operator {
+                uuid: steve.uuid,
+                name: "Steve".into(),
+                level: 4,
+                bypasses_player_limit: false,
+            })
+            .unwrap();
+
+        assert!(access.check_login(&steve).is_ok());
+        assert!(access.deop("Steve").unwrap());
+        assert!(access.check_login(&steve).is_err());
+
+        assert!(access.add_to_whitelist(steve.uuid, "Steve").unwrap());
+        assert!(!access.add_to_whitelist(steve.uuid, "Steve").unwrap());
+        assert!(access.check_login(&steve).is_ok());
+    }
+
+    #[test]
+    fn changes_are_saved() {
+        let dir = TempDir::new();
+        let access = AccessControl::load(&dir.0, true).unwrap();
+
+        let uuid = Uuid::from_u128(1);
+
+        access.add_to_whitelist(uuid, "Steve").unwrap();
+        access
+            .ban_player(PlayerBan {
+                uuid: Some(Uuid::from_u128(2)),
+                name: "Alex".into(),
+                info: BanInfo::new("testing"),
+            })
+            .unwrap();
+
+        let reloaded = AccessControl::load(&dir.0, true).unwrap();
+        assert!(reloaded.is_whitelisted(uuid));
+        assert!(reloaded
+            .player_ban(Uuid::from_u128(2), "SomeoneElse")
+            .is_some());
+
+        access.remove_from_whitelist("steve").unwrap();
+        reloaded.reload().unwrap();
+        assert!(!reloaded.is_whitelisted(uuid));
+
+        // No temporary files are left behind.
+        assert!(!dir.0.join(format!("{WHITELIST_FILE}.tmp")).exists());
+
+        // Expiry times which cannot be represented are saved as permanent.
+        access
+            .ban_ip(IpBan {
+                ip: Ipv4Addr::LOCALHOST.into(),
+                info: BanInfo {
+                    expires: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1 << 40)),
+                    ..BanInfo::new("far future")
+                },
+            })
+            .unwrap();
+
+        reloaded.reload().unwrap();
+        assert_eq!(reloaded.banned_ips()[0].info.expires, None);
+    }
+}
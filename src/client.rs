@@ -2,7 +2,7 @@
 
 use std::collections::{HashSet, VecDeque};
 use std::iter::FusedIterator;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{cmp, mem};
 
 use anyhow::{bail, Context};
@@ -13,19 +13,20 @@ use tracing::{error, info, warn};
 use uuid::Uuid;
 use valence_protocol::packets::c2s::play::ClientCommand;
 use valence_protocol::packets::s2c::play::{
-    AcknowledgeBlockChange, ClearTitles, CombatDeath, CustomSoundEffect, DisconnectPlay,
-    EntityAnimationS2c, EntityEvent, GameEvent, KeepAliveS2c, LoginPlayOwned, OpenScreen,
-    PluginMessageS2c, RemoveEntities, ResourcePackS2c, RespawnOwned, SetActionBarText,
-    SetCenterChunk, SetContainerContent, SetDefaultSpawnPosition, SetEntityMetadata,
-    SetEntityVelocity, SetExperience, SetHeadRotation, SetHealth, SetRenderDistance,
-    SetSubtitleText, SetTitleAnimationTimes, SetTitleText, SynchronizePlayerPosition,
-    SystemChatMessage, TeleportEntity, UnloadChunk, UpdateAttributes, UpdateEntityPosition,
-    UpdateEntityPositionAndRotation, UpdateEntityRotation, UpdateTime,
+    AcknowledgeBlockChange, ClearTitles, CloseContainerS2c, CombatDeath, CustomSoundEffect,
+    DisconnectPlay, EntityAnimationS2c, EntityEvent, GameEvent, KeepAliveS2c, LoginPlayOwned,
+    OpenScreen, PlaceGhostRecipe, PluginMessageS2c, RemoveEntities, ResourcePackS2c, RespawnOwned,
+    SetActionBarText, SetCenterChunk, SetContainerContent, SetDefaultSpawnPosition,
+    SetEntityMetadata, SetEntityVelocity, SetExperience, SetHeadRotation, SetHealth,
+    SetRenderDistance, SetSubtitleText, SetTitleAnimationTimes, SetTitleText,
+    SynchronizePlayerPosition, SystemChatMessage, TeleportEntity, UnloadChunk, UpdateAttributes,
+    UpdateEntityPosition, UpdateEntityPositionAndRotation, UpdateEntityRotation, UpdateRecipeBook,
+    UpdateRecipes, UpdateTime,
 };
 use valence_protocol::packets::C2sPlayPacket;
 use valence_protocol::types::{
-    Action, AttributeProperty, GameMode, GameStateChangeReason, SoundCategory,
-    SyncPlayerPosLookFlags,
+    Action, AttributeProperty, GameMode, GameStateChangeReason, RecipeBookId, RecipeBookSettings,
+    SoundCategory, SyncPlayerPosLookFlags, UpdateRecipeBookAction,
 };
 use valence_protocol::{
     ident, types, BlockPos, ByteAngle, Encode, Ident, ItemStack, Packet, RawBytes, Text, Username,
@@ -41,11 +42,13 @@ use crate::entity::{
     self, velocity_to_packet_units, Entities, EntityId, EntityKind, StatusOrAnimation,
 };
 use crate::inventory::{
-    Inventories, Inventory, InventoryDirtyable, InventoryError, InventoryId, PlayerInventory,
-    SlotId, WindowInventory,
+    ClickSimulation, DragState, Inventories, Inventory, InventoryDirtyable, InventoryError,
+    InventoryId, PlayerInventory, SlotId, Window, WindowInventory, WindowUpdate,
 };
+use crate::metrics::TickPhase;
 use crate::player_list::{PlayerListId, PlayerLists};
 use crate::player_textures::SignedPlayerTextures;
+use crate::recipe::{RecipeId, Recipes};
 use crate::server::{NewClientData, PlayPacketController, SharedServer};
 use crate::slab_versioned::{Key, VersionedSlab};
 use crate::util::{chunks_in_view_distance, is_chunk_in_view_distance};
@@ -251,9 +254,15 @@ pub struct Client<C: Config> {
     player_data: Player,
     entity_events: Vec<entity::EntityEvent>,
     /// The item currently being held by the client's cursor in an inventory
-    /// screen. Does not work for creative mode.
+    /// screen. Does not work for creative mode. This is kept up to date by
+    /// simulating the client's clicks, and is never set to the item the client
+    /// claims to be holding.
     pub cursor_held_item: Option<ItemStack>, // TODO: make private or pub(crate)
+    /// The drag in progress in an inventory window, if any.
+    inventory_drag: Option<DragState>,
     selected_hotbar_slot: SlotId,
+    /// The state of the client's recipe books, as last reported by the client.
+    recipe_book_settings: RecipeBookSettings,
 }
 
 #[bitfield(u16)]
@@ -320,7 +329,9 @@ impl<C: Config> Client<C> {
             player_data: Player::new(),
             entity_events: Vec::new(),
             cursor_held_item: None,
+            inventory_drag: None,
             selected_hotbar_slot: PlayerInventory::HOTBAR_SLOTS.start,
+            recipe_book_settings: RecipeBookSettings::default(),
         }
     }
 
@@ -811,16 +822,223 @@ impl<C: Config> Client<C> {
         window_title: impl Into<Text>,
     ) {
         if let Some(inv) = inventories.get(id) {
-            let window = WindowInventory::new(1, id);
+            let mut window = WindowInventory::new(1, id);
             self.queue_packet(&OpenScreen {
                 window_id: VarInt(window.window_id.into()),
                 window_type: inv.window_type,
                 window_title: window_title.into(),
             });
+            let slots = window
+                .slots(inv, &self.inventory)
+                .into_iter()
+                .map(|s| s.cloned())
+                .collect();
+            self.queue_packet(&SetContainerContent {
+                window_id: window.window_id,
+                state_id: window.next_state_id(),
+                slots,
+                carried_item: self.cursor_held_item.clone(),
+            });
             self.open_inventory = Some(window);
         }
     }
 
+    /// Closes the inventory window the client currently has open, if any.
+    pub fn close_inventory(&mut self) {
+        if let Some(window) = self.open_inventory.take() {
+            self.queue_packet(&CloseContainerS2c {
+                window_id: window.window_id,
+            });
+        }
+    }
+
+    /// Applies a [`ContainerClick`] sent by this client using the same rules as
+    /// the vanilla server, rather than trusting the slot changes reported by
+    /// the client. The click is simulated against the client's inventory and
+    /// the object inventory of the open window, if any.
+    ///
+    /// If the client's prediction of the click differs from the result, the
+    /// contents of the window are resent to the client.
+    ///
+    /// The output of the window's crafting grid, if any, is computed from
+    /// `recipes`, and taking the output consumes the ingredients.
+    ///
+    /// Returns the stacks that were thrown out of the window.
+    fn click_container(
+        &mut self,
+        inventories: &mut Inventories,
+        recipes: &Recipes,
+        click: &ContainerClick,
+    ) -> Vec<ItemStack> {
+        let object = match &self.open_inventory {
+            _ if click.window_id == 0 => None,
+            Some(window) if window.window_id == click.window_id => {
+                inventories.get_mut(window.object_inventory)
+            }
+            _ => {
+                // The client clicked in a window that isn't open.
+                self.inventory.mark_dirty(true);
+                return Vec::new();
+            }
+        };
+
+        let expected_state_id = match &self.open_inventory {
+            Some(window) if click.window_id != 0 => window.state_id,
+            _ => self.inventory.state_id,
+        };
+
+        let player_was_dirty = self.inventory.is_dirty();
+        let creative = self.game_mode() == GameMode::Creative;
+        let mut window = Window::new(object, &mut self.inventory);
+
+        let before: Vec<_> = window.slots().into_iter().map(|s| s.cloned()).collect();
+
+        let mut sim = ClickSimulation {
+            window: &mut window,
+            cursor: &mut self.cursor_held_item,
+            drag: &mut self.inventory_drag,
+            creative,
+            recipes,
+            dropped: Vec::new(),
+        };
+
+        sim.click(click.slot_id, click.button, click.mode);
+
+        let dropped = sim.dropped;
+
+        let after: Vec<_> = window.slots().into_iter().map(|s| s.cloned()).collect();
+
+        // A mismatched state ID means the client acted on outdated information.
+        let prediction_matches = click.state_id.0 == expected_state_id
+            && click.carried_item == self.cursor_held_item
+            && click
+                .slot_changes
+                .iter()
+                .all(|(slot_id, stack)| after.get(*slot_id as usize) == Some(stack))
+            && before
+                .iter()
+                .zip(&after)
+                .enumerate()
+                .filter(|(_, (before, after))| before != after)
+                .all(|(slot_id, _)| {
+                    click
+                        .slot_changes
+                        .iter()
+                        .any(|(s, _)| *s as usize == slot_id)
+                });
+
+        if prediction_matches {
+            // The client already knows the state of its own inventory.
+            let Window { player, .. } = window;
+            player.mark_dirty(player_was_dirty);
+
+            if let Some(window) = &mut self.open_inventory {
+                if click.window_id != 0 {
+                    window
+                        .predicted_slots
+                        .extend(click.slot_changes.iter().cloned());
+                }
+            }
+        } else if click.window_id == 0 {
+            self.inventory.mark_dirty(true);
+        } else if let Some(window) = &mut self.open_inventory {
+            let msg = SetContainerContent {
+                window_id: click.window_id,
+                state_id: window.next_state_id(),
+                slots: after,
+                carried_item: self.cursor_held_item.clone(),
+            };
+            self.queue_packet(&msg);
+        }
+
+        dropped
+    }
+
+    /// Fills the crafting grid of a window with ingredients from the client's
+    /// inventory, as requested through the recipe book with
+    /// [`ClientEvent::PlaceRecipe`]. Whatever was in the grid is moved back
+    /// into the client's inventory first.
+    ///
+    /// If the client doesn't have the ingredients, a ghost of the recipe is
+    /// shown in the grid instead.
+    pub fn place_recipe(
+        &mut self,
+        inventories: &mut Inventories,
+        recipes: &Recipes,
+        placement: &RecipePlacement,
+    ) {
+        let object = match &self.open_inventory {
+            _ if placement.window_id == 0 => None,
+            Some(window) if window.window_id == placement.window_id => {
+                inventories.get_mut(window.object_inventory)
+            }
+            _ => return,
+        };
+
+        let mut window = Window::new(object, &mut self.inventory);
+
+        let Some((grid, width)) = window.crafting_grid() else {
+            return;
+        };
+
+        let Some(layout) = recipes
+            .get(&placement.recipe)
+            .and_then(|recipe| recipe.grid_layout(width, grid.len()))
+        else {
+            return;
+        };
+
+        let placed = window.place_recipe(&layout, placement.make_all);
+        window.update_crafting_output(recipes);
+
+        let slots: Vec<_> = window.slots().into_iter().map(|s| s.cloned()).collect();
+
+        if !placed {
+            self.queue_packet(&PlaceGhostRecipe {
+                window_id: placement.window_id as i8,
+                recipe: placement.recipe.as_str_ident(),
+            });
+        }
+
+        // The client does not predict the placement, so the window is resent.
+        if placement.window_id == 0 {
+            self.inventory.mark_dirty(true);
+        } else if let Some(window) = &mut self.open_inventory {
+            let msg = SetContainerContent {
+                window_id: placement.window_id,
+                state_id: window.next_state_id(),
+                slots,
+                carried_item: self.cursor_held_item.clone(),
+            };
+            self.queue_packet(&msg);
+        }
+    }
+
+    /// Unlocks recipes in the client's recipe book. The recipes are
+    /// highlighted as new.
+    pub fn unlock_recipes(&mut self, recipes: impl IntoIterator<Item = RecipeId>) {
+        let recipes: Vec<_> = recipes.into_iter().collect();
+
+        self.queue_packet(&UpdateRecipeBook {
+            action: UpdateRecipeBookAction::Add {
+                settings: self.recipe_book_settings,
+                recipe_ids: recipes.iter().map(|id| id.as_str_ident()).collect(),
+            },
+        });
+    }
+
+    /// Removes recipes from the client's recipe book.
+    pub fn lock_recipes(&mut self, recipes: impl IntoIterator<Item = RecipeId>) {
+        let recipes: Vec<_> = recipes.into_iter().collect();
+
+        self.queue_packet(&UpdateRecipeBook {
+            action: UpdateRecipeBookAction::Remove {
+                settings: self.recipe_book_settings,
+                recipe_ids: recipes.iter().map(|id| id.as_str_ident()).collect(),
+            },
+        });
+    }
+
     /// Disconnects this client from the server with the provided reason. This
     /// has no effect if the client is already disconnected.
     ///
@@ -893,6 +1111,36 @@ impl<C: Config> Client<C> {
         }
     }
 
+    /// Simulates the clicks received this tick in the order they were sent,
+    /// so that each click starts from the state left by the previous one.
+    ///
+    /// Stacks thrown out of a window are queued as
+    /// [`ClientEvent::DropItemStack`] right after the click which threw them.
+    pub(crate) fn apply_clicks(&mut self, inventories: &mut Inventories, recipes: &Recipes) {
+        if !self
+            .events
+            .iter()
+            .any(|e| matches!(e, ClientEvent::ClickContainer(_)))
+        {
+            return;
+        }
+
+        for event in mem::take(&mut self.events) {
+            let dropped = match &event {
+                ClientEvent::ClickContainer(click) => {
+                    self.click_container(inventories, recipes, click)
+                }
+                _ => Vec::new(),
+            };
+
+            self.events.push_back(event);
+
+            for stack in dropped {
+                self.events.push_back(ClientEvent::DropItemStack { stack });
+            }
+        }
+    }
+
     fn handle_serverbound_packet(
         &mut self,
         entities: &Entities<C>,
@@ -944,24 +1192,17 @@ impl<C: Config> Client<C> {
             C2sPlayPacket::CommandSuggestionsRequest(_) => {}
             C2sPlayPacket::ClickContainerButton(_) => {}
             C2sPlayPacket::ClickContainer(p) => {
-                if p.slot_idx == -999 {
-                    // client is trying to drop the currently held stack
-                    let held = mem::replace(&mut self.cursor_held_item, None);
-                    match held {
-                        None => {}
-                        Some(stack) => self.events.push_back(ClientEvent::DropItemStack { stack }),
-                    }
-                } else {
-                    self.cursor_held_item = p.carried_item.clone();
-                    self.events.push_back(ClientEvent::ClickContainer {
+                // The click is simulated by `apply_clicks` once all packets are handled.
+                self.events
+                    .push_back(ClientEvent::ClickContainer(ContainerClick {
                         window_id: p.window_id,
                         state_id: p.state_id,
                         slot_id: p.slot_idx,
+                        button: p.button,
                         mode: p.mode,
                         slot_changes: p.slots,
                         carried_item: p.carried_item,
-                    });
-                }
+                    }));
             }
             C2sPlayPacket::CloseContainerC2s(c) => {
                 self.events.push_back(ClientEvent::CloseScreen {
@@ -1057,7 +1298,14 @@ impl<C: Config> Client<C> {
                 });
             }
             C2sPlayPacket::PickItem(_) => {}
-            C2sPlayPacket::PlaceRecipe(_) => {}
+            C2sPlayPacket::PlaceRecipe(p) => {
+                self.events
+                    .push_back(ClientEvent::PlaceRecipe(RecipePlacement {
+                        window_id: p.window_id as u8,
+                        recipe: p.recipe.to_owned_ident(),
+                        make_all: p.make_all,
+                    }));
+            }
             C2sPlayPacket::PlayerAbilitiesC2s(_) => {}
             C2sPlayPacket::PlayerAction(p) => {
                 if p.sequence.0 != 0 {
@@ -1103,7 +1351,29 @@ impl<C: Config> Client<C> {
             }
             C2sPlayPacket::PlayerInput(_) => {}
             C2sPlayPacket::PongPlay(_) => {}
-            C2sPlayPacket::ChangeRecipeBookSettings(_) => {}
+            C2sPlayPacket::ChangeRecipeBookSettings(p) => {
+                let settings = &mut self.recipe_book_settings;
+                let (open, filter) = match p.book_id {
+                    RecipeBookId::Crafting => (
+                        &mut settings.crafting_book_open,
+                        &mut settings.crafting_filter_active,
+                    ),
+                    RecipeBookId::Furnace => (
+                        &mut settings.smelting_book_open,
+                        &mut settings.smelting_filter_active,
+                    ),
+                    RecipeBookId::BlastFurnace => (
+                        &mut settings.blast_furnace_book_open,
+                        &mut settings.blast_furnace_filter_active,
+                    ),
+                    RecipeBookId::Smoker => (
+                        &mut settings.smoker_book_open,
+                        &mut settings.smoker_filter_active,
+                    ),
+                };
+                *open = p.book_open;
+                *filter = p.filter_active;
+            }
             C2sPlayPacket::SetSeenRecipe(_) => {}
             C2sPlayPacket::RenameItem(_) => {}
             C2sPlayPacket::ResourcePackC2s(p) => self
@@ -1174,6 +1444,7 @@ impl<C: Config> Client<C> {
         worlds: &Worlds<C>,
         player_lists: &PlayerLists<C>,
         inventories: &Inventories,
+        recipes: &Recipes,
     ) {
         if let Some(mut ctrl) = self.ctrl.take() {
             match self.update_fallible(
@@ -1183,6 +1454,7 @@ impl<C: Config> Client<C> {
                 worlds,
                 player_lists,
                 inventories,
+                recipes,
             ) {
                 Ok(()) => self.ctrl = Some(ctrl),
                 Err(e) => {
@@ -1201,6 +1473,7 @@ impl<C: Config> Client<C> {
     /// Called by [`Self::update`] with the possibility of exiting early with an
     /// error. If an error does occur, the client is abruptly disconnected and
     /// the error is reported.
+    #[allow(clippy::too_many_arguments)]
     fn update_fallible(
         &mut self,
         ctrl: &mut PlayPacketController,
@@ -1209,6 +1482,7 @@ impl<C: Config> Client<C> {
         worlds: &Worlds<C>,
         player_lists: &PlayerLists<C>,
         inventories: &Inventories,
+        recipes: &Recipes,
     ) -> anyhow::Result<()> {
         let world = match worlds.get(self.world) {
             Some(world) => world,
@@ -1229,6 +1503,14 @@ impl<C: Config> Client<C> {
 
             dimension_names.push(ident!("{LIBRARY_NAMESPACE}:dummy_dimension"));
 
+            // Recipes must be known before the user's recipe book packets arrive, so they
+            // are prepended as well, and end up directly after the login packet.
+            if !recipes.is_empty() {
+                ctrl.prepend_packet(&UpdateRecipes {
+                    recipes: recipes.to_packet_data(),
+                })?;
+            }
+
             // The login packet is prepended so that it is sent before all the other
             // packets. Some packets don't work correctly when sent before the login packet,
             // which is why we're doing this.
@@ -1322,6 +1604,12 @@ impl<C: Config> Client<C> {
                 // Update current player list.
                 player_lists.get(id).send_update_packets(ctrl)?;
             }
+
+            if recipes.is_modified() {
+                ctrl.append_packet(&UpdateRecipes {
+                    recipes: recipes.to_packet_data(),
+                })?;
+            }
         }
 
         // Set player attributes
@@ -1382,6 +1670,8 @@ impl<C: Config> Client<C> {
             }
         }
 
+        let chunk_sending_start = Instant::now();
+
         let center = ChunkPos::at(self.position.x, self.position.z);
 
         // Send the update view position packet if the client changes the chunk they're
@@ -1434,6 +1724,10 @@ impl<C: Config> Client<C> {
             }
         }
 
+        shared
+            .metrics()
+            .add_client_phase(TickPhase::ChunkSending, chunk_sending_start.elapsed());
+
         // Acknowledge broken/placed blocks.
         if self.block_change_sequence != 0 {
             ctrl.append_packet(&AcknowledgeBlockChange {
@@ -1479,6 +1773,8 @@ impl<C: Config> Client<C> {
             })?;
         }
 
+        let entity_tracking_start = Instant::now();
+
         let mut entities_to_unload = Vec::new();
 
         // Update all entities that are visible and unload entities that are no
@@ -1624,12 +1920,17 @@ impl<C: Config> Client<C> {
         send_entity_events(ctrl, 0, &self.entity_events)?;
         self.entity_events.clear();
 
+        shared
+            .metrics()
+            .add_client_phase(TickPhase::EntityTracking, entity_tracking_start.elapsed());
+
         self.player_data.clear_modifications();
         self.old_position = self.position;
         self.bits.set_created_this_tick(false);
 
         // Update the player's inventory
         if self.inventory.is_dirty() {
+            self.inventory.state_id = self.inventory.state_id.wrapping_add(1);
             ctrl.append_packet(&SetContainerContent {
                 window_id: 0,
                 state_id: VarInt(self.inventory.state_id),
@@ -1644,32 +1945,26 @@ impl<C: Config> Client<C> {
                         .collect(),
                 carried_item: self.cursor_held_item.clone(),
             })?;
-            self.inventory.state_id = self.inventory.state_id.wrapping_add(1);
             self.inventory.mark_dirty(false);
         }
 
         // Update the client's UI if they have an open inventory.
-        if let Some(window) = self.open_inventory.as_ref() {
+        if let Some(window) = self.open_inventory.as_mut() {
             // this client has an inventory open
             let obj_inv_id = window.object_inventory;
             if let Some(obj_inv) = inventories.get(obj_inv_id) {
-                if obj_inv.is_dirty() {
-                    let window_id = window.window_id;
-                    let slots = window.slots(obj_inv, &self.inventory)
-                        .into_iter()
-                        // FIXME: cloning is necessary here to build the packet.
-                        // However, it should be possible to avoid the clone if this packet
-                        // could consume refs
-                        .map(|s| s.cloned())
-                        .collect();
-                    let carried_item = self.cursor_held_item.clone();
-                    ctrl.append_packet(&SetContainerContent {
-                        window_id,
-                        state_id: VarInt(1),
-                        slots,
-                        carried_item,
-                    })?;
+                for update in window.sync(obj_inv, &self.inventory, &self.cursor_held_item) {
+                    match update {
+                        WindowUpdate::Contents(pkt) => ctrl.append_packet(&pkt)?,
+                        WindowUpdate::Slot(pkt) => ctrl.append_packet(&pkt)?,
+                    }
                 }
+            } else {
+                // The inventory was removed, so the window can't be open anymore.
+                ctrl.append_packet(&CloseContainerS2c {
+                    window_id: window.window_id,
+                })?;
+                self.open_inventory = None;
             }
         }
 
@@ -1699,3 +1994,87 @@ fn send_entity_events(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use valence_protocol::packets::c2s::play::ClickContainer;
+    use valence_protocol::types::ClickContainerMode;
+    use valence_protocol::ItemKind;
+
+    use super::*;
+    use crate::config::MockConfig;
+    use crate::server::testing::{MockClient, TestServer};
+
+    fn click(slot_idx: i16, carried_item: Option<ItemStack>) -> ClickContainer {
+        ClickContainer {
+            window_id: 0,
+            state_id: VarInt(0),
+            slot_idx,
+            button: 0,
+            mode: ClickContainerMode::Click,
+            slots: vec![],
+            carried_item,
+        }
+    }
+
+    fn join_with_stone() -> (TestServer<MockConfig>, MockClient) {
+        let mut test = TestServer::new(MockConfig::default(), ()).unwrap();
+        let (world, _) = test.server_mut().worlds.insert(DimensionId::default(), ());
+
+        let client = test.connect("test").unwrap();
+        let c = test.server_mut().clients.get_mut(client.id()).unwrap();
+        c.spawn(world);
+        c.inventory
+            .set_slot(36, Some(ItemStack::new(ItemKind::Stone, 64, None)));
+
+        test.tick();
+
+        (test, client)
+    }
+
+    #[test]
+    fn clicks_ignore_claimed_cursor() {
+        let (mut test, mut client) = join_with_stone();
+
+        let lie = Some(ItemStack::new(ItemKind::Diamond, 64, None));
+        client.send(&click(36, lie)).unwrap();
+        client.send(&click(-999, None)).unwrap();
+
+        test.tick();
+
+        let c = test.server_mut().clients.get_mut(client.id()).unwrap();
+        assert_eq!(c.inventory.slot(36), None);
+        assert_eq!(c.cursor_held_item, None);
+
+        let dropped: Vec<_> = c
+            .events()
+            .filter_map(|e| match e {
+                ClientEvent::DropItemStack { stack } => Some(stack.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(dropped, [ItemStack::new(ItemKind::Stone, 64, None)]);
+    }
+
+    #[test]
+    fn clicks_in_one_tick_are_applied_in_order() {
+        let (mut test, mut client) = join_with_stone();
+
+        let stone = Some(ItemStack::new(ItemKind::Stone, 64, None));
+        client.send(&click(36, stone)).unwrap();
+        client.send(&click(37, None)).unwrap();
+
+        test.tick();
+
+        let c = test.server_mut().clients.get_mut(client.id()).unwrap();
+        assert_eq!(c.inventory.slot(36), None);
+        assert_eq!(
+            c.inventory.slot(37),
+            Some(&ItemStack::new(ItemKind::Stone, 64, None))
+        );
+        assert_eq!(c.cursor_held_item, None);
+        assert!(c
+            .events()
+            .all(|e| !matches!(e, ClientEvent::DropItemStack { .. })));
+    }
+}
@@ -12,6 +12,7 @@ use super::Client;
 use crate::config::Config;
 use crate::entity::{Entity, EntityEvent, EntityId, TrackedData};
 use crate::inventory::{Inventory, InventoryDirtyable, SlotId};
+use crate::recipe::RecipeId;
 
 /// Represents an action performed by a client.
 ///
@@ -143,9 +144,10 @@ pub enum ClientEvent {
     /// The client is attempting to drop a stack of items.
     ///
     /// If the client is in creative mode, the items come from the void, so it
-    /// is safe to trust the contents of this event. Otherwise, you may need to
-    /// do some validation to make sure items are actually coming from the
-    /// user's inventory.
+    /// is safe to trust the contents of this event. Otherwise, the stack was
+    /// thrown out of an inventory window by the preceding
+    /// [`ClientEvent::ClickContainer`] and has already been removed from the
+    /// window.
     DropItemStack {
         // TODO: maybe we could add `from_slot_id` to make validation easier
         stack: ItemStack,
@@ -159,24 +161,56 @@ pub enum ClientEvent {
         slot: Option<ItemStack>,
     },
     /// The client is in survival mode, and is trying to modify an inventory.
-    ClickContainer {
-        window_id: u8,
-        state_id: VarInt,
-        /// The slot that was clicked
-        slot_id: SlotId,
-        /// The type of click that the user performed
-        mode: ClickContainerMode,
-        /// A list of slot ids and what their contents should be set to.
-        ///
-        /// It's not safe to blindly trust the contents of this. Servers need to
-        /// validate it if they want to prevent item duping.
-        slot_changes: Vec<(SlotId, Option<ItemStack>)>,
-        /// The item that is now being carried by the user's cursor
-        carried_item: Option<ItemStack>,
-    },
+    ///
+    /// By the time this event is received, the click has already been
+    /// simulated with vanilla behavior and applied to the client's inventory,
+    /// its [cursor item] and the open window. If the client predicted a
+    /// different result, the window is resent to it.
+    ///
+    /// [cursor item]: super::Client::cursor_held_item
+    ClickContainer(ContainerClick),
+    /// The client selected a recipe in the recipe book to fill the crafting
+    /// grid with.
+    ///
+    /// Use [`Client::place_recipe`](super::Client::place_recipe) to fill the
+    /// grid with vanilla behavior.
+    PlaceRecipe(RecipePlacement),
     RespawnRequest,
 }
 
+/// A click in an inventory window, as reported by the client.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ContainerClick {
+    pub window_id: u8,
+    pub state_id: VarInt,
+    /// The slot that was clicked
+    pub slot_id: SlotId,
+    /// The mouse button or key that was pressed. The meaning depends on the
+    /// `mode`.
+    pub button: i8,
+    /// The type of click that the user performed
+    pub mode: ClickContainerMode,
+    /// A list of slot ids and what the client predicts their contents to be
+    /// after the click.
+    ///
+    /// This is only the client's prediction. The slots are set from the
+    /// server's simulation of the click instead.
+    pub slot_changes: Vec<(SlotId, Option<ItemStack>)>,
+    /// The item the client predicts is being carried by its cursor after the
+    /// click.
+    pub carried_item: Option<ItemStack>,
+}
+
+/// A request from the recipe book to fill a crafting grid.
+#[derive(Clone, PartialEq, Debug)]
+pub struct RecipePlacement {
+    pub window_id: u8,
+    pub recipe: RecipeId,
+    /// `true` if the client is shift-clicking the recipe to place as many
+    /// sets of ingredients as possible.
+    pub make_all: bool,
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct Settings {
     /// e.g. en_US
@@ -343,7 +377,8 @@ pub fn handle_event_default<C: Config>(
             // client already knows what the updated state of the inventory is.
             client.inventory.mark_dirty(previous_dirty);
         }
-        ClientEvent::ClickContainer { .. } => {}
+        ClientEvent::ClickContainer(_) => {}
+        ClientEvent::PlaceRecipe(_) => {}
         ClientEvent::RespawnRequest => {}
     }
 
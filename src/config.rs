@@ -2,6 +2,8 @@
 
 use std::borrow::Cow;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::path::PathBuf;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use serde::Serialize;
@@ -13,7 +15,7 @@ use valence_protocol::MAX_PACKET_SIZE;
 
 use crate::biome::Biome;
 use crate::dimension::Dimension;
-use crate::server::{NewClientData, Server, SharedServer};
+use crate::server::{LoginPlugin, NewClientData, Server, SharedServer};
 use crate::{Ticks, STANDARD_TPS};
 
 /// A trait for the configuration of a server.
@@ -56,6 +58,73 @@ pub trait Config: Sized + Send + Sync + 'static {
         1024
     }
 
+    /// Called once at startup to get the maximum number of simultaneous
+    /// connections allowed from a single IP address. Like
+    /// [`Config::max_connections`], this includes all connections.
+    ///
+    /// Connections over the limit are closed before any packets are read.
+    /// When the server is behind a proxy, every connection comes from the
+    /// proxy's address.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns `None`, which disables the limit.
+    fn max_connections_per_ip(&self) -> Option<usize> {
+        None
+    }
+
+    /// Called once at startup to get the maximum rate of login attempts
+    /// allowed from a single IP address.
+    ///
+    /// Login attempts over the limit are disconnected before
+    /// [`Config::login`] is called. As with
+    /// [`Config::max_connections_per_ip`], this should be disabled when the
+    /// server is behind a proxy.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns `None`, which disables the limit.
+    fn login_rate_limit(&self) -> Option<RateLimit> {
+        None
+    }
+
+    /// Called once at startup to get the maximum amount of time to wait for
+    /// each packet to be received or sent before a connection reaches the
+    /// play state.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns 5 seconds.
+    fn handshake_timeout(&self) -> Duration {
+        Duration::from_secs(5)
+    }
+
+    /// Called once at startup to get the maximum total amount of time a
+    /// connection may take to reach the play state, including any time
+    /// spent authenticating and in [`Config::login`]. Connections which take
+    /// longer are closed.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns 30 seconds.
+    fn login_timeout(&self) -> Duration {
+        Duration::from_secs(30)
+    }
+
+    /// Called for every new connection before any packets are read to decide
+    /// if the connection should be accepted. If `false` is returned, the
+    /// connection is closed immediately.
+    ///
+    /// This is called from within the connection accept loop, so it must not
+    /// block.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns `true`, accepting every connection.
+    fn accept_connection(&self, shared: &SharedServer<Self>, remote_addr: SocketAddr) -> bool {
+        true
+    }
+
     /// Called once at startup to get the socket address the server will
     /// be bound to.
     ///
@@ -66,6 +135,94 @@ pub trait Config: Sized + Send + Sync + 'static {
         SocketAddrV4::new(Ipv4Addr::new(0, 0, 0, 0), 25565).into()
     }
 
+    /// Called once at startup to get the socket address of the UDP [Query]
+    /// listener. Query requests are answered using the data returned by
+    /// [`Config::server_list_ping`].
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns `None`, which disables the Query listener.
+    ///
+    /// [Query]: https://wiki.vg/Query
+    fn query_address(&self) -> Option<SocketAddr> {
+        None
+    }
+
+    /// Called once at startup to get the socket address of the [RCON]
+    /// listener. Commands sent by authenticated RCON clients are delivered to
+    /// the update loop through [`Server::rcon`].
+    ///
+    /// At most 8 RCON connections are handled at once. Connections are closed
+    /// after a minute without any packets or after three wrong passwords.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns `None`, which disables the RCON listener.
+    ///
+    /// [RCON]: https://wiki.vg/RCON
+    /// [`Server::rcon`]: crate::server::Server::rcon
+    fn rcon_address(&self) -> Option<SocketAddr> {
+        None
+    }
+
+    /// Called once at startup to get the socket address of the HTTP listener
+    /// serving the server's [metrics] at the `/metrics` path.
+    ///
+    /// Metrics are collected regardless of this setting and can be accessed
+    /// with [`SharedServer::metrics`].
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns `None`, which disables the metrics listener.
+    ///
+    /// [metrics]: crate::metrics
+    /// [`SharedServer::metrics`]: crate::server::SharedServer::metrics
+    fn metrics_address(&self) -> Option<SocketAddr> {
+        None
+    }
+
+    /// Called once at startup to get the directory containing the server's
+    /// ban lists, whitelist, and operators in the vanilla format.
+    ///
+    /// If a directory is returned, the lists are loaded from it and every
+    /// client is checked against them before [`Config::login`] is called.
+    /// The lists can be read and modified at runtime with
+    /// [`SharedServer::access_control`].
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns `None`, which disables access control.
+    ///
+    /// [`SharedServer::access_control`]: crate::server::SharedServer::access_control
+    fn access_control_dir(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Called once at startup to determine if clients must be on the
+    /// whitelist to join. Has no effect unless
+    /// [`Config::access_control_dir`] returns a directory.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns `false`.
+    fn enforce_whitelist(&self) -> bool {
+        false
+    }
+
+    /// Called once at startup to get the password RCON clients must send to
+    /// authenticate. This method is not called unless
+    /// [`Config::rcon_address`] returns an address.
+    ///
+    /// The password must not be empty.
+    ///
+    /// # Default Implementation
+    ///
+    /// Returns an empty string, which is invalid. The method must be
+    /// overridden to enable RCON.
+    fn rcon_password(&self) -> String {
+        String::new()
+    }
+
     /// Called once at startup to get the tick rate, which is the number of game
     /// updates that should occur in one second.
     ///
@@ -243,6 +400,12 @@ pub trait Config: Sized + Send + Sync + 'static {
     /// Called when the server receives a Server List Ping query.
     /// Data for the response can be provided or the query can be ignored.
     ///
+    /// This is also called for the legacy ping sent by pre-1.7 clients and for
+    /// [Query](Config::query_address) stat requests. Only the player counts,
+    /// the player sample names and the plain text of the description are used
+    /// in those cases, and `protocol_version` is `-1` unless the client is a
+    /// 1.6 client reporting its legacy protocol version.
+    ///
     /// This method is called from within a tokio runtime.
     ///
     /// # Default Implementation
@@ -265,7 +428,10 @@ pub trait Config: Sized + Send + Sync + 'static {
     ///
     /// This method is the appropriate place to perform asynchronous
     /// operations such as database queries which may take some time to
-    /// complete.
+    /// complete. Login plugin requests can be sent to the client with
+    /// `plugin`, for instance to negotiate with a mod loader. Each request
+    /// must wait for the client's response, so this time is counted against
+    /// [`Config::login_timeout`].
     ///
     /// This method is called from within a tokio runtime.
     ///
@@ -274,7 +440,12 @@ pub trait Config: Sized + Send + Sync + 'static {
     /// The client is allowed to join unconditionally.
     ///
     /// [`Clients`]: crate::client::Clients
-    async fn login(&self, shared: &SharedServer<Self>, ncd: &NewClientData) -> Result<(), Text> {
+    async fn login(
+        &self,
+        shared: &SharedServer<Self>,
+        ncd: &NewClientData,
+        plugin: &mut LoginPlugin<'_>,
+    ) -> Result<(), Text> {
         Ok(())
     }
 
@@ -393,13 +564,26 @@ pub struct PlayerSampleEntry<'a> {
     ///
     /// This string can contain
     /// [legacy formatting codes](https://minecraft.fandom.com/wiki/Formatting_codes).
+    /// [`Text::to_legacy_string`] converts formatted text into this form.
     pub name: Cow<'a, str>,
     /// The player UUID.
     pub id: Uuid,
 }
 
+/// The maximum number of times something may happen within a period of time.
+/// Used by [`Config::login_rate_limit`].
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct RateLimit {
+    /// The number of events allowed within `period`. Must be nonzero.
+    pub max: u32,
+    /// The length of the sliding window events are counted in. Must be
+    /// nonzero.
+    pub period: Duration,
+}
+
 /// A minimal `Config` implementation for testing purposes.
 #[cfg(test)]
+#[derive(Default)]
 pub(crate) struct MockConfig<S = (), Cl = (), E = (), W = (), Ch = (), P = ()> {
     _marker: std::marker::PhantomData<(S, Cl, E, W, Ch, P)>,
 }
@@ -1,10 +1,21 @@
+use std::collections::HashMap;
+use std::mem;
 use std::ops::Range;
 
 use thiserror::Error;
-use valence_protocol::{ItemStack, VarInt};
+use valence_protocol::packets::s2c::play::{SetContainerContent, SetContainerSlot};
+use valence_protocol::{ItemKind, ItemStack, VarInt};
 
+use crate::client::{ClientId, Clients};
+use crate::config::Config;
 use crate::slab_versioned::{Key, VersionedSlab};
 
+mod click;
+mod transaction;
+
+pub(crate) use click::{ClickSimulation, DragState, Window};
+pub use transaction::Transaction;
+
 pub type SlotId = i16;
 
 pub trait Inventory {
@@ -18,7 +29,12 @@ pub trait Inventory {
         self.slot_range().count()
     }
 
-    // TODO: `entry()` style api
+    /// The slots which bulk operations such as [`Self::insert_stack`],
+    /// [`Self::remove_item`] and [`Self::sort`] act upon. By default this is
+    /// every slot in the inventory.
+    fn general_slots(&self) -> Range<SlotId> {
+        self.slot_range()
+    }
 
     fn slots(&self) -> Vec<Option<&ItemStack>> {
         (0..self.slot_count())
@@ -26,6 +42,155 @@ pub trait Inventory {
             .collect()
     }
 
+    /// Gets the entry for a slot for in-place manipulation.
+    fn entry(&mut self, slot_id: SlotId) -> SlotEntry<'_, Self>
+    where
+        Self: Sized,
+    {
+        SlotEntry { inv: self, slot_id }
+    }
+
+    /// Returns the first empty general slot, if any.
+    fn first_empty_slot(&self) -> Option<SlotId> {
+        self.general_slots().find(|&s| self.slot(s).is_none())
+    }
+
+    /// Returns the total number of items of the given kind in the general
+    /// slots.
+    fn count_item(&self, item: ItemKind) -> usize {
+        self.general_slots()
+            .filter_map(|s| self.slot(s))
+            .filter(|stack| stack.item == item)
+            .map(|stack| stack.count() as usize)
+            .sum()
+    }
+
+    /// Inserts a stack into the general slots. Items are first merged into
+    /// existing compatible stacks, then placed into empty slots. The
+    /// item's maximum stack size is respected.
+    ///
+    /// Returns the items that did not fit, if any.
+    fn insert_stack(&mut self, stack: ItemStack) -> Option<ItemStack> {
+        let max = stack.item.max_stack();
+        let mut remaining = stack.count();
+
+        for slot_id in self.general_slots() {
+            if remaining == 0 {
+                break;
+            }
+            let Some(existing) = self.slot(slot_id) else {
+                continue;
+            };
+            if existing.item != stack.item || existing.nbt != stack.nbt || existing.count() >= max {
+                continue;
+            }
+            let moved = remaining.min(max - existing.count());
+            let mut existing = existing.clone();
+            existing.set_count(existing.count() + moved);
+            self.set_slot(slot_id, Some(existing));
+            remaining -= moved;
+        }
+
+        for slot_id in self.general_slots() {
+            if remaining == 0 {
+                break;
+            }
+            if self.slot(slot_id).is_some() {
+                continue;
+            }
+            let moved = remaining.min(max);
+            self.set_slot(
+                slot_id,
+                Some(ItemStack::new(stack.item, moved, stack.nbt.clone())),
+            );
+            remaining -= moved;
+        }
+
+        (remaining > 0).then(|| ItemStack::new(stack.item, remaining, stack.nbt))
+    }
+
+    /// Removes up to `amount` items of the given kind from the general slots.
+    ///
+    /// Returns the number of items that were removed.
+    fn remove_item(&mut self, item: ItemKind, amount: usize) -> usize {
+        let mut removed = 0;
+
+        for slot_id in self.general_slots() {
+            if removed == amount {
+                break;
+            }
+            let Some(stack) = self.slot(slot_id) else {
+                continue;
+            };
+            if stack.item != item {
+                continue;
+            }
+            let taken = (amount - removed).min(stack.count() as usize);
+            let slot = if taken == stack.count() as usize {
+                None
+            } else {
+                let mut stack = stack.clone();
+                stack.set_count(stack.count() - taken as u8);
+                Some(stack)
+            };
+            self.set_slot(slot_id, slot);
+            removed += taken;
+        }
+
+        removed
+    }
+
+    /// Merges and sorts the stacks in the general slots by item kind,
+    /// leaving empty slots at the end.
+    fn sort(&mut self) {
+        let mut stacks: Vec<ItemStack> = Vec::new();
+
+        for slot_id in self.general_slots() {
+            let Some(mut stack) = self.slot(slot_id).cloned() else {
+                continue;
+            };
+            let max = stack.item.max_stack();
+            let mut merged = false;
+            for existing in stacks.iter_mut() {
+                if existing.item != stack.item || existing.nbt != stack.nbt {
+                    continue;
+                }
+                let moved = stack.count().min(max.saturating_sub(existing.count()));
+                existing.set_count(existing.count() + moved);
+                if moved == stack.count() {
+                    merged = true;
+                    break;
+                }
+                stack.set_count(stack.count() - moved);
+            }
+            if !merged {
+                stacks.push(stack);
+            }
+        }
+
+        // The sort is stable, so stacks with equal keys keep their relative order.
+        stacks.sort_by_key(|s| (s.item.to_raw(), u8::MAX - s.count()));
+
+        let mut stacks = stacks.into_iter();
+        for slot_id in self.general_slots() {
+            let stack = stacks.next();
+            if self.slot(slot_id) != stack.as_ref() {
+                self.set_slot(slot_id, stack);
+            }
+        }
+    }
+
+    /// Starts a transaction on this inventory. Changes made through the
+    /// transaction are only applied once it is committed.
+    ///
+    /// See [`Transaction`] for more information.
+    fn transaction(&mut self) -> Transaction<'_, Self>
+    where
+        Self: Sized,
+    {
+        Transaction::new(self)
+    }
+
     /// Decreases the count for stack in the slot by amount. If there is not
     /// enough items in the stack to perform the operation, then it will fail.
     ///
@@ -56,6 +221,61 @@ pub trait Inventory {
     }
 }
 
+/// A view into a single slot of an inventory, obtained from
+/// [`Inventory::entry`].
+pub struct SlotEntry<'a, I: Inventory> {
+    inv: &'a mut I,
+    slot_id: SlotId,
+}
+
+impl<'a, I: Inventory> SlotEntry<'a, I> {
+    pub fn slot_id(&self) -> SlotId {
+        self.slot_id
+    }
+
+    /// Gets the contents of the slot.
+    pub fn get(&self) -> Option<&ItemStack> {
+        self.inv.slot(self.slot_id)
+    }
+
+    /// Returns `true` if the slot is empty.
+    pub fn is_empty(&self) -> bool {
+        self.get().is_none()
+    }
+
+    /// Sets the contents of the slot, returning the previous contents.
+    pub fn set(&mut self, slot: Option<ItemStack>) -> Option<ItemStack> {
+        self.inv.set_slot(self.slot_id, slot)
+    }
+
+    /// Empties the slot, returning the previous contents.
+    pub fn take(&mut self) -> Option<ItemStack> {
+        if self.is_empty() {
+            None
+        } else {
+            self.set(None)
+        }
+    }
+
+    /// Sets the slot to `stack` if it is empty.
+    pub fn or_insert(mut self, stack: ItemStack) -> Self {
+        if self.is_empty() {
+            self.set(Some(stack));
+        }
+        self
+    }
+
+    /// Modifies the stack in the slot if it is occupied. The slot is emptied
+    /// if the closure returns `false`.
+    pub fn and_modify(mut self, f: impl FnOnce(&mut ItemStack) -> bool) -> Self {
+        if let Some(mut stack) = self.get().cloned() {
+            let keep = f(&mut stack);
+            self.set(keep.then_some(stack));
+        }
+        self
+    }
+}
+
 pub(crate) trait InventoryDirtyable {
     fn mark_dirty(&mut self, dirty: bool);
     fn is_dirty(&self) -> bool;
@@ -95,6 +315,10 @@ impl PlayerInventory {
 }
 
 impl Inventory for PlayerInventory {
+    fn general_slots(&self) -> Range<SlotId> {
+        Self::GENERAL_SLOTS
+    }
+
     fn slot(&self, slot_id: SlotId) -> Option<&ItemStack> {
         if !self.slot_range().contains(&slot_id) {
             return None;
@@ -131,11 +355,11 @@ pub struct ConfigurableInventory {
     /// The slots that the player can place items into for crafting. The
     /// crafting result slot is always zero, and should not be included in this
     /// range.
-    #[allow(dead_code)] // TODO: implement crafting
     crafting_slots: Option<Range<SlotId>>,
     /// The type of window that should be used to display this inventory.
     pub window_type: VarInt,
-    dirty: bool,
+    /// The slots that were modified this tick.
+    modified_slots: Vec<SlotId>,
 }
 
 impl ConfigurableInventory {
@@ -144,9 +368,14 @@ impl ConfigurableInventory {
             slots: vec![None; size],
             crafting_slots,
             window_type,
-            dirty: false,
+            modified_slots: Vec::new(),
         }
     }
+
+    /// Returns the slots that were modified this tick.
+    pub(crate) fn modified_slots(&self) -> &[SlotId] {
+        &self.modified_slots
+    }
 }
 
 impl Inventory for ConfigurableInventory {
@@ -161,7 +390,9 @@ impl Inventory for ConfigurableInventory {
         if !self.slot_range().contains(&slot_id) {
             return None;
         }
-        self.mark_dirty(true);
+        if !self.modified_slots.contains(&slot_id) {
+            self.modified_slots.push(slot_id);
+        }
         std::mem::replace(&mut self.slots[slot_id as usize], slot)
     }
 
@@ -172,11 +403,14 @@ impl Inventory for ConfigurableInventory {
 
 impl InventoryDirtyable for ConfigurableInventory {
     fn mark_dirty(&mut self, dirty: bool) {
-        self.dirty = dirty
+        self.modified_slots.clear();
+        if dirty {
+            self.modified_slots.extend(self.slot_range());
+        }
     }
 
     fn is_dirty(&self) -> bool {
-        self.dirty
+        !self.modified_slots.is_empty()
     }
 }
 /// Represents what the player sees when they open an object's Inventory.
@@ -187,6 +421,11 @@ impl InventoryDirtyable for ConfigurableInventory {
 pub struct WindowInventory {
     pub window_id: u8,
     pub object_inventory: InventoryId,
+    /// The last state ID sent to the client for this window.
+    pub(crate) state_id: i32,
+    /// Slot changes made by the client this tick which it has already
+    /// predicted, and so do not need to be sent back.
+    pub(crate) predicted_slots: Vec<(SlotId, Option<ItemStack>)>,
 }
 
 impl WindowInventory {
@@ -194,6 +433,70 @@ impl WindowInventory {
         WindowInventory {
             window_id: window_id.into(),
             object_inventory,
+            state_id: 0,
+            predicted_slots: Vec::new(),
+        }
+    }
+
+    /// Increments the state ID of this window and returns the new value.
+    pub(crate) fn next_state_id(&mut self) -> VarInt {
+        self.state_id = self.state_id.wrapping_add(1);
+        VarInt(self.state_id)
+    }
+
+    /// Returns the packets which show the slots of `obj_inventory` modified
+    /// this tick to the client viewing this window. Slots the client has
+    /// already predicted correctly are skipped.
+    ///
+    /// If more than half of the slots were modified, the entire window is
+    /// resent instead of the individual slots.
+    pub(crate) fn sync(
+        &mut self,
+        obj_inventory: &ConfigurableInventory,
+        player_inventory: &PlayerInventory,
+        carried_item: &Option<ItemStack>,
+    ) -> Vec<WindowUpdate> {
+        let predicted = mem::take(&mut self.predicted_slots);
+
+        let modified: Vec<_> = obj_inventory
+            .modified_slots()
+            .iter()
+            .copied()
+            .filter(|&slot_id| {
+                !predicted
+                    .iter()
+                    .any(|(s, stack)| *s == slot_id && stack.as_ref() == obj_inventory.slot(*s))
+            })
+            .collect();
+
+        if modified.len() > obj_inventory.slot_count() / 2 {
+            let slots = self
+                .slots(obj_inventory, player_inventory)
+                .into_iter()
+                // FIXME: cloning is necessary here to build the packet.
+                // However, it should be possible to avoid the clone if this packet
+                // could consume refs
+                .map(|s| s.cloned())
+                .collect();
+
+            vec![WindowUpdate::Contents(SetContainerContent {
+                window_id: self.window_id,
+                state_id: self.next_state_id(),
+                slots,
+                carried_item: carried_item.clone(),
+            })]
+        } else {
+            modified
+                .into_iter()
+                .map(|slot_id| {
+                    WindowUpdate::Slot(SetContainerSlot {
+                        window_id: self.window_id as i8,
+                        state_id: self.next_state_id(),
+                        slot_idx: slot_id,
+                        slot_data: obj_inventory.slot(slot_id).cloned(),
+                    })
+                })
+                .collect()
         }
     }
 
@@ -215,18 +518,31 @@ impl WindowInventory {
     }
 }
 
+/// A packet updating the contents of a window on the client.
+#[derive(Clone, Debug)]
+pub(crate) enum WindowUpdate {
+    Contents(SetContainerContent),
+    Slot(SetContainerSlot),
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
 pub struct InventoryId(Key);
 
 /// Manages all inventories that are present in the server.
+///
+/// Modifications to an inventory are automatically sent to every client
+/// viewing it.
 pub struct Inventories {
     slab: VersionedSlab<ConfigurableInventory>,
+    /// The clients with a window open for each inventory.
+    viewers: HashMap<InventoryId, Vec<ClientId>>,
 }
 
 impl Inventories {
     pub(crate) fn new() -> Self {
         Self {
             slab: VersionedSlab::new(),
+            viewers: HashMap::new(),
         }
     }
 
@@ -240,7 +556,10 @@ impl Inventories {
     }
 
     /// Removes an inventory from the server.
+    ///
+    /// Clients viewing the inventory will have their window closed.
     pub fn remove(&mut self, inv: InventoryId) -> Option<ConfigurableInventory> {
+        self.viewers.remove(&inv);
         self.slab.remove(inv.0)
     }
 
@@ -262,11 +581,37 @@ impl Inventories {
         self.slab.get_mut(inv.0)
     }
 
-    pub(crate) fn update(&mut self) {
+    /// Returns the clients which have a window open displaying the given
+    /// inventory.
+    ///
+    /// The viewers are collected at the end of every tick, so clients which
+    /// opened or closed the inventory during the current tick are not
+    /// reflected until the next one.
+    pub fn viewers(&self, inv: InventoryId) -> &[ClientId] {
+        self.viewers
+            .get(&inv)
+            .map(|v| v.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn update<C: Config>(&mut self, clients: &Clients<C>) {
         // now that we have synced all the dirty inventories, mark them as clean
         for (_, inv) in self.slab.iter_mut() {
             inv.mark_dirty(false);
         }
+
+        self.viewers.clear();
+
+        for (id, client) in clients.iter() {
+            if let Some(window) = &client.open_inventory {
+                if self.get(window.object_inventory).is_some() {
+                    self.viewers
+                        .entry(window.object_inventory)
+                        .or_default()
+                        .push(id);
+                }
+            }
+        }
     }
 }
 
@@ -302,4 +647,193 @@ mod test {
         assert!(matches!(inv.consume(slot_id, 10), Ok(_)));
         assert_eq!(inv.slot(slot_id), None);
     }
+
+    /// Creates a chest and the windows of two clients viewing it.
+    fn shared_chest() -> (ConfigurableInventory, [WindowInventory; 2]) {
+        let mut inventories = Inventories::new();
+        let (id, _) = inventories.insert(ConfigurableInventory::new(27, VarInt(2), None));
+
+        let chest = inventories.remove(id).unwrap();
+        let windows = [WindowInventory::new(1, id), WindowInventory::new(1, id)];
+
+        (chest, windows)
+    }
+
+    #[test]
+    fn viewers_receive_modified_slot() {
+        let (mut chest, mut windows) = shared_chest();
+        let player = PlayerInventory::new();
+
+        let stack = ItemStack::new(ItemKind::Diamond, 3, None);
+        chest.set_slot(4, Some(stack.clone()));
+
+        for window in &mut windows {
+            match &window.sync(&chest, &player, &None)[..] {
+                [WindowUpdate::Slot(pkt)] => {
+                    assert_eq!(pkt.window_id, 1);
+                    assert_eq!(pkt.slot_idx, 4);
+                    assert_eq!(pkt.slot_data, Some(stack.clone()));
+                }
+                updates => panic!("unexpected updates: {updates:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn many_modified_slots_resend_contents() {
+        let (mut chest, mut windows) = shared_chest();
+        let player = PlayerInventory::new();
+
+        for slot in 0..20 {
+            chest.set_slot(slot, Some(ItemStack::new(ItemKind::Stone, 1, None)));
+        }
+
+        for window in &mut windows {
+            match &window.sync(&chest, &player, &None)[..] {
+                [WindowUpdate::Contents(pkt)] => {
+                    assert_eq!(pkt.window_id, 1);
+                    assert_eq!(pkt.slots.len(), 27 + PlayerInventory::GENERAL_SLOTS.len());
+                    assert_eq!(pkt.slots.iter().flatten().count(), 20);
+                }
+                updates => panic!("unexpected updates: {updates:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn state_ids_increase() {
+        let (mut chest, [mut window, _]) = shared_chest();
+        let player = PlayerInventory::new();
+
+        let mut state_ids = vec![];
+
+        for slot in [0, 1, 2] {
+            chest.set_slot(slot, Some(ItemStack::new(ItemKind::Stick, 1, None)));
+
+            for update in window.sync(&chest, &player, &None) {
+                state_ids.push(match update {
+                    WindowUpdate::Contents(pkt) => pkt.state_id.0,
+                    WindowUpdate::Slot(pkt) => pkt.state_id.0,
+                });
+            }
+
+            // Done by `Inventories::update` at the end of every tick.
+            chest.mark_dirty(false);
+        }
+
+        assert_eq!(state_ids, [1, 2, 3]);
+        assert_eq!(window.state_id, 3);
+    }
+
+    #[test]
+    fn predicted_slots_are_skipped() {
+        let (mut chest, [mut predicting, mut other]) = shared_chest();
+        let player = PlayerInventory::new();
+
+        let stack = Some(ItemStack::new(ItemKind::Apple, 1, None));
+        chest.set_slot(0, stack.clone());
+        predicting.predicted_slots.push((0, stack));
+
+        assert!(predicting.sync(&chest, &player, &None).is_empty());
+        assert_eq!(other.sync(&chest, &player, &None).len(), 1);
+
+        // Predictions only apply to the tick they were made in.
+        assert!(predicting.predicted_slots.is_empty());
+    }
+
+    #[test]
+    fn test_insert_stack_merges() {
+        let mut inv = ConfigurableInventory::new(3, VarInt(0), None);
+        inv.set_slot(1, Some(ItemStack::new(ItemKind::Bone, 60, None)));
+        let rest = inv.insert_stack(ItemStack::new(ItemKind::Bone, 100, None));
+        assert_eq!(inv.slot(1).unwrap().count(), 64);
+        assert_eq!(inv.slot(0).unwrap().count(), 64);
+        assert_eq!(inv.slot(2).unwrap().count(), 32);
+        assert_eq!(rest, None);
+
+        let rest = inv.insert_stack(ItemStack::new(ItemKind::Bone, 40, None));
+        assert_eq!(rest.unwrap().count(), 8);
+        assert_eq!(inv.count_item(ItemKind::Bone), 192);
+        assert_eq!(inv.first_empty_slot(), None);
+    }
+
+    #[test]
+    fn test_insert_stack_player_general_slots() {
+        let mut inv = PlayerInventory::new();
+        inv.insert_stack(ItemStack::new(ItemKind::EnderPearl, 20, None));
+        assert_eq!(inv.slot(9).unwrap().count(), 16);
+        assert_eq!(inv.slot(10).unwrap().count(), 4);
+        assert_eq!(inv.first_empty_slot(), Some(11));
+    }
+
+    #[test]
+    fn test_remove_item() {
+        let mut inv = PlayerInventory::new();
+        inv.set_slot(9, Some(ItemStack::new(ItemKind::Bone, 5, None)));
+        inv.set_slot(12, Some(ItemStack::new(ItemKind::Stick, 5, None)));
+        inv.set_slot(20, Some(ItemStack::new(ItemKind::Bone, 10, None)));
+        assert_eq!(inv.remove_item(ItemKind::Bone, 8), 8);
+        assert_eq!(inv.slot(9), None);
+        assert_eq!(inv.slot(20).unwrap().count(), 7);
+        assert_eq!(inv.remove_item(ItemKind::Bone, 100), 7);
+        assert_eq!(inv.count_item(ItemKind::Bone), 0);
+        assert_eq!(inv.count_item(ItemKind::Stick), 5);
+    }
+
+    #[test]
+    fn test_entry() {
+        let mut inv = ConfigurableInventory::new(2, VarInt(0), None);
+        inv.entry(0)
+            .or_insert(ItemStack::new(ItemKind::Bone, 1, None));
+        inv.entry(0)
+            .or_insert(ItemStack::new(ItemKind::Stick, 1, None))
+            .and_modify(|s| {
+                s.set_count(s.count() + 1);
+                true
+            });
+        assert_eq!(inv.slot(0), Some(&ItemStack::new(ItemKind::Bone, 2, None)));
+        inv.entry(0).and_modify(|_| false);
+        assert_eq!(inv.slot(0), None);
+    }
+
+    #[test]
+    fn test_transaction() {
+        let mut inv = ConfigurableInventory::new(2, VarInt(0), None);
+        inv.set_slot(0, Some(ItemStack::new(ItemKind::Bone, 3, None)));
+        inv.mark_dirty(false);
+
+        let mut tx = inv.transaction();
+        assert_eq!(tx.remove_item(ItemKind::Bone, 3), 3);
+        assert_eq!(tx.count_item(ItemKind::Bone), 0);
+        tx.abort();
+        assert_eq!(inv.count_item(ItemKind::Bone), 3);
+        assert!(!inv.is_dirty());
+
+        let mut tx = inv.transaction();
+        tx.remove_item(ItemKind::Bone, 1);
+        tx.insert_stack(ItemStack::new(ItemKind::Stick, 1, None));
+        tx.commit();
+        assert_eq!(inv.count_item(ItemKind::Bone), 2);
+        assert_eq!(inv.slot(1), Some(&ItemStack::new(ItemKind::Stick, 1, None)));
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut inv = ConfigurableInventory::new(5, VarInt(0), None);
+        inv.set_slot(0, Some(ItemStack::new(ItemKind::Stick, 10, None)));
+        inv.set_slot(2, Some(ItemStack::new(ItemKind::Bone, 50, None)));
+        inv.set_slot(3, Some(ItemStack::new(ItemKind::Stick, 5, None)));
+        inv.set_slot(4, Some(ItemStack::new(ItemKind::Bone, 50, None)));
+        inv.sort();
+
+        let stacks: Vec<_> = inv.slots().into_iter().flatten().cloned().collect();
+        assert_eq!(stacks.len(), 3);
+        assert!(stacks
+            .windows(2)
+            .all(|w| w[0].item.to_raw() <= w[1].item.to_raw()));
+        assert_eq!(inv.slot(3), None);
+        assert_eq!(inv.slot(4), None);
+        assert_eq!(inv.count_item(ItemKind::Bone), 100);
+        assert_eq!(inv.count_item(ItemKind::Stick), 15);
+    }
 }
@@ -0,0 +1,1226 @@
+use std::mem;
+use std::ops::Range;
+
+use valence_protocol::types::ClickContainerMode;
+use valence_protocol::{AttributeSlot, ItemStack, VarInt};
+
+use super::{ConfigurableInventory, Inventory, PlayerInventory, SlotId};
+use crate::recipe::{Ingredient, Recipes};
+
+/// The slot ID clients use to refer to the area outside of a window.
+const OUTSIDE_SLOT: SlotId = -999;
+
+/// The hotbar button used to swap with the offhand slot.
+const OFFHAND_BUTTON: i8 = 40;
+
+/// The slot of the offhand in the player's inventory.
+const OFFHAND_SLOT: SlotId = 45;
+
+/// The crafting grid of the player's inventory.
+const PLAYER_CRAFTING_SLOTS: Range<SlotId> = 1..5;
+
+/// The armor slots of the player's inventory, from head to feet.
+const ARMOR_SLOTS: Range<SlotId> = 5..9;
+
+/// Returns the armor slot of the player's inventory which items worn in
+/// `equipment` go into.
+fn armor_slot(equipment: AttributeSlot) -> Option<SlotId> {
+    match equipment {
+        AttributeSlot::Head => Some(ARMOR_SLOTS.start),
+        AttributeSlot::Chest => Some(ARMOR_SLOTS.start + 1),
+        AttributeSlot::Legs => Some(ARMOR_SLOTS.start + 2),
+        AttributeSlot::Feet => Some(ARMOR_SLOTS.start + 3),
+        AttributeSlot::MainHand | AttributeSlot::OffHand => None,
+    }
+}
+
+/// Returns the slot holding the result of windows of the given type which
+/// don't have a crafting grid, such as the result slot of a furnace.
+fn result_slot(window_type: VarInt) -> Option<SlotId> {
+    match window_type.0 {
+        // Anvil, blast furnace, furnace, grindstone, merchant, smithing table,
+        // smoker and cartography table.
+        7 | 9 | 13 | 14 | 18 | 20 | 21 | 22 => Some(2),
+        // Loom
+        17 => Some(3),
+        // Stonecutter
+        23 => Some(1),
+        _ => None,
+    }
+}
+
+/// All of the slots a client can see and click in a window.
+///
+/// When an object inventory is open, its slots come first, followed by the
+/// [general slots] of the player's inventory. Otherwise, the window is the
+/// player's entire inventory.
+///
+/// [general slots]: PlayerInventory::GENERAL_SLOTS
+pub(crate) struct Window<'a> {
+    pub(crate) object: Option<&'a mut ConfigurableInventory>,
+    pub(crate) player: &'a mut PlayerInventory,
+}
+
+impl<'a> Window<'a> {
+    pub(crate) fn new(
+        object: Option<&'a mut ConfigurableInventory>,
+        player: &'a mut PlayerInventory,
+    ) -> Self {
+        Self { object, player }
+    }
+
+    /// Converts a window slot to a slot in the player's inventory, if the slot
+    /// belongs to the player.
+    fn window_to_player_slot(&self, slot_id: SlotId) -> Option<SlotId> {
+        if !self.slot_range().contains(&slot_id) {
+            return None;
+        }
+
+        match &self.object {
+            Some(obj) => {
+                let obj_count = obj.slot_count() as SlotId;
+                (slot_id >= obj_count)
+                    .then(|| slot_id - obj_count + PlayerInventory::GENERAL_SLOTS.start)
+            }
+            None => Some(slot_id),
+        }
+    }
+
+    /// Converts a slot in the player's inventory to a window slot, if the slot
+    /// is visible in this window.
+    fn player_to_window_slot(&self, slot_id: SlotId) -> Option<SlotId> {
+        match &self.object {
+            Some(obj) => PlayerInventory::GENERAL_SLOTS.contains(&slot_id).then(|| {
+                slot_id - PlayerInventory::GENERAL_SLOTS.start + obj.slot_count() as SlotId
+            }),
+            None => Some(slot_id),
+        }
+    }
+
+    /// Returns `true` if items can only be taken out of the slot, such as
+    /// crafting results.
+    fn is_output_slot(&self, slot_id: SlotId) -> bool {
+        match &self.object {
+            Some(obj) if obj.crafting_slots.is_some() => slot_id == 0,
+            Some(obj) => result_slot(obj.window_type) == Some(slot_id),
+            None => slot_id == 0,
+        }
+    }
+
+    /// Returns `true` if the stack is allowed to be put in the slot.
+    ///
+    /// Output slots don't accept anything, and the armor slots of the player's
+    /// inventory only accept items worn in that slot.
+    fn may_place(&self, slot_id: SlotId, stack: &ItemStack) -> bool {
+        if self.is_output_slot(slot_id) {
+            return false;
+        }
+
+        match &self.object {
+            Some(_) => true,
+            None if ARMOR_SLOTS.contains(&slot_id) => {
+                armor_slot(stack.item.equipment_slot()) == Some(slot_id)
+            }
+            None => true,
+        }
+    }
+
+    /// Returns the largest number of items like `stack` which the slot can
+    /// hold. Armor slots hold a single item.
+    fn max_stack(&self, slot_id: SlotId, stack: &ItemStack) -> u8 {
+        if self.object.is_none() && ARMOR_SLOTS.contains(&slot_id) {
+            1
+        } else {
+            stack.item.max_stack()
+        }
+    }
+
+    /// Returns the slots of the crafting grid in this window and the width of
+    /// the grid, if there is one. The output of the grid is always slot zero.
+    pub(crate) fn crafting_grid(&self) -> Option<(Range<SlotId>, usize)> {
+        let slots = match &self.object {
+            Some(obj) => obj.crafting_slots.clone()?,
+            None => PLAYER_CRAFTING_SLOTS,
+        };
+
+        // Crafting grids are square.
+        let width = (1..).find(|w| w * w >= slots.len()).unwrap();
+
+        Some((slots, width))
+    }
+
+    /// Returns the range of window slots that a shift-clicked stack from
+    /// `slot_id` is moved into, and whether the range is filled in reverse.
+    fn quick_move_target(&self, slot_id: SlotId, stack: &ItemStack) -> (Range<SlotId>, bool) {
+        let main = PlayerInventory::GENERAL_SLOTS.start..PlayerInventory::HOTBAR_SLOTS.start;
+        let hotbar = PlayerInventory::HOTBAR_SLOTS;
+
+        let to_window = |r: Range<SlotId>| {
+            self.player_to_window_slot(r.start).unwrap()
+                ..self.player_to_window_slot(r.end - 1).unwrap() + 1
+        };
+
+        let player_slot = self.window_to_player_slot(slot_id);
+
+        match &self.object {
+            Some(obj) => match player_slot {
+                // Object slots go into the player's inventory, starting from the end of the
+                // hotbar.
+                None => (to_window(PlayerInventory::GENERAL_SLOTS), true),
+                // With a crafting grid, stacks move between the main inventory and hotbar.
+                Some(s) if obj.crafting_slots.is_some() => {
+                    if main.contains(&s) {
+                        (to_window(hotbar), false)
+                    } else {
+                        (to_window(main), false)
+                    }
+                }
+                Some(_) => (0..obj.slot_count() as SlotId, false),
+            },
+            None => {
+                // Armor and shields in the main inventory or hotbar are equipped if their slot
+                // is empty.
+                let equip_slot = match stack.item.equipment_slot() {
+                    AttributeSlot::OffHand => Some(OFFHAND_SLOT),
+                    equipment => armor_slot(equipment),
+                };
+
+                match equip_slot {
+                    Some(s)
+                        if PlayerInventory::GENERAL_SLOTS.contains(&slot_id)
+                            && self.slot(s).is_none() =>
+                    {
+                        (s..s + 1, false)
+                    }
+                    _ if main.contains(&slot_id) => (hotbar, false),
+                    _ if hotbar.contains(&slot_id) => (main, false),
+                    // Crafting, armor and offhand slots.
+                    _ => (PlayerInventory::GENERAL_SLOTS, slot_id == 0),
+                }
+            }
+        }
+    }
+
+    /// Sets the output slot of the crafting grid to the result of the recipe
+    /// matching the grid, if any.
+    pub(crate) fn update_crafting_output(&mut self, recipes: &Recipes) {
+        let Some((grid, width)) = self.crafting_grid() else {
+            return;
+        };
+
+        let stacks: Vec<_> = grid.map(|s| self.slot(s)).collect();
+        let output = recipes
+            .craft(&stacks, width)
+            .and_then(|(_, recipe)| recipe.result())
+            .cloned();
+
+        if self.slot(0) != output.as_ref() {
+            self.set_slot(0, output);
+        }
+    }
+
+    /// Moves the contents of the crafting grid into the player's inventory and
+    /// fills the grid with ingredients taken from the player's inventory,
+    /// following the layout of a recipe. With `make_all`, as many sets of
+    /// ingredients as possible are placed.
+    ///
+    /// Only stacks without NBT are used as ingredients. Returns `false` and
+    /// leaves the window unchanged if not a single set of ingredients could be
+    /// placed.
+    pub(crate) fn place_recipe(&mut self, layout: &[(usize, &Ingredient)], make_all: bool) -> bool {
+        let Some((grid, _)) = self.crafting_grid() else {
+            return false;
+        };
+
+        if layout.is_empty() {
+            return false;
+        }
+
+        let mut tx = self.transaction();
+
+        for slot_id in grid.clone() {
+            if let Some(stack) = tx.set_slot(slot_id, None) {
+                if tx.insert_stack(stack).is_some() {
+                    return false;
+                }
+            }
+        }
+
+        let mut sets = 0;
+
+        while sets == 0 || make_all {
+            let mut set_tx = tx.transaction();
+
+            let complete = layout.iter().all(|&(i, ingredient)| {
+                let grid_slot = grid.start + i as SlotId;
+                let existing = set_tx.slot(grid_slot).cloned();
+
+                let source = set_tx.general_slots().find(|&s| {
+                    set_tx.slot(s).is_some_and(|stack| {
+                        stack.nbt.is_none()
+                            && ingredient.items.contains(&stack.item)
+                            && existing.as_ref().is_none_or(|e| {
+                                e.item == stack.item && e.count() < e.item.max_stack()
+                            })
+                    })
+                });
+
+                let Some(source) = source else {
+                    return false;
+                };
+
+                let item = set_tx.slot(source).unwrap().item;
+                let _ = set_tx.consume(source, 1u8);
+
+                let count = existing.map_or(0, |e| e.count());
+                set_tx.set_slot(grid_slot, Some(ItemStack::new(item, count + 1, None)));
+                true
+            });
+
+            if !complete {
+                break;
+            }
+
+            set_tx.commit();
+            sets += 1;
+        }
+
+        if sets == 0 {
+            return false;
+        }
+
+        tx.commit();
+        true
+    }
+
+    fn take_slot(&mut self, slot_id: SlotId) -> Option<ItemStack> {
+        if self.slot(slot_id).is_some() {
+            self.set_slot(slot_id, None)
+        } else {
+            None
+        }
+    }
+}
+
+impl Inventory for Window<'_> {
+    fn slot(&self, slot_id: SlotId) -> Option<&ItemStack> {
+        match self.window_to_player_slot(slot_id) {
+            Some(s) => self.player.slot(s),
+            None => self.object.as_ref()?.slot(slot_id),
+        }
+    }
+
+    fn set_slot(&mut self, slot_id: SlotId, slot: Option<ItemStack>) -> Option<ItemStack> {
+        match self.window_to_player_slot(slot_id) {
+            Some(s) => self.player.set_slot(s, slot),
+            None => self.object.as_mut()?.set_slot(slot_id, slot),
+        }
+    }
+
+    fn slot_range(&self) -> Range<SlotId> {
+        match &self.object {
+            Some(obj) => 0..(obj.slot_count() + PlayerInventory::GENERAL_SLOTS.len()) as SlotId,
+            None => self.player.slot_range(),
+        }
+    }
+
+    /// The general slots of the player's inventory.
+    fn general_slots(&self) -> Range<SlotId> {
+        let general = PlayerInventory::GENERAL_SLOTS;
+        self.player_to_window_slot(general.start).unwrap()
+            ..self.player_to_window_slot(general.end - 1).unwrap() + 1
+    }
+}
+
+/// The state of an in-progress drag, which spans multiple click packets.
+#[derive(Clone, Debug)]
+pub(crate) struct DragState {
+    kind: DragKind,
+    slots: Vec<SlotId>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum DragKind {
+    /// Splits the cursor evenly between the slots.
+    Left,
+    /// Places one item from the cursor in each slot.
+    Right,
+    /// Places a full stack in each slot. Only available in creative mode.
+    Middle,
+}
+
+/// Simulates the vanilla behavior of a single click in a window.
+pub(crate) struct ClickSimulation<'a, 'b> {
+    pub(crate) window: &'a mut Window<'b>,
+    pub(crate) cursor: &'a mut Option<ItemStack>,
+    pub(crate) drag: &'a mut Option<DragState>,
+    pub(crate) creative: bool,
+    /// The recipes used to compute the output of the crafting grid.
+    pub(crate) recipes: &'a Recipes,
+    /// Stacks that were thrown out of the window.
+    pub(crate) dropped: Vec<ItemStack>,
+}
+
+impl ClickSimulation<'_, '_> {
+    pub(crate) fn click(&mut self, slot_id: SlotId, button: i8, mode: ClickContainerMode) {
+        // Any click which isn't part of a drag cancels the drag.
+        if mode != ClickContainerMode::Drag {
+            *self.drag = None;
+        }
+
+        if slot_id != OUTSIDE_SLOT && !self.window.slot_range().contains(&slot_id) {
+            return;
+        }
+
+        match mode {
+            ClickContainerMode::Click => self.pickup(slot_id, button),
+            ClickContainerMode::ShiftClick => self.quick_move(slot_id),
+            ClickContainerMode::Hotbar => self.swap(slot_id, button),
+            ClickContainerMode::CreativeMiddleClick => self.clone_stack(slot_id),
+            ClickContainerMode::DropKey => self.throw(slot_id, button),
+            ClickContainerMode::Drag => self.drag(slot_id, button),
+            ClickContainerMode::DoubleClick => self.pickup_all(slot_id, button),
+        }
+
+        self.update_crafting_output();
+    }
+
+    fn update_crafting_output(&mut self) {
+        self.window.update_crafting_output(self.recipes);
+    }
+
+    /// Removes one item from every occupied slot of the crafting grid. Called
+    /// after the output of the grid is taken.
+    ///
+    /// Ingredients with a [recipe remainder] leave it in their slot. If the
+    /// slot is still occupied, the remainder goes into the player's
+    /// inventory or is dropped.
+    ///
+    /// [recipe remainder]: valence_protocol::ItemKind::recipe_remainder
+    fn consume_crafting_inputs(&mut self) {
+        let Some((grid, _)) = self.window.crafting_grid() else {
+            return;
+        };
+
+        for slot_id in grid {
+            let mut slot = self.window.slot(slot_id).cloned();
+            let Some(taken) = take(&mut slot, 1) else {
+                continue;
+            };
+
+            let Some(kind) = taken.item.recipe_remainder() else {
+                self.window.set_slot(slot_id, slot);
+                continue;
+            };
+
+            let remainder = ItemStack::new(kind, 1, None);
+
+            let leftover = if slot.as_ref().is_none_or(|s| can_stack(s, &remainder)) {
+                merge(&mut slot, Some(remainder), kind.max_stack())
+            } else {
+                Some(remainder)
+            };
+
+            self.window.set_slot(slot_id, slot);
+
+            let general = self.window.general_slots();
+            if let Some(stack) = self.insert(leftover, general, false) {
+                self.dropped.push(stack);
+            }
+        }
+    }
+
+    fn pickup(&mut self, slot_id: SlotId, button: i8) {
+        if slot_id == OUTSIDE_SLOT {
+            let amount = if button == 0 { u8::MAX } else { 1 };
+            if let Some(stack) = take(self.cursor, amount) {
+                self.dropped.push(stack);
+            }
+            return;
+        }
+
+        if self.window.is_output_slot(slot_id) {
+            self.take_output(slot_id);
+            return;
+        }
+
+        let mut slot = self.window.slot(slot_id).cloned();
+
+        match (self.cursor.clone(), slot.clone()) {
+            (None, None) => return,
+            (None, Some(s)) => {
+                let amount = if button == 0 {
+                    s.count()
+                } else {
+                    s.count() - s.count() / 2
+                };
+                *self.cursor = take(&mut slot, amount);
+            }
+            (Some(c), None) => {
+                if !self.window.may_place(slot_id, &c) {
+                    return;
+                }
+                let amount = if button == 0 { c.count() } else { 1 };
+                slot = take(self.cursor, amount.min(self.window.max_stack(slot_id, &c)));
+            }
+            (Some(c), Some(s)) if can_stack(&c, &s) => {
+                if self.window.may_place(slot_id, &c) {
+                    let max = self.window.max_stack(slot_id, &c);
+                    let room = max.saturating_sub(s.count());
+                    let amount = if button == 0 { room } else { room.min(1) };
+                    let moved = take(self.cursor, amount);
+                    merge(&mut slot, moved, u8::MAX);
+                } else {
+                    // The slot's stack is taken instead if the cursor can't be placed.
+                    let room = c.item.max_stack().saturating_sub(c.count());
+                    let moved = take(&mut slot, room);
+                    merge(self.cursor, moved, u8::MAX);
+                }
+            }
+            (Some(c), Some(_)) => {
+                if !self.window.may_place(slot_id, &c)
+                    || c.count() > self.window.max_stack(slot_id, &c)
+                {
+                    return;
+                }
+                mem::swap(self.cursor, &mut slot);
+            }
+        }
+
+        self.window.set_slot(slot_id, slot);
+    }
+
+    /// Takes the stack from an output slot into the cursor if it fits.
+    fn take_output(&mut self, slot_id: SlotId) {
+        let Some(output) = self.window.slot(slot_id) else {
+            return;
+        };
+
+        let fits = match self.cursor.as_ref() {
+            None => true,
+            Some(c) => can_stack(c, output) && c.count() + output.count() <= c.item.max_stack(),
+        };
+
+        if fits {
+            let output = self.window.take_slot(slot_id);
+            merge(self.cursor, output, u8::MAX);
+            self.consume_crafting_inputs();
+        }
+    }
+
+    fn quick_move(&mut self, slot_id: SlotId) {
+        if slot_id == OUTSIDE_SLOT {
+            return;
+        }
+
+        if self.window.is_output_slot(slot_id) {
+            self.quick_craft(slot_id);
+            return;
+        }
+
+        let mut stack = self.window.slot(slot_id).cloned();
+        if stack.is_none() {
+            return;
+        }
+
+        let (target, reverse) = self
+            .window
+            .quick_move_target(slot_id, stack.as_ref().unwrap());
+        stack = self.insert(stack, target, reverse);
+
+        self.window.set_slot(slot_id, stack);
+    }
+
+    /// Repeatedly moves the output into the player's inventory until the
+    /// crafting grid runs out of ingredients or the output no longer fits.
+    fn quick_craft(&mut self, slot_id: SlotId) {
+        while let Some(output) = self.window.slot(slot_id).cloned() {
+            let (target, reverse) = self.window.quick_move_target(slot_id, &output);
+
+            if self.capacity(&output, target.clone()) < output.count() as usize {
+                return;
+            }
+
+            let output = self.window.take_slot(slot_id);
+            self.insert(output, target.clone(), reverse);
+            self.consume_crafting_inputs();
+            self.update_crafting_output();
+        }
+    }
+
+    /// Returns the number of items like `stack` which can be inserted into the
+    /// range of slots.
+    fn capacity(&self, stack: &ItemStack, range: Range<SlotId>) -> usize {
+        range
+            .filter(|&s| self.window.may_place(s, stack))
+            .map(|s| {
+                let max = self.window.max_stack(s, stack) as usize;
+                match self.window.slot(s) {
+                    None => max,
+                    Some(existing) if can_stack(existing, stack) => {
+                        max.saturating_sub(existing.count() as usize)
+                    }
+                    Some(_) => 0,
+                }
+            })
+            .sum()
+    }
+
+    /// Inserts the stack into the range of slots, first merging with matching
+    /// stacks and then filling empty slots. Returns what could not be
+    /// inserted.
+    fn insert(
+        &mut self,
+        mut stack: Option<ItemStack>,
+        range: Range<SlotId>,
+        reverse: bool,
+    ) -> Option<ItemStack> {
+        let slots: Vec<SlotId> = if reverse {
+            range.rev().collect()
+        } else {
+            range.collect()
+        };
+
+        for fill_empty in [false, true] {
+            for &slot_id in &slots {
+                let Some(s) = &stack else {
+                    return None;
+                };
+
+                if !self.window.may_place(slot_id, s) {
+                    continue;
+                }
+
+                let mut slot = self.window.slot(slot_id).cloned();
+
+                let matches = match &slot {
+                    Some(existing) => !fill_empty && can_stack(existing, s),
+                    None => fill_empty,
+                };
+
+                if matches {
+                    let max = self.window.max_stack(slot_id, s);
+                    stack = merge(&mut slot, stack, max);
+                    self.window.set_slot(slot_id, slot);
+                }
+            }
+        }
+
+        stack
+    }
+
+    fn swap(&mut self, slot_id: SlotId, button: i8) {
+        if slot_id == OUTSIDE_SLOT {
+            return;
+        }
+
+        let player_slot = match button {
+            0..=8 => PlayerInventory::HOTBAR_SLOTS.start + button as SlotId,
+            OFFHAND_BUTTON => OFFHAND_SLOT,
+            _ => return,
+        };
+
+        if self.window.window_to_player_slot(slot_id) == Some(player_slot) {
+            return;
+        }
+
+        if self.window.is_output_slot(slot_id) {
+            if self.window.player.slot(player_slot).is_none() {
+                let output = self.window.take_slot(slot_id);
+                self.window.player.set_slot(player_slot, output);
+                self.consume_crafting_inputs();
+            }
+            return;
+        }
+
+        let clicked = self.window.slot(slot_id).cloned();
+        let mut held = self.window.player.slot(player_slot).cloned();
+
+        let Some(h) = &held else {
+            if clicked.is_some() {
+                self.window.set_slot(slot_id, None);
+                self.window.player.set_slot(player_slot, clicked);
+            }
+            return;
+        };
+
+        if !self.window.may_place(slot_id, h) {
+            return;
+        }
+
+        let max = self.window.max_stack(slot_id, h);
+
+        if h.count() > max {
+            // Only part of the held stack fits, so the clicked stack goes elsewhere in the
+            // player's inventory.
+            let placed = take(&mut held, max);
+            self.window.set_slot(slot_id, placed);
+            self.window.player.set_slot(player_slot, held);
+
+            let general = self.window.general_slots();
+            if let Some(stack) = self.insert(clicked, general, false) {
+                self.dropped.push(stack);
+            }
+        } else {
+            self.window.set_slot(slot_id, held);
+            self.window.player.set_slot(player_slot, clicked);
+        }
+    }
+
+    fn clone_stack(&mut self, slot_id: SlotId) {
+        if !self.creative || self.cursor.is_some() {
+            return;
+        }
+
+        if let Some(s) = self.window.slot(slot_id) {
+            *self.cursor = Some(ItemStack::new(s.item, s.item.max_stack(), s.nbt.clone()));
+        }
+    }
+
+    fn throw(&mut self, slot_id: SlotId, button: i8) {
+        if slot_id == OUTSIDE_SLOT || self.cursor.is_some() {
+            return;
+        }
+
+        let mut slot = self.window.slot(slot_id).cloned();
+
+        let amount = if button == 0 && !self.window.is_output_slot(slot_id) {
+            1
+        } else {
+            u8::MAX
+        };
+
+        if let Some(stack) = take(&mut slot, amount) {
+            self.dropped.push(stack);
+            self.window.set_slot(slot_id, slot);
+
+            if self.window.is_output_slot(slot_id) {
+                self.consume_crafting_inputs();
+            }
+        }
+    }
+
+    fn drag(&mut self, slot_id: SlotId, button: i8) {
+        let kind = match button >> 2 {
+            0 => DragKind::Left,
+            1 => DragKind::Right,
+            2 if self.creative => DragKind::Middle,
+            _ => {
+                *self.drag = None;
+                return;
+            }
+        };
+
+        match button & 3 {
+            // Start
+            0 => {
+                *self.drag =
+                    (slot_id == OUTSIDE_SLOT && self.cursor.is_some()).then(|| DragState {
+                        kind,
+                        slots: Vec::new(),
+                    });
+            }
+            // Add slot
+            1 => {
+                let Some(cursor) = self.cursor.as_ref() else {
+                    *self.drag = None;
+                    return;
+                };
+
+                let Some(drag) = self.drag.as_mut().filter(|d| d.kind == kind) else {
+                    *self.drag = None;
+                    return;
+                };
+
+                let compatible = match self.window.slot(slot_id) {
+                    Some(s) => can_stack(s, cursor),
+                    None => true,
+                };
+
+                if compatible
+                    && self.window.may_place(slot_id, cursor)
+                    && !drag.slots.contains(&slot_id)
+                    && (kind == DragKind::Middle || cursor.count() as usize > drag.slots.len())
+                {
+                    drag.slots.push(slot_id);
+                }
+            }
+            // End
+            2 => {
+                let Some(drag) = self.drag.take().filter(|d| d.kind == kind) else {
+                    return;
+                };
+
+                if let [slot_id] = drag.slots[..] {
+                    match kind {
+                        DragKind::Left => self.pickup(slot_id, 0),
+                        DragKind::Right => self.pickup(slot_id, 1),
+                        DragKind::Middle => self.clone_stack(slot_id),
+                    }
+                    return;
+                }
+
+                self.distribute(&drag);
+            }
+            _ => *self.drag = None,
+        }
+    }
+
+    /// Spreads the cursor over the slots of a finished drag.
+    fn distribute(&mut self, drag: &DragState) {
+        let Some(template) = self.cursor.clone() else {
+            return;
+        };
+
+        if drag.slots.is_empty() {
+            return;
+        }
+
+        let per_slot = match drag.kind {
+            DragKind::Left => template.count() / drag.slots.len() as u8,
+            DragKind::Right => 1,
+            DragKind::Middle => template.item.max_stack(),
+        };
+
+        for &slot_id in &drag.slots {
+            let max = self.window.max_stack(slot_id, &template);
+            let mut slot = self.window.slot(slot_id).cloned();
+            let existing = slot.as_ref().map(|s| s.count()).unwrap_or(0);
+            let amount = per_slot.min(max.saturating_sub(existing));
+
+            let moved = if drag.kind == DragKind::Middle {
+                Some(ItemStack::new(template.item, amount, template.nbt.clone()))
+                    .filter(|_| amount > 0)
+            } else {
+                take(self.cursor, amount)
+            };
+
+            if moved.is_some() {
+                merge(&mut slot, moved, max);
+                self.window.set_slot(slot_id, slot);
+            }
+        }
+    }
+
+    fn pickup_all(&mut self, slot_id: SlotId, button: i8) {
+        let Some(cursor) = self.cursor.as_ref() else {
+            return;
+        };
+
+        // The clicked slot must be empty and not an output slot.
+        if slot_id == OUTSIDE_SLOT
+            || self.window.slot(slot_id).is_some()
+            || self.window.is_output_slot(slot_id)
+        {
+            return;
+        }
+
+        let max = cursor.item.max_stack();
+        let mut slots: Vec<SlotId> = self.window.slot_range().collect();
+        if button != 0 {
+            slots.reverse();
+        }
+
+        // Non-full stacks are taken before full ones.
+        for take_full in [false, true] {
+            for &s in &slots {
+                let Some(cursor) = self.cursor.as_ref() else {
+                    return;
+                };
+
+                if cursor.count() >= max {
+                    return;
+                }
+
+                if self.window.is_output_slot(s) {
+                    continue;
+                }
+
+                let mut slot = self.window.slot(s).cloned();
+
+                if let Some(stack) = &slot {
+                    if can_stack(stack, cursor) && (stack.count() >= max) == take_full {
+                        let moved = take(&mut slot, max - cursor.count());
+                        merge(self.cursor, moved, max);
+                        self.window.set_slot(s, slot);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns `true` if the two stacks can be combined into one.
+fn can_stack(a: &ItemStack, b: &ItemStack) -> bool {
+    a.item == b.item && a.nbt == b.nbt
+}
+
+/// Removes up to `amount` items from `stack` and returns them as a new stack.
+fn take(stack: &mut Option<ItemStack>, amount: u8) -> Option<ItemStack> {
+    let s = stack.as_mut()?;
+    let amount = amount.min(s.count());
+
+    if amount == 0 {
+        None
+    } else if amount == s.count() {
+        stack.take()
+    } else {
+        s.set_count(s.count() - amount);
+        Some(ItemStack::new(s.item, amount, s.nbt.clone()))
+    }
+}
+
+/// Adds `src` to `dst` without exceeding `max` items in `dst`. The caller must
+/// ensure the stacks are compatible. Returns the items which did not fit.
+fn merge(dst: &mut Option<ItemStack>, mut src: Option<ItemStack>, max: u8) -> Option<ItemStack> {
+    match dst {
+        None => {
+            let amount = src.as_ref()?.count().min(max);
+            *dst = take(&mut src, amount);
+            src
+        }
+        Some(d) => {
+            let room = max.saturating_sub(d.count());
+            let moved = take(&mut src, room)?;
+            d.set_count(d.count() + moved.count());
+            src
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_protocol::{ident, ItemKind};
+
+    use super::*;
+    use crate::recipe::{Recipe, ShapelessRecipe};
+
+    fn click(
+        inv: &mut PlayerInventory,
+        cursor: &mut Option<ItemStack>,
+        slot_id: SlotId,
+        button: i8,
+        mode: ClickContainerMode,
+    ) -> Vec<ItemStack> {
+        let mut window = Window::new(None, inv);
+        let mut drag = None;
+        let mut sim = ClickSimulation {
+            window: &mut window,
+            cursor,
+            drag: &mut drag,
+            creative: false,
+            recipes: &Recipes::new(),
+            dropped: Vec::new(),
+        };
+        sim.click(slot_id, button, mode);
+        sim.dropped
+    }
+
+    #[test]
+    fn pickup_and_place() {
+        let mut inv = PlayerInventory::new();
+        let mut cursor = None;
+        inv.set_slot(9, Some(ItemStack::new(ItemKind::Diamond, 10, None)));
+
+        click(&mut inv, &mut cursor, 9, 1, ClickContainerMode::Click);
+        assert_eq!(cursor, Some(ItemStack::new(ItemKind::Diamond, 5, None)));
+        assert_eq!(inv.slot(9).unwrap().count(), 5);
+
+        click(&mut inv, &mut cursor, 10, 1, ClickContainerMode::Click);
+        assert_eq!(cursor.as_ref().unwrap().count(), 4);
+        assert_eq!(inv.slot(10).unwrap().count(), 1);
+
+        click(&mut inv, &mut cursor, 9, 0, ClickContainerMode::Click);
+        assert_eq!(cursor, None);
+        assert_eq!(inv.slot(9).unwrap().count(), 9);
+    }
+
+    #[test]
+    fn merge_respects_max_stack() {
+        let mut inv = PlayerInventory::new();
+        let mut cursor = Some(ItemStack::new(ItemKind::EnderPearl, 10, None));
+        inv.set_slot(9, Some(ItemStack::new(ItemKind::EnderPearl, 10, None)));
+
+        click(&mut inv, &mut cursor, 9, 0, ClickContainerMode::Click);
+        assert_eq!(inv.slot(9).unwrap().count(), 16);
+        assert_eq!(cursor.unwrap().count(), 4);
+    }
+
+    #[test]
+    fn quick_move_between_main_and_hotbar() {
+        let mut inv = PlayerInventory::new();
+        let mut cursor = None;
+        inv.set_slot(37, Some(ItemStack::new(ItemKind::Stone, 60, None)));
+        inv.set_slot(9, Some(ItemStack::new(ItemKind::Stone, 10, None)));
+
+        click(&mut inv, &mut cursor, 9, 0, ClickContainerMode::ShiftClick);
+        assert_eq!(inv.slot(37).unwrap().count(), 64);
+        assert_eq!(inv.slot(36).unwrap().count(), 6);
+        assert_eq!(inv.slot(9), None);
+    }
+
+    #[test]
+    fn drag_splits_evenly() {
+        let mut inv = PlayerInventory::new();
+        let mut window = Window::new(None, &mut inv);
+        let mut cursor = Some(ItemStack::new(ItemKind::Dirt, 7, None));
+        let mut drag = None;
+        let mut sim = ClickSimulation {
+            window: &mut window,
+            cursor: &mut cursor,
+            drag: &mut drag,
+            creative: false,
+            recipes: &Recipes::new(),
+            dropped: Vec::new(),
+        };
+
+        sim.click(OUTSIDE_SLOT, 0, ClickContainerMode::Drag);
+        for slot in [9, 10, 11] {
+            sim.click(slot, 1, ClickContainerMode::Drag);
+        }
+        sim.click(OUTSIDE_SLOT, 2, ClickContainerMode::Drag);
+
+        for slot in [9, 10, 11] {
+            assert_eq!(inv.slot(slot).unwrap().count(), 2);
+        }
+        assert_eq!(cursor.unwrap().count(), 1);
+    }
+
+    #[test]
+    fn double_click_gathers() {
+        let mut inv = PlayerInventory::new();
+        let mut cursor = Some(ItemStack::new(ItemKind::Apple, 1, None));
+        inv.set_slot(9, Some(ItemStack::new(ItemKind::Apple, 64, None)));
+        inv.set_slot(20, Some(ItemStack::new(ItemKind::Apple, 3, None)));
+
+        click(&mut inv, &mut cursor, 9, 0, ClickContainerMode::DoubleClick);
+        // The clicked slot isn't empty, so nothing happens.
+        assert_eq!(cursor.as_ref().unwrap().count(), 1);
+
+        click(
+            &mut inv,
+            &mut cursor,
+            10,
+            0,
+            ClickContainerMode::DoubleClick,
+        );
+        assert_eq!(inv.slot(20), None);
+        assert_eq!(inv.slot(9).unwrap().count(), 4);
+        assert_eq!(cursor.unwrap().count(), 64);
+    }
+
+    #[test]
+    fn throw_from_slot() {
+        let mut inv = PlayerInventory::new();
+        let mut cursor = None;
+        inv.set_slot(9, Some(ItemStack::new(ItemKind::Apple, 3, None)));
+
+        let dropped = click(&mut inv, &mut cursor, 9, 0, ClickContainerMode::DropKey);
+        assert_eq!(dropped, vec![ItemStack::new(ItemKind::Apple, 1, None)]);
+        assert_eq!(inv.slot(9).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn armor_slots_take_matching_equipment() {
+        let mut inv = PlayerInventory::new();
+        let mut cursor = Some(ItemStack::new(ItemKind::IronHelmet, 1, None));
+
+        click(&mut inv, &mut cursor, 6, 0, ClickContainerMode::Click);
+        assert_eq!(inv.slot(6), None);
+
+        click(&mut inv, &mut cursor, 5, 0, ClickContainerMode::Click);
+        assert_eq!(
+            inv.slot(5),
+            Some(&ItemStack::new(ItemKind::IronHelmet, 1, None))
+        );
+        assert_eq!(cursor, None);
+
+        // Armor slots hold a single item.
+        cursor = Some(ItemStack::new(ItemKind::CarvedPumpkin, 5, None));
+        inv.set_slot(5, None);
+        click(&mut inv, &mut cursor, 5, 0, ClickContainerMode::Click);
+        assert_eq!(inv.slot(5).unwrap().count(), 1);
+        assert_eq!(cursor.as_ref().unwrap().count(), 4);
+
+        // Swapping with a stack that doesn't fit is not allowed.
+        inv.set_slot(5, Some(ItemStack::new(ItemKind::IronHelmet, 1, None)));
+        click(&mut inv, &mut cursor, 5, 0, ClickContainerMode::Click);
+        assert_eq!(inv.slot(5).unwrap().item, ItemKind::IronHelmet);
+        assert_eq!(cursor.as_ref().unwrap().count(), 4);
+
+        inv.set_slot(36, Some(ItemStack::new(ItemKind::Stone, 1, None)));
+        click(&mut inv, &mut cursor, 7, 0, ClickContainerMode::Hotbar);
+        assert_eq!(inv.slot(7), None);
+        assert_eq!(inv.slot(36).unwrap().item, ItemKind::Stone);
+    }
+
+    #[test]
+    fn quick_move_equips_armor_and_shields() {
+        let mut inv = PlayerInventory::new();
+        let mut cursor = None;
+        inv.set_slot(9, Some(ItemStack::new(ItemKind::IronBoots, 1, None)));
+        inv.set_slot(10, Some(ItemStack::new(ItemKind::Shield, 1, None)));
+        inv.set_slot(11, Some(ItemStack::new(ItemKind::GoldenBoots, 1, None)));
+
+        click(&mut inv, &mut cursor, 9, 0, ClickContainerMode::ShiftClick);
+        click(&mut inv, &mut cursor, 10, 0, ClickContainerMode::ShiftClick);
+        assert_eq!(inv.slot(8).unwrap().item, ItemKind::IronBoots);
+        assert_eq!(inv.slot(OFFHAND_SLOT).unwrap().item, ItemKind::Shield);
+
+        // The boots slot is taken, so the second pair goes to the hotbar.
+        click(&mut inv, &mut cursor, 11, 0, ClickContainerMode::ShiftClick);
+        assert_eq!(inv.slot(8).unwrap().item, ItemKind::IronBoots);
+        assert_eq!(inv.slot(36).unwrap().item, ItemKind::GoldenBoots);
+    }
+
+    #[test]
+    fn result_slots_are_take_only() {
+        // A furnace, whose result is slot 2.
+        let mut furnace = ConfigurableInventory::new(3, VarInt(13), None);
+        furnace.set_slot(0, Some(ItemStack::new(ItemKind::Dirt, 64, None)));
+        furnace.set_slot(1, Some(ItemStack::new(ItemKind::Coal, 64, None)));
+
+        let mut inv = PlayerInventory::new();
+        inv.set_slot(9, Some(ItemStack::new(ItemKind::Stone, 64, None)));
+
+        let mut window = Window::new(Some(&mut furnace), &mut inv);
+        let mut cursor = Some(ItemStack::new(ItemKind::IronIngot, 1, None));
+        let mut drag = None;
+        let mut sim = ClickSimulation {
+            window: &mut window,
+            cursor: &mut cursor,
+            drag: &mut drag,
+            creative: false,
+            recipes: &Recipes::new(),
+            dropped: Vec::new(),
+        };
+
+        sim.click(2, 0, ClickContainerMode::Click);
+        assert_eq!(sim.window.slot(2), None);
+
+        // Shift clicking doesn't fill the result slot either.
+        sim.click(3, 0, ClickContainerMode::ShiftClick);
+        assert_eq!(sim.window.slot(2), None);
+        assert_eq!(sim.window.slot(3).unwrap().count(), 64);
+
+        sim.window
+            .set_slot(2, Some(ItemStack::new(ItemKind::IronIngot, 5, None)));
+        sim.click(2, 0, ClickContainerMode::Click);
+        assert_eq!(sim.window.slot(2), None);
+        assert_eq!(sim.cursor.as_ref().unwrap().count(), 6);
+    }
+
+    #[test]
+    fn double_click_skips_result_slots() {
+        let mut furnace = ConfigurableInventory::new(3, VarInt(13), None);
+        furnace.set_slot(2, Some(ItemStack::new(ItemKind::IronIngot, 5, None)));
+
+        let mut inv = PlayerInventory::new();
+        inv.set_slot(9, Some(ItemStack::new(ItemKind::IronIngot, 3, None)));
+
+        let mut window = Window::new(Some(&mut furnace), &mut inv);
+        let mut cursor = Some(ItemStack::new(ItemKind::IronIngot, 1, None));
+        let mut drag = None;
+        let mut sim = ClickSimulation {
+            window: &mut window,
+            cursor: &mut cursor,
+            drag: &mut drag,
+            creative: false,
+            recipes: &Recipes::new(),
+            dropped: Vec::new(),
+        };
+
+        // Double clicking the result slot itself does nothing.
+        sim.click(2, 0, ClickContainerMode::DoubleClick);
+        assert_eq!(sim.cursor.as_ref().unwrap().count(), 1);
+
+        sim.click(4, 0, ClickContainerMode::DoubleClick);
+        assert_eq!(sim.cursor.as_ref().unwrap().count(), 4);
+        assert_eq!(sim.window.slot(2).unwrap().count(), 5);
+        assert_eq!(sim.window.slot(3), None);
+    }
+
+    #[test]
+    fn crafting_output() {
+        let mut recipes = Recipes::new();
+        recipes.insert(
+            ident!("oak_planks"),
+            Recipe::Shapeless(ShapelessRecipe {
+                group: String::new(),
+                ingredients: vec![ItemKind::OakLog.into()],
+                result: ItemStack::new(ItemKind::OakPlanks, 4, None),
+            }),
+        );
+
+        let mut inv = PlayerInventory::new();
+        let mut cursor = Some(ItemStack::new(ItemKind::OakLog, 3, None));
+        let mut window = Window::new(None, &mut inv);
+        let mut drag = None;
+        let mut sim = ClickSimulation {
+            window: &mut window,
+            cursor: &mut cursor,
+            drag: &mut drag,
+            creative: false,
+            recipes: &recipes,
+            dropped: Vec::new(),
+        };
+
+        // Place the logs in the crafting grid.
+        sim.click(1, 0, ClickContainerMode::Click);
+        assert_eq!(
+            sim.window.slot(0),
+            Some(&ItemStack::new(ItemKind::OakPlanks, 4, None))
+        );
+
+        // Take one output.
+        sim.click(0, 0, ClickContainerMode::Click);
+        assert_eq!(sim.window.slot(1).unwrap().count(), 2);
+        assert_eq!(sim.cursor.as_ref().unwrap().count(), 4);
+
+        // Craft the rest into the inventory.
+        sim.click(0, 0, ClickContainerMode::ShiftClick);
+        assert_eq!(sim.window.slot(1), None);
+        assert_eq!(sim.window.slot(0), None);
+        assert_eq!(inv.count_item(ItemKind::OakPlanks), 8);
+    }
+
+    #[test]
+    fn crafting_leaves_remainders() {
+        let mut recipes = Recipes::new();
+        recipes.insert(
+            ident!("sweet_milk"),
+            Recipe::Shapeless(ShapelessRecipe {
+                group: String::new(),
+                ingredients: vec![ItemKind::MilkBucket.into(), ItemKind::Sugar.into()],
+                result: ItemStack::new(ItemKind::Cake, 1, None),
+            }),
+        );
+
+        let mut inv = PlayerInventory::new();
+        inv.set_slot(1, Some(ItemStack::new(ItemKind::MilkBucket, 1, None)));
+        inv.set_slot(2, Some(ItemStack::new(ItemKind::Sugar, 2, None)));
+
+        let mut cursor = None;
+        let mut window = Window::new(None, &mut inv);
+        let mut drag = None;
+        let mut sim = ClickSimulation {
+            window: &mut window,
+            cursor: &mut cursor,
+            drag: &mut drag,
+            creative: false,
+            recipes: &recipes,
+            dropped: Vec::new(),
+        };
+
+        sim.update_crafting_output();
+        sim.click(0, 0, ClickContainerMode::Click);
+
+        assert_eq!(sim.cursor, &Some(ItemStack::new(ItemKind::Cake, 1, None)));
+        assert_eq!(
+            sim.window.slot(1),
+            Some(&ItemStack::new(ItemKind::Bucket, 1, None))
+        );
+        assert_eq!(sim.window.slot(2).unwrap().count(), 1);
+        assert!(sim.dropped.is_empty());
+    }
+}
@@ -0,0 +1,74 @@
+use std::ops::Range;
+
+use valence_protocol::ItemStack;
+
+use super::{Inventory, SlotId};
+
+/// A set of pending changes to an inventory, obtained from
+/// [`Inventory::transaction`].
+///
+/// A transaction is itself an [`Inventory`], so every operation on the trait
+/// can be performed on it. Reads observe the pending changes, but the
+/// underlying inventory is left untouched until [`Self::commit`] is called.
+/// Dropping the transaction without committing discards the changes.
+///
+/// This is useful for operations which must either succeed entirely or not
+/// happen at all, such as taking several different items for a trade.
+#[must_use = "changes are discarded unless the transaction is committed"]
+pub struct Transaction<'a, I: Inventory> {
+    inv: &'a mut I,
+    changes: Vec<(SlotId, Option<ItemStack>)>,
+}
+
+impl<'a, I: Inventory> Transaction<'a, I> {
+    pub(super) fn new(inv: &'a mut I) -> Self {
+        Self {
+            inv,
+            changes: Vec::new(),
+        }
+    }
+
+    /// Applies the pending changes to the underlying inventory.
+    pub fn commit(self) {
+        for (slot_id, slot) in self.changes {
+            if self.inv.slot(slot_id) != slot.as_ref() {
+                self.inv.set_slot(slot_id, slot);
+            }
+        }
+    }
+
+    /// Discards the pending changes. This is equivalent to dropping the
+    /// transaction.
+    pub fn abort(self) {}
+}
+
+impl<I: Inventory> Inventory for Transaction<'_, I> {
+    fn slot(&self, slot_id: SlotId) -> Option<&ItemStack> {
+        match self.changes.iter().find(|(s, _)| *s == slot_id) {
+            Some((_, slot)) => slot.as_ref(),
+            None => self.inv.slot(slot_id),
+        }
+    }
+
+    fn set_slot(&mut self, slot_id: SlotId, slot: Option<ItemStack>) -> Option<ItemStack> {
+        if !self.slot_range().contains(&slot_id) {
+            return None;
+        }
+        match self.changes.iter_mut().find(|(s, _)| *s == slot_id) {
+            Some((_, prev)) => std::mem::replace(prev, slot),
+            None => {
+                let prev = self.inv.slot(slot_id).cloned();
+                self.changes.push((slot_id, slot));
+                prev
+            }
+        }
+    }
+
+    fn slot_range(&self) -> Range<SlotId> {
+        self.inv.slot_range()
+    }
+
+    fn general_slots(&self) -> Range<SlotId> {
+        self.inv.general_slots()
+    }
+}
@@ -95,11 +95,12 @@
 /// [async_trait]: https://docs.rs/async-trait/latest/async_trait/
 pub use async_trait::async_trait;
 #[doc(inline)]
-pub use server::start_server;
+pub use server::{start_server, start_server_manual};
 pub use valence_protocol as protocol;
 #[doc(inline)]
 pub use {uuid, valence_nbt as nbt, vek};
 
+pub mod access;
 pub mod biome;
 mod bvh;
 pub mod chunk;
@@ -109,8 +110,11 @@ pub mod config;
 pub mod dimension;
 pub mod entity;
 pub mod inventory;
+pub mod metrics;
 pub mod player_list;
 pub mod player_textures;
+pub mod rcon;
+pub mod recipe;
 #[doc(hidden)]
 pub mod server;
 mod slab;
@@ -125,7 +129,10 @@ pub mod world;
 pub mod prelude {
     pub use biome::{Biome, BiomeId};
     pub use chunk::{Chunk, ChunkPos, Chunks, LoadedChunk, UnloadedChunk};
-    pub use client::{handle_event_default, Client, ClientEvent, ClientId, Clients};
+    pub use client::{
+        handle_event_default, Client, ClientEvent, ClientId, Clients, ContainerClick,
+        RecipePlacement,
+    };
     pub use config::{Config, ConnectionMode, PlayerSampleEntry, ServerListPing};
     pub use dimension::{Dimension, DimensionId};
     pub use entity::{Entities, Entity, EntityEvent, EntityId, EntityKind, TrackedData};
@@ -133,7 +140,11 @@ pub mod prelude {
         ConfigurableInventory, Inventories, Inventory, InventoryId, PlayerInventory, SlotId,
     };
     pub use player_list::{PlayerList, PlayerListEntry, PlayerListId, PlayerLists};
-    pub use server::{NewClientData, Server, SharedServer, ShutdownResult};
+    pub use rcon::{Rcon, RconCommand};
+    pub use recipe::{Recipe, RecipeId, Recipes};
+    pub use server::{
+        LoginPlugin, ManualServer, NewClientData, Server, SharedServer, ShutdownResult,
+    };
     pub use spatial_index::{RaycastHit, SpatialIndex};
     pub use util::{
         chunks_in_view_distance, from_yaw_and_pitch, is_chunk_in_view_distance, to_yaw_and_pitch,
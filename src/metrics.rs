@@ -0,0 +1,491 @@
+//! Built-in performance metrics.
+//!
+//! Every server collects [`Metrics`], which can be accessed through
+//! [`SharedServer::metrics`]. [`Metrics::render`] formats them in the
+//! Prometheus [text exposition format]. They can also be served over HTTP by
+//! returning an address from [`Config::metrics_address`].
+//!
+//! [`SharedServer::metrics`]: crate::server::SharedServer::metrics
+//! [`Config::metrics_address`]: crate::config::Config::metrics_address
+//! [text exposition format]: https://prometheus.io/docs/instrumenting/exposition_formats/
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+use crate::server::byte_channel::ChannelUsage;
+
+/// The upper bounds of the histogram buckets, in seconds.
+const BUCKETS: [f64; 12] = [
+    0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5,
+];
+
+/// A part of a game update whose duration is measured.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum TickPhase {
+    /// The entire game update.
+    Total,
+    /// Reading and handling the packets sent by clients.
+    ServerboundPackets,
+    /// The call to [`Config::update`](crate::config::Config::update).
+    UserUpdate,
+    /// Updating the spatial index of every world.
+    SpatialIndex,
+    /// Updating every client, which includes chunk sending and entity
+    /// tracking.
+    ClientUpdate,
+    /// Loading and unloading chunks for clients. Clients are updated in
+    /// parallel, so this is the sum over all clients and can exceed the
+    /// duration of [`TickPhase::ClientUpdate`].
+    ChunkSending,
+    /// Spawning, updating, and despawning the entities visible to clients.
+    /// Like [`TickPhase::ChunkSending`], this is the sum over all clients.
+    EntityTracking,
+    /// Updating entities, chunks, player lists, inventories, and recipes at
+    /// the end of the tick.
+    Cleanup,
+}
+
+impl TickPhase {
+    pub const ALL: [TickPhase; 8] = [
+        TickPhase::Total,
+        TickPhase::ServerboundPackets,
+        TickPhase::UserUpdate,
+        TickPhase::SpatialIndex,
+        TickPhase::ClientUpdate,
+        TickPhase::ChunkSending,
+        TickPhase::EntityTracking,
+        TickPhase::Cleanup,
+    ];
+
+    /// The name of this phase as used in the `phase` label.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TickPhase::Total => "total",
+            TickPhase::ServerboundPackets => "serverbound_packets",
+            TickPhase::UserUpdate => "user_update",
+            TickPhase::SpatialIndex => "spatial_index",
+            TickPhase::ClientUpdate => "client_update",
+            TickPhase::ChunkSending => "chunk_sending",
+            TickPhase::EntityTracking => "entity_tracking",
+            TickPhase::Cleanup => "cleanup",
+        }
+    }
+}
+
+/// A histogram of durations with fixed buckets.
+#[derive(Debug, Default)]
+pub struct Histogram {
+    /// The number of observations in each bucket, not including the
+    /// observations of lower buckets.
+    buckets: [AtomicU64; BUCKETS.len()],
+    count: AtomicU64,
+    sum_nanos: AtomicU64,
+}
+
+impl Histogram {
+    pub fn observe(&self, duration: Duration) {
+        let secs = duration.as_secs_f64();
+
+        if let Some(idx) = BUCKETS.iter().position(|&le| secs <= le) {
+            self.buckets[idx].fetch_add(1, Ordering::Relaxed);
+        }
+
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// The number of observations.
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// The sum of all observations.
+    pub fn sum(&self) -> Duration {
+        Duration::from_nanos(self.sum_nanos.load(Ordering::Relaxed))
+    }
+
+    /// Returns the upper bound of each bucket in seconds along with the
+    /// number of observations less than or equal to it. Observations larger
+    /// than the last bucket are only included in [`Histogram::count`].
+    pub fn buckets(&self) -> impl Iterator<Item = (f64, u64)> + '_ {
+        BUCKETS
+            .iter()
+            .zip(&self.buckets)
+            .scan(0, |total, (&le, count)| {
+                *total += count.load(Ordering::Relaxed);
+                Some((le, *total))
+            })
+    }
+}
+
+/// Metrics collected by a server.
+#[derive(Default)]
+pub struct Metrics {
+    ticks: [Histogram; TickPhase::ALL.len()],
+    /// Time spent chunk sending in the current tick, summed over all clients.
+    chunk_sending_nanos: AtomicU64,
+    /// Time spent entity tracking in the current tick, summed over all
+    /// clients.
+    entity_tracking_nanos: AtomicU64,
+    connections_opened: AtomicU64,
+    connections_closed: AtomicU64,
+    clients: Mutex<Vec<Weak<ClientMetrics>>>,
+}
+
+/// Traffic metrics for a single client in the play state.
+pub(crate) struct ClientMetrics {
+    username: String,
+    bytes_received: AtomicU64,
+    bytes_sent: AtomicU64,
+    incoming: ChannelUsage,
+    outgoing: ChannelUsage,
+}
+
+impl ClientMetrics {
+    pub fn add_bytes_received(&self, n: usize) {
+        self.bytes_received.fetch_add(n as u64, Ordering::Relaxed);
+    }
+
+    pub fn add_bytes_sent(&self, n: usize) {
+        self.bytes_sent.fetch_add(n as u64, Ordering::Relaxed);
+    }
+}
+
+impl Metrics {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Gets the histogram of durations for a phase of the game update.
+    pub fn tick_duration(&self, phase: TickPhase) -> &Histogram {
+        let idx = TickPhase::ALL.iter().position(|&p| p == phase).unwrap();
+        &self.ticks[idx]
+    }
+
+    /// The number of connections currently in the handshaking, status, or
+    /// login state.
+    pub fn pending_connections(&self) -> u64 {
+        let closed = self.connections_closed.load(Ordering::Relaxed);
+        self.connections_opened
+            .load(Ordering::Relaxed)
+            .saturating_sub(closed)
+    }
+
+    /// The total number of connections accepted since the server started.
+    pub fn total_connections(&self) -> u64 {
+        self.connections_opened.load(Ordering::Relaxed)
+    }
+
+    /// The number of connected clients in the play state.
+    pub fn clients(&self) -> usize {
+        self.live_clients().len()
+    }
+
+    pub(crate) fn connection_opened(&self) {
+        self.connections_opened.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn connection_closed(&self) {
+        self.connections_closed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Starts collecting metrics for a client which entered the play state.
+    /// The client stops being tracked once the returned value is dropped.
+    pub(crate) fn register_client(
+        &self,
+        username: &str,
+        incoming: ChannelUsage,
+        outgoing: ChannelUsage,
+    ) -> Arc<ClientMetrics> {
+        let client = Arc::new(ClientMetrics {
+            username: username.to_owned(),
+            bytes_received: AtomicU64::new(0),
+            bytes_sent: AtomicU64::new(0),
+            incoming,
+            outgoing,
+        });
+
+        let mut clients = self.clients.lock().unwrap();
+        clients.retain(|c| c.strong_count() > 0);
+        clients.push(Arc::downgrade(&client));
+
+        client
+    }
+
+    /// Adds to the time spent in [`TickPhase::ChunkSending`] or
+    /// [`TickPhase::EntityTracking`] during the current tick.
+    pub(crate) fn add_client_phase(&self, phase: TickPhase, duration: Duration) {
+        let nanos = match phase {
+            TickPhase::ChunkSending => &self.chunk_sending_nanos,
+            TickPhase::EntityTracking => &self.entity_tracking_nanos,
+            _ => unreachable!("{phase:?} is not measured per client"),
+        };
+
+        nanos.fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    fn live_clients(&self) -> Vec<Arc<ClientMetrics>> {
+        self.clients
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .collect()
+    }
+
+    /// Formats the metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        let _ = self.write_metrics(&mut out);
+
+        out
+    }
+
+    fn write_metrics(&self, out: &mut String) -> std::fmt::Result {
+        writeln!(
+            out,
+            "# HELP valence_tick_duration_seconds Time spent in each phase of a game update."
+        )?;
+        writeln!(out, "# TYPE valence_tick_duration_seconds histogram")?;
+
+        for phase in TickPhase::ALL {
+            let hist = self.tick_duration(phase);
+            let phase = phase.as_str();
+
+            for (le, count) in hist.buckets() {
+                writeln!(
+                    out,
+                    "valence_tick_duration_seconds_bucket{{phase=\"{phase}\",le=\"{le}\"}} {count}"
+                )?;
+            }
+
+            let count = hist.count();
+            let sum = hist.sum().as_secs_f64();
+
+            writeln!(
+                out,
+                "valence_tick_duration_seconds_bucket{{phase=\"{phase}\",le=\"+Inf\"}} {count}"
+            )?;
+            writeln!(
+                out,
+                "valence_tick_duration_seconds_sum{{phase=\"{phase}\"}} {sum}"
+            )?;
+            writeln!(
+                out,
+                "valence_tick_duration_seconds_count{{phase=\"{phase}\"}} {count}"
+            )?;
+        }
+
+        writeln!(
+            out,
+            "# HELP valence_pending_connections Number of connections which have not reached the \
+             play state."
+        )?;
+        writeln!(out, "# TYPE valence_pending_connections gauge")?;
+        writeln!(
+            out,
+            "valence_pending_connections {}",
+            self.pending_connections()
+        )?;
+
+        writeln!(
+            out,
+            "# HELP valence_connections_total Number of connections accepted."
+        )?;
+        writeln!(out, "# TYPE valence_connections_total counter")?;
+        writeln!(
+            out,
+            "valence_connections_total {}",
+            self.total_connections()
+        )?;
+
+        let clients = self.live_clients();
+
+        writeln!(
+            out,
+            "# HELP valence_clients Number of connected clients in the play state."
+        )?;
+        writeln!(out, "# TYPE valence_clients gauge")?;
+        writeln!(out, "valence_clients {}", clients.len())?;
+
+        type ClientValue = fn(&ClientMetrics) -> u64;
+
+        let client_metrics: [(&str, &str, &str, ClientValue); 6] = [
+            (
+                "valence_client_received_bytes_total",
+                "counter",
+                "Bytes received from the client.",
+                |c| c.bytes_received.load(Ordering::Relaxed),
+            ),
+            (
+                "valence_client_sent_bytes_total",
+                "counter",
+                "Bytes sent to the client.",
+                |c| c.bytes_sent.load(Ordering::Relaxed),
+            ),
+            (
+                "valence_client_incoming_queue_bytes",
+                "gauge",
+                "Bytes received from the client waiting to be handled.",
+                |c| c.incoming.len() as u64,
+            ),
+            (
+                "valence_client_incoming_queue_capacity_bytes",
+                "gauge",
+                "Capacity of the queue of bytes received from the client.",
+                |c| c.incoming.limit() as u64,
+            ),
+            (
+                "valence_client_outgoing_queue_bytes",
+                "gauge",
+                "Bytes waiting to be sent to the client.",
+                |c| c.outgoing.len() as u64,
+            ),
+            (
+                "valence_client_outgoing_queue_capacity_bytes",
+                "gauge",
+                "Capacity of the queue of bytes waiting to be sent to the client.",
+                |c| c.outgoing.limit() as u64,
+            ),
+        ];
+
+        for (name, kind, help, value) in client_metrics {
+            writeln!(out, "# HELP {name} {help}")?;
+            writeln!(out, "# TYPE {name} {kind}")?;
+
+            for client in &clients {
+                writeln!(
+                    out,
+                    "{name}{{client=\"{}\"}} {}",
+                    client.username,
+                    value(client)
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Measures the phases of a single game update.
+pub(crate) struct TickTimer<'a> {
+    metrics: &'a Metrics,
+    tick_start: Instant,
+    phase_start: Instant,
+}
+
+impl<'a> TickTimer<'a> {
+    pub fn start(metrics: &'a Metrics) -> Self {
+        let now = Instant::now();
+
+        Self {
+            metrics,
+            tick_start: now,
+            phase_start: now,
+        }
+    }
+
+    /// Records the time since the previous phase ended as the duration of
+    /// `phase`.
+    pub fn end_phase(&mut self, phase: TickPhase) {
+        let now = Instant::now();
+        self.metrics
+            .tick_duration(phase)
+            .observe(now - self.phase_start);
+        self.phase_start = now;
+    }
+
+    /// Records the duration of the whole tick and of the phases measured per
+    /// client.
+    pub fn end_tick(self) {
+        let m = self.metrics;
+
+        m.tick_duration(TickPhase::Total)
+            .observe(self.tick_start.elapsed());
+
+        for (phase, nanos) in [
+            (TickPhase::ChunkSending, &m.chunk_sending_nanos),
+            (TickPhase::EntityTracking, &m.entity_tracking_nanos),
+        ] {
+            let nanos = nanos.swap(0, Ordering::Relaxed);
+            m.tick_duration(phase).observe(Duration::from_nanos(nanos));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::byte_channel::byte_channel;
+
+    #[test]
+    fn histogram_buckets() {
+        let hist = Histogram::default();
+
+        hist.observe(Duration::from_micros(50));
+        hist.observe(Duration::from_millis(3));
+        hist.observe(Duration::from_millis(4));
+        hist.observe(Duration::from_secs(2));
+
+        assert_eq!(hist.count(), 4);
+        assert_eq!(hist.sum(), Duration::from_micros(2_007_050));
+
+        let buckets: Vec<_> = hist.buckets().collect();
+        assert_eq!(buckets[0], (0.0001, 1));
+        assert_eq!(buckets[4], (0.0025, 1));
+        assert_eq!(buckets[5], (0.005, 3));
+        assert_eq!(buckets.last(), Some(&(0.5, 3)));
+    }
+
+    #[test]
+    fn render_clients() {
+        let metrics = Metrics::new();
+
+        let (mut incoming_tx, incoming_rx) = byte_channel(64);
+        let (outgoing_tx, _outgoing_rx) = byte_channel(128);
+
+        incoming_tx.try_send("hello".as_bytes().into()).unwrap();
+
+        let client = metrics.register_client("steve", incoming_rx.usage(), outgoing_tx.usage());
+        client.add_bytes_received(10);
+        client.add_bytes_sent(20);
+
+        metrics.connection_opened();
+        metrics.connection_opened();
+        metrics.connection_closed();
+
+        let mut timer = TickTimer::start(&metrics);
+        timer.end_phase(TickPhase::UserUpdate);
+        metrics.add_client_phase(TickPhase::ChunkSending, Duration::from_millis(1));
+        timer.end_tick();
+
+        assert_eq!(metrics.tick_duration(TickPhase::UserUpdate).count(), 1);
+        assert_eq!(
+            metrics.tick_duration(TickPhase::ChunkSending).sum(),
+            Duration::from_millis(1)
+        );
+
+        let text = metrics.render();
+
+        for line in [
+            "valence_pending_connections 1",
+            "valence_connections_total 2",
+            "valence_clients 1",
+            "valence_client_received_bytes_total{client=\"steve\"} 10",
+            "valence_client_sent_bytes_total{client=\"steve\"} 20",
+            "valence_client_incoming_queue_bytes{client=\"steve\"} 5",
+            "valence_client_outgoing_queue_capacity_bytes{client=\"steve\"} 128",
+            "valence_tick_duration_seconds_count{phase=\"total\"} 1",
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing {line:?}");
+        }
+
+        drop(client);
+        assert_eq!(metrics.clients(), 0);
+        assert!(!metrics.render().contains("steve"));
+    }
+}
@@ -0,0 +1,83 @@
+//! Remote console commands.
+//!
+//! When an [RCON address] is configured, authenticated RCON clients can send
+//! commands to the server. Each command is delivered to the update loop as an
+//! [`RconCommand`] through [`Server::rcon`].
+//!
+//! [RCON address]: crate::config::Config::rcon_address
+//! [`Server::rcon`]: crate::server::Server::rcon
+
+use std::net::SocketAddr;
+
+use flume::Receiver;
+use tokio::sync::oneshot;
+use valence_protocol::text::Translations;
+use valence_protocol::Text;
+
+/// The queue of RCON commands waiting to be handled.
+pub struct Rcon {
+    commands: Receiver<RconCommand>,
+}
+
+impl Rcon {
+    pub(crate) fn new(commands: Receiver<RconCommand>) -> Self {
+        Self { commands }
+    }
+
+    /// Removes an [`RconCommand`] from the command queue.
+    ///
+    /// If there are no remaining commands, `None` is returned.
+    pub fn pop_command(&mut self) -> Option<RconCommand> {
+        self.commands.try_recv().ok()
+    }
+}
+
+/// A command sent by an authenticated RCON client.
+///
+/// The client waits for the response until [`RconCommand::respond`] is called
+/// or the command is dropped. Dropping the command responds with an empty
+/// string.
+#[derive(Debug)]
+pub struct RconCommand {
+    command: String,
+    remote_addr: SocketAddr,
+    response: oneshot::Sender<String>,
+}
+
+impl RconCommand {
+    pub(crate) fn new(
+        command: String,
+        remote_addr: SocketAddr,
+        response: oneshot::Sender<String>,
+    ) -> Self {
+        Self {
+            command,
+            remote_addr,
+            response,
+        }
+    }
+
+    /// Gets the command string sent by the client.
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    /// Gets the remote address of the RCON client.
+    pub fn remote_addr(&self) -> SocketAddr {
+        self.remote_addr
+    }
+
+    /// Sends the response to the RCON client. The text is rendered as a plain
+    /// string without any formatting.
+    pub fn respond(self, response: impl Into<Text>) {
+        let _ = self.response.send(response.into().to_string());
+    }
+
+    /// Like [`RconCommand::respond`], but translation keys in the text are
+    /// resolved using `translations`.
+    pub fn respond_translated(self, response: impl Into<Text>, translations: &Translations) {
+        let _ = self
+            .response
+            .send(response.into().to_translated_string(translations));
+    }
+}
@@ -0,0 +1,818 @@
+//! Crafting and cooking recipes.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::{fs, io};
+
+use anyhow::{bail, ensure, Context};
+use indexmap::IndexMap;
+use serde_json::Value as Json;
+pub use valence_protocol::types::CookingRecipeKind;
+use valence_protocol::types::Recipe as RecipePacketData;
+use valence_protocol::{Ident, ItemKind, ItemStack, VarInt};
+
+/// The identifier of a recipe, such as `minecraft:crafting_table`.
+pub type RecipeId = Ident<String>;
+
+/// A recipe known to the server.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Recipe {
+    Shaped(ShapedRecipe),
+    Shapeless(ShapelessRecipe),
+    Cooking(CookingRecipe),
+    /// A recipe with hardcoded logic, such as dyeing armor. These are sent to
+    /// clients so the recipe book knows about them, but the server never
+    /// crafts them.
+    Special {
+        /// The type of the recipe, e.g. `minecraft:crafting_special_armordye`.
+        kind: Ident<String>,
+    },
+}
+
+/// A crafting recipe where the ingredients must be arranged in a pattern.
+///
+/// The pattern may be placed anywhere in the crafting grid and may be
+/// mirrored horizontally. A recipe whose pattern is empty or doesn't have
+/// `width * height` ingredients never matches.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ShapedRecipe {
+    pub group: String,
+    pub width: usize,
+    pub height: usize,
+    /// The `width * height` ingredients of the pattern in row-major order.
+    pub pattern: Vec<Ingredient>,
+    pub result: ItemStack,
+}
+
+/// A crafting recipe where the ingredients may be placed anywhere in the
+/// crafting grid.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ShapelessRecipe {
+    pub group: String,
+    pub ingredients: Vec<Ingredient>,
+    pub result: ItemStack,
+}
+
+/// A recipe for furnaces, blast furnaces, smokers and campfires.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CookingRecipe {
+    pub kind: CookingRecipeKind,
+    pub group: String,
+    pub ingredient: Ingredient,
+    pub result: ItemStack,
+    pub experience: f32,
+    /// The time it takes to cook the ingredient, in ticks.
+    pub cooking_time: u32,
+}
+
+/// The item kinds accepted by one slot of a recipe.
+///
+/// An empty ingredient only matches an empty slot.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Ingredient {
+    pub items: Vec<ItemKind>,
+}
+
+impl Ingredient {
+    pub fn new(items: impl Into<Vec<ItemKind>>) -> Self {
+        Self {
+            items: items.into(),
+        }
+    }
+
+    /// Returns an ingredient which only matches an empty slot.
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns `true` if the contents of a slot satisfy this ingredient.
+    pub fn matches(&self, slot: Option<&ItemStack>) -> bool {
+        match slot {
+            Some(stack) => self.items.contains(&stack.item),
+            None => self.is_empty(),
+        }
+    }
+
+    fn to_packet_data(&self) -> Vec<Option<ItemStack>> {
+        self.items
+            .iter()
+            .map(|&item| Some(ItemStack::new(item, 1, None)))
+            .collect()
+    }
+}
+
+impl From<ItemKind> for Ingredient {
+    fn from(item: ItemKind) -> Self {
+        Self::new([item])
+    }
+}
+
+impl ShapedRecipe {
+    /// Returns `true` if the pattern is not empty and has one ingredient for
+    /// every position.
+    fn is_well_formed(&self) -> bool {
+        self.width > 0 && self.height > 0 && self.pattern.len() == self.width * self.height
+    }
+
+    /// Returns `true` if the pattern fits in a grid of the given size.
+    fn fits(&self, grid_width: usize, grid_size: usize) -> bool {
+        self.is_well_formed()
+            && grid_width > 0
+            && self.width <= grid_width
+            && self.height <= grid_size / grid_width
+    }
+
+    /// Returns `true` if the grid contains exactly this recipe's pattern,
+    /// possibly offset or mirrored.
+    fn matches(&self, grid: &[Option<&ItemStack>], grid_width: usize) -> bool {
+        if !self.fits(grid_width, grid.len()) {
+            return false;
+        }
+
+        let grid_height = grid.len() / grid_width;
+
+        for offset_y in 0..=grid_height - self.height {
+            for offset_x in 0..=grid_width - self.width {
+                for mirrored in [false, true] {
+                    let matches = (0..grid_height).all(|y| {
+                        (0..grid_width).all(|x| {
+                            let slot = grid[y * grid_width + x];
+
+                            let in_pattern = (offset_x..offset_x + self.width).contains(&x)
+                                && (offset_y..offset_y + self.height).contains(&y);
+
+                            if !in_pattern {
+                                return slot.is_none();
+                            }
+
+                            let px = x - offset_x;
+                            let px = if mirrored { self.width - 1 - px } else { px };
+                            let py = y - offset_y;
+
+                            self.pattern[py * self.width + px].matches(slot)
+                        })
+                    });
+
+                    if matches {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        false
+    }
+}
+
+impl ShapelessRecipe {
+    /// Returns `true` if every occupied slot of the grid can be assigned to a
+    /// distinct ingredient of this recipe.
+    fn matches(&self, grid: &[Option<&ItemStack>]) -> bool {
+        fn assign(stacks: &[&ItemStack], ingredients: &[Ingredient], used: &mut [bool]) -> bool {
+            let Some((stack, rest)) = stacks.split_first() else {
+                return true;
+            };
+
+            for (i, ingredient) in ingredients.iter().enumerate() {
+                if !used[i] && ingredient.matches(Some(stack)) {
+                    used[i] = true;
+                    if assign(rest, ingredients, used) {
+                        return true;
+                    }
+                    used[i] = false;
+                }
+            }
+
+            false
+        }
+
+        let stacks: Vec<_> = grid.iter().flatten().copied().collect();
+
+        stacks.len() == self.ingredients.len()
+            && assign(
+                &stacks,
+                &self.ingredients,
+                &mut vec![false; self.ingredients.len()],
+            )
+    }
+}
+
+impl Recipe {
+    /// Returns the item stack produced by this recipe, if it isn't a special
+    /// recipe.
+    pub fn result(&self) -> Option<&ItemStack> {
+        match self {
+            Recipe::Shaped(r) => Some(&r.result),
+            Recipe::Shapeless(r) => Some(&r.result),
+            Recipe::Cooking(r) => Some(&r.result),
+            Recipe::Special { .. } => None,
+        }
+    }
+
+    /// Returns the ingredients of a crafting recipe laid out for a crafting
+    /// grid of the given width, or `None` if this is not a crafting recipe or
+    /// it does not fit in the grid.
+    ///
+    /// Each element of the returned vector is a grid index paired with the
+    /// ingredient which belongs there.
+    pub(crate) fn grid_layout(
+        &self,
+        grid_width: usize,
+        grid_size: usize,
+    ) -> Option<Vec<(usize, &Ingredient)>> {
+        match self {
+            Recipe::Shaped(r) => {
+                if !r.fits(grid_width, grid_size) {
+                    return None;
+                }
+
+                Some(
+                    r.pattern
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, ingredient)| !ingredient.is_empty())
+                        .map(|(i, ingredient)| (i / r.width * grid_width + i % r.width, ingredient))
+                        .collect(),
+                )
+            }
+            Recipe::Shapeless(r) => (r.ingredients.len() <= grid_size)
+                .then(|| r.ingredients.iter().enumerate().collect()),
+            _ => None,
+        }
+    }
+
+    fn to_packet_data<'a>(&'a self, id: &'a RecipeId) -> RecipePacketData<'a> {
+        let recipe_id = id.as_str_ident();
+
+        match self {
+            Recipe::Shaped(r) => RecipePacketData::CraftingShaped {
+                recipe_id,
+                width: VarInt(r.width as i32),
+                height: VarInt(r.height as i32),
+                group: &r.group,
+                ingredients: r.pattern.iter().map(|i| i.to_packet_data()).collect(),
+                result: Some(r.result.clone()),
+            },
+            Recipe::Shapeless(r) => RecipePacketData::CraftingShapeless {
+                recipe_id,
+                group: &r.group,
+                ingredients: r.ingredients.iter().map(|i| i.to_packet_data()).collect(),
+                result: Some(r.result.clone()),
+            },
+            Recipe::Cooking(r) => RecipePacketData::Cooking {
+                kind: r.kind,
+                recipe_id,
+                group: &r.group,
+                ingredient: r.ingredient.to_packet_data(),
+                result: Some(r.result.clone()),
+                experience: r.experience,
+                cooking_time: VarInt(r.cooking_time as i32),
+            },
+            Recipe::Special { kind } => RecipePacketData::CraftingSpecial {
+                kind: kind.as_str_ident(),
+                recipe_id,
+            },
+        }
+    }
+}
+
+/// Contains all the recipes known to the server.
+///
+/// Clients are sent every recipe when they join, and again whenever the
+/// recipes are modified. Recipes are used to compute the output of crafting
+/// grids and to fill them from the recipe book.
+///
+/// Recipes are kept in the order they were inserted. When several recipes
+/// match, the one inserted first is used.
+pub struct Recipes {
+    recipes: IndexMap<RecipeId, Recipe>,
+    modified: bool,
+}
+
+impl Recipes {
+    pub(crate) fn new() -> Self {
+        Self {
+            recipes: IndexMap::new(),
+            modified: false,
+        }
+    }
+
+    /// Adds a recipe, returning the recipe previously registered under the
+    /// same identifier. A replaced recipe keeps its position in the order.
+    pub fn insert(&mut self, id: RecipeId, recipe: Recipe) -> Option<Recipe> {
+        self.modified = true;
+        self.recipes.insert(id, recipe)
+    }
+
+    pub fn remove(&mut self, id: &RecipeId) -> Option<Recipe> {
+        let recipe = self.recipes.shift_remove(id);
+        self.modified |= recipe.is_some();
+        recipe
+    }
+
+    pub fn clear(&mut self) {
+        self.modified |= !self.recipes.is_empty();
+        self.recipes.clear();
+    }
+
+    pub fn get(&self, id: &RecipeId) -> Option<&Recipe> {
+        self.recipes.get(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.recipes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recipes.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&RecipeId, &Recipe)> + '_ {
+        self.recipes.iter()
+    }
+
+    /// Finds the crafting recipe matching the contents of a crafting grid.
+    ///
+    /// The grid is given in row-major order with `grid_width` slots per row.
+    /// If several recipes match, the first one inserted is returned.
+    pub fn craft(
+        &self,
+        grid: &[Option<&ItemStack>],
+        grid_width: usize,
+    ) -> Option<(&RecipeId, &Recipe)> {
+        if grid_width == 0 || grid.iter().all(|s| s.is_none()) {
+            return None;
+        }
+
+        self.recipes.iter().find(|(_, recipe)| match recipe {
+            Recipe::Shaped(r) => r.matches(grid, grid_width),
+            Recipe::Shapeless(r) => r.matches(grid),
+            _ => false,
+        })
+    }
+
+    /// Finds the cooking recipe of the given kind which accepts `item`.
+    pub fn cook(
+        &self,
+        kind: CookingRecipeKind,
+        item: ItemKind,
+    ) -> Option<(&RecipeId, &CookingRecipe)> {
+        self.recipes.iter().find_map(|(id, recipe)| match recipe {
+            Recipe::Cooking(r) if r.kind == kind && r.ingredient.items.contains(&item) => {
+                Some((id, r))
+            }
+            _ => None,
+        })
+    }
+
+    /// Loads the recipes of a vanilla data pack namespace, such as the
+    /// `data/minecraft` directory extracted from the server jar.
+    ///
+    /// Recipes are read from the `recipes` subdirectory and item tags used by
+    /// ingredients are resolved from `tags/items`. Recipe types which the
+    /// server does not support, such as stonecutting, are skipped.
+    pub fn load_vanilla(&mut self, dir: impl AsRef<Path>) -> anyhow::Result<()> {
+        let dir = dir.as_ref();
+        let namespace = dir
+            .file_name()
+            .and_then(|n| n.to_str())
+            .context("invalid data pack namespace directory")?;
+
+        let mut tags = HashMap::new();
+        let tags_dir = dir.join("tags/items");
+        if tags_dir.is_dir() {
+            read_json_files(&tags_dir, namespace, "", &mut |name, json| {
+                let values = json["values"]
+                    .as_array()
+                    .context("missing tag values")?
+                    .iter()
+                    .filter_map(|v| match v {
+                        Json::String(s) => Some(s.clone()),
+                        Json::Object(o) => o.get("id")?.as_str().map(str::to_owned),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+
+                tags.insert(name, values);
+                Ok(())
+            })?;
+        }
+
+        let mut recipes = vec![];
+        read_json_files(&dir.join("recipes"), namespace, "", &mut |name, json| {
+            if let Some(recipe) = parse_vanilla_recipe(&json, &tags)? {
+                recipes.push((Ident::new(name)?, recipe));
+            }
+            Ok(())
+        })?;
+
+        for (id, recipe) in recipes {
+            self.insert(id, recipe);
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    pub(crate) fn to_packet_data(&self) -> Vec<RecipePacketData<'_>> {
+        self.recipes
+            .iter()
+            .map(|(id, recipe)| recipe.to_packet_data(id))
+            .collect()
+    }
+
+    pub(crate) fn update(&mut self) {
+        self.modified = false;
+    }
+}
+
+/// Calls `f` with the identifier and contents of every JSON file in `dir` and
+/// its subdirectories.
+fn read_json_files(
+    dir: &Path,
+    namespace: &str,
+    prefix: &str,
+    f: &mut impl FnMut(String, Json) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let mut paths = fs::read_dir(dir)
+        .with_context(|| format!("reading {}", dir.display()))?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    // Directory listings are in no particular order, so the recipes are
+    // sorted to load them the same way every time.
+    paths.sort();
+
+    for path in paths {
+        let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+
+        if path.is_dir() {
+            read_json_files(&path, namespace, &format!("{prefix}{stem}/"), f)?;
+        } else if path.extension().is_some_and(|e| e == "json") {
+            let json = serde_json::from_slice(&fs::read(&path)?)
+                .with_context(|| format!("parsing {}", path.display()))?;
+
+            f(format!("{namespace}:{prefix}{stem}"), json)
+                .with_context(|| format!("loading {}", path.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_vanilla_recipe(
+    json: &Json,
+    tags: &HashMap<String, Vec<String>>,
+) -> anyhow::Result<Option<Recipe>> {
+    let kind: Ident<String> = json["type"]
+        .as_str()
+        .context("missing recipe type")?
+        .parse()?;
+    let group = json["group"].as_str().unwrap_or_default().to_owned();
+
+    let recipe = match kind.path() {
+        "crafting_shaped" => {
+            let rows: Vec<_> = json["pattern"]
+                .as_array()
+                .context("missing pattern")?
+                .iter()
+                .map(|row| row.as_str().context("pattern row is not a string"))
+                .collect::<anyhow::Result<_>>()?;
+
+            let width = rows.iter().map(|r| r.chars().count()).max().unwrap_or(0);
+            let height = rows.len();
+
+            let mut pattern = vec![];
+            for row in rows {
+                for x in 0..width {
+                    let ingredient = match row.chars().nth(x) {
+                        None | Some(' ') => Ingredient::empty(),
+                        Some(c) => parse_ingredient(&json["key"][c.to_string()], tags)
+                            .with_context(|| format!("in key '{c}'"))?,
+                    };
+                    pattern.push(ingredient);
+                }
+            }
+
+            Recipe::Shaped(ShapedRecipe {
+                group,
+                width,
+                height,
+                pattern,
+                result: parse_result(&json["result"])?,
+            })
+        }
+        "crafting_shapeless" => Recipe::Shapeless(ShapelessRecipe {
+            group,
+            ingredients: json["ingredients"]
+                .as_array()
+                .context("missing ingredients")?
+                .iter()
+                .map(|i| parse_ingredient(i, tags))
+                .collect::<anyhow::Result<_>>()?,
+            result: parse_result(&json["result"])?,
+        }),
+        path if path.starts_with("crafting_special_") => Recipe::Special { kind: kind.clone() },
+        path => match CookingRecipeKind::from_name(path) {
+            Some(cooking_kind) => {
+                let default_time = match cooking_kind {
+                    CookingRecipeKind::Smelting => 200,
+                    CookingRecipeKind::Blasting | CookingRecipeKind::Smoking => 100,
+                    CookingRecipeKind::CampfireCooking => 600,
+                };
+
+                Recipe::Cooking(CookingRecipe {
+                    kind: cooking_kind,
+                    group,
+                    ingredient: parse_ingredient(&json["ingredient"], tags)?,
+                    result: parse_result(&json["result"])?,
+                    experience: json["experience"].as_f64().unwrap_or(0.0) as f32,
+                    cooking_time: json["cookingtime"].as_u64().unwrap_or(default_time) as u32,
+                })
+            }
+            None => return Ok(None),
+        },
+    };
+
+    Ok(Some(recipe))
+}
+
+fn parse_ingredient(
+    json: &Json,
+    tags: &HashMap<String, Vec<String>>,
+) -> anyhow::Result<Ingredient> {
+    let mut items = vec![];
+
+    let choices = match json {
+        Json::Array(choices) => choices.as_slice(),
+        choice => std::slice::from_ref(choice),
+    };
+
+    for choice in choices {
+        if let Some(item) = choice["item"].as_str() {
+            items.push(parse_item(item)?);
+        } else if let Some(tag) = choice["tag"].as_str() {
+            resolve_tag(tag, tags, &mut items, 0)?;
+        } else {
+            bail!("ingredient must have an item or tag");
+        }
+    }
+
+    ensure!(!items.is_empty(), "ingredient matches no items");
+
+    Ok(Ingredient::new(items))
+}
+
+fn resolve_tag(
+    tag: &str,
+    tags: &HashMap<String, Vec<String>>,
+    items: &mut Vec<ItemKind>,
+    depth: usize,
+) -> anyhow::Result<()> {
+    ensure!(depth < 16, "tag \"{tag}\" is nested too deeply");
+
+    let tag: Ident<String> = tag.parse()?;
+    let values = tags
+        .get(&format!("{}:{}", tag.namespace(), tag.path()))
+        .with_context(|| format!("unknown item tag \"{tag}\""))?;
+
+    for value in values {
+        match value.strip_prefix('#') {
+            Some(nested) => resolve_tag(nested, tags, items, depth + 1)?,
+            None => {
+                let item = parse_item(value)?;
+                if !items.contains(&item) {
+                    items.push(item);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_result(json: &Json) -> anyhow::Result<ItemStack> {
+    // Cooking recipes use a bare item identifier for their result.
+    if let Some(item) = json.as_str() {
+        return Ok(ItemStack::new(parse_item(item)?, 1, None));
+    }
+
+    let item = parse_item(json["item"].as_str().context("missing result item")?)?;
+    let count = json["count"].as_u64().unwrap_or(1);
+
+    Ok(ItemStack::new(item, count.try_into()?, None))
+}
+
+fn parse_item(id: &str) -> anyhow::Result<ItemKind> {
+    let ident: Ident<String> = id.parse()?;
+    ensure!(ident.namespace() == "minecraft", "unknown item \"{id}\"");
+    ItemKind::from_str(ident.path()).with_context(|| format!("unknown item \"{id}\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stack(item: ItemKind) -> Option<ItemStack> {
+        Some(ItemStack::new(item, 1, None))
+    }
+
+    #[test]
+    fn shaped_matches_offset_and_mirrored() {
+        // An axe, which is asymmetric.
+        let recipe = ShapedRecipe {
+            group: String::new(),
+            width: 2,
+            height: 3,
+            pattern: vec![
+                ItemKind::Stone.into(),
+                ItemKind::Stone.into(),
+                ItemKind::Stone.into(),
+                ItemKind::Stick.into(),
+                Ingredient::empty(),
+                ItemKind::Stick.into(),
+            ],
+            result: ItemStack::new(ItemKind::StoneAxe, 1, None),
+        };
+
+        let s = stack(ItemKind::Stone);
+        let t = stack(ItemKind::Stick);
+
+        let grid = [
+            None,
+            s.clone(),
+            s.clone(),
+            None,
+            t.clone(),
+            s.clone(),
+            None,
+            t.clone(),
+            None,
+        ];
+        let grid: Vec<_> = grid.iter().map(|s| s.as_ref()).collect();
+        assert!(recipe.matches(&grid, 3));
+
+        let grid = [
+            s.clone(),
+            s.clone(),
+            None,
+            s.clone(),
+            t.clone(),
+            None,
+            None,
+            t.clone(),
+            None,
+        ];
+        let grid: Vec<_> = grid.iter().map(|s| s.as_ref()).collect();
+        assert!(recipe.matches(&grid, 3));
+
+        let grid = [
+            s.clone(),
+            s.clone(),
+            t.clone(),
+            s.clone(),
+            t.clone(),
+            None,
+            None,
+            t,
+            None,
+        ];
+        let grid: Vec<_> = grid.iter().map(|s| s.as_ref()).collect();
+        assert!(!recipe.matches(&grid, 3));
+    }
+
+    #[test]
+    fn malformed_shaped_recipes_never_match() {
+        let s = stack(ItemKind::Stone);
+        let grid = [s.clone(), s.clone(), s.clone(), s];
+        let grid: Vec<_> = grid.iter().map(|s| s.as_ref()).collect();
+
+        let recipe = |width, height, len| ShapedRecipe {
+            group: String::new(),
+            width,
+            height,
+            pattern: vec![ItemKind::Stone.into(); len],
+            result: ItemStack::new(ItemKind::Cobblestone, 1, None),
+        };
+
+        assert!(recipe(2, 2, 4).matches(&grid, 2));
+
+        for recipe in [
+            recipe(2, 2, 3),
+            recipe(2, 2, 5),
+            recipe(0, 2, 4),
+            recipe(2, 0, 0),
+        ] {
+            assert!(!recipe.matches(&grid, 2));
+            assert!(Recipe::Shaped(recipe).grid_layout(2, 4).is_none());
+        }
+    }
+
+    #[test]
+    fn shapeless_matches_any_order() {
+        let recipe = ShapelessRecipe {
+            group: String::new(),
+            ingredients: vec![
+                Ingredient::new([ItemKind::Sugar]),
+                Ingredient::new([ItemKind::Egg, ItemKind::Sugar]),
+            ],
+            result: ItemStack::new(ItemKind::Cake, 1, None),
+        };
+
+        let grid = [stack(ItemKind::Sugar), None, None, stack(ItemKind::Sugar)];
+        let grid: Vec<_> = grid.iter().map(|s| s.as_ref()).collect();
+        assert!(recipe.matches(&grid));
+
+        let grid = [None, stack(ItemKind::Egg), None, None];
+        let grid: Vec<_> = grid.iter().map(|s| s.as_ref()).collect();
+        assert!(!recipe.matches(&grid));
+    }
+
+    #[test]
+    fn parse_vanilla_json() {
+        let tags = HashMap::from([(
+            "minecraft:planks".to_owned(),
+            vec![
+                "minecraft:oak_planks".to_owned(),
+                "minecraft:birch_planks".to_owned(),
+            ],
+        )]);
+
+        let json = serde_json::json!({
+            "type": "minecraft:crafting_shaped",
+            "pattern": ["#", "#"],
+            "key": { "#": { "tag": "minecraft:planks" } },
+            "result": { "item": "minecraft:stick", "count": 4 }
+        });
+
+        let Some(Recipe::Shaped(recipe)) = parse_vanilla_recipe(&json, &tags).unwrap() else {
+            panic!("expected a shaped recipe");
+        };
+
+        assert_eq!((recipe.width, recipe.height), (1, 2));
+        assert_eq!(
+            recipe.pattern[0].items,
+            [ItemKind::OakPlanks, ItemKind::BirchPlanks]
+        );
+        assert_eq!(recipe.result, ItemStack::new(ItemKind::Stick, 4, None));
+
+        let json = serde_json::json!({
+            "type": "minecraft:smelting",
+            "ingredient": { "item": "minecraft:raw_iron" },
+            "result": "minecraft:iron_ingot",
+            "experience": 0.7
+        });
+
+        let Some(Recipe::Cooking(recipe)) = parse_vanilla_recipe(&json, &tags).unwrap() else {
+            panic!("expected a cooking recipe");
+        };
+
+        assert_eq!(recipe.cooking_time, 200);
+        assert_eq!(recipe.result.item, ItemKind::IronIngot);
+    }
+
+    #[test]
+    fn craft_prefers_first_inserted() {
+        let shapeless = |result| {
+            Recipe::Shapeless(ShapelessRecipe {
+                group: String::new(),
+                ingredients: vec![ItemKind::Stick.into()],
+                result: ItemStack::new(result, 1, None),
+            })
+        };
+
+        let mut recipes = Recipes::new();
+        for (i, result) in [ItemKind::Torch, ItemKind::Ladder, ItemKind::Bowl]
+            .into_iter()
+            .enumerate()
+        {
+            recipes.insert(Ident::new(format!("test:{i}")).unwrap(), shapeless(result));
+        }
+
+        let grid = [stack(ItemKind::Stick)];
+        let grid: Vec<_> = grid.iter().map(|s| s.as_ref()).collect();
+
+        let crafted = |recipes: &Recipes| recipes.craft(&grid, 1).unwrap().1.result().cloned();
+
+        assert_eq!(crafted(&recipes), stack(ItemKind::Torch));
+
+        recipes.remove(&Ident::new("test:0".to_owned()).unwrap());
+        assert_eq!(crafted(&recipes), stack(ItemKind::Ladder));
+
+        // Replacing a recipe keeps its place.
+        recipes.insert(
+            Ident::new("test:1".to_owned()).unwrap(),
+            shapeless(ItemKind::Chest),
+        );
+        assert_eq!(crafted(&recipes), stack(ItemKind::Chest));
+    }
+}
@@ -20,7 +20,8 @@ use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::runtime::{Handle, Runtime};
 use tokio::sync::Semaphore;
-use tracing::{error, info, info_span, instrument, trace, warn};
+use tokio::time::timeout;
+use tracing::{debug, error, info, info_span, instrument, trace, warn};
 use uuid::Uuid;
 use valence_nbt::{compound, Compound, List};
 use valence_protocol::packets::c2s::handshake::HandshakeOwned;
@@ -33,21 +34,34 @@ use valence_protocol::{
     ident, PacketDecoder, PacketEncoder, Username, VarInt, MINECRAFT_VERSION, PROTOCOL_VERSION,
 };
 
+use crate::access::AccessControl;
 use crate::biome::{validate_biomes, Biome, BiomeId};
 use crate::client::{Client, Clients};
-use crate::config::{Config, ConnectionMode, ServerListPing};
+use crate::config::{Config, ConnectionMode, RateLimit, ServerListPing};
 use crate::dimension::{validate_dimensions, Dimension, DimensionId};
 use crate::entity::Entities;
 use crate::inventory::Inventories;
+use crate::metrics::{Metrics, TickPhase, TickTimer};
 use crate::player_list::PlayerLists;
 use crate::player_textures::SignedPlayerTextures;
+use crate::rcon::{Rcon, RconCommand};
+use crate::recipe::Recipes;
 use crate::server::packet_controller::InitialPacketController;
+use crate::server::throttle::{ConnectionGuard, Throttle};
 use crate::world::Worlds;
 use crate::Ticks;
 
-mod byte_channel;
+pub(crate) mod byte_channel;
+mod legacy_ping;
 mod login;
+mod metrics;
 mod packet_controller;
+mod query;
+mod rcon;
+pub mod testing;
+mod throttle;
+
+pub use login::LoginPlugin;
 
 /// Contains the entire state of a running Minecraft server, accessible from
 /// within the [update](crate::config::Config::update) loop.
@@ -66,6 +80,10 @@ pub struct Server<C: Config> {
     pub player_lists: PlayerLists<C>,
     /// All of the inventories on the server.
     pub inventories: Inventories,
+    /// All of the recipes on the server.
+    pub recipes: Recipes,
+    /// Commands received from RCON clients.
+    pub rcon: Rcon,
 }
 
 /// A handle to a Minecraft server containing the subset of functionality which
@@ -86,9 +104,17 @@ impl<C: Config> Clone for SharedServer<C> {
 struct SharedServerInner<C: Config> {
     cfg: C,
     address: SocketAddr,
+    query_address: Option<SocketAddr>,
+    rcon_address: Option<SocketAddr>,
+    rcon_password: Arc<str>,
+    metrics_address: Option<SocketAddr>,
     tick_rate: Ticks,
     connection_mode: ConnectionMode,
     max_connections: usize,
+    max_connections_per_ip: Option<usize>,
+    login_rate_limit: Option<RateLimit>,
+    handshake_timeout: Duration,
+    login_timeout: Duration,
     incoming_capacity: usize,
     outgoing_capacity: usize,
     /// The tokio handle used by the server.
@@ -111,6 +137,8 @@ struct SharedServerInner<C: Config> {
     /// A semaphore used to limit the number of simultaneous connections to the
     /// server. Closing this semaphore stops new connections.
     connection_sema: Arc<Semaphore>,
+    /// Enforces the per-IP connection and login limits.
+    throttle: Arc<Throttle>,
     /// The result that will be returned when the server is shut down.
     shutdown_result: Mutex<Option<ShutdownResult>>,
     /// The RSA keypair used for encryption with clients.
@@ -120,6 +148,8 @@ struct SharedServerInner<C: Config> {
     public_key_der: Box<[u8]>,
     /// For session server requests.
     http_client: HttpClient,
+    metrics: Metrics,
+    access_control: Option<AccessControl>,
 }
 
 /// Contains information about a new client.
@@ -155,6 +185,36 @@ impl<C: Config> SharedServer<C> {
         self.0.address
     }
 
+    /// Gets the socket address the Query listener is bound to, if it is
+    /// enabled.
+    pub fn query_address(&self) -> Option<SocketAddr> {
+        self.0.query_address
+    }
+
+    /// Gets the socket address the RCON listener is bound to, if it is enabled.
+    pub fn rcon_address(&self) -> Option<SocketAddr> {
+        self.0.rcon_address
+    }
+
+    /// Gets the socket address the metrics listener is bound to, if it is
+    /// enabled.
+    pub fn metrics_address(&self) -> Option<SocketAddr> {
+        self.0.metrics_address
+    }
+
+    /// Gets the [`Metrics`] collected by this server.
+    pub fn metrics(&self) -> &Metrics {
+        &self.0.metrics
+    }
+
+    /// Gets the server's ban lists, whitelist, and operators, if an
+    /// [access control directory] is configured.
+    ///
+    /// [access control directory]: crate::config::Config::access_control_dir
+    pub fn access_control(&self) -> Option<&AccessControl> {
+        self.0.access_control.as_ref()
+    }
+
     /// Gets the configured tick rate of this server.
     pub fn tick_rate(&self) -> Ticks {
         self.0.tick_rate
@@ -170,6 +230,28 @@ impl<C: Config> SharedServer<C> {
         self.0.max_connections
     }
 
+    /// Gets the maximum number of connections allowed from a single IP
+    /// address at once, if there is a limit.
+    pub fn max_connections_per_ip(&self) -> Option<usize> {
+        self.0.max_connections_per_ip
+    }
+
+    /// Gets the maximum rate of login attempts allowed from a single IP
+    /// address, if there is a limit.
+    pub fn login_rate_limit(&self) -> Option<RateLimit> {
+        self.0.login_rate_limit
+    }
+
+    /// Gets the configured timeout for each packet before the play state.
+    pub fn handshake_timeout(&self) -> Duration {
+        self.0.handshake_timeout
+    }
+
+    /// Gets the configured timeout for reaching the play state.
+    pub fn login_timeout(&self) -> Duration {
+        self.0.login_timeout
+    }
+
     /// Gets the configured incoming capacity.
     pub fn incoming_capacity(&self) -> usize {
         self.0.incoming_capacity
@@ -261,13 +343,116 @@ impl<C: Config> SharedServer<C> {
 ///
 /// This function blocks the current thread and returns once the server has shut
 /// down, a runtime error occurs, or the configuration is found to be invalid.
+///
+/// To control when game updates happen, use [`start_server_manual`] instead.
 pub fn start_server<C: Config>(config: C, data: C::ServerState) -> ShutdownResult {
+    let mut server = start_server_manual(config, data)?;
+
+    do_update_loop(&mut server)
+}
+
+/// Consumes the configuration and starts the server without running the update
+/// loop.
+///
+/// This is like [`start_server`], but it returns as soon as the server is
+/// initialized. Connections are accepted in the background, but game updates
+/// only happen when [`ManualServer::tick`] is called. This lets the caller
+/// decide when ticks happen, such as when the server is embedded in another
+/// application or driven by tests. The configured
+/// [tick rate](crate::config::Config::tick_rate) is not enforced.
+pub fn start_server_manual<C: Config>(
+    config: C,
+    data: C::ServerState,
+) -> Result<ManualServer<C>, Box<dyn Error + Send + Sync + 'static>> {
     let shared = setup_server(config)
         .context("failed to initialize server")
         .map_err(Box::<dyn Error + Send + Sync + 'static>::from)?;
 
     let _guard = shared.tokio_handle().enter();
 
+    let (rcon_commands_tx, rcon_commands_rx) = flume::bounded(256);
+
+    let server = init_server(shared.clone(), data, rcon_commands_rx);
+
+    if let Some(addr) = shared.query_address() {
+        tokio::spawn(query::do_query_loop(shared.clone(), addr));
+    }
+
+    if let Some(addr) = shared.rcon_address() {
+        tokio::spawn(rcon::do_rcon_loop(
+            shared.clone(),
+            addr,
+            shared.0.rcon_password.clone(),
+            rcon_commands_tx,
+        ));
+    }
+
+    if let Some(addr) = shared.metrics_address() {
+        tokio::spawn(metrics::do_metrics_loop(shared.clone(), addr));
+    }
+
+    tokio::spawn(do_accept_loop(shared));
+
+    Ok(ManualServer { server })
+}
+
+/// A running server whose game updates are driven by the caller. Returned by
+/// [`start_server_manual`].
+pub struct ManualServer<C: Config> {
+    server: Server<C>,
+}
+
+impl<C: Config> ManualServer<C> {
+    pub fn server(&self) -> &Server<C> {
+        &self.server
+    }
+
+    pub fn server_mut(&mut self) -> &mut Server<C> {
+        &mut self.server
+    }
+
+    /// Runs a single game update immediately.
+    ///
+    /// If [`SharedServer::shutdown`] was called, no update is run and the
+    /// shutdown result is returned instead. The server should be dropped
+    /// afterwards.
+    pub fn tick(&mut self) -> Option<ShutdownResult> {
+        let shared = self.server.shared.clone();
+
+        let _guard = shared.tokio_handle().enter();
+        let _span = info_span!("update_loop", tick = shared.current_tick()).entered();
+
+        if let Some(res) = shared.0.shutdown_result.lock().unwrap().take() {
+            return Some(res);
+        }
+
+        while let Ok(msg) = shared.0.new_clients_rx.try_recv() {
+            info!(
+                username = %msg.ncd.username,
+                uuid = %msg.ncd.uuid,
+                ip = %msg.ncd.remote_addr,
+                "inserting client"
+            );
+
+            self.server
+                .clients
+                .insert(Client::new(msg.ctrl, msg.ncd, Default::default()));
+        }
+
+        do_tick(&mut self.server);
+
+        shared.0.tick_counter.fetch_add(1, Ordering::SeqCst);
+
+        None
+    }
+}
+
+/// Creates the [`Server`] and calls [`Config::init`] on it.
+fn init_server<C: Config>(
+    shared: SharedServer<C>,
+    data: C::ServerState,
+    rcon_commands_rx: Receiver<RconCommand>,
+) -> Server<C> {
     let mut server = Server {
         state: data,
         shared: shared.clone(),
@@ -276,19 +461,71 @@ pub fn start_server<C: Config>(config: C, data: C::ServerState) -> ShutdownResul
         worlds: Worlds::new(shared.clone()),
         player_lists: PlayerLists::new(),
         inventories: Inventories::new(),
+        recipes: Recipes::new(),
+        rcon: Rcon::new(rcon_commands_rx),
     };
 
     info_span!("configured_init").in_scope(|| shared.config().init(&mut server));
 
-    tokio::spawn(do_accept_loop(shared));
-
-    do_update_loop(&mut server)
+    server
 }
 
 #[instrument(skip_all)]
 fn setup_server<C: Config>(cfg: C) -> anyhow::Result<SharedServer<C>> {
     let max_connections = cfg.max_connections();
+
+    let max_connections_per_ip = cfg.max_connections_per_ip();
+
+    ensure!(
+        max_connections_per_ip != Some(0),
+        "maximum connections per IP must be nonzero"
+    );
+
+    let login_rate_limit = cfg.login_rate_limit();
+
+    if let Some(limit) = login_rate_limit {
+        ensure!(limit.max > 0, "login rate limit must be nonzero");
+        ensure!(
+            !limit.period.is_zero(),
+            "login rate limit period must be nonzero"
+        );
+    }
+
+    let handshake_timeout = cfg.handshake_timeout();
+
+    ensure!(
+        !handshake_timeout.is_zero(),
+        "handshake timeout must be nonzero"
+    );
+
+    let login_timeout = cfg.login_timeout();
+
+    ensure!(!login_timeout.is_zero(), "login timeout must be nonzero");
+
     let address = cfg.address();
+    let query_address = cfg.query_address();
+
+    let rcon_address = cfg.rcon_address();
+    let rcon_password = match rcon_address {
+        Some(_) => cfg.rcon_password(),
+        None => String::new(),
+    };
+
+    ensure!(
+        rcon_address.is_none() || !rcon_password.is_empty(),
+        "RCON password must not be empty"
+    );
+
+    let metrics_address = cfg.metrics_address();
+
+    let access_control = match cfg.access_control_dir() {
+        Some(dir) => Some(
+            AccessControl::load(dir, cfg.enforce_whitelist())
+                .context("failed to load access control lists")?,
+        ),
+        None => None,
+    };
+
     let tick_rate = cfg.tick_rate();
 
     ensure!(tick_rate > 0, "tick rate must be greater than zero");
@@ -341,9 +578,17 @@ fn setup_server<C: Config>(cfg: C) -> anyhow::Result<SharedServer<C>> {
     let server = SharedServerInner {
         cfg,
         address,
+        query_address,
+        rcon_address,
+        rcon_password: rcon_password.into(),
+        metrics_address,
         tick_rate,
         connection_mode,
         max_connections,
+        max_connections_per_ip,
+        login_rate_limit,
+        handshake_timeout,
+        login_timeout,
         incoming_capacity: incoming_packet_capacity,
         outgoing_capacity: outgoing_packet_capacity,
         tokio_handle,
@@ -356,10 +601,13 @@ fn setup_server<C: Config>(cfg: C) -> anyhow::Result<SharedServer<C>> {
         new_clients_tx,
         tick_counter: AtomicI64::new(0),
         connection_sema: Arc::new(Semaphore::new(max_connections)),
+        throttle: Arc::new(Throttle::new(max_connections_per_ip, login_rate_limit)),
         shutdown_result: Mutex::new(None),
         rsa_key,
         public_key_der,
         http_client: HttpClient::new(),
+        metrics: Metrics::new(),
+        access_control,
     };
 
     Ok(SharedServer(Arc::new(server)))
@@ -392,70 +640,78 @@ fn make_registry_codec(dimensions: &[Dimension], biomes: &[Biome]) -> Compound {
     }
 }
 
-fn do_update_loop(server: &mut Server<impl Config>) -> ShutdownResult {
-    let mut tick_start = Instant::now();
-    let mut current_tick = 0;
-    let shared = server.shared.clone();
+fn do_update_loop(server: &mut ManualServer<impl Config>) -> ShutdownResult {
+    let tick_duration = Duration::from_secs_f64((server.server.shared.0.tick_rate as f64).recip());
 
     loop {
-        let _span = info_span!("update_loop", tick = current_tick).entered();
+        let tick_start = Instant::now();
 
-        if let Some(res) = shared.0.shutdown_result.lock().unwrap().take() {
+        if let Some(res) = server.tick() {
             return res;
         }
 
-        while let Ok(msg) = shared.0.new_clients_rx.try_recv() {
-            info!(
-                username = %msg.ncd.username,
-                uuid = %msg.ncd.uuid,
-                ip = %msg.ncd.remote_addr,
-                "inserting client"
-            );
+        // Sleep for the remainder of the tick.
+        thread::sleep(tick_duration.saturating_sub(tick_start.elapsed()));
+    }
+}
 
-            server
-                .clients
-                .insert(Client::new(msg.ctrl, msg.ncd, Default::default()));
-        }
+/// Runs a single game update, from reading the serverbound packets of every
+/// client to flushing the clientbound packets.
+fn do_tick(server: &mut Server<impl Config>) {
+    let shared = server.shared.clone();
+    let mut timer = TickTimer::start(shared.metrics());
 
-        // Get serverbound packets first so they are not dealt with a tick late.
+    // Get serverbound packets first so they are not dealt with a tick late.
 
-        server.clients.par_iter_mut().for_each(|(_, client)| {
-            client.handle_serverbound_packets(&server.entities);
-        });
+    server.clients.par_iter_mut().for_each(|(_, client)| {
+        client.handle_serverbound_packets(&server.entities);
+    });
 
-        info_span!("configured_update").in_scope(|| shared.config().update(server));
+    // Clicks can modify inventories shared between clients, so they are applied
+    // one client at a time.
+    for (_, client) in server.clients.iter_mut() {
+        client.apply_clicks(&mut server.inventories, &server.recipes);
+    }
 
-        server.worlds.par_iter_mut().for_each(|(id, world)| {
-            world.spatial_index.update(&server.entities, id);
-        });
+    timer.end_phase(TickPhase::ServerboundPackets);
 
-        server.clients.par_iter_mut().for_each(|(_, client)| {
-            client.update(
-                &shared,
-                &server.entities,
-                &server.worlds,
-                &server.player_lists,
-                &server.inventories,
-            );
-        });
+    info_span!("configured_update").in_scope(|| shared.config().update(server));
 
-        server.entities.update();
+    timer.end_phase(TickPhase::UserUpdate);
 
-        server.worlds.par_iter_mut().for_each(|(_, world)| {
-            world.chunks.update();
-        });
+    server.worlds.par_iter_mut().for_each(|(id, world)| {
+        world.spatial_index.update(&server.entities, id);
+    });
 
-        server.player_lists.update();
+    timer.end_phase(TickPhase::SpatialIndex);
 
-        server.inventories.update();
+    server.clients.par_iter_mut().for_each(|(_, client)| {
+        client.update(
+            &shared,
+            &server.entities,
+            &server.worlds,
+            &server.player_lists,
+            &server.inventories,
+            &server.recipes,
+        );
+    });
 
-        // Sleep for the remainder of the tick.
-        let tick_duration = Duration::from_secs_f64((shared.0.tick_rate as f64).recip());
-        thread::sleep(tick_duration.saturating_sub(tick_start.elapsed()));
+    timer.end_phase(TickPhase::ClientUpdate);
 
-        tick_start = Instant::now();
-        current_tick = shared.0.tick_counter.fetch_add(1, Ordering::SeqCst) + 1;
-    }
+    server.entities.update();
+
+    server.worlds.par_iter_mut().for_each(|(_, world)| {
+        world.chunks.update();
+    });
+
+    server.player_lists.update();
+
+    server.inventories.update(&server.clients);
+
+    server.recipes.update();
+
+    timer.end_phase(TickPhase::Cleanup);
+    timer.end_tick();
 }
 
 #[instrument(skip_all)]
@@ -472,10 +728,32 @@ async fn do_accept_loop(server: SharedServer<impl Config>) {
         match server.0.connection_sema.clone().acquire_owned().await {
             Ok(permit) => match listener.accept().await {
                 Ok((stream, remote_addr)) => {
+                    if !server.config().accept_connection(&server, remote_addr) {
+                        trace!(%remote_addr, "connection rejected by config");
+                        continue;
+                    }
+
+                    let Some(guard) = server.0.throttle.try_connect(remote_addr.ip(), permit)
+                    else {
+                        debug!(%remote_addr, "too many connections from address");
+                        continue;
+                    };
+
                     let server = server.clone();
+                    server.metrics().connection_opened();
+
                     tokio::spawn(async move {
-                        handle_connection(server, stream, remote_addr).await;
-                        drop(permit);
+                        let res = timeout(
+                            server.login_timeout(),
+                            handle_connection(server.clone(), stream, remote_addr, guard),
+                        )
+                        .await;
+
+                        if res.is_err() {
+                            debug!(%remote_addr, "connection timed out before reaching the play state");
+                        }
+
+                        server.metrics().connection_closed();
                     });
                 }
                 Err(e) => {
@@ -488,18 +766,32 @@ async fn do_accept_loop(server: SharedServer<impl Config>) {
     }
 }
 
-#[instrument(skip(server, stream))]
+#[instrument(skip(server, stream, guard))]
 async fn handle_connection(
     server: SharedServer<impl Config>,
-    stream: TcpStream,
+    mut stream: TcpStream,
     remote_addr: SocketAddr,
+    guard: ConnectionGuard,
 ) {
     trace!("handling connection");
 
+    let conn_timeout = server.handshake_timeout();
+
     if let Err(e) = stream.set_nodelay(true) {
         error!("failed to set TCP_NODELAY: {e}");
     }
 
+    match legacy_ping::handle_legacy_ping(&server, &mut stream, remote_addr, conn_timeout).await {
+        Ok(true) => return,
+        Ok(false) => {}
+        Err(e) => {
+            if !is_unexpected_eof(&e) {
+                warn!("legacy ping ended with error: {e:#}");
+            }
+            return;
+        }
+    }
+
     let (read, write) = stream.into_split();
 
     let ctrl = InitialPacketController::new(
@@ -507,27 +799,27 @@ async fn handle_connection(
         write,
         PacketEncoder::new(),
         PacketDecoder::new(),
-        Duration::from_secs(5),
+        conn_timeout,
     );
 
-    // TODO: peek stream for 0xFE legacy ping
-
-    if let Err(e) = handle_handshake(server, ctrl, remote_addr).await {
-        // EOF can happen if the client disconnects while joining, which isn't
-        // very erroneous.
-        if let Some(e) = e.downcast_ref::<io::Error>() {
-            if e.kind() == io::ErrorKind::UnexpectedEof {
-                return;
-            }
+    if let Err(e) = handle_handshake(server, ctrl, remote_addr, guard).await {
+        if !is_unexpected_eof(&e) {
+            warn!("connection ended with error: {e:#}");
         }
-        warn!("connection ended with error: {e:#}");
     }
 }
 
+/// EOF can happen if the client disconnects while joining, which isn't very
+/// erroneous.
+fn is_unexpected_eof(e: &anyhow::Error) -> bool {
+    matches!(e.downcast_ref::<io::Error>(), Some(e) if e.kind() == io::ErrorKind::UnexpectedEof)
+}
+
 async fn handle_handshake(
     server: SharedServer<impl Config>,
     mut ctrl: InitialPacketController<OwnedReadHalf, OwnedWriteHalf>,
     remote_addr: SocketAddr,
+    guard: ConnectionGuard,
 ) -> anyhow::Result<()> {
     let handshake = ctrl.recv_packet::<HandshakeOwned>().await?;
 
@@ -546,14 +838,16 @@ async fn handle_handshake(
             .context("error handling login")?
         {
             Some(ncd) => {
-                let msg = NewClientMessage {
-                    ncd,
-                    ctrl: ctrl.into_play_packet_controller(
-                        server.0.incoming_capacity,
-                        server.0.outgoing_capacity,
-                        server.tokio_handle().clone(),
-                    ),
-                };
+                let ctrl = ctrl.into_play_packet_controller(
+                    server.0.incoming_capacity,
+                    server.0.outgoing_capacity,
+                    server.tokio_handle().clone(),
+                    server.metrics(),
+                    ncd.username.as_str(),
+                    guard,
+                );
+
+                let msg = NewClientMessage { ncd, ctrl };
 
                 let _ = server.0.new_clients_tx.send_async(msg).await;
                 Ok(())
@@ -632,6 +926,21 @@ async fn handle_login(
         return Ok(None);
     }
 
+    if !server
+        .0
+        .throttle
+        .try_login(remote_addr.ip(), Instant::now())
+    {
+        debug!(%remote_addr, "login attempt throttled");
+
+        ctrl.send_packet(&DisconnectLogin {
+            reason: "Connection throttled! Please wait before reconnecting.".into(),
+        })
+        .await?;
+
+        return Ok(None);
+    }
+
     let LoginStart {
         username,
         sig_data: _,   // TODO
@@ -656,7 +965,17 @@ async fn handle_login(
         ctrl.set_compression(Some(threshold));
     }
 
-    if let Err(reason) = server.0.cfg.login(server, &ncd).await {
+    if let Some(access) = server.access_control() {
+        if let Err(reason) = access.check_login(&ncd) {
+            info!("disconnect at login: \"{reason}\"");
+            ctrl.send_packet(&DisconnectLogin { reason }).await?;
+            return Ok(None);
+        }
+    }
+
+    let mut plugin = LoginPlugin::new(ctrl);
+
+    if let Err(reason) = server.0.cfg.login(server, &ncd, &mut plugin).await {
         info!("disconnect at login: \"{reason}\"");
         ctrl.send_packet(&DisconnectLogin { reason }).await?;
         return Ok(None);
@@ -671,3 +990,171 @@ async fn handle_login(
 
     Ok(Some(ncd))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use async_trait::async_trait;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use valence_protocol::packets::c2s::handshake::Handshake;
+    use valence_protocol::packets::c2s::login::LoginPluginResponse;
+    use valence_protocol::packets::s2c::login::S2cLoginPacket;
+    use valence_protocol::{Ident, PacketDecoder, PacketEncoder, RawBytes, Text};
+
+    use super::*;
+
+    struct TestConfig;
+
+    impl Config for TestConfig {
+        type ServerState = ();
+        type ClientState = ();
+        type EntityState = ();
+        type WorldState = ();
+        type ChunkState = ();
+        type PlayerListState = ();
+
+        fn address(&self) -> SocketAddr {
+            (Ipv4Addr::LOCALHOST, 0).into()
+        }
+    }
+
+    #[test]
+    fn manual_ticks() {
+        let mut server = start_server_manual(TestConfig, ()).unwrap();
+        let shared = server.server().shared.clone();
+
+        let start = Instant::now();
+
+        for _ in 0..100 {
+            assert!(server.tick().is_none());
+        }
+
+        assert_eq!(shared.current_tick(), 100);
+
+        // Ticks are not limited by the tick rate.
+        let real_time = Duration::from_secs_f64(100.0 / shared.tick_rate() as f64);
+        assert!(start.elapsed() < real_time);
+
+        shared.shutdown::<&str>(Ok(()));
+
+        assert!(matches!(server.tick(), Some(Ok(()))));
+        assert_eq!(shared.current_tick(), 100);
+    }
+
+    struct PluginConfig {
+        address: SocketAddr,
+    }
+
+    #[async_trait]
+    impl Config for PluginConfig {
+        type ServerState = ();
+        type ClientState = ();
+        type EntityState = ();
+        type WorldState = ();
+        type ChunkState = ();
+        type PlayerListState = ();
+
+        fn address(&self) -> SocketAddr {
+            self.address
+        }
+
+        fn connection_mode(&self) -> ConnectionMode {
+            ConnectionMode::Offline
+        }
+
+        async fn login(
+            &self,
+            _shared: &SharedServer<Self>,
+            _ncd: &NewClientData,
+            plugin: &mut LoginPlugin<'_>,
+        ) -> Result<(), Text> {
+            let channel = Ident::new("valence:test").unwrap();
+
+            match plugin.request(channel, b"ping").await {
+                Ok(Some(data)) if data == b"pong" => Ok(()),
+                _ => Err("bad plugin response".into()),
+            }
+        }
+    }
+
+    /// Logs in to the server, answering login plugin requests with
+    /// `response`. Returns the disconnect reason if the login failed.
+    async fn plugin_login(address: SocketAddr, response: Option<&[u8]>) -> Option<String> {
+        let mut stream = loop {
+            // The listener is started asynchronously.
+            match TcpStream::connect(address).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+            }
+        };
+
+        let mut enc = PacketEncoder::new();
+        let mut dec = PacketDecoder::new();
+
+        enc.append_packet(&Handshake {
+            protocol_version: VarInt(PROTOCOL_VERSION),
+            server_address: "localhost",
+            server_port: address.port(),
+            next_state: HandshakeNextState::Login,
+        })
+        .unwrap();
+
+        enc.append_packet(&LoginStart {
+            username: Username::new("test").unwrap(),
+            sig_data: None,
+            profile_id: None,
+        })
+        .unwrap();
+
+        stream.write_all(&enc.take()).await.unwrap();
+
+        loop {
+            while !dec.has_next_packet().unwrap() {
+                dec.reserve(1024);
+                let mut buf = dec.take_capacity();
+                assert_ne!(stream.read_buf(&mut buf).await.unwrap(), 0);
+                dec.queue_bytes(buf);
+            }
+
+            match dec.try_next_packet::<S2cLoginPacket>().unwrap().unwrap() {
+                S2cLoginPacket::LoginPluginRequest(pkt) => {
+                    assert_eq!(pkt.channel.as_str(), "valence:test");
+                    assert_eq!(pkt.data.0, b"ping");
+
+                    enc.append_packet(&LoginPluginResponse {
+                        message_id: pkt.message_id,
+                        data: response.map(RawBytes),
+                    })
+                    .unwrap();
+
+                    stream.write_all(&enc.take()).await.unwrap();
+                }
+                S2cLoginPacket::LoginSuccess(_) => return None,
+                S2cLoginPacket::DisconnectLogin(pkt) => return Some(pkt.reason.to_string()),
+                pkt => panic!("unexpected packet {pkt:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn login_plugin_request() {
+        // Find a free port for the server to use.
+        let address = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let server = start_server_manual(PluginConfig { address }, ()).unwrap();
+        let shared = server.server().shared.clone();
+
+        shared.tokio_handle().block_on(async {
+            assert_eq!(plugin_login(address, Some(b"pong")).await, None);
+
+            assert_eq!(
+                plugin_login(address, None).await.as_deref(),
+                Some("bad plugin response")
+            );
+        });
+    }
+}
@@ -116,6 +116,12 @@ impl ByteSender {
     pub fn is_disconnected(&self) -> bool {
         self.shared.mtx.lock().unwrap().disconnected
     }
+
+    pub fn usage(&self) -> ChannelUsage {
+        ChannelUsage {
+            shared: self.shared.clone(),
+        }
+    }
 }
 
 /// Contains any excess bytes not sent.
@@ -175,6 +181,12 @@ impl ByteReceiver {
     pub fn is_disconnected(&self) -> bool {
         self.shared.mtx.lock().unwrap().disconnected
     }
+
+    pub fn usage(&self) -> ChannelUsage {
+        ChannelUsage {
+            shared: self.shared.clone(),
+        }
+    }
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Error)]
@@ -191,6 +203,24 @@ pub enum RecvError {
     Disconnected,
 }
 
+/// A handle for observing how much of a byte channel's capacity is in use.
+#[derive(Clone)]
+pub struct ChannelUsage {
+    shared: Arc<Shared>,
+}
+
+impl ChannelUsage {
+    /// The number of bytes waiting in the channel.
+    pub fn len(&self) -> usize {
+        self.shared.mtx.lock().unwrap().bytes.len()
+    }
+
+    /// The maximum number of bytes the channel can hold.
+    pub fn limit(&self) -> usize {
+        self.shared.limit
+    }
+}
+
 impl Drop for ByteSender {
     fn drop(&mut self) {
         self.shared.mtx.lock().unwrap().disconnected = true;
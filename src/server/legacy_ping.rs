@@ -0,0 +1,318 @@
+//! Handles the legacy (pre-1.7) server list ping.
+//!
+//! Older clients, as well as many uptime monitors and server list sites, send
+//! a `0xFE` byte instead of a regular handshake packet. The response is a
+//! "kick" packet containing the server's status encoded as a string.
+
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use tracing::trace;
+use valence_protocol::{MINECRAFT_VERSION, PROTOCOL_VERSION};
+
+use crate::config::{Config, ServerListPing};
+use crate::server::SharedServer;
+
+/// How long to wait for more bytes before answering a ping that may be the
+/// prefix of a newer ping format.
+const GRACE_PERIOD: Duration = Duration::from_millis(100);
+
+/// The legacy ping formats, named after the oldest client that uses them.
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum LegacyPing {
+    /// Beta 1.8 to 1.3: a lone `0xFE`.
+    Beta,
+    /// 1.4 to 1.5: `0xFE 0x01`.
+    V1_4,
+    /// 1.6: `0xFE 0x01 0xFA` followed by an `MC|PingHost` plugin message.
+    V1_6 {
+        protocol: u8,
+        hostname: String,
+        port: i32,
+    },
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum ParseState {
+    /// No more bytes are needed.
+    Complete(LegacyPing),
+    /// More bytes could still arrive. The ping is the best interpretation of
+    /// the bytes received so far.
+    Partial(LegacyPing),
+}
+
+/// Answers the connection if it begins with a legacy server list ping.
+///
+/// Returns `Ok(false)` without consuming anything from the stream if this is
+/// not a legacy ping.
+pub(super) async fn handle_legacy_ping(
+    server: &SharedServer<impl Config>,
+    stream: &mut TcpStream,
+    remote_addr: SocketAddr,
+    conn_timeout: Duration,
+) -> anyhow::Result<bool> {
+    let mut first = [0];
+    if timeout(conn_timeout, stream.peek(&mut first)).await?? == 0 || first[0] != 0xfe {
+        return Ok(false);
+    }
+
+    let mut buf = vec![];
+
+    let ping = loop {
+        let state = parse_legacy_ping(&buf);
+
+        let wait = match state {
+            Some(ParseState::Complete(ping)) => break ping,
+            Some(ParseState::Partial(_)) => GRACE_PERIOD,
+            None => conn_timeout,
+        };
+
+        match timeout(wait, stream.read_buf(&mut buf)).await {
+            Ok(Ok(0)) | Err(_) => match state {
+                Some(ParseState::Partial(ping)) => break ping,
+                _ => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+            },
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => return Err(e.into()),
+        }
+    };
+
+    let protocol_version = match &ping {
+        LegacyPing::V1_6 {
+            protocol,
+            hostname,
+            port,
+        } => {
+            trace!("legacy ping for {hostname}:{port}");
+            *protocol as i32
+        }
+        _ => -1,
+    };
+
+    let ServerListPing::Respond {
+        online_players,
+        max_players,
+        description,
+        ..
+    } = server
+        .0
+        .cfg
+        .server_list_ping(server, remote_addr, protocol_version)
+        .await
+    else {
+        return Ok(true);
+    };
+
+    let response = legacy_response(
+        &ping,
+        online_players,
+        max_players,
+        &description.to_legacy_string('§'),
+    );
+
+    timeout(conn_timeout, stream.write_all(&response)).await??;
+
+    Ok(true)
+}
+
+/// Interprets the bytes of a legacy ping. Returns `None` if the buffer is
+/// empty.
+fn parse_legacy_ping(buf: &[u8]) -> Option<ParseState> {
+    match buf {
+        [] => None,
+        [0xfe] => Some(ParseState::Partial(LegacyPing::Beta)),
+        [0xfe, 0x01] | [0xfe, 0x01, 0xfa] => Some(ParseState::Partial(LegacyPing::V1_4)),
+        [0xfe, 0x01, 0xfa, rest @ ..] => Some(match parse_ping_host(rest) {
+            Ok(Some(ping)) => ParseState::Complete(ping),
+            Ok(None) => ParseState::Partial(LegacyPing::V1_4),
+            // Answer malformed plugin messages like a 1.4 ping.
+            Err(()) => ParseState::Complete(LegacyPing::V1_4),
+        }),
+        [0xfe, 0x01, ..] => Some(ParseState::Complete(LegacyPing::V1_4)),
+        _ => Some(ParseState::Complete(LegacyPing::Beta)),
+    }
+}
+
+/// Parses the `MC|PingHost` plugin message sent by 1.6 clients. Returns
+/// `Ok(None)` if the message is incomplete.
+fn parse_ping_host(mut buf: &[u8]) -> Result<Option<LegacyPing>, ()> {
+    fn read_u16(buf: &mut &[u8]) -> Option<u16> {
+        let (bytes, rest) = buf.split_first_chunk::<2>()?;
+        *buf = rest;
+        Some(u16::from_be_bytes(*bytes))
+    }
+
+    fn read_string(buf: &mut &[u8]) -> Option<Result<String, ()>> {
+        let len = read_u16(buf)? as usize * 2;
+        if buf.len() < len {
+            return None;
+        }
+        let (bytes, rest) = buf.split_at(len);
+        *buf = rest;
+        let units = bytes
+            .chunks_exact(2)
+            .map(|c| u16::from_be_bytes([c[0], c[1]]));
+        Some(
+            char::decode_utf16(units)
+                .collect::<Result<_, _>>()
+                .map_err(|_| ()),
+        )
+    }
+
+    let Some(channel) = read_string(&mut buf) else {
+        return Ok(None);
+    };
+
+    if channel? != "MC|PingHost" {
+        return Err(());
+    }
+
+    let Some(data_len) = read_u16(&mut buf) else {
+        return Ok(None);
+    };
+
+    if buf.len() < data_len as usize {
+        return Ok(None);
+    }
+
+    let mut data = &buf[..data_len as usize];
+
+    let Some((&protocol, rest)) = data.split_first() else {
+        return Err(());
+    };
+    data = rest;
+
+    let hostname = read_string(&mut data).ok_or(())??;
+
+    let port = data
+        .first_chunk::<4>()
+        .map(|b| i32::from_be_bytes(*b))
+        .ok_or(())?;
+
+    Ok(Some(LegacyPing::V1_6 {
+        protocol,
+        hostname,
+        port,
+    }))
+}
+
+/// Removes `§` formatting codes, including the character following each `§`.
+fn strip_formatting(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            res.push(c);
+        }
+    }
+
+    res
+}
+
+/// Builds the kick packet answering the given ping. `motd` may contain legacy
+/// `§` formatting codes, which are kept for 1.4 and later clients.
+fn legacy_response(ping: &LegacyPing, online: i32, max: i32, motd: &str) -> Vec<u8> {
+    let string = match ping {
+        // '§' is the field separator in this format, so it cannot appear in the
+        // MOTD.
+        LegacyPing::Beta => format!("{}§{online}§{max}", strip_formatting(motd)),
+        LegacyPing::V1_4 | LegacyPing::V1_6 { .. } => format!(
+            "§1\0{PROTOCOL_VERSION}\0{MINECRAFT_VERSION}\0{}\0{online}\0{max}",
+            motd.replace('\0', "")
+        ),
+    };
+
+    let units: Vec<u16> = string.encode_utf16().take(u16::MAX as usize).collect();
+
+    let mut buf = Vec::with_capacity(3 + units.len() * 2);
+    buf.push(0xff);
+    buf.extend_from_slice(&(units.len() as u16).to_be_bytes());
+    for unit in units {
+        buf.extend_from_slice(&unit.to_be_bytes());
+    }
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_protocol::text::{Color, TextFormat};
+
+    use super::*;
+
+    fn utf16(s: &str) -> Vec<u8> {
+        let mut buf = (s.encode_utf16().count() as u16).to_be_bytes().to_vec();
+        buf.extend(s.encode_utf16().flat_map(u16::to_be_bytes));
+        buf
+    }
+
+    #[test]
+    fn parse_legacy_formats() {
+        assert_eq!(parse_legacy_ping(&[]), None);
+        assert_eq!(
+            parse_legacy_ping(&[0xfe]),
+            Some(ParseState::Partial(LegacyPing::Beta))
+        );
+        assert_eq!(
+            parse_legacy_ping(&[0xfe, 0x01]),
+            Some(ParseState::Partial(LegacyPing::V1_4))
+        );
+
+        let mut data = vec![74];
+        data.extend(utf16("localhost"));
+        data.extend(25565_i32.to_be_bytes());
+
+        let mut buf = vec![0xfe, 0x01, 0xfa];
+        buf.extend(utf16("MC|PingHost"));
+        buf.extend((data.len() as u16).to_be_bytes());
+        buf.extend(data);
+
+        assert_eq!(
+            parse_legacy_ping(&buf[..buf.len() - 1]),
+            Some(ParseState::Partial(LegacyPing::V1_4))
+        );
+        assert_eq!(
+            parse_legacy_ping(&buf),
+            Some(ParseState::Complete(LegacyPing::V1_6 {
+                protocol: 74,
+                hostname: "localhost".into(),
+                port: 25565,
+            }))
+        );
+    }
+
+    #[test]
+    fn legacy_response_format() {
+        let res = legacy_response(&LegacyPing::Beta, 3, 20, "A §cMOTD§");
+        assert_eq!(&res[..1], &[0xff]);
+        assert_eq!(&res[1..], &utf16("A MOTD§3§20"));
+
+        let res = legacy_response(&LegacyPing::V1_4, 3, 20, "§cMOTD");
+        assert_eq!(
+            &res[1..],
+            &utf16(&format!(
+                "§1\0{PROTOCOL_VERSION}\0{MINECRAFT_VERSION}\0§cMOTD\03\020"
+            ))
+        );
+
+        let ping = LegacyPing::V1_6 {
+            protocol: 74,
+            hostname: "localhost".into(),
+            port: 25565,
+        };
+        let motd = ("A ".color(Color::RED) + "MOTD".bold()).to_legacy_string('§');
+        let res = legacy_response(&ping, 3, 20, &motd);
+        assert_eq!(
+            &res[1..],
+            &utf16(&format!(
+                "§1\0{PROTOCOL_VERSION}\0{MINECRAFT_VERSION}\0§cA §lMOTD\03\020"
+            ))
+        );
+    }
+}
@@ -189,6 +189,64 @@ pub(super) fn bungeecord(
     })
 }
 
+/// Exchanges [login plugin messages] with a client during
+/// [`Config::login`].
+///
+/// Login plugin messages are commonly used for mod loader handshakes and
+/// custom authentication. Each request is answered by the client, even if it
+/// does not understand the channel.
+///
+/// [login plugin messages]: https://wiki.vg/Plugin_channels
+/// [`Config::login`]: crate::config::Config::login
+pub struct LoginPlugin<'a> {
+    ctrl: &'a mut InitialPacketController<OwnedReadHalf, OwnedWriteHalf>,
+    next_message_id: i32,
+}
+
+impl<'a> LoginPlugin<'a> {
+    pub(super) fn new(
+        ctrl: &'a mut InitialPacketController<OwnedReadHalf, OwnedWriteHalf>,
+    ) -> Self {
+        Self {
+            ctrl,
+            next_message_id: 0,
+        }
+    }
+
+    /// Sends a login plugin request with the given data on `channel` and
+    /// waits for the client's response.
+    ///
+    /// Returns the data sent back by the client, or `None` if the client did
+    /// not understand the channel. An error is returned if the connection
+    /// fails or the client responds to a different request.
+    pub async fn request(
+        &mut self,
+        channel: Ident<&str>,
+        data: &[u8],
+    ) -> anyhow::Result<Option<Vec<u8>>> {
+        let message_id = self.next_message_id;
+        self.next_message_id = self.next_message_id.wrapping_add(1);
+
+        self.ctrl
+            .send_packet(&LoginPluginRequest {
+                message_id: VarInt(message_id),
+                channel,
+                data: RawBytes(data),
+            })
+            .await?;
+
+        let response: LoginPluginResponse = self.ctrl.recv_packet().await?;
+
+        ensure!(
+            response.message_id.0 == message_id,
+            "mismatched plugin response ID (got {}, expected {message_id})",
+            response.message_id.0,
+        );
+
+        Ok(response.data.map(|data| data.0.to_vec()))
+    }
+}
+
 fn auth_digest(bytes: &[u8]) -> String {
     BigInt::from_signed_bytes_be(bytes).to_str_radix(16)
 }
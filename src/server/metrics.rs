@@ -0,0 +1,113 @@
+//! A minimal HTTP listener serving [`Metrics`](crate::metrics::Metrics) to
+//! Prometheus and similar tools.
+
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+use tracing::{error, instrument, trace, warn};
+
+use crate::config::Config;
+use crate::server::{is_unexpected_eof, SharedServer};
+
+/// The maximum size of an HTTP request head.
+const MAX_REQUEST_SIZE: usize = 8192;
+
+#[instrument(skip_all)]
+pub(super) async fn do_metrics_loop(server: SharedServer<impl Config>, address: SocketAddr) {
+    let listener = match TcpListener::bind(address).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            server.shutdown(Err(e).context("failed to start metrics listener"));
+            return;
+        }
+    };
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, remote_addr)) => {
+                let server = server.clone();
+
+                tokio::spawn(async move {
+                    trace!(%remote_addr, "handling metrics request");
+
+                    let res = timeout(
+                        Duration::from_secs(5),
+                        handle_metrics_request(&server, stream),
+                    )
+                    .await;
+
+                    match res {
+                        Ok(Ok(())) => {}
+                        Ok(Err(e)) => {
+                            if !is_unexpected_eof(&e) {
+                                warn!("metrics request ended with error: {e:#}");
+                            }
+                        }
+                        Err(_) => warn!("metrics request from {remote_addr} timed out"),
+                    }
+                });
+            }
+            Err(e) => {
+                error!("failed to accept incoming metrics connection: {e}");
+            }
+        }
+    }
+}
+
+async fn handle_metrics_request(
+    server: &SharedServer<impl Config>,
+    mut stream: TcpStream,
+) -> anyhow::Result<()> {
+    let mut buf = Vec::new();
+
+    // Only the request line matters, but the whole head is read so that the
+    // client isn't reset before it finishes sending.
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+        if buf.len() >= MAX_REQUEST_SIZE {
+            bail!("request head is too large");
+        }
+
+        if stream.read_buf(&mut buf).await? == 0 {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+    }
+
+    let request_line = buf.split(|&b| b == b'\r').next().unwrap_or_default();
+    let mut parts = request_line.split(|&b| b == b' ');
+
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+
+    let response = if method != b"GET" {
+        response(
+            "405 Method Not Allowed",
+            "text/plain",
+            "method not allowed\n",
+        )
+    } else if path == b"/metrics" {
+        response(
+            "200 OK",
+            "text/plain; version=0.0.4",
+            &server.metrics().render(),
+        )
+    } else {
+        response("404 Not Found", "text/plain", "not found\n")
+    };
+
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+
+    Ok(())
+}
+
+fn response(status: &str, content_type: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: \
+         close\r\n\r\n{body}",
+        body.len()
+    )
+}
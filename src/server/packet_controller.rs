@@ -1,4 +1,5 @@
 use std::io::ErrorKind;
+use std::sync::Arc;
 use std::time::Duration;
 
 use anyhow::Result;
@@ -10,7 +11,9 @@ use tokio::time::timeout;
 use tracing::debug;
 use valence_protocol::{Decode, Encode, Packet, PacketDecoder, PacketEncoder};
 
+use crate::metrics::{ClientMetrics, Metrics};
 use crate::server::byte_channel::{byte_channel, ByteReceiver, ByteSender, TryRecvError};
+use crate::server::throttle::ConnectionGuard;
 
 pub struct InitialPacketController<R, W> {
     reader: R,
@@ -116,6 +119,9 @@ where
         incoming_limit: usize,
         outgoing_limit: usize,
         handle: Handle,
+        metrics: &Metrics,
+        username: &str,
+        guard: ConnectionGuard,
     ) -> PlayPacketController
     where
         R: Send + 'static,
@@ -162,14 +168,19 @@ where
             }
         });
 
+        let metrics =
+            metrics.register_client(username, incoming_receiver.usage(), outgoing_sender.usage());
+
         PlayPacketController {
             enc: self.enc,
             dec: self.dec,
             send: outgoing_sender,
             recv: incoming_receiver,
-            reader_task,
+            metrics,
+            reader_task: Some(reader_task),
             writer_task: Some(writer_task),
             handle,
+            _guard: Some(guard),
         }
     }
 }
@@ -182,12 +193,39 @@ pub struct PlayPacketController {
     dec: PacketDecoder,
     send: ByteSender,
     recv: ByteReceiver,
-    reader_task: JoinHandle<()>,
+    metrics: Arc<ClientMetrics>,
+    reader_task: Option<JoinHandle<()>>,
     writer_task: Option<JoinHandle<()>>,
     handle: Handle,
+    /// Keeps the connection counted against the server's connection limits.
+    _guard: Option<ConnectionGuard>,
 }
 
 impl PlayPacketController {
+    /// Creates a controller which exchanges packet data with the other ends
+    /// of the given byte channels instead of a socket.
+    pub fn from_channels(
+        send: ByteSender,
+        recv: ByteReceiver,
+        handle: Handle,
+        metrics: &Metrics,
+        username: &str,
+    ) -> Self {
+        let metrics = metrics.register_client(username, recv.usage(), send.usage());
+
+        Self {
+            enc: PacketEncoder::new(),
+            dec: PacketDecoder::new(),
+            send,
+            recv,
+            metrics,
+            reader_task: None,
+            writer_task: None,
+            handle,
+            _guard: None,
+        }
+    }
+
     pub fn append_packet<P>(&mut self, pkt: &P) -> Result<()>
     where
         P: Encode + Packet + ?Sized,
@@ -213,6 +251,7 @@ impl PlayPacketController {
     pub fn try_recv(&mut self) -> bool {
         match self.recv.try_recv() {
             Ok(bytes) => {
+                self.metrics.add_bytes_received(bytes.len());
                 self.dec.queue_bytes(bytes);
                 true
             }
@@ -228,6 +267,7 @@ impl PlayPacketController {
 
     pub fn flush(&mut self) -> Result<()> {
         let bytes = self.enc.take();
+        self.metrics.add_bytes_sent(bytes.len());
         self.send.try_send(bytes)?;
         Ok(())
     }
@@ -235,7 +275,9 @@ impl PlayPacketController {
 
 impl Drop for PlayPacketController {
     fn drop(&mut self) {
-        self.reader_task.abort();
+        if let Some(reader_task) = &self.reader_task {
+            reader_task.abort();
+        }
 
         let _ = self.flush();
 
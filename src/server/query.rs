@@ -0,0 +1,372 @@
+//! The UDP [Query] protocol used by server list sites and monitoring tools.
+//!
+//! [Query]: https://wiki.vg/Query
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tokio::net::UdpSocket;
+use tracing::{instrument, warn};
+use valence_protocol::MINECRAFT_VERSION;
+
+use crate::config::{Config, ServerListPing};
+use crate::server::SharedServer;
+
+/// The length of the periods challenge tokens are derived from. A token stays
+/// valid until the end of the period after the one it was issued in.
+const CHALLENGE_PERIOD: Duration = Duration::from_secs(30);
+
+const TYPE_HANDSHAKE: u8 = 9;
+const TYPE_STAT: u8 = 0;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Request {
+    Handshake { session_id: i32 },
+    BasicStat { session_id: i32, token: i32 },
+    FullStat { session_id: i32, token: i32 },
+}
+
+/// The server status reported in stat responses.
+#[derive(Clone, Debug)]
+struct Status {
+    motd: String,
+    online_players: i32,
+    max_players: i32,
+    players: Vec<String>,
+    address: SocketAddr,
+}
+
+/// Issues challenge tokens without storing anything per remote address, so
+/// spoofed handshakes cannot use up memory. Each token is an HMAC of the
+/// remote address and the current period under a random secret.
+struct Challenges {
+    secret: [u8; 32],
+    start: Instant,
+}
+
+impl Challenges {
+    fn new() -> Self {
+        Self {
+            secret: rand::random(),
+            start: Instant::now(),
+        }
+    }
+
+    fn period(&self, now: Instant) -> u64 {
+        now.saturating_duration_since(self.start).as_secs() / CHALLENGE_PERIOD.as_secs()
+    }
+
+    fn token(&self, addr: SocketAddr, period: u64) -> i32 {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret).unwrap();
+
+        match addr.ip() {
+            IpAddr::V4(ip) => mac.update(&ip.octets()),
+            IpAddr::V6(ip) => mac.update(&ip.octets()),
+        }
+        mac.update(&addr.port().to_be_bytes());
+        mac.update(&period.to_be_bytes());
+
+        let hash = mac.finalize().into_bytes();
+
+        i32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]]) & 0x00ff_ffff
+    }
+
+    fn issue(&self, addr: SocketAddr, now: Instant) -> i32 {
+        self.token(addr, self.period(now))
+    }
+
+    fn validate(&self, addr: SocketAddr, token: i32, now: Instant) -> bool {
+        let period = self.period(now);
+
+        token == self.token(addr, period) || (period > 0 && token == self.token(addr, period - 1))
+    }
+}
+
+#[instrument(skip(server))]
+pub(super) async fn do_query_loop(server: SharedServer<impl Config>, address: SocketAddr) {
+    let socket = match UdpSocket::bind(address).await {
+        Ok(socket) => Arc::new(socket),
+        Err(e) => {
+            server.shutdown(Err(e).context("failed to start query listener"));
+            return;
+        }
+    };
+
+    let challenges = Challenges::new();
+    let mut buf = [0; 1460];
+
+    loop {
+        let (len, remote_addr) = match socket.recv_from(&mut buf).await {
+            Ok(res) => res,
+            Err(e) => {
+                warn!("failed to receive query packet: {e}");
+                continue;
+            }
+        };
+
+        let Some(req) = parse_request(&buf[..len]) else {
+            continue;
+        };
+
+        match req {
+            Request::Handshake { session_id } => {
+                let token = challenges.issue(remote_addr, Instant::now());
+                let res = handshake_response(session_id, token);
+
+                if let Err(e) = socket.send_to(&res, remote_addr).await {
+                    warn!("failed to send query handshake: {e}");
+                }
+            }
+            Request::BasicStat { session_id, token } | Request::FullStat { session_id, token } => {
+                if !challenges.validate(remote_addr, token, Instant::now()) {
+                    continue;
+                }
+
+                let server = server.clone();
+                let socket = socket.clone();
+
+                // The callback may take a while, so don't block other requests on it.
+                tokio::spawn(async move {
+                    let ServerListPing::Respond {
+                        online_players,
+                        max_players,
+                        player_sample,
+                        description,
+                        ..
+                    } = server
+                        .0
+                        .cfg
+                        .server_list_ping(&server, remote_addr, -1)
+                        .await
+                    else {
+                        return;
+                    };
+
+                    let server_addr = server.address();
+
+                    let status = Status {
+                        motd: description.to_string(),
+                        online_players,
+                        max_players,
+                        players: player_sample.iter().map(|p| p.name.to_string()).collect(),
+                        address: SocketAddr::new(
+                            host_ip(server_addr.ip(), remote_addr).await,
+                            server_addr.port(),
+                        ),
+                    };
+
+                    let res = if matches!(req, Request::FullStat { .. }) {
+                        full_stat_response(session_id, &status)
+                    } else {
+                        basic_stat_response(session_id, &status)
+                    };
+
+                    if let Err(e) = socket.send_to(&res, remote_addr).await {
+                        warn!("failed to send query stat: {e}");
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// Returns the IP address to report to `remote_addr` for a server bound to
+/// `server_ip`.
+///
+/// A server bound to the unspecified address listens on every interface, so
+/// the address of the interface the remote address is reached through is
+/// reported instead.
+async fn host_ip(server_ip: IpAddr, remote_addr: SocketAddr) -> IpAddr {
+    if !server_ip.is_unspecified() {
+        return server_ip;
+    }
+
+    let unspecified: IpAddr = match remote_addr {
+        SocketAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        SocketAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+
+    // Connecting a UDP socket sends nothing, but picks the local address
+    // packets to the remote address would be sent from.
+    let local_addr = async {
+        let socket = UdpSocket::bind((unspecified, 0)).await?;
+        socket.connect(remote_addr).await?;
+        socket.local_addr()
+    };
+
+    match local_addr.await {
+        Ok(addr) => addr.ip(),
+        Err(e) => {
+            warn!("failed to find local address for query response: {e}");
+            server_ip
+        }
+    }
+}
+
+fn parse_request(buf: &[u8]) -> Option<Request> {
+    let [0xfe, 0xfd, kind, rest @ ..] = buf else {
+        return None;
+    };
+
+    let session_id = i32::from_be_bytes(*rest.first_chunk::<4>()?) & 0x0f0f0f0f;
+    let rest = &rest[4..];
+
+    match *kind {
+        TYPE_HANDSHAKE => Some(Request::Handshake { session_id }),
+        TYPE_STAT => {
+            let token = i32::from_be_bytes(*rest.first_chunk::<4>()?);
+
+            // Full stat requests are padded with four extra bytes.
+            if rest.len() >= 8 {
+                Some(Request::FullStat { session_id, token })
+            } else {
+                Some(Request::BasicStat { session_id, token })
+            }
+        }
+        _ => None,
+    }
+}
+
+fn response_header(kind: u8, session_id: i32) -> Vec<u8> {
+    let mut buf = vec![kind];
+    buf.extend_from_slice(&session_id.to_be_bytes());
+    buf
+}
+
+/// Appends a null-terminated string. Any null bytes in the string are removed.
+fn write_str(buf: &mut Vec<u8>, s: &str) {
+    buf.extend(s.bytes().filter(|&b| b != 0));
+    buf.push(0);
+}
+
+fn handshake_response(session_id: i32, token: i32) -> Vec<u8> {
+    let mut buf = response_header(TYPE_HANDSHAKE, session_id);
+    write_str(&mut buf, &token.to_string());
+    buf
+}
+
+fn basic_stat_response(session_id: i32, status: &Status) -> Vec<u8> {
+    let mut buf = response_header(TYPE_STAT, session_id);
+    write_str(&mut buf, &status.motd);
+    write_str(&mut buf, "SMP");
+    write_str(&mut buf, "world");
+    write_str(&mut buf, &status.online_players.to_string());
+    write_str(&mut buf, &status.max_players.to_string());
+    // The port is the only little-endian value in the protocol.
+    buf.extend_from_slice(&status.address.port().to_le_bytes());
+    write_str(&mut buf, &status.address.ip().to_string());
+    buf
+}
+
+fn full_stat_response(session_id: i32, status: &Status) -> Vec<u8> {
+    let mut buf = response_header(TYPE_STAT, session_id);
+    buf.extend_from_slice(b"splitnum\0\x80\0");
+
+    for (key, value) in [
+        ("hostname", status.motd.as_str()),
+        ("gametype", "SMP"),
+        ("game_id", "MINECRAFT"),
+        ("version", MINECRAFT_VERSION),
+        ("plugins", ""),
+        ("map", "world"),
+        ("numplayers", &status.online_players.to_string()),
+        ("maxplayers", &status.max_players.to_string()),
+        ("hostport", &status.address.port().to_string()),
+        ("hostip", &status.address.ip().to_string()),
+    ] {
+        write_str(&mut buf, key);
+        write_str(&mut buf, value);
+    }
+    buf.push(0);
+
+    buf.extend_from_slice(b"\x01player_\0\0");
+    for name in &status.players {
+        write_str(&mut buf, name);
+    }
+    buf.push(0);
+
+    buf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_query_requests() {
+        assert_eq!(
+            parse_request(&[0xfe, 0xfd, 9, 0, 0, 0, 1]),
+            Some(Request::Handshake { session_id: 1 })
+        );
+        assert_eq!(
+            parse_request(&[0xfe, 0xfd, 0, 0x7f, 0, 0, 1, 0, 0x91, 0x29, 0x5b]),
+            Some(Request::BasicStat {
+                session_id: 0x0f000001,
+                token: 0x0091295b
+            })
+        );
+        assert_eq!(
+            parse_request(&[0xfe, 0xfd, 0, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 0]),
+            Some(Request::FullStat {
+                session_id: 1,
+                token: 2
+            })
+        );
+        assert_eq!(parse_request(&[0xfe, 0xfd, 0, 0, 0, 0, 1]), None);
+        assert_eq!(parse_request(&[0xfe, 0x00, 9, 0, 0, 0, 1]), None);
+    }
+
+    #[test]
+    fn challenge_tokens() {
+        let challenges = Challenges::new();
+        let a = "127.0.0.1:1000".parse().unwrap();
+        let b = "127.0.0.1:1001".parse().unwrap();
+
+        let now = challenges.start;
+        let token = challenges.issue(a, now);
+        assert!(challenges.validate(a, token, now));
+        assert!(!challenges.validate(b, token, now));
+        assert!(!challenges.validate(a, token.wrapping_add(1), now));
+
+        // Tokens expire after the period following the one they were issued in.
+        assert!(challenges.validate(a, token, now + CHALLENGE_PERIOD));
+        assert!(!challenges.validate(a, token, now + CHALLENGE_PERIOD * 2));
+        assert_ne!(challenges.issue(a, now + CHALLENGE_PERIOD), token);
+    }
+
+    #[tokio::test]
+    async fn host_ip_of_unspecified_address() {
+        let remote_addr = "127.0.0.1:25565".parse().unwrap();
+        let server_ip = "192.168.1.2".parse().unwrap();
+
+        assert_eq!(host_ip(server_ip, remote_addr).await, server_ip);
+        assert_eq!(
+            host_ip(Ipv4Addr::UNSPECIFIED.into(), remote_addr).await,
+            Ipv4Addr::LOCALHOST
+        );
+    }
+
+    #[test]
+    fn stat_responses() {
+        let status = Status {
+            motd: "A Minecraft Server".into(),
+            online_players: 2,
+            max_players: 20,
+            players: vec!["Notch".into(), "jeb_".into()],
+            address: "127.0.0.1:25565".parse().unwrap(),
+        };
+
+        assert_eq!(
+            basic_stat_response(1, &status),
+            b"\0\0\0\0\x01A Minecraft Server\0SMP\0world\x002\x0020\0\xdd\x63127.0.0.1\0"
+        );
+
+        let full = full_stat_response(1, &status);
+        assert!(full.starts_with(b"\0\0\0\0\x01splitnum\0\x80\0hostname\0A Minecraft Server\0"));
+        assert!(full.ends_with(b"\0\0\x01player_\0\0Notch\0jeb_\0\0"));
+    }
+}
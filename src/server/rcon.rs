@@ -0,0 +1,406 @@
+//! The [RCON] listener.
+//!
+//! [RCON]: https://wiki.vg/RCON
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, ensure, Context};
+use flume::Sender;
+use subtle::ConstantTimeEq;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::{oneshot, Semaphore};
+use tokio::time::timeout;
+use tracing::{error, instrument, trace, warn};
+
+use crate::config::Config;
+use crate::rcon::RconCommand;
+use crate::server::{is_unexpected_eof, SharedServer};
+
+const TYPE_RESPONSE: i32 = 0;
+const TYPE_COMMAND: i32 = 2;
+const TYPE_AUTH_RESPONSE: i32 = 2;
+const TYPE_LOGIN: i32 = 3;
+
+/// The maximum length of a packet sent by a client, excluding the length
+/// prefix.
+const MAX_PACKET_LEN: i32 = 1460;
+/// The maximum payload length of a single response packet. Longer responses
+/// are split across multiple packets.
+const MAX_RESPONSE_PAYLOAD_LEN: usize = 4096;
+/// The maximum number of RCON connections handled at once. Further
+/// connections are not accepted until another one is closed.
+const MAX_CONNECTIONS: usize = 8;
+/// How long a connection can go without sending a packet before it is
+/// closed.
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+/// The number of wrong passwords a connection can send before it is closed.
+const MAX_FAILED_LOGINS: u32 = 3;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct RconPacket {
+    request_id: i32,
+    kind: i32,
+    payload: String,
+}
+
+#[instrument(skip(server, password, commands))]
+pub(super) async fn do_rcon_loop(
+    server: SharedServer<impl Config>,
+    address: SocketAddr,
+    password: Arc<str>,
+    commands: Sender<RconCommand>,
+) {
+    let listener = match TcpListener::bind(address).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            server.shutdown(Err(e).context("failed to start RCON listener"));
+            return;
+        }
+    };
+
+    accept_rcon_connections(listener, password, commands).await
+}
+
+async fn accept_rcon_connections(
+    listener: TcpListener,
+    password: Arc<str>,
+    commands: Sender<RconCommand>,
+) {
+    let sema = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+
+    loop {
+        // The semaphore is never closed.
+        let permit = sema.clone().acquire_owned().await.unwrap();
+
+        match listener.accept().await {
+            Ok((stream, remote_addr)) => {
+                let password = password.clone();
+                let commands = commands.clone();
+
+                tokio::spawn(async move {
+                    trace!(%remote_addr, "handling RCON connection");
+
+                    let (read, write) = stream.into_split();
+
+                    if let Err(e) =
+                        handle_rcon_connection(read, write, remote_addr, &password, &commands).await
+                    {
+                        if !is_unexpected_eof(&e) {
+                            warn!("RCON connection ended with error: {e:#}");
+                        }
+                    }
+
+                    drop(permit);
+                });
+            }
+            Err(e) => {
+                error!("failed to accept incoming RCON connection: {e}");
+            }
+        }
+    }
+}
+
+async fn handle_rcon_connection(
+    mut read: impl AsyncRead + Unpin,
+    mut write: impl AsyncWrite + Unpin,
+    remote_addr: SocketAddr,
+    password: &str,
+    commands: &Sender<RconCommand>,
+) -> anyhow::Result<()> {
+    let mut authenticated = false;
+    let mut failed_logins = 0;
+
+    loop {
+        let Ok(packet) = timeout(READ_TIMEOUT, read_packet(&mut read)).await else {
+            bail!("timed out waiting for an RCON packet");
+        };
+
+        let RconPacket {
+            request_id,
+            kind,
+            payload,
+        } = packet?;
+
+        let response = match kind {
+            TYPE_LOGIN => {
+                // The comparison takes the same time no matter where the first wrong byte is.
+                authenticated = payload.as_bytes().ct_eq(password.as_bytes()).into();
+
+                if authenticated {
+                    encode_packet(request_id, TYPE_AUTH_RESPONSE, "")
+                } else {
+                    failed_logins += 1;
+                    encode_packet(-1, TYPE_AUTH_RESPONSE, "")
+                }
+            }
+            TYPE_COMMAND if authenticated => {
+                let (tx, rx) = oneshot::channel();
+
+                if commands
+                    .send_async(RconCommand::new(payload, remote_addr, tx))
+                    .await
+                    .is_err()
+                {
+                    bail!("server is shutting down");
+                }
+
+                let response = rx.await.unwrap_or_default();
+
+                split_response(&response)
+                    .flat_map(|chunk| encode_packet(request_id, TYPE_RESPONSE, chunk))
+                    .collect()
+            }
+            TYPE_COMMAND => encode_packet(-1, TYPE_AUTH_RESPONSE, ""),
+            _ => encode_packet(
+                request_id,
+                TYPE_RESPONSE,
+                &format!("Unknown request {kind:x}"),
+            ),
+        };
+
+        write.write_all(&response).await?;
+
+        ensure!(
+            failed_logins < MAX_FAILED_LOGINS,
+            "too many failed RCON login attempts"
+        );
+    }
+}
+
+async fn read_packet(mut r: impl AsyncRead + Unpin) -> anyhow::Result<RconPacket> {
+    let len = r.read_i32_le().await?;
+
+    ensure!(
+        (10..=MAX_PACKET_LEN).contains(&len),
+        "invalid RCON packet length of {len}"
+    );
+
+    let mut buf = vec![0; len as usize];
+    r.read_exact(&mut buf).await?;
+
+    let request_id = i32::from_le_bytes(buf[0..4].try_into().unwrap());
+    let kind = i32::from_le_bytes(buf[4..8].try_into().unwrap());
+
+    // The payload is null-terminated and followed by an extra null byte.
+    let payload = &buf[8..];
+    let payload = match payload.iter().position(|&b| b == 0) {
+        Some(end) => &payload[..end],
+        None => payload,
+    };
+
+    Ok(RconPacket {
+        request_id,
+        kind,
+        payload: String::from_utf8_lossy(payload).into_owned(),
+    })
+}
+
+fn encode_packet(request_id: i32, kind: i32, payload: &str) -> Vec<u8> {
+    let len = 4 + 4 + payload.len() + 2;
+
+    let mut buf = Vec::with_capacity(4 + len);
+    buf.extend_from_slice(&(len as i32).to_le_bytes());
+    buf.extend_from_slice(&request_id.to_le_bytes());
+    buf.extend_from_slice(&kind.to_le_bytes());
+    buf.extend_from_slice(payload.as_bytes());
+    buf.extend_from_slice(&[0, 0]);
+    buf
+}
+
+/// Splits a response into payloads no longer than
+/// [`MAX_RESPONSE_PAYLOAD_LEN`] without splitting characters. An empty
+/// response yields a single empty payload.
+fn split_response(mut response: &str) -> impl Iterator<Item = &str> {
+    let mut first = true;
+
+    std::iter::from_fn(move || {
+        if response.is_empty() && !first {
+            return None;
+        }
+        first = false;
+
+        let mut end = response.len().min(MAX_RESPONSE_PAYLOAD_LEN);
+        while !response.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        let (chunk, rest) = response.split_at(end);
+        response = rest;
+        Some(chunk)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use tokio::io::duplex;
+    use tokio::net::TcpStream;
+
+    use super::*;
+
+    const ADDR: &str = "127.0.0.1:25575";
+
+    #[test]
+    fn split_long_responses() {
+        assert_eq!(split_response("").collect::<Vec<_>>(), [""]);
+        assert_eq!(split_response("abc").collect::<Vec<_>>(), ["abc"]);
+
+        let long = "é".repeat(MAX_RESPONSE_PAYLOAD_LEN);
+        let chunks: Vec<_> = split_response(&long).collect();
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks.iter().all(|c| c.len() <= MAX_RESPONSE_PAYLOAD_LEN));
+        assert_eq!(chunks.concat(), long);
+    }
+
+    #[tokio::test]
+    async fn rcon_session() {
+        let (client, server) = duplex(64 * 1024);
+        let (server_read, server_write) = tokio::io::split(server);
+        let (mut client_read, mut client_write) = tokio::io::split(client);
+        let (commands_tx, commands_rx) = flume::unbounded();
+
+        tokio::spawn(async move {
+            let _ = handle_rcon_connection(
+                server_read,
+                server_write,
+                ADDR.parse().unwrap(),
+                "hunter2",
+                &commands_tx,
+            )
+            .await;
+        });
+
+        // Commands are rejected before logging in.
+        client_write
+            .write_all(&encode_packet(1, TYPE_COMMAND, "list"))
+            .await
+            .unwrap();
+        assert_eq!(read_packet(&mut client_read).await.unwrap().request_id, -1);
+
+        client_write
+            .write_all(&encode_packet(2, TYPE_LOGIN, "wrong"))
+            .await
+            .unwrap();
+        assert_eq!(read_packet(&mut client_read).await.unwrap().request_id, -1);
+
+        client_write
+            .write_all(&encode_packet(3, TYPE_LOGIN, "hunter2"))
+            .await
+            .unwrap();
+        assert_eq!(
+            read_packet(&mut client_read).await.unwrap(),
+            RconPacket {
+                request_id: 3,
+                kind: TYPE_AUTH_RESPONSE,
+                payload: String::new(),
+            }
+        );
+
+        client_write
+            .write_all(&encode_packet(4, TYPE_COMMAND, "list"))
+            .await
+            .unwrap();
+
+        let cmd = commands_rx.recv_async().await.unwrap();
+        assert_eq!(cmd.command(), "list");
+        cmd.respond("There are 0 players online");
+
+        assert_eq!(
+            read_packet(&mut client_read).await.unwrap(),
+            RconPacket {
+                request_id: 4,
+                kind: TYPE_RESPONSE,
+                payload: "There are 0 players online".into(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_logins_close_connection() {
+        let (client, server) = duplex(64 * 1024);
+        let (server_read, server_write) = tokio::io::split(server);
+        let (mut client_read, mut client_write) = tokio::io::split(client);
+        let (commands_tx, _commands_rx) = flume::unbounded();
+
+        let handle = tokio::spawn(async move {
+            handle_rcon_connection(
+                server_read,
+                server_write,
+                ADDR.parse().unwrap(),
+                "hunter2",
+                &commands_tx,
+            )
+            .await
+        });
+
+        for request_id in 0..MAX_FAILED_LOGINS as i32 {
+            client_write
+                .write_all(&encode_packet(request_id, TYPE_LOGIN, "wrong"))
+                .await
+                .unwrap();
+            assert_eq!(read_packet(&mut client_read).await.unwrap().request_id, -1);
+        }
+
+        assert!(handle.await.unwrap().is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn idle_connections_time_out() {
+        let (client, server) = duplex(64 * 1024);
+        let (server_read, server_write) = tokio::io::split(server);
+        let (commands_tx, _commands_rx) = flume::unbounded();
+
+        let err = handle_rcon_connection(
+            server_read,
+            server_write,
+            ADDR.parse().unwrap(),
+            "hunter2",
+            &commands_tx,
+        )
+        .await
+        .unwrap_err();
+
+        assert!(err.to_string().contains("timed out"));
+        drop(client);
+    }
+
+    async fn login(stream: &mut TcpStream) -> anyhow::Result<RconPacket> {
+        stream
+            .write_all(&encode_packet(1, TYPE_LOGIN, "hunter2"))
+            .await?;
+        read_packet(stream).await
+    }
+
+    #[tokio::test]
+    async fn connections_are_capped() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let (commands_tx, _commands_rx) = flume::unbounded();
+
+        tokio::spawn(accept_rcon_connections(
+            listener,
+            "hunter2".into(),
+            commands_tx,
+        ));
+
+        let mut streams = vec![];
+        for _ in 0..MAX_CONNECTIONS {
+            let mut stream = TcpStream::connect(address).await.unwrap();
+            assert_eq!(login(&mut stream).await.unwrap().request_id, 1);
+            streams.push(stream);
+        }
+
+        // The extra connection is not handled until another one is closed.
+        let mut extra = TcpStream::connect(address).await.unwrap();
+        assert!(timeout(Duration::from_millis(100), login(&mut extra))
+            .await
+            .is_err());
+
+        streams.pop();
+        assert_eq!(read_packet(&mut extra).await.unwrap().request_id, 1);
+    }
+}
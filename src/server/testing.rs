@@ -0,0 +1,342 @@
+//! Running a server in-process for tests.
+//!
+//! A [`TestServer`] owns a [`Server`] without binding any sockets and only
+//! advances when [`TestServer::tick`] is called. Clients are attached with
+//! [`TestServer::connect`], which returns a [`MockClient`] used to send
+//! serverbound packets and inspect the clientbound packets the server sent.
+//!
+//! # Examples
+//!
+//! ```
+//! use valence::prelude::*;
+//! use valence::protocol::packets::s2c::play::S2cPlayPacket;
+//! use valence::server::testing::TestServer;
+//!
+//! struct Game;
+//!
+//! impl Config for Game {
+//!     type ServerState = ();
+//!     type ClientState = ();
+//!     type EntityState = ();
+//!     type WorldState = ();
+//!     type ChunkState = ();
+//!     type PlayerListState = ();
+//! }
+//!
+//! let mut test = TestServer::new(Game, ()).unwrap();
+//!
+//! let (world, _) = test.server_mut().worlds.insert(DimensionId::default(), ());
+//!
+//! let mut client = test.connect("steve").unwrap();
+//! test.server_mut()
+//!     .clients
+//!     .get_mut(client.id())
+//!     .unwrap()
+//!     .spawn(world);
+//!
+//! test.tick();
+//!
+//! assert!(client
+//!     .received_packets()
+//!     .any(|pkt| matches!(pkt, S2cPlayPacket::LoginPlay(_))));
+//! ```
+
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::atomic::Ordering;
+
+use anyhow::Context;
+use valence_protocol::packets::s2c::play::S2cPlayPacket;
+use valence_protocol::{Decode, Encode, Packet, PacketDecoder, PacketEncoder, Username};
+
+use crate::client::{Client, ClientId};
+use crate::config::Config;
+use crate::server::byte_channel::{byte_channel, ByteReceiver, ByteSender, TryRecvError};
+use crate::server::{do_tick, init_server, login, setup_server, PlayPacketController, Server};
+
+/// A server which is stepped manually and whose clients are connected
+/// through in-memory channels.
+pub struct TestServer<C: Config> {
+    server: Server<C>,
+}
+
+impl<C: Config> TestServer<C> {
+    /// Creates the server and calls [`Config::init`], like [`start_server`]
+    /// does.
+    ///
+    /// No listeners are started, so the server's addresses from the config
+    /// are not used. Commands never arrive over RCON.
+    ///
+    /// [`start_server`]: crate::server::start_server
+    pub fn new(config: C, data: C::ServerState) -> anyhow::Result<Self> {
+        let shared = setup_server(config).context("failed to initialize server")?;
+
+        let _guard = shared.tokio_handle().enter();
+
+        // The sender is dropped immediately so no RCON commands are received.
+        let (_, rcon_commands_rx) = flume::bounded(1);
+
+        Ok(Self {
+            server: init_server(shared, data, rcon_commands_rx),
+        })
+    }
+
+    pub fn server(&self) -> &Server<C> {
+        &self.server
+    }
+
+    pub fn server_mut(&mut self) -> &mut Server<C> {
+        &mut self.server
+    }
+
+    /// Adds a client to the server as if it had just logged in with the
+    /// given username in [offline mode].
+    ///
+    /// The client is inserted into [`Server::clients`] immediately. Unlike a
+    /// real login, [`Config::login`] is not called.
+    ///
+    /// [offline mode]: crate::config::ConnectionMode::Offline
+    pub fn connect(&mut self, username: &str) -> anyhow::Result<MockClient> {
+        let username = Username::new(username.to_owned())?;
+
+        let ncd = login::offline(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)), username.clone())?;
+
+        let shared = &self.server.shared;
+
+        let (incoming_sender, incoming_receiver) = byte_channel(shared.incoming_capacity());
+        let (outgoing_sender, outgoing_receiver) = byte_channel(shared.outgoing_capacity());
+
+        let ctrl = PlayPacketController::from_channels(
+            outgoing_sender,
+            incoming_receiver,
+            shared.tokio_handle().clone(),
+            shared.metrics(),
+            username.as_str(),
+        );
+
+        let (id, _) = self
+            .server
+            .clients
+            .insert(Client::new(ctrl, ncd, Default::default()));
+
+        Ok(MockClient {
+            id,
+            username,
+            send: incoming_sender,
+            recv: outgoing_receiver,
+            enc: PacketEncoder::new(),
+            dec: PacketDecoder::new(),
+            received: vec![],
+            disconnected: false,
+        })
+    }
+
+    /// Runs a single game update and advances
+    /// [`SharedServer::current_tick`].
+    ///
+    /// This does not sleep, so tests can run as many ticks as they need
+    /// without waiting. Calls to [`SharedServer::shutdown`] have no effect.
+    ///
+    /// [`SharedServer::current_tick`]: crate::server::SharedServer::current_tick
+    /// [`SharedServer::shutdown`]: crate::server::SharedServer::shutdown
+    pub fn tick(&mut self) {
+        let shared = self.server.shared.clone();
+        let _guard = shared.tokio_handle().enter();
+
+        do_tick(&mut self.server);
+
+        shared.0.tick_counter.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// A client connected to a [`TestServer`].
+///
+/// Dropping the mock client disconnects it from the server.
+pub struct MockClient {
+    id: ClientId,
+    username: Username<String>,
+    send: ByteSender,
+    recv: ByteReceiver,
+    enc: PacketEncoder,
+    dec: PacketDecoder,
+    /// The data of every clientbound packet received so far, starting with
+    /// the packet ID.
+    received: Vec<Vec<u8>>,
+    disconnected: bool,
+}
+
+impl MockClient {
+    /// The ID of this client in [`Server::clients`].
+    pub fn id(&self) -> ClientId {
+        self.id
+    }
+
+    pub fn username(&self) -> Username<&str> {
+        self.username.as_str_username()
+    }
+
+    /// Sends a serverbound packet. It is handled by the server during the
+    /// next tick.
+    ///
+    /// An error is returned if the server disconnected the client or the
+    /// server's [incoming capacity] is exceeded.
+    ///
+    /// [incoming capacity]: crate::config::Config::incoming_capacity
+    pub fn send<P>(&mut self, pkt: &P) -> anyhow::Result<()>
+    where
+        P: Encode + Packet + ?Sized,
+    {
+        self.enc.append_packet(pkt)?;
+        self.send.try_send(self.enc.take())?;
+        Ok(())
+    }
+
+    /// Returns an iterator over the clientbound packets received since the
+    /// client was connected or [`MockClient::clear_received`] was last
+    /// called, in the order they were sent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the server sent a packet which could not be decoded.
+    pub fn received_packets(&mut self) -> impl Iterator<Item = S2cPlayPacket<'_>> + '_ {
+        self.read_incoming();
+
+        self.received.iter().map(|data| {
+            S2cPlayPacket::decode(&mut data.as_slice()).expect("failed to decode packet")
+        })
+    }
+
+    /// Forgets all packets received so far.
+    pub fn clear_received(&mut self) {
+        self.read_incoming();
+        self.received.clear();
+    }
+
+    /// Returns `true` if the server has disconnected this client.
+    pub fn is_disconnected(&mut self) -> bool {
+        self.read_incoming();
+        self.disconnected
+    }
+
+    /// Moves all packets sent by the server into `received`.
+    fn read_incoming(&mut self) {
+        loop {
+            match self.recv.try_recv() {
+                Ok(bytes) => self.dec.queue_bytes(bytes),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.disconnected = true;
+                    break;
+                }
+            }
+        }
+
+        loop {
+            match self.dec.try_next_packet::<RawPacket>() {
+                Ok(Some(RawPacket(data))) => self.received.push(data.to_vec()),
+                Ok(None) => break,
+                Err(e) => panic!("failed to read clientbound packet: {e:#}"),
+            }
+        }
+    }
+}
+
+/// The undecoded contents of a packet.
+struct RawPacket<'a>(&'a [u8]);
+
+impl<'a> Decode<'a> for RawPacket<'a> {
+    fn decode(r: &mut &'a [u8]) -> anyhow::Result<Self> {
+        Ok(Self(std::mem::take(r)))
+    }
+}
+
+impl Packet for RawPacket<'_> {
+    fn packet_name(&self) -> &'static str {
+        "RawPacket"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use valence_protocol::packets::c2s::play::{ConfirmTeleport, SetPlayerPosition};
+    use vek::Vec3;
+
+    use super::*;
+    use crate::config::MockConfig;
+    use crate::dimension::DimensionId;
+
+    fn join() -> (TestServer<MockConfig>, MockClient) {
+        let mut test = TestServer::new(MockConfig::default(), ()).unwrap();
+        let (world, _) = test.server.worlds.insert(DimensionId::default(), ());
+
+        let client = test.connect("test").unwrap();
+        test.server
+            .clients
+            .get_mut(client.id())
+            .unwrap()
+            .spawn(world);
+
+        test.tick();
+
+        (test, client)
+    }
+
+    #[test]
+    fn client_moves_after_teleport() {
+        let (mut test, mut client) = join();
+
+        assert_eq!(test.server.shared.current_tick(), 1);
+
+        let names: Vec<_> = client.received_packets().map(|p| p.packet_name()).collect();
+        assert_eq!(names.first(), Some(&"LoginPlay"));
+
+        let teleport_id = client
+            .received_packets()
+            .find_map(|pkt| match pkt {
+                S2cPlayPacket::SynchronizePlayerPosition(p) => Some(p.teleport_id),
+                _ => None,
+            })
+            .expect("missing initial teleport");
+
+        client.clear_received();
+
+        client.send(&ConfirmTeleport { teleport_id }).unwrap();
+        client
+            .send(&SetPlayerPosition {
+                position: [1.0, 2.0, 3.0],
+                on_ground: true,
+            })
+            .unwrap();
+
+        test.tick();
+
+        let c = test.server.clients.get(client.id()).unwrap();
+        assert_eq!(c.position(), Vec3::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn server_disconnects_client() {
+        let (mut test, mut client) = join();
+
+        test.server
+            .clients
+            .get_mut(client.id())
+            .unwrap()
+            .disconnect("bye");
+
+        assert!(client.is_disconnected());
+        assert!(client
+            .received_packets()
+            .any(|pkt| matches!(pkt, S2cPlayPacket::DisconnectPlay(_))));
+    }
+
+    #[test]
+    fn client_disconnects() {
+        let (mut test, client) = join();
+        let id = client.id();
+
+        drop(client);
+        test.tick();
+
+        assert!(test.server.clients.get(id).unwrap().is_disconnected());
+    }
+}
@@ -0,0 +1,200 @@
+//! Per-IP limits on connections and login attempts.
+
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use tokio::sync::OwnedSemaphorePermit;
+
+use crate::config::RateLimit;
+
+/// Tracks the connections and login attempts made from each IP address.
+pub(super) struct Throttle {
+    max_connections_per_ip: Option<usize>,
+    login_rate_limit: Option<RateLimit>,
+    connections: Mutex<HashMap<IpAddr, usize>>,
+    logins: Mutex<LoginAttempts>,
+}
+
+struct LoginAttempts {
+    /// The times of the recent login attempts from each address, oldest
+    /// first.
+    attempts: HashMap<IpAddr, VecDeque<Instant>>,
+    last_purge: Instant,
+}
+
+/// Keeps a connection counted against the server's limits until dropped.
+pub(crate) struct ConnectionGuard {
+    throttle: Arc<Throttle>,
+    ip: IpAddr,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        let mut connections = self.throttle.connections.lock().unwrap();
+
+        if let Some(count) = connections.get_mut(&self.ip) {
+            *count -= 1;
+
+            if *count == 0 {
+                connections.remove(&self.ip);
+            }
+        }
+    }
+}
+
+impl Throttle {
+    pub fn new(max_connections_per_ip: Option<usize>, login_rate_limit: Option<RateLimit>) -> Self {
+        Self {
+            max_connections_per_ip,
+            login_rate_limit,
+            connections: Mutex::new(HashMap::new()),
+            logins: Mutex::new(LoginAttempts {
+                attempts: HashMap::new(),
+                last_purge: Instant::now(),
+            }),
+        }
+    }
+
+    /// Counts a new connection from `ip`, returning `None` if there are
+    /// already too many connections from that address. The connection holds
+    /// on to `permit` until the returned guard is dropped.
+    pub fn try_connect(
+        self: &Arc<Self>,
+        ip: IpAddr,
+        permit: OwnedSemaphorePermit,
+    ) -> Option<ConnectionGuard> {
+        let mut connections = self.connections.lock().unwrap();
+        let count = connections.entry(ip).or_insert(0);
+
+        if self.max_connections_per_ip.is_some_and(|max| *count >= max) {
+            return None;
+        }
+
+        *count += 1;
+
+        Some(ConnectionGuard {
+            throttle: self.clone(),
+            ip,
+            _permit: permit,
+        })
+    }
+
+    /// Records a login attempt from `ip` made at `now`. Returns `false` if
+    /// the attempt exceeds the login rate limit.
+    pub fn try_login(&self, ip: IpAddr, now: Instant) -> bool {
+        let Some(limit) = self.login_rate_limit else {
+            return true;
+        };
+
+        let mut logins = self.logins.lock().unwrap();
+
+        // Occasionally forget addresses which haven't tried to log in recently.
+        if now.saturating_duration_since(logins.last_purge) >= limit.period {
+            logins.attempts.retain(|_, attempts| {
+                attempts
+                    .back()
+                    .is_some_and(|&t| now.saturating_duration_since(t) < limit.period)
+            });
+            logins.last_purge = now;
+        }
+
+        let attempts = logins.attempts.entry(ip).or_default();
+
+        while attempts
+            .front()
+            .is_some_and(|&t| now.saturating_duration_since(t) >= limit.period)
+        {
+            attempts.pop_front();
+        }
+
+        if attempts.len() >= limit.max as usize {
+            return false;
+        }
+
+        attempts.push_back(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    use tokio::sync::Semaphore;
+
+    use super::*;
+
+    fn permit(sema: &Arc<Semaphore>) -> OwnedSemaphorePermit {
+        sema.clone().try_acquire_owned().unwrap()
+    }
+
+    #[test]
+    fn connections_per_ip() {
+        let throttle = Arc::new(Throttle::new(Some(2), None));
+        let sema = Arc::new(Semaphore::new(10));
+
+        let a = IpAddr::from(Ipv4Addr::new(1, 2, 3, 4));
+        let b = IpAddr::from(Ipv4Addr::new(5, 6, 7, 8));
+
+        let first = throttle.try_connect(a, permit(&sema)).unwrap();
+        let _second = throttle.try_connect(a, permit(&sema)).unwrap();
+        assert!(throttle.try_connect(a, permit(&sema)).is_none());
+        let _other = throttle.try_connect(b, permit(&sema)).unwrap();
+
+        // Rejected connections give their permits back.
+        assert_eq!(sema.available_permits(), 7);
+
+        drop(first);
+        assert_eq!(sema.available_permits(), 8);
+        assert!(throttle.try_connect(a, permit(&sema)).is_some());
+    }
+
+    #[test]
+    fn login_rate_limit() {
+        let throttle = Throttle::new(
+            None,
+            Some(RateLimit {
+                max: 2,
+                period: Duration::from_secs(10),
+            }),
+        );
+
+        let ip = IpAddr::from(Ipv4Addr::LOCALHOST);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert!(throttle.try_login(ip, at(0)));
+        assert!(throttle.try_login(ip, at(1)));
+        assert!(!throttle.try_login(ip, at(2)));
+
+        // Other addresses are limited separately.
+        assert!(throttle.try_login(Ipv4Addr::new(1, 1, 1, 1).into(), at(2)));
+
+        // The first attempt falls out of the window.
+        assert!(throttle.try_login(ip, at(10)));
+        assert!(!throttle.try_login(ip, at(10)));
+        assert!(throttle.try_login(ip, at(11)));
+    }
+
+    #[test]
+    fn unlimited() {
+        let throttle = Arc::new(Throttle::new(None, None));
+        let sema = Arc::new(Semaphore::new(100));
+
+        let ip = IpAddr::from(Ipv4Addr::LOCALHOST);
+        let now = Instant::now();
+
+        let guards: Vec<_> = (0..100)
+            .map(|_| throttle.try_connect(ip, permit(&sema)).unwrap())
+            .collect();
+
+        assert!((0..100).all(|_| throttle.try_login(ip, now)));
+
+        drop(guards);
+        assert!(throttle.connections.lock().unwrap().is_empty());
+    }
+}
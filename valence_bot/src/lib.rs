@@ -0,0 +1,499 @@
+//! A headless Minecraft client for load and integration testing servers.
+//!
+//! A [`Bot`] connects to a server in offline mode and takes care of the
+//! parts of the protocol every client must handle: the handshake, login,
+//! compression, keep alives, and teleport confirmations. Everything else the
+//! server sends is handed to the caller through [`Bot::recv`], and packets
+//! can be sent back with [`Bot::send`].
+//!
+//! Encryption is not supported, so the server must not be in online mode.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use valence_bot::Bot;
+//! use valence_protocol::packets::s2c::play::S2cPlayPacket;
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let mut bot = Bot::connect("127.0.0.1:25565", "bot0").await?;
+//!
+//! loop {
+//!     if let S2cPlayPacket::DisconnectPlay(pkt) = bot.recv().await? {
+//!         println!("{} was disconnected: {}", bot.username(), pkt.reason);
+//!         break;
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Many bots running the same script can be started with [`spawn_swarm`].
+
+use std::future::Future;
+use std::io;
+use std::io::ErrorKind;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use bytes::{Buf, BytesMut};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+use uuid::Uuid;
+use valence_protocol::packets::c2s::handshake::Handshake;
+use valence_protocol::packets::c2s::login::{LoginPluginResponse, LoginStart};
+use valence_protocol::packets::c2s::play::{
+    ConfirmTeleport, KeepAliveC2s, SetPlayerPosition, SetPlayerPositionAndRotation,
+};
+use valence_protocol::packets::s2c::login::S2cLoginPacket;
+use valence_protocol::packets::s2c::play::S2cPlayPacket;
+use valence_protocol::types::HandshakeNextState;
+use valence_protocol::username::Username;
+use valence_protocol::{Encode, Packet, PacketDecoder, PacketEncoder, VarInt, PROTOCOL_VERSION};
+
+/// A client connected to a server and in the play state.
+pub struct Bot {
+    username: Username<String>,
+    uuid: Uuid,
+    entity_id: Option<i32>,
+    position: [f64; 3],
+    yaw: f32,
+    pitch: f32,
+    read: OwnedReadHalf,
+    write: OwnedWriteHalf,
+    enc: PacketEncoder,
+    dec: PacketDecoder,
+    /// Encoded bytes which have not been written to the socket yet.
+    pending: BytesMut,
+}
+
+impl Bot {
+    /// Connects to the server at `addr` and logs in with `username`.
+    ///
+    /// This returns once the server has sent the login success packet. An
+    /// error is returned if the username is invalid, the server disconnects
+    /// the bot during login, or the server is in online mode.
+    pub async fn connect(
+        addr: impl ToSocketAddrs,
+        username: impl Into<String>,
+    ) -> anyhow::Result<Self> {
+        let username = Username::new(username.into())?;
+
+        let stream = TcpStream::connect(addr).await?;
+        stream.set_nodelay(true)?;
+
+        let server_addr = stream.peer_addr()?;
+        let (read, write) = stream.into_split();
+
+        let mut bot = Self {
+            username,
+            uuid: Uuid::nil(),
+            entity_id: None,
+            position: [0.0; 3],
+            yaw: 0.0,
+            pitch: 0.0,
+            read,
+            write,
+            enc: PacketEncoder::new(),
+            dec: PacketDecoder::new(),
+            pending: BytesMut::new(),
+        };
+
+        bot.enc.append_packet(&Handshake {
+            protocol_version: VarInt(PROTOCOL_VERSION),
+            server_address: &server_addr.ip().to_string(),
+            server_port: server_addr.port(),
+            next_state: HandshakeNextState::Login,
+        })?;
+
+        bot.enc.append_packet(&LoginStart {
+            username: bot.username.as_str_username(),
+            sig_data: None,
+            profile_id: None,
+        })?;
+
+        bot.flush().await?;
+
+        loop {
+            bot.fill_decoder().await?;
+
+            let pkt = bot
+                .dec
+                .try_next_packet::<S2cLoginPacket>()?
+                .context("missing packet")?;
+
+            match pkt {
+                S2cLoginPacket::DisconnectLogin(pkt) => {
+                    bail!("disconnected during login: {}", pkt.reason)
+                }
+                S2cLoginPacket::EncryptionRequest(_) => {
+                    bail!("got encryption request. The server must be in offline mode.")
+                }
+                S2cLoginPacket::LoginSuccess(pkt) => {
+                    bot.uuid = pkt.uuid;
+                    return Ok(bot);
+                }
+                S2cLoginPacket::SetCompression(pkt) => {
+                    let threshold = pkt.threshold.0;
+
+                    bot.enc
+                        .set_compression((threshold >= 0).then_some(threshold as u32));
+                    bot.dec.set_compression(threshold >= 0);
+                }
+                S2cLoginPacket::LoginPluginRequest(pkt) => {
+                    let message_id = pkt.message_id;
+
+                    // Bots don't understand any login plugin channels.
+                    bot.enc.append_packet(&LoginPluginResponse {
+                        message_id,
+                        data: None,
+                    })?;
+                    bot.flush().await?;
+                }
+            }
+        }
+    }
+
+    pub fn username(&self) -> Username<&str> {
+        self.username.as_str_username()
+    }
+
+    /// The UUID the server assigned to the bot during login.
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    /// The entity ID of the bot, or `None` if the login play packet has not
+    /// been received yet.
+    pub fn entity_id(&self) -> Option<i32> {
+        self.entity_id
+    }
+
+    /// The position of the bot as last set by the server or by
+    /// [`Bot::set_position`].
+    pub fn position(&self) -> [f64; 3] {
+        self.position
+    }
+
+    pub fn yaw(&self) -> f32 {
+        self.yaw
+    }
+
+    pub fn pitch(&self) -> f32 {
+        self.pitch
+    }
+
+    /// Waits for the next play packet from the server.
+    ///
+    /// Keep alives and teleports are answered automatically before the packet
+    /// is returned, but the replies are only written to the server by the next
+    /// call to [`Bot::recv`], [`Bot::send`], or [`Bot::flush`].
+    ///
+    /// This method is cancel safe, so it can be used in `tokio::select!`.
+    pub async fn recv(&mut self) -> anyhow::Result<S2cPlayPacket<'_>> {
+        self.flush().await?;
+        self.fill_decoder().await?;
+
+        let pkt = self
+            .dec
+            .try_next_packet::<S2cPlayPacket>()?
+            .context("missing packet")?;
+
+        match &pkt {
+            S2cPlayPacket::KeepAliveS2c(p) => {
+                self.enc.append_packet(&KeepAliveC2s { id: p.id })?;
+            }
+            S2cPlayPacket::LoginPlay(p) => {
+                self.entity_id = Some(p.entity_id);
+            }
+            S2cPlayPacket::SynchronizePlayerPosition(p) => {
+                let relative = [p.flags.x(), p.flags.y(), p.flags.z()];
+
+                for ((pos, new_pos), relative) in
+                    self.position.iter_mut().zip(p.position).zip(relative)
+                {
+                    if relative {
+                        *pos += new_pos;
+                    } else {
+                        *pos = new_pos;
+                    }
+                }
+
+                if p.flags.y_rot() {
+                    self.yaw += p.yaw;
+                } else {
+                    self.yaw = p.yaw;
+                }
+
+                if p.flags.x_rot() {
+                    self.pitch += p.pitch;
+                } else {
+                    self.pitch = p.pitch;
+                }
+
+                // The vanilla client confirms the teleport and then sends back
+                // its new position.
+                self.enc.append_packet(&ConfirmTeleport {
+                    teleport_id: p.teleport_id,
+                })?;
+
+                self.enc.append_packet(&SetPlayerPositionAndRotation {
+                    position: self.position,
+                    yaw: self.yaw,
+                    pitch: self.pitch,
+                    on_ground: false,
+                })?;
+            }
+            _ => {}
+        }
+
+        Ok(pkt)
+    }
+
+    /// Sends a packet to the server, along with any packets that have not
+    /// been written yet.
+    pub async fn send<P>(&mut self, pkt: &P) -> anyhow::Result<()>
+    where
+        P: Encode + Packet + ?Sized,
+    {
+        self.enc.append_packet(pkt)?;
+        self.flush().await
+    }
+
+    /// Moves the bot to `position` and tells the server about it.
+    pub async fn set_position(
+        &mut self,
+        position: [f64; 3],
+        on_ground: bool,
+    ) -> anyhow::Result<()> {
+        self.position = position;
+        self.send(&SetPlayerPosition {
+            position,
+            on_ground,
+        })
+        .await
+    }
+
+    /// Writes all buffered packets to the server.
+    ///
+    /// This method is cancel safe. If it is cancelled, the remaining data is
+    /// written by the next call.
+    pub async fn flush(&mut self) -> anyhow::Result<()> {
+        self.pending.unsplit(self.enc.take());
+
+        while self.pending.has_remaining() {
+            self.write.write_buf(&mut self.pending).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads from the server until the decoder holds a complete packet.
+    async fn fill_decoder(&mut self) -> anyhow::Result<()> {
+        while !self.dec.has_next_packet()? {
+            self.dec.reserve(4096);
+            let mut buf = self.dec.take_capacity();
+
+            if self.read.read_buf(&mut buf).await? == 0 {
+                return Err(io::Error::from(ErrorKind::UnexpectedEof).into());
+            }
+
+            self.dec.queue_bytes(buf);
+        }
+
+        Ok(())
+    }
+}
+
+/// Spawns `count` bots which connect to `addr` and run `script`.
+///
+/// The bots are named `{name_prefix}{n}` where `n` counts up from zero, so the
+/// prefix must be short enough for every name to be a valid username. One bot
+/// connects every `join_interval` to avoid overwhelming the server with
+/// logins.
+///
+/// The returned handles resolve to the result of each bot's connection
+/// attempt and script.
+pub fn spawn_swarm<F, Fut>(
+    addr: SocketAddr,
+    count: usize,
+    name_prefix: &str,
+    join_interval: Duration,
+    script: F,
+) -> Vec<JoinHandle<anyhow::Result<()>>>
+where
+    F: Fn(Bot) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+{
+    let script = Arc::new(script);
+
+    (0..count)
+        .map(|n| {
+            let script = script.clone();
+            let username = format!("{name_prefix}{n}");
+
+            tokio::spawn(async move {
+                sleep(join_interval * n as u32).await;
+
+                let bot = Bot::connect(addr, username.as_str())
+                    .await
+                    .with_context(|| format!("connecting {username}"))?;
+
+                script(bot).await
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, TcpListener};
+    use std::time::Instant;
+
+    use valence::prelude::*;
+    use valence_protocol::packets::c2s::play::PluginMessageC2s;
+    use valence_protocol::RawBytes;
+
+    use super::*;
+
+    struct Game {
+        address: SocketAddr,
+    }
+
+    impl Config for Game {
+        type ServerState = Option<WorldId>;
+        type ClientState = ();
+        type EntityState = ();
+        type WorldState = ();
+        type ChunkState = ();
+        type PlayerListState = ();
+
+        fn address(&self) -> SocketAddr {
+            self.address
+        }
+
+        fn connection_mode(&self) -> ConnectionMode {
+            ConnectionMode::Offline
+        }
+
+        fn init(&self, server: &mut Server<Self>) {
+            let (world, _) = server.worlds.insert(DimensionId::default(), ());
+            server.state = Some(world);
+        }
+
+        fn update(&self, server: &mut Server<Self>) {
+            let world = server.state.unwrap();
+
+            for (_, client) in server.clients.iter_mut() {
+                if client.created_this_tick() {
+                    client.spawn(world);
+                }
+
+                // Plugin messages are sent back to the client unchanged.
+                while let Some(event) = client.pop_event() {
+                    if let ClientEvent::PluginMessageReceived { channel, data } = event {
+                        client.send_plugin_message(channel.as_str_ident(), &data);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Starts a server on a free port and ticks it until the future returned
+    /// by `f` completes successfully.
+    fn run_with_server<F, Fut>(f: F) -> ManualServer<Game>
+    where
+        F: FnOnce(SocketAddr) -> Fut,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        let address = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let mut server = valence::start_server_manual(Game { address }, None).unwrap();
+        let handle = server.server().shared.tokio_handle().clone();
+
+        // The listener is started asynchronously.
+        while std::net::TcpStream::connect(address).is_err() {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let task = handle.spawn(f(address));
+        let deadline = Instant::now() + Duration::from_secs(10);
+
+        while !task.is_finished() {
+            assert!(Instant::now() < deadline, "timed out");
+            assert!(server.tick().is_none());
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        handle.block_on(task).unwrap().unwrap();
+        server
+    }
+
+    /// Receives packets until the server sends the login play packet.
+    async fn wait_for_play(bot: &mut Bot) -> anyhow::Result<()> {
+        while !matches!(bot.recv().await?, S2cPlayPacket::LoginPlay(_)) {}
+        Ok(())
+    }
+
+    #[test]
+    fn bot_round_trips_packet() {
+        let server = run_with_server(|address| async move {
+            let mut bot = Bot::connect(address, "bot").await?;
+            wait_for_play(&mut bot).await?;
+            assert!(bot.entity_id().is_some());
+
+            bot.send(&PluginMessageC2s {
+                channel: Ident::new("valence:echo")?,
+                data: RawBytes(b"hello"),
+            })
+            .await?;
+
+            loop {
+                if let S2cPlayPacket::PluginMessageS2c(pkt) = bot.recv().await? {
+                    if pkt.channel.as_str() == "valence:echo" {
+                        assert_eq!(pkt.data.0, b"hello");
+                        return Ok(());
+                    }
+                }
+            }
+        });
+
+        let (_, client) = server.server().clients.iter().next().unwrap();
+        assert_eq!(client.username().as_str(), "bot");
+    }
+
+    #[test]
+    fn swarm_joins() {
+        let server = run_with_server(|address| async move {
+            let handles = spawn_swarm(
+                address,
+                3,
+                "swarm",
+                Duration::from_millis(10),
+                |mut bot| async move { wait_for_play(&mut bot).await },
+            );
+
+            for handle in handles {
+                handle.await??;
+            }
+
+            Ok(())
+        });
+
+        let mut names: Vec<_> = server
+            .server()
+            .clients
+            .iter()
+            .map(|(_, c)| c.username().as_str().to_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["swarm0", "swarm1", "swarm2"]);
+    }
+}
@@ -0,0 +1,85 @@
+use std::f64::consts::TAU;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use clap::Parser;
+use tokio::time::{interval, MissedTickBehavior};
+use valence_bot::{spawn_swarm, Bot};
+use valence_protocol::packets::s2c::play::S2cPlayPacket;
+
+/// Connects many bots to a server which walk in circles around where they
+/// spawned.
+#[derive(Parser, Clone, Debug)]
+#[clap(author, version, about)]
+struct Cli {
+    /// The socket address of the server.
+    server: SocketAddr,
+    /// The number of bots to connect.
+    count: usize,
+    /// The prefix of the bots' usernames. Each bot is named with the prefix
+    /// followed by its number.
+    #[clap(long, default_value = "bot")]
+    prefix: String,
+    /// Milliseconds to wait between bots connecting.
+    #[clap(long, default_value_t = 10)]
+    join_interval: u64,
+}
+
+/// How often the bots send their position, in milliseconds.
+const MOVE_INTERVAL: u64 = 50;
+/// The radius of the circle the bots walk in.
+const RADIUS: f64 = 3.0;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    let handles = spawn_swarm(
+        cli.server,
+        cli.count,
+        &cli.prefix,
+        Duration::from_millis(cli.join_interval),
+        walk_in_circles,
+    );
+
+    for handle in handles {
+        if let Err(e) = handle.await? {
+            eprintln!("{e:#}");
+        }
+    }
+
+    Ok(())
+}
+
+async fn walk_in_circles(mut bot: Bot) -> anyhow::Result<()> {
+    let mut center = None;
+    let mut angle: f64 = 0.0;
+
+    let mut interval = interval(Duration::from_millis(MOVE_INTERVAL));
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        tokio::select! {
+            pkt = bot.recv() => match pkt? {
+                S2cPlayPacket::SynchronizePlayerPosition(_) => {
+                    angle = 0.0;
+                    center = Some(bot.position());
+                }
+                S2cPlayPacket::DisconnectPlay(pkt) => {
+                    eprintln!("{} was disconnected: {}", bot.username(), pkt.reason);
+                    return Ok(());
+                }
+                _ => {}
+            },
+            _ = interval.tick() => {
+                // Wait for the server to tell the bot where it is.
+                if let Some([x, y, z]) = center {
+                    angle = (angle + 0.1) % TAU;
+
+                    let pos = [x + RADIUS * angle.cos(), y, z + RADIUS * angle.sin()];
+                    bot.set_position(pos, true).await?;
+                }
+            }
+        }
+    }
+}
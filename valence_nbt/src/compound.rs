@@ -122,6 +122,20 @@ impl Compound {
         }
     }
 
+    /// Inserts the entries of `other` into this compound. Compounds present in
+    /// both are merged recursively, while other values from `other` replace
+    /// existing values.
+    pub fn merge(&mut self, other: Compound) {
+        for (k, v) in other {
+            match (self.get_mut(k.as_str()), v) {
+                (Some(Value::Compound(this)), Value::Compound(other)) => this.merge(other),
+                (_, v) => {
+                    self.insert(k, v);
+                }
+            }
+        }
+    }
+
     pub fn entry<K>(&mut self, k: K) -> Entry
     where
         K: Into<String>,
@@ -0,0 +1,112 @@
+//! Reading and writing compressed NBT data.
+//!
+//! Files such as `level.dat` and `playerdata/*.dat` are gzip compressed,
+//! while chunk payloads in region files are usually zlib compressed.
+//!
+//! This module is only available with the `compression` feature enabled.
+//!
+//! # Examples
+//!
+//! ```
+//! use valence_nbt::compression::{from_compressed_reader, to_compressed_writer, Compression};
+//! use valence_nbt::{compound, DecodeLimits};
+//!
+//! let c = compound! {
+//!     "DataVersion" => 3120,
+//! };
+//!
+//! let mut buf = vec![];
+//! to_compressed_writer(&mut buf, &c, "", Compression::Gzip).unwrap();
+//!
+//! let (decoded, _) =
+//!     from_compressed_reader(buf.as_slice(), Compression::Gzip, DecodeLimits::default()).unwrap();
+//!
+//! assert_eq!(c, decoded);
+//! ```
+
+use std::io::{Read, Write};
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+use flate2::write::{GzEncoder, ZlibEncoder};
+
+use crate::{from_binary_reader_with_limits, to_binary_writer, Compound, DecodeLimits, Result};
+
+/// The compression scheme applied to NBT binary data.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Compression {
+    /// Gzip compression, used by `level.dat` and player data files.
+    Gzip,
+    /// Zlib compression, used by most chunks stored in region files.
+    Zlib,
+    /// No compression.
+    None,
+}
+
+impl Compression {
+    /// Returns the compression scheme identified by the compression type byte
+    /// in a region file chunk header, or `None` if it is unknown.
+    pub fn from_region_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(Self::Gzip),
+            2 => Some(Self::Zlib),
+            3 => Some(Self::None),
+            _ => None,
+        }
+    }
+
+    /// Returns the compression type byte used in region file chunk headers.
+    pub fn region_id(self) -> u8 {
+        match self {
+            Self::Gzip => 1,
+            Self::Zlib => 2,
+            Self::None => 3,
+        }
+    }
+}
+
+/// Decodes NBT binary data compressed with the given scheme from the
+/// provided reader.
+///
+/// The size limit applies to the data after it has been decompressed.
+///
+/// The decompressor buffers reads from `reader` itself. Uncompressed data is
+/// read directly, so the reader should be buffered as described in
+/// [`from_binary_reader`](crate::from_binary_reader).
+///
+/// The string returned is the name of the root compound.
+pub fn from_compressed_reader<R: Read>(
+    reader: R,
+    compression: Compression,
+    limits: DecodeLimits,
+) -> Result<(Compound, String)> {
+    match compression {
+        Compression::Gzip => from_binary_reader_with_limits(GzDecoder::new(reader), limits),
+        Compression::Zlib => from_binary_reader_with_limits(ZlibDecoder::new(reader), limits),
+        Compression::None => from_binary_reader_with_limits(reader, limits),
+    }
+}
+
+/// Encodes NBT binary data compressed with the given scheme to the provided
+/// writer.
+pub fn to_compressed_writer<W: Write>(
+    writer: W,
+    compound: &Compound,
+    root_name: &str,
+    compression: Compression,
+) -> Result<()> {
+    match compression {
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(writer, flate2::Compression::default());
+            to_binary_writer(&mut encoder, compound, root_name)?;
+            encoder.finish()?;
+        }
+        Compression::Zlib => {
+            let mut encoder = ZlibEncoder::new(writer, flate2::Compression::default());
+            to_binary_writer(&mut encoder, compound, root_name)?;
+            encoder.finish()?;
+        }
+        Compression::None => to_binary_writer(writer, compound, root_name)?,
+    }
+
+    Ok(())
+}
@@ -0,0 +1,105 @@
+//! Checks shared by the binary decoders.
+
+use crate::tag::Tag;
+use crate::{Error, Result};
+
+/// Maximum recursion depth to prevent overflowing the call stack.
+pub(crate) const MAX_DEPTH: usize = 512;
+
+/// Tracks how deeply lists and compounds are nested while decoding.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Depth {
+    current: usize,
+    max: usize,
+}
+
+impl Depth {
+    /// Allows at most `max` levels of nesting. Values above [`MAX_DEPTH`] are
+    /// clamped.
+    pub(crate) fn new(max: usize) -> Self {
+        Self {
+            current: 0,
+            max: max.min(MAX_DEPTH),
+        }
+    }
+
+    pub(crate) fn current(self) -> usize {
+        self.current
+    }
+
+    /// Enters a list or compound, failing if the maximum depth was reached.
+    /// Must be followed by a call to [`Depth::exit`].
+    pub(crate) fn enter(&mut self) -> Result<()> {
+        if self.current >= self.max {
+            return Err(Error::new_static("reached maximum recursion depth"));
+        }
+
+        self.current += 1;
+        Ok(())
+    }
+
+    pub(crate) fn exit(&mut self) {
+        self.current -= 1;
+    }
+}
+
+impl Default for Depth {
+    fn default() -> Self {
+        Self::new(MAX_DEPTH)
+    }
+}
+
+/// Converts the byte preceding a value to its tag.
+pub(crate) fn tag_from_byte(byte: u8) -> Result<Tag> {
+    match byte {
+        0 => Ok(Tag::End),
+        1 => Ok(Tag::Byte),
+        2 => Ok(Tag::Short),
+        3 => Ok(Tag::Int),
+        4 => Ok(Tag::Long),
+        5 => Ok(Tag::Float),
+        6 => Ok(Tag::Double),
+        7 => Ok(Tag::ByteArray),
+        8 => Ok(Tag::String),
+        9 => Ok(Tag::List),
+        10 => Ok(Tag::Compound),
+        11 => Ok(Tag::IntArray),
+        12 => Ok(Tag::LongArray),
+        byte => Err(Error::new_owned(format!("invalid tag byte of {byte:#x}"))),
+    }
+}
+
+/// Returns the minimum encoded size of a value with the given tag.
+pub(crate) fn min_size(tag: Tag) -> usize {
+    match tag {
+        Tag::End | Tag::Byte | Tag::Compound => 1,
+        Tag::Short | Tag::String => 2,
+        Tag::Int | Tag::Float | Tag::ByteArray | Tag::IntArray | Tag::LongArray => 4,
+        Tag::Long | Tag::Double => 8,
+        Tag::List => 5,
+    }
+}
+
+/// Checks the length of a list or array before any of its elements are read.
+///
+/// `min_elem_size` is the smallest size of an encoded element and `remaining`
+/// is the number of bytes which can still be read, so lengths which could not
+/// possibly fit are rejected before memory is reserved for them.
+pub(crate) fn check_len(
+    desc: &str,
+    len: i32,
+    min_elem_size: usize,
+    remaining: u64,
+) -> Result<usize> {
+    if len.is_negative() {
+        return Err(Error::new_owned(format!("negative {desc} length of {len}")));
+    }
+
+    if len as u64 * min_elem_size as u64 > remaining {
+        return Err(Error::new_owned(format!(
+            "{desc} of length {len} exceeds remainder of input"
+        )));
+    }
+
+    Ok(len as usize)
+}
@@ -0,0 +1,301 @@
+use std::io::Read;
+
+use byteorder::{BigEndian, ReadBytesExt};
+use cesu8::Cesu8DecodingError;
+
+use crate::decode::{check_len, min_size, tag_from_byte, Depth, MAX_DEPTH};
+use crate::tag::Tag;
+use crate::{Compound, Error, List, Result, Value};
+
+/// Limits placed on NBT data decoded from a reader to defend against
+/// malicious input.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DecodeLimits {
+    /// The maximum nesting depth of lists and compounds. Values above 512 are
+    /// clamped to 512 so that hostile input cannot overflow the stack.
+    pub max_depth: usize,
+    /// The maximum number of bytes read from the reader. When decoding
+    /// compressed data, this limits the size of the data after decompression.
+    ///
+    /// This also bounds the amount of memory allocated for the decoded
+    /// compound.
+    pub max_bytes: u64,
+}
+
+impl DecodeLimits {
+    /// Limits which permit any input that fits in memory. Only use this with
+    /// trusted input.
+    pub const UNLIMITED: Self = Self {
+        max_depth: MAX_DEPTH,
+        max_bytes: u64::MAX,
+    };
+}
+
+impl Default for DecodeLimits {
+    /// A maximum depth of 512 and a maximum size of 2 MiB, which is the quota
+    /// the vanilla server applies to NBT received over the network.
+    fn default() -> Self {
+        Self {
+            max_depth: MAX_DEPTH,
+            max_bytes: 2 * 1024 * 1024,
+        }
+    }
+}
+
+/// Decodes uncompressed NBT binary data from the provided reader using the
+/// [default limits](DecodeLimits::default).
+///
+/// The data is read a few bytes at a time, so readers such as files and
+/// sockets should be wrapped in a [`BufReader`] first. Bytes following the
+/// root compound are not read from the reader itself, but a buffered reader
+/// may have read them into its buffer.
+///
+/// [`BufReader`]: std::io::BufReader
+///
+/// The string returned is the name of the root compound.
+pub fn from_binary_reader<R: Read>(reader: R) -> Result<(Compound, String)> {
+    from_binary_reader_with_limits(reader, DecodeLimits::default())
+}
+
+/// Like [`from_binary_reader`], but with the given limits.
+pub fn from_binary_reader_with_limits<R: Read>(
+    reader: R,
+    limits: DecodeLimits,
+) -> Result<(Compound, String)> {
+    let mut state = DecodeState {
+        reader,
+        depth: Depth::new(limits.max_depth),
+        limits,
+        remaining: limits.max_bytes,
+    };
+
+    let root_tag = state.read_tag()?;
+    if root_tag != Tag::Compound {
+        return Err(Error::new_owned(format!(
+            "expected root tag for compound (got {root_tag})",
+        )));
+    }
+
+    let root_name = state.read_string()?;
+    let root = state.read_compound()?;
+
+    debug_assert_eq!(state.depth.current(), 0);
+
+    Ok((root, root_name))
+}
+
+/// Upper bound on the number of elements preallocated for a list or array
+/// before any of its elements are read. Lists can still grow beyond this.
+const MAX_PREALLOC_LEN: usize = 64 * 1024;
+
+struct DecodeState<R> {
+    reader: R,
+    depth: Depth,
+    limits: DecodeLimits,
+    /// Number of bytes which can still be read before exceeding the size
+    /// limit.
+    remaining: u64,
+}
+
+impl<R: Read> DecodeState<R> {
+    #[inline]
+    fn check_depth<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        self.depth.enter()?;
+        let res = f(self);
+        self.depth.exit();
+        res
+    }
+
+    /// Accounts for `n` bytes about to be read.
+    fn consume(&mut self, n: u64) -> Result<()> {
+        match self.remaining.checked_sub(n) {
+            Some(remaining) => {
+                self.remaining = remaining;
+                Ok(())
+            }
+            None => Err(Error::new_owned(format!(
+                "NBT data exceeds size limit of {} bytes",
+                self.limits.max_bytes
+            ))),
+        }
+    }
+
+    /// Checks that a list or array of `len` elements could fit in the
+    /// remaining size limit, then returns its initial capacity.
+    fn check_len(&self, desc: &str, len: i32, elem_tag: Tag) -> Result<usize> {
+        let len = check_len(desc, len, min_size(elem_tag), self.remaining)?;
+        Ok(len.min(MAX_PREALLOC_LEN))
+    }
+
+    fn read_tag(&mut self) -> Result<Tag> {
+        tag_from_byte(self.read_byte()? as u8)
+    }
+
+    fn read_value(&mut self, tag: Tag) -> Result<Value> {
+        match tag {
+            Tag::End => unreachable!("illegal TAG_End argument"),
+            Tag::Byte => Ok(self.read_byte()?.into()),
+            Tag::Short => Ok(self.read_short()?.into()),
+            Tag::Int => Ok(self.read_int()?.into()),
+            Tag::Long => Ok(self.read_long()?.into()),
+            Tag::Float => Ok(self.read_float()?.into()),
+            Tag::Double => Ok(self.read_double()?.into()),
+            Tag::ByteArray => Ok(self.read_byte_array()?.into()),
+            Tag::String => Ok(self.read_string()?.into()),
+            Tag::List => self.check_depth(|st| Ok(st.read_any_list()?.into())),
+            Tag::Compound => self.check_depth(|st| Ok(st.read_compound()?.into())),
+            Tag::IntArray => Ok(self.read_int_array()?.into()),
+            Tag::LongArray => Ok(self.read_long_array()?.into()),
+        }
+    }
+
+    fn read_byte(&mut self) -> Result<i8> {
+        self.consume(1)?;
+        Ok(self.reader.read_i8()?)
+    }
+
+    fn read_short(&mut self) -> Result<i16> {
+        self.consume(2)?;
+        Ok(self.reader.read_i16::<BigEndian>()?)
+    }
+
+    fn read_int(&mut self) -> Result<i32> {
+        self.consume(4)?;
+        Ok(self.reader.read_i32::<BigEndian>()?)
+    }
+
+    fn read_long(&mut self) -> Result<i64> {
+        self.consume(8)?;
+        Ok(self.reader.read_i64::<BigEndian>()?)
+    }
+
+    fn read_float(&mut self) -> Result<f32> {
+        self.consume(4)?;
+        Ok(self.reader.read_f32::<BigEndian>()?)
+    }
+
+    fn read_double(&mut self) -> Result<f64> {
+        self.consume(8)?;
+        Ok(self.reader.read_f64::<BigEndian>()?)
+    }
+
+    /// Reads exactly `len` bytes after checking them against the size limit.
+    fn read_bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+        self.consume(len as u64)?;
+
+        let mut buf = Vec::with_capacity(len.min(MAX_PREALLOC_LEN));
+        (&mut self.reader).take(len as u64).read_to_end(&mut buf)?;
+
+        if buf.len() != len {
+            return Err(Error::new_static("unexpected end of input"));
+        }
+
+        Ok(buf)
+    }
+
+    fn read_byte_array(&mut self) -> Result<Vec<i8>> {
+        let len = self.read_int()?;
+        self.check_len("byte array", len, Tag::Byte)?;
+
+        let array = self.read_bytes(len as usize)?;
+        Ok(array.into_iter().map(|b| b as i8).collect())
+    }
+
+    fn read_string(&mut self) -> Result<String> {
+        let len = self.read_short()? as u16;
+        let bytes = self.read_bytes(len.into())?;
+
+        match cesu8::from_java_cesu8(&bytes) {
+            Ok(cow) => Ok(cow.into_owned()),
+            Err(Cesu8DecodingError) => {
+                Err(Error::new_static("could not convert CESU-8 data to UTF-8"))
+            }
+        }
+    }
+
+    fn read_any_list(&mut self) -> Result<List> {
+        match self.read_tag()? {
+            Tag::End => match self.read_int()? {
+                0 => Ok(List::Byte(Vec::new())),
+                len => Err(Error::new_owned(format!(
+                    "TAG_End list with nonzero length of {len}"
+                ))),
+            },
+            Tag::Byte => Ok(self.read_list(Tag::Byte, |st| st.read_byte())?.into()),
+            Tag::Short => Ok(self.read_list(Tag::Short, |st| st.read_short())?.into()),
+            Tag::Int => Ok(self.read_list(Tag::Int, |st| st.read_int())?.into()),
+            Tag::Long => Ok(self.read_list(Tag::Long, |st| st.read_long())?.into()),
+            Tag::Float => Ok(self.read_list(Tag::Float, |st| st.read_float())?.into()),
+            Tag::Double => Ok(self.read_list(Tag::Double, |st| st.read_double())?.into()),
+            Tag::ByteArray => Ok(self
+                .read_list(Tag::ByteArray, |st| st.read_byte_array())?
+                .into()),
+            Tag::String => Ok(self.read_list(Tag::String, |st| st.read_string())?.into()),
+            Tag::List => {
+                self.check_depth(|st| Ok(st.read_list(Tag::List, |st| st.read_any_list())?.into()))
+            }
+            Tag::Compound => self
+                .check_depth(|st| Ok(st.read_list(Tag::Compound, |st| st.read_compound())?.into())),
+            Tag::IntArray => Ok(self
+                .read_list(Tag::IntArray, |st| st.read_int_array())?
+                .into()),
+            Tag::LongArray => Ok(self
+                .read_list(Tag::LongArray, |st| st.read_long_array())?
+                .into()),
+        }
+    }
+
+    /// Assumes the element tag has already been read.
+    #[inline]
+    fn read_list<T, F>(&mut self, elem_type: Tag, mut read_elem: F) -> Result<Vec<T>>
+    where
+        F: FnMut(&mut Self) -> Result<T>,
+    {
+        let len = self.read_int()?;
+        let capacity = self.check_len(&format!("{elem_type} list"), len, elem_type)?;
+
+        let mut list = Vec::with_capacity(capacity);
+        for _ in 0..len {
+            list.push(read_elem(self)?);
+        }
+
+        Ok(list)
+    }
+
+    fn read_compound(&mut self) -> Result<Compound> {
+        let mut compound = Compound::new();
+
+        loop {
+            let tag = self.read_tag()?;
+            if tag == Tag::End {
+                return Ok(compound);
+            }
+
+            compound.insert(self.read_string()?, self.read_value(tag)?);
+        }
+    }
+
+    fn read_int_array(&mut self) -> Result<Vec<i32>> {
+        let len = self.read_int()?;
+        let capacity = self.check_len("int array", len, Tag::Int)?;
+
+        let mut array = Vec::with_capacity(capacity);
+        for _ in 0..len {
+            array.push(self.read_int()?);
+        }
+
+        Ok(array)
+    }
+
+    fn read_long_array(&mut self) -> Result<Vec<i64>> {
+        let len = self.read_int()?;
+        let capacity = self.check_len("long array", len, Tag::Long)?;
+
+        let mut array = Vec::with_capacity(capacity);
+        for _ in 0..len {
+            array.push(self.read_long()?);
+        }
+
+        Ok(array)
+    }
+}
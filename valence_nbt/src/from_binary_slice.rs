@@ -1,8 +1,10 @@
+use std::borrow::Cow;
 use std::mem;
 
 use byteorder::{BigEndian, ReadBytesExt};
 use cesu8::Cesu8DecodingError;
 
+use crate::decode::{check_len, min_size, tag_from_byte, Depth};
 use crate::tag::Tag;
 use crate::{Compound, Error, List, Result, Value};
 
@@ -10,7 +12,7 @@ use crate::{Compound, Error, List, Result, Value};
 ///
 /// The string returned is the name of the root compound.
 pub fn from_binary_slice(slice: &mut &[u8]) -> Result<(Compound, String)> {
-    let mut state = DecodeState { slice, depth: 0 };
+    let mut state = DecodeState::new(slice);
 
     let root_tag = state.read_tag()?;
     if root_tag != Tag::Compound {
@@ -22,50 +24,39 @@ pub fn from_binary_slice(slice: &mut &[u8]) -> Result<(Compound, String)> {
     let root_name = state.read_string()?;
     let root = state.read_compound()?;
 
-    debug_assert_eq!(state.depth, 0);
+    debug_assert_eq!(state.depth.current(), 0);
 
     Ok((root, root_name))
 }
 
-/// Maximum recursion depth to prevent overflowing the call stack.
-const MAX_DEPTH: usize = 512;
-
-struct DecodeState<'a, 'b> {
-    slice: &'a mut &'b [u8],
-    /// Current recursion depth.
-    depth: usize,
+pub(crate) struct DecodeState<'a, 'b> {
+    pub(crate) slice: &'a mut &'b [u8],
+    pub(crate) depth: Depth,
 }
 
-impl DecodeState<'_, '_> {
-    #[inline]
-    fn check_depth<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
-        if self.depth >= MAX_DEPTH {
-            return Err(Error::new_static("reached maximum recursion depth"));
+impl<'a, 'b> DecodeState<'a, 'b> {
+    pub(crate) fn new(slice: &'a mut &'b [u8]) -> Self {
+        Self {
+            slice,
+            depth: Depth::default(),
         }
+    }
 
-        self.depth += 1;
+    #[inline]
+    pub(crate) fn check_depth<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        self.depth.enter()?;
         let res = f(self);
-        self.depth -= 1;
+        self.depth.exit();
         res
     }
 
-    fn read_tag(&mut self) -> Result<Tag> {
-        match self.slice.read_u8()? {
-            0 => Ok(Tag::End),
-            1 => Ok(Tag::Byte),
-            2 => Ok(Tag::Short),
-            3 => Ok(Tag::Int),
-            4 => Ok(Tag::Long),
-            5 => Ok(Tag::Float),
-            6 => Ok(Tag::Double),
-            7 => Ok(Tag::ByteArray),
-            8 => Ok(Tag::String),
-            9 => Ok(Tag::List),
-            10 => Ok(Tag::Compound),
-            11 => Ok(Tag::IntArray),
-            12 => Ok(Tag::LongArray),
-            byte => Err(Error::new_owned(format!("invalid tag byte of {byte:#x}"))),
-        }
+    pub(crate) fn read_tag(&mut self) -> Result<Tag> {
+        tag_from_byte(self.slice.read_u8()?)
+    }
+
+    /// The number of bytes left in the input.
+    pub(crate) fn remaining(&self) -> u64 {
+        self.slice.len() as u64
     }
 
     fn read_value(&mut self, tag: Tag) -> Result<Value> {
@@ -86,46 +77,35 @@ impl DecodeState<'_, '_> {
         }
     }
 
-    fn read_byte(&mut self) -> Result<i8> {
+    pub(crate) fn read_byte(&mut self) -> Result<i8> {
         Ok(self.slice.read_i8()?)
     }
 
-    fn read_short(&mut self) -> Result<i16> {
+    pub(crate) fn read_short(&mut self) -> Result<i16> {
         Ok(self.slice.read_i16::<BigEndian>()?)
     }
 
-    fn read_int(&mut self) -> Result<i32> {
+    pub(crate) fn read_int(&mut self) -> Result<i32> {
         Ok(self.slice.read_i32::<BigEndian>()?)
     }
 
-    fn read_long(&mut self) -> Result<i64> {
+    pub(crate) fn read_long(&mut self) -> Result<i64> {
         Ok(self.slice.read_i64::<BigEndian>()?)
     }
 
-    fn read_float(&mut self) -> Result<f32> {
+    pub(crate) fn read_float(&mut self) -> Result<f32> {
         Ok(self.slice.read_f32::<BigEndian>()?)
     }
 
-    fn read_double(&mut self) -> Result<f64> {
+    pub(crate) fn read_double(&mut self) -> Result<f64> {
         Ok(self.slice.read_f64::<BigEndian>()?)
     }
 
     fn read_byte_array(&mut self) -> Result<Vec<i8>> {
-        let len = self.slice.read_i32::<BigEndian>()?;
-
-        if len.is_negative() {
-            return Err(Error::new_owned(format!(
-                "negative byte array length of {len}"
-            )));
-        }
-
-        if len as usize > self.slice.len() {
-            return Err(Error::new_owned(format!(
-                "byte array length of {len} exceeds remainder of input"
-            )));
-        }
+        let len = self.read_int()?;
+        let len = check_len("byte array", len, 1, self.remaining())?;
 
-        let (left, right) = self.slice.split_at(len as usize);
+        let (left, right) = self.slice.split_at(len);
 
         let array = left.iter().map(|b| *b as i8).collect();
         *self.slice = right;
@@ -134,6 +114,12 @@ impl DecodeState<'_, '_> {
     }
 
     fn read_string(&mut self) -> Result<String> {
+        Ok(self.read_str()?.into_owned())
+    }
+
+    /// Reads a string, borrowing from the input if it does not need to be
+    /// converted from modified UTF-8.
+    pub(crate) fn read_str(&mut self) -> Result<Cow<'b, str>> {
         let len = self.slice.read_u16::<BigEndian>()?.into();
 
         if len > self.slice.len() {
@@ -147,7 +133,7 @@ impl DecodeState<'_, '_> {
         match cesu8::from_java_cesu8(left) {
             Ok(cow) => {
                 *self.slice = right;
-                Ok(cow.into())
+                Ok(cow)
             }
             Err(Cesu8DecodingError) => {
                 Err(Error::new_static("could not convert CESU-8 data to UTF-8"))
@@ -163,66 +149,45 @@ impl DecodeState<'_, '_> {
                     "TAG_End list with nonzero length of {len}"
                 ))),
             },
-            Tag::Byte => Ok(self.read_list(Tag::Byte, 1, |st| st.read_byte())?.into()),
-            Tag::Short => Ok(self.read_list(Tag::Short, 2, |st| st.read_short())?.into()),
-            Tag::Int => Ok(self.read_list(Tag::Int, 4, |st| st.read_int())?.into()),
-            Tag::Long => Ok(self.read_list(Tag::Long, 8, |st| st.read_long())?.into()),
-            Tag::Float => Ok(self.read_list(Tag::Float, 4, |st| st.read_float())?.into()),
-            Tag::Double => Ok(self
-                .read_list(Tag::Double, 8, |st| st.read_double())?
-                .into()),
+            Tag::Byte => Ok(self.read_list(Tag::Byte, |st| st.read_byte())?.into()),
+            Tag::Short => Ok(self.read_list(Tag::Short, |st| st.read_short())?.into()),
+            Tag::Int => Ok(self.read_list(Tag::Int, |st| st.read_int())?.into()),
+            Tag::Long => Ok(self.read_list(Tag::Long, |st| st.read_long())?.into()),
+            Tag::Float => Ok(self.read_list(Tag::Float, |st| st.read_float())?.into()),
+            Tag::Double => Ok(self.read_list(Tag::Double, |st| st.read_double())?.into()),
             Tag::ByteArray => Ok(self
-                .read_list(Tag::ByteArray, 4, |st| st.read_byte_array())?
-                .into()),
-            Tag::String => Ok(self
-                .read_list(Tag::String, 2, |st| st.read_string())?
+                .read_list(Tag::ByteArray, |st| st.read_byte_array())?
                 .into()),
-            Tag::List => self
-                .check_depth(|st| Ok(st.read_list(Tag::List, 5, |st| st.read_any_list())?.into())),
-            Tag::Compound => self.check_depth(|st| {
-                Ok(st
-                    .read_list(Tag::Compound, 1, |st| st.read_compound())?
-                    .into())
-            }),
+            Tag::String => Ok(self.read_list(Tag::String, |st| st.read_string())?.into()),
+            Tag::List => {
+                self.check_depth(|st| Ok(st.read_list(Tag::List, |st| st.read_any_list())?.into()))
+            }
+            Tag::Compound => self
+                .check_depth(|st| Ok(st.read_list(Tag::Compound, |st| st.read_compound())?.into())),
             Tag::IntArray => Ok(self
-                .read_list(Tag::IntArray, 4, |st| st.read_int_array())?
+                .read_list(Tag::IntArray, |st| st.read_int_array())?
                 .into()),
             Tag::LongArray => Ok(self
-                .read_list(Tag::LongArray, 4, |st| st.read_long_array())?
+                .read_list(Tag::LongArray, |st| st.read_long_array())?
                 .into()),
         }
     }
 
     /// Assumes the element tag has already been read.
-    ///
-    /// `min_elem_size` is the minimum size of the list element when encoded.
     #[inline]
-    fn read_list<T, F>(
-        &mut self,
-        elem_type: Tag,
-        min_elem_size: usize,
-        mut read_elem: F,
-    ) -> Result<Vec<T>>
+    fn read_list<T, F>(&mut self, elem_type: Tag, mut read_elem: F) -> Result<Vec<T>>
     where
         F: FnMut(&mut Self) -> Result<T>,
     {
         let len = self.read_int()?;
-
-        if len.is_negative() {
-            return Err(Error::new_owned(format!(
-                "negative {elem_type} list length of {len}",
-            )));
-        }
-
-        // Ensure we don't reserve more than the maximum amount of memory required given
-        // the size of the remaining input.
-        if len as u64 * min_elem_size as u64 > self.slice.len() as u64 {
-            return Err(Error::new_owned(format!(
-                "{elem_type} list of length {len} exceeds remainder of input"
-            )));
-        }
-
-        let mut list = Vec::with_capacity(len as usize);
+        let len = check_len(
+            &format!("{elem_type} list"),
+            len,
+            min_size(elem_type),
+            self.remaining(),
+        )?;
+
+        let mut list = Vec::with_capacity(len);
         for _ in 0..len {
             list.push(read_elem(self)?);
         }
@@ -245,20 +210,9 @@ impl DecodeState<'_, '_> {
 
     fn read_int_array(&mut self) -> Result<Vec<i32>> {
         let len = self.read_int()?;
+        let len = check_len("int array", len, mem::size_of::<i32>(), self.remaining())?;
 
-        if len.is_negative() {
-            return Err(Error::new_owned(format!(
-                "negative int array length of {len}",
-            )));
-        }
-
-        if len as u64 * mem::size_of::<i32>() as u64 > self.slice.len() as u64 {
-            return Err(Error::new_owned(format!(
-                "int array of length {len} exceeds remainder of input"
-            )));
-        }
-
-        let mut array = Vec::with_capacity(len as usize);
+        let mut array = Vec::with_capacity(len);
         for _ in 0..len {
             array.push(self.read_int()?);
         }
@@ -268,20 +222,9 @@ impl DecodeState<'_, '_> {
 
     fn read_long_array(&mut self) -> Result<Vec<i64>> {
         let len = self.read_int()?;
+        let len = check_len("long array", len, mem::size_of::<i64>(), self.remaining())?;
 
-        if len.is_negative() {
-            return Err(Error::new_owned(format!(
-                "negative long array length of {len}",
-            )));
-        }
-
-        if len as u64 * mem::size_of::<i64>() as u64 > self.slice.len() as u64 {
-            return Err(Error::new_owned(format!(
-                "long array of length {len} exceeds remainder of input"
-            )));
-        }
-
-        let mut array = Vec::with_capacity(len as usize);
+        let mut array = Vec::with_capacity(len);
         for _ in 0..len {
             array.push(self.read_long()?);
         }
@@ -43,11 +43,71 @@
 //! assert_eq!(root_name, "");
 //! ```
 //!
+//! Values can also be converted to and from [SNBT], the textual format used
+//! by commands. [`Value`], [`List`] and [`Compound`] implement
+//! [`Display`](std::fmt::Display) using this format.
+//!
+//! ```rust
+//! use valence_nbt::{compound, Compound};
+//!
+//! let c = compound! {
+//!     "int" => 0xdead,
+//!     "long_array" => vec![1_i64, 2],
+//! };
+//!
+//! assert_eq!(c.to_string(), "{int:57005,long_array:[L;1L,2L]}");
+//! assert_eq!(
+//!     "{int:57005,long_array:[L;1L,2L]}"
+//!         .parse::<Compound>()
+//!         .unwrap(),
+//!     c
+//! );
+//! ```
+//!
+//! [SNBT]: https://minecraft.fandom.com/wiki/NBT_format#SNBT_format
+//!
+//! Data that is not already in memory can be decoded from any [`Read`]er
+//! with [`from_binary_reader`]. Limits on the nesting depth and size of the
+//! input guard against malicious data.
+//!
+//! ```rust
+//! use valence_nbt::{from_binary_reader_with_limits, DecodeLimits};
+//!
+//! let some_bytes = [10, 0, 0, 3, 0, 3, 105, 110, 116, 0, 0, 222, 173, 0];
+//!
+//! let limits = DecodeLimits {
+//!     max_bytes: 8,
+//!     ..Default::default()
+//! };
+//!
+//! assert!(from_binary_reader_with_limits(some_bytes.as_slice(), limits).is_err());
+//! ```
+//!
+//! [`Read`]: std::io::Read
+//!
+//! When only a few fields of a large compound are needed,
+//! [`from_binary_slice_ref`] avoids decoding the rest of it. The returned
+//! [`CompoundRef`] borrows from the input and decodes values on demand.
+//!
+//! ```rust
+//! use valence_nbt::{from_binary_slice_ref, ValueRef};
+//!
+//! let some_bytes = [10, 0, 0, 3, 0, 3, 105, 110, 116, 0, 0, 222, 173, 0];
+//!
+//! let (nbt, _) = from_binary_slice_ref(&mut some_bytes.as_slice()).unwrap();
+//!
+//! assert_eq!(nbt.get("int"), Some(ValueRef::Int(0xdead)));
+//! ```
+//!
 //! # Features
 //!
 //! - `preserve_order`: Causes the order of fields in [`Compound`]s to be
-//! preserved during insertion and deletion at a slight cost to performance.
-//! The iterators on `Compound` can then implement [`DoubleEndedIterator`].
+//!   preserved during insertion and deletion at a slight cost to performance.
+//!   The iterators on `Compound` can then implement [`DoubleEndedIterator`].
+//! - `compression`: Adds the `compression` module for reading and writing gzip
+//!   and zlib compressed NBT data.
+//! - `serde`: Adds the `serde` module for converting between NBT and types
+//!   implementing serde's `Serialize` and `Deserialize` traits.
 
 #![deny(
     rustdoc::broken_intra_doc_links,
@@ -68,16 +128,29 @@
 
 pub use compound::Compound;
 pub use error::Error;
+pub use from_binary_reader::{from_binary_reader, from_binary_reader_with_limits, DecodeLimits};
 pub use from_binary_slice::from_binary_slice;
+pub use path::NbtPath;
+pub use snbt::{from_snbt_str, to_snbt_string};
 pub use to_binary_writer::to_binary_writer;
 pub use value::{List, Value};
+pub use value_ref::{from_binary_slice_ref, CompoundRef, ValueRef};
 
 pub mod compound;
+#[cfg(feature = "compression")]
+pub mod compression;
+mod decode;
 mod error;
+mod from_binary_reader;
 mod from_binary_slice;
 mod modified_utf8;
+pub mod path;
+#[cfg(feature = "serde")]
+pub mod serde;
+mod snbt;
 mod to_binary_writer;
 pub mod value;
+pub mod value_ref;
 
 mod tag;
 #[cfg(test)]
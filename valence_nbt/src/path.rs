@@ -0,0 +1,857 @@
+//! NBT paths, which select values nested inside a compound.
+//!
+//! Paths use the syntax of the vanilla `/data` command, such as
+//! `display.Lore[0]` or `Items[{Slot:1b}].tag`. A path is a sequence of
+//! nodes:
+//!
+//! - `name` or `"quoted name"` selects the entry of a compound with that name.
+//! - `name{...}` selects the named entry if it is a compound matching the given
+//!   pattern.
+//! - `{...}` selects the root compound if it matches the pattern. This may only
+//!   appear at the start of a path.
+//! - `[i]` selects the `i`th element of a list or array. Negative indices count
+//!   back from the end.
+//! - `[]` selects every element of a list or array.
+//! - `[{...}]` selects every compound in a list matching the pattern.
+//!
+//! A compound matches a pattern if every entry in the pattern is present in
+//! the compound and matches. Lists in a pattern match if every element of the
+//! pattern list matches some element of the list. Other values must be equal.
+//!
+//! # Examples
+//!
+//! ```
+//! use valence_nbt::{compound, List, NbtPath, Value};
+//!
+//! let mut item = compound! {
+//!     "id" => "minecraft:diamond_sword",
+//!     "tag" => compound! {
+//!         "display" => compound! {
+//!             "Lore" => List::String(vec!["\"Sharp\"".into()]),
+//!         },
+//!     },
+//! };
+//!
+//! let path: NbtPath = "tag.display.Lore[0]".parse().unwrap();
+//!
+//! assert_eq!(path.get(&item).unwrap(), [Value::from("\"Sharp\"")]);
+//!
+//! assert_eq!(path.set(&mut item, "\"Blunt\"".into()).unwrap(), 1);
+//! assert_eq!(path.get(&item).unwrap(), [Value::from("\"Blunt\"")]);
+//!
+//! let name: NbtPath = "tag.display.Name".parse().unwrap();
+//! assert_eq!(name.set(&mut item, "\"Excalibur\"".into()).unwrap(), 1);
+//!
+//! assert_eq!(path.remove(&mut item), 1);
+//! assert!(path.get(&item).is_err());
+//! ```
+
+use std::borrow::Cow;
+use std::fmt::Write;
+use std::str::FromStr;
+use std::{fmt, mem};
+
+use crate::snbt::{is_unquoted_char, write_quoted, SnbtReader};
+use crate::tag::Tag;
+use crate::{Compound, Error, List, Result, Value};
+
+/// A parsed NBT path. See the [module documentation](self) for the syntax.
+#[derive(Clone, PartialEq, Debug)]
+pub struct NbtPath {
+    /// Never empty.
+    nodes: Vec<Node>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Node {
+    MatchRootObject(Compound),
+    CompoundChild(String),
+    MatchObject(String, Compound),
+    IndexedElement(i32),
+    AllElements,
+    MatchElement(Compound),
+}
+
+impl NbtPath {
+    /// Returns copies of every value selected by this path.
+    ///
+    /// Returns an error if nothing is selected.
+    pub fn get(&self, root: &Compound) -> Result<Vec<Value>> {
+        let mut refs = vec![Ref::Compound(root)];
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            refs = refs.into_iter().flat_map(|r| node.get(r)).collect();
+
+            if refs.is_empty() {
+                return Err(self.not_found(i));
+            }
+        }
+
+        Ok(refs.into_iter().map(Ref::into_value).collect())
+    }
+
+    /// Returns the number of values selected by this path.
+    pub fn count(&self, root: &Compound) -> usize {
+        let mut refs = vec![Ref::Compound(root)];
+
+        for node in &self.nodes {
+            refs = refs.into_iter().flat_map(|r| node.get(r)).collect();
+        }
+
+        refs.len()
+    }
+
+    /// Replaces every value selected by this path with `value`, creating
+    /// missing compounds and lists along the way.
+    ///
+    /// Returns the number of values which changed. Returns an error if the
+    /// parent of the last node could not be found or created.
+    pub fn set(&self, root: &mut Compound, value: Value) -> Result<usize> {
+        let last = self.nodes.last().unwrap();
+
+        Ok(self
+            .get_or_create_parents(root)?
+            .into_iter()
+            .map(|parent| last.set(parent, &value))
+            .sum())
+    }
+
+    /// Removes every value selected by this path.
+    ///
+    /// Returns the number of values removed.
+    pub fn remove(&self, root: &mut Compound) -> usize {
+        let (last, parents) = self.nodes.split_last().unwrap();
+
+        let mut muts = vec![Mut::Compound(root)];
+        for node in parents {
+            muts = muts.into_iter().flat_map(|m| node.get_mut(m)).collect();
+        }
+
+        muts.into_iter().map(|parent| last.remove(parent)).sum()
+    }
+
+    /// Merges `source` into every compound selected by this path, creating
+    /// the compounds if they are missing. Entries of `source` replace existing
+    /// entries, except that compounds present in both are merged recursively.
+    ///
+    /// Returns the number of compounds which changed. Returns an error if
+    /// nothing is selected or a selected value is not a compound, in which
+    /// case `root` is left unchanged.
+    pub fn merge(&self, root: &mut Compound, source: &Compound) -> Result<usize> {
+        // Work on a copy so that an error doesn't leave some targets merged or
+        // missing parents created.
+        let mut new_root = root.clone();
+        let changed = self.merge_in_place(&mut new_root, source)?;
+        *root = new_root;
+
+        Ok(changed)
+    }
+
+    fn merge_in_place(&self, root: &mut Compound, source: &Compound) -> Result<usize> {
+        let last = self.nodes.last().unwrap();
+
+        let targets: Vec<_> = self
+            .get_or_create_parents(root)?
+            .into_iter()
+            .flat_map(|parent| last.get_or_create(parent, || Value::Compound(Compound::new())))
+            .collect();
+
+        if targets.is_empty() {
+            return Err(self.not_found(self.nodes.len() - 1));
+        }
+
+        let mut changed = 0;
+
+        for target in targets {
+            let Mut::Compound(target) = target else {
+                return Err(Error::new_owned(format!(
+                    "cannot merge into non-compound value selected by {self}"
+                )));
+            };
+
+            let before = target.clone();
+            target.merge(source.clone());
+
+            if *target != before {
+                changed += 1;
+            }
+        }
+
+        Ok(changed)
+    }
+
+    /// Evaluates all but the last node, creating missing values on the way.
+    fn get_or_create_parents<'a>(&self, root: &'a mut Compound) -> Result<Vec<Mut<'a>>> {
+        let mut muts = vec![Mut::Compound(root)];
+
+        for (i, pair) in self.nodes.windows(2).enumerate() {
+            let [node, next] = pair else { unreachable!() };
+
+            muts = muts
+                .into_iter()
+                .flat_map(|m| node.get_or_create(m, || next.preferred_parent()))
+                .collect();
+
+            if muts.is_empty() {
+                return Err(self.not_found(i));
+            }
+        }
+
+        Ok(muts)
+    }
+
+    fn not_found(&self, node_idx: usize) -> Error {
+        Error::new_owned(format!(
+            "found no elements matching {}",
+            DisplayNodes(&self.nodes[..=node_idx])
+        ))
+    }
+}
+
+impl FromStr for NbtPath {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut reader = SnbtReader {
+            input: s,
+            pos: 0,
+            depth: 0,
+        };
+
+        let mut nodes = vec![];
+
+        loop {
+            nodes.push(read_node(&mut reader, nodes.is_empty())?);
+
+            match reader.peek() {
+                None => break,
+                Some('[' | '{') => {}
+                Some('.') => reader.pos += 1,
+                Some(_) => return Err(reader.error("expected '.'")),
+            }
+        }
+
+        Ok(Self { nodes })
+    }
+}
+
+fn read_node(reader: &mut SnbtReader, first: bool) -> Result<Node> {
+    match reader.peek() {
+        Some('{') if first => Ok(Node::MatchRootObject(reader.read_compound()?)),
+        Some('[') => {
+            reader.pos += 1;
+
+            let node = match reader.peek() {
+                Some('{') => Node::MatchElement(reader.read_compound()?),
+                Some(']') => Node::AllElements,
+                _ => {
+                    let rest = &reader.input[reader.pos..];
+                    let digits = rest.strip_prefix('-').unwrap_or(rest);
+                    let len = rest.len() - digits.len()
+                        + digits
+                            .find(|c: char| !c.is_ascii_digit())
+                            .unwrap_or(digits.len());
+
+                    let index = rest[..len]
+                        .parse()
+                        .map_err(|_| reader.error("expected index"))?;
+                    reader.pos += len;
+
+                    Node::IndexedElement(index)
+                }
+            };
+
+            if reader.peek() != Some(']') {
+                return Err(reader.error("expected ']'"));
+            }
+            reader.pos += 1;
+
+            Ok(node)
+        }
+        Some('"' | '\'') => {
+            let name = reader.read_quoted_string()?;
+            read_object_node(reader, name)
+        }
+        _ => {
+            let rest = &reader.input[reader.pos..];
+            let len = rest
+                .find([' ', '"', '\'', '[', ']', '.', '{', '}'])
+                .unwrap_or(rest.len());
+
+            if len == 0 {
+                return Err(reader.error("invalid path node"));
+            }
+
+            reader.pos += len;
+            read_object_node(reader, rest[..len].to_owned())
+        }
+    }
+}
+
+fn read_object_node(reader: &mut SnbtReader, name: String) -> Result<Node> {
+    if reader.peek() == Some('{') {
+        Ok(Node::MatchObject(name, reader.read_compound()?))
+    } else {
+        Ok(Node::CompoundChild(name))
+    }
+}
+
+impl fmt::Display for NbtPath {
+    /// Writes this path in the syntax it is parsed from.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        DisplayNodes(&self.nodes).fmt(f)
+    }
+}
+
+struct DisplayNodes<'a>(&'a [Node]);
+
+impl fmt::Display for DisplayNodes<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_name(f: &mut fmt::Formatter, name: &str) -> fmt::Result {
+            if !name.is_empty() && name.chars().all(is_unquoted_char) && !name.contains('.') {
+                f.write_str(name)
+            } else {
+                write_quoted(f, name)
+            }
+        }
+
+        for (i, node) in self.0.iter().enumerate() {
+            if i != 0 && matches!(node, Node::CompoundChild(_) | Node::MatchObject(..)) {
+                f.write_char('.')?;
+            }
+
+            match node {
+                Node::MatchRootObject(pattern) => write!(f, "{pattern}")?,
+                Node::CompoundChild(name) => write_name(f, name)?,
+                Node::MatchObject(name, pattern) => {
+                    write_name(f, name)?;
+                    write!(f, "{pattern}")?;
+                }
+                Node::IndexedElement(index) => write!(f, "[{index}]")?,
+                Node::AllElements => f.write_str("[]")?,
+                Node::MatchElement(pattern) => write!(f, "[{pattern}]")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A shared reference to a value selected by a node. Elements of lists and
+/// arrays which are not compounds or lists are copied because lists do not
+/// store them as [`Value`]s.
+enum Ref<'a> {
+    Compound(&'a Compound),
+    List(&'a List),
+    Other(Cow<'a, Value>),
+}
+
+impl<'a> Ref<'a> {
+    fn from_value(value: &'a Value) -> Self {
+        match value {
+            Value::Compound(c) => Ref::Compound(c),
+            Value::List(l) => Ref::List(l),
+            v => Ref::Other(Cow::Borrowed(v)),
+        }
+    }
+
+    fn into_value(self) -> Value {
+        match self {
+            Ref::Compound(c) => Value::Compound(c.clone()),
+            Ref::List(l) => Value::List(l.clone()),
+            Ref::Other(v) => v.into_owned(),
+        }
+    }
+
+    /// Returns the elements of a list or array.
+    fn elements(&self) -> Vec<Ref<'a>> {
+        fn copied<'b, T: Copy + Into<Value>>(array: &[T]) -> Vec<Ref<'b>> {
+            array
+                .iter()
+                .map(|&v| Ref::Other(Cow::Owned(v.into())))
+                .collect()
+        }
+
+        fn cloned<'b, T: Clone + Into<Value>>(list: &[T]) -> Vec<Ref<'b>> {
+            list.iter()
+                .map(|v| Ref::Other(Cow::Owned(v.clone().into())))
+                .collect()
+        }
+
+        match *self {
+            Ref::List(list) => match list {
+                List::Byte(l) => copied(l),
+                List::Short(l) => copied(l),
+                List::Int(l) => copied(l),
+                List::Long(l) => copied(l),
+                List::Float(l) => copied(l),
+                List::Double(l) => copied(l),
+                List::ByteArray(l) => cloned(l),
+                List::String(l) => cloned(l),
+                List::List(l) => l.iter().map(Ref::List).collect(),
+                List::Compound(l) => l.iter().map(Ref::Compound).collect(),
+                List::IntArray(l) => cloned(l),
+                List::LongArray(l) => cloned(l),
+            },
+            Ref::Other(ref v) => match v.as_ref() {
+                Value::ByteArray(a) => copied(a),
+                Value::IntArray(a) => copied(a),
+                Value::LongArray(a) => copied(a),
+                _ => vec![],
+            },
+            Ref::Compound(_) => vec![],
+        }
+    }
+}
+
+/// Returns whether `target` matches `pattern`, as described in the module
+/// documentation.
+fn matches(pattern: &Ref, target: &Ref) -> bool {
+    match (pattern, target) {
+        (Ref::Compound(pattern), Ref::Compound(target)) => compound_matches(pattern, target),
+        (Ref::List(pattern), Ref::List(target)) => {
+            if pattern.is_empty() {
+                return target.is_empty();
+            }
+
+            let target_elems = Ref::List(target).elements();
+
+            Ref::List(pattern)
+                .elements()
+                .iter()
+                .all(|p| target_elems.iter().any(|t| matches(p, t)))
+        }
+        (Ref::Other(pattern), Ref::Other(target)) => pattern == target,
+        _ => false,
+    }
+}
+
+fn compound_matches(pattern: &Compound, target: &Compound) -> bool {
+    pattern.iter().all(|(k, p)| match target.get(k) {
+        Some(t) => matches(&Ref::from_value(p), &Ref::from_value(t)),
+        None => false,
+    })
+}
+
+/// A mutable reference to a value selected by a node. Values which cannot
+/// contain other values and are not stored as [`Value`]s are represented by
+/// `Other`.
+enum Mut<'a> {
+    Compound(&'a mut Compound),
+    List(&'a mut List),
+    ByteArray(&'a mut Vec<i8>),
+    IntArray(&'a mut Vec<i32>),
+    LongArray(&'a mut Vec<i64>),
+    Other,
+}
+
+impl<'a> Mut<'a> {
+    fn from_value(value: &'a mut Value) -> Self {
+        match value {
+            Value::Compound(c) => Mut::Compound(c),
+            Value::List(l) => Mut::List(l),
+            Value::ByteArray(a) => Mut::ByteArray(a),
+            Value::IntArray(a) => Mut::IntArray(a),
+            Value::LongArray(a) => Mut::LongArray(a),
+            _ => Mut::Other,
+        }
+    }
+
+    /// Returns the elements of a list or array.
+    fn into_elements(self) -> Vec<Mut<'a>> {
+        fn others<'b, T>(elems: &[T]) -> Vec<Mut<'b>> {
+            elems.iter().map(|_| Mut::Other).collect()
+        }
+
+        match self {
+            Mut::List(list) => match list {
+                List::Byte(l) => others(l),
+                List::Short(l) => others(l),
+                List::Int(l) => others(l),
+                List::Long(l) => others(l),
+                List::Float(l) => others(l),
+                List::Double(l) => others(l),
+                List::ByteArray(l) => l.iter_mut().map(Mut::ByteArray).collect(),
+                List::String(l) => others(l),
+                List::List(l) => l.iter_mut().map(Mut::List).collect(),
+                List::Compound(l) => l.iter_mut().map(Mut::Compound).collect(),
+                List::IntArray(l) => l.iter_mut().map(Mut::IntArray).collect(),
+                List::LongArray(l) => l.iter_mut().map(Mut::LongArray).collect(),
+            },
+            Mut::ByteArray(a) => others(a),
+            Mut::IntArray(a) => others(a),
+            Mut::LongArray(a) => others(a),
+            Mut::Compound(_) | Mut::Other => vec![],
+        }
+    }
+
+    /// Returns the number of elements if this is a list or array.
+    fn len(&self) -> Option<usize> {
+        match self {
+            Mut::List(l) => Some(l.len()),
+            Mut::ByteArray(a) => Some(a.len()),
+            Mut::IntArray(a) => Some(a.len()),
+            Mut::LongArray(a) => Some(a.len()),
+            Mut::Compound(_) | Mut::Other => None,
+        }
+    }
+
+    /// Converts `value` to the type of this list or array's elements, or
+    /// returns `None` if it cannot be stored here. Arrays accept any number.
+    fn convert_element(&self, value: &Value) -> Option<Value> {
+        let number = || match *value {
+            Value::Byte(v) => Some(v as i64),
+            Value::Short(v) => Some(v as i64),
+            Value::Int(v) => Some(v as i64),
+            Value::Long(v) => Some(v),
+            Value::Float(v) => Some(v as i64),
+            Value::Double(v) => Some(v as i64),
+            _ => None,
+        };
+
+        match self {
+            Mut::List(l) => {
+                let compatible = match list_tag(l) {
+                    None => true,
+                    Some(tag) => tag == Tag::element_type(value),
+                };
+
+                compatible.then(|| value.clone())
+            }
+            Mut::ByteArray(_) => number().map(|n| Value::Byte(n as i8)),
+            Mut::IntArray(_) => number().map(|n| Value::Int(n as i32)),
+            Mut::LongArray(_) => number().map(Value::Long),
+            Mut::Compound(_) | Mut::Other => None,
+        }
+    }
+
+    /// Calls `f` with the elements of this list or array. `f` must only add
+    /// elements returned by [`Mut::convert_element`] or, for lists, make every
+    /// element identical.
+    fn modify_elements<T>(&mut self, f: impl FnOnce(&mut Vec<Value>) -> T) -> Option<T> {
+        macro_rules! modify_array {
+            ($array:expr, $variant:ident) => {{
+                let mut values = $array.iter().map(|&v| Value::$variant(v)).collect();
+                let res = f(&mut values);
+
+                **$array = values
+                    .into_iter()
+                    .map(|v| match v {
+                        Value::$variant(v) => v,
+                        _ => unreachable!("array element of wrong type"),
+                    })
+                    .collect();
+
+                Some(res)
+            }};
+        }
+
+        match self {
+            Mut::List(list) => {
+                let mut values = mem::replace(&mut **list, List::Byte(vec![])).into_values();
+                let res = f(&mut values);
+                **list = List::from_values(values);
+                Some(res)
+            }
+            Mut::ByteArray(a) => modify_array!(a, Byte),
+            Mut::IntArray(a) => modify_array!(a, Int),
+            Mut::LongArray(a) => modify_array!(a, Long),
+            Mut::Compound(_) | Mut::Other => None,
+        }
+    }
+}
+
+/// Returns the tag of a list's elements, or `None` if it is empty.
+fn list_tag(list: &List) -> Option<Tag> {
+    if list.is_empty() {
+        return None;
+    }
+
+    Some(match list {
+        List::Byte(_) => Tag::Byte,
+        List::Short(_) => Tag::Short,
+        List::Int(_) => Tag::Int,
+        List::Long(_) => Tag::Long,
+        List::Float(_) => Tag::Float,
+        List::Double(_) => Tag::Double,
+        List::ByteArray(_) => Tag::ByteArray,
+        List::String(_) => Tag::String,
+        List::List(_) => Tag::List,
+        List::Compound(_) => Tag::Compound,
+        List::IntArray(_) => Tag::IntArray,
+        List::LongArray(_) => Tag::LongArray,
+    })
+}
+
+/// Resolves a possibly negative index into a list or array of length `len`.
+fn resolve_index(index: i32, len: usize) -> Option<usize> {
+    let index = if index < 0 {
+        len as i64 + index as i64
+    } else {
+        index as i64
+    };
+
+    (0..len as i64).contains(&index).then_some(index as usize)
+}
+
+impl Node {
+    fn get<'a>(&self, parent: Ref<'a>) -> Vec<Ref<'a>> {
+        match (self, parent) {
+            (Node::MatchRootObject(pattern), Ref::Compound(c)) => {
+                if compound_matches(pattern, c) {
+                    vec![Ref::Compound(c)]
+                } else {
+                    vec![]
+                }
+            }
+            (Node::CompoundChild(name), Ref::Compound(c)) => c
+                .get(name.as_str())
+                .map(Ref::from_value)
+                .into_iter()
+                .collect(),
+            (Node::MatchObject(name, pattern), Ref::Compound(c)) => match c.get(name.as_str()) {
+                Some(Value::Compound(child)) if compound_matches(pattern, child) => {
+                    vec![Ref::Compound(child)]
+                }
+                _ => vec![],
+            },
+            (Node::IndexedElement(index), parent) => {
+                let elems = parent.elements();
+
+                match resolve_index(*index, elems.len()) {
+                    Some(i) => elems.into_iter().skip(i).take(1).collect(),
+                    None => vec![],
+                }
+            }
+            (Node::AllElements, parent) => parent.elements(),
+            (Node::MatchElement(pattern), parent @ Ref::List(_)) => parent
+                .elements()
+                .into_iter()
+                .filter(|e| matches!(e, Ref::Compound(c) if compound_matches(pattern, c)))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    fn get_mut<'a>(&self, parent: Mut<'a>) -> Vec<Mut<'a>> {
+        match (self, parent) {
+            (Node::MatchRootObject(pattern), Mut::Compound(c)) => {
+                if compound_matches(pattern, c) {
+                    vec![Mut::Compound(c)]
+                } else {
+                    vec![]
+                }
+            }
+            (Node::CompoundChild(name), Mut::Compound(c)) => c
+                .get_mut(name.as_str())
+                .map(Mut::from_value)
+                .into_iter()
+                .collect(),
+            (Node::MatchObject(name, pattern), Mut::Compound(c)) => {
+                match c.get_mut(name.as_str()) {
+                    Some(Value::Compound(child)) if compound_matches(pattern, child) => {
+                        vec![Mut::Compound(child)]
+                    }
+                    _ => vec![],
+                }
+            }
+            (Node::IndexedElement(index), parent) => {
+                let elems = parent.into_elements();
+
+                match resolve_index(*index, elems.len()) {
+                    Some(i) => elems.into_iter().skip(i).take(1).collect(),
+                    None => vec![],
+                }
+            }
+            (Node::AllElements, parent) => parent.into_elements(),
+            (Node::MatchElement(pattern), parent @ Mut::List(_)) => parent
+                .into_elements()
+                .into_iter()
+                .filter(|e| matches!(e, Mut::Compound(c) if compound_matches(pattern, c)))
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Like [`Node::get_mut`], but inserts the value returned by `create` if
+    /// this node selects nothing and is able to create the value.
+    fn get_or_create<'a>(&self, parent: Mut<'a>, create: impl FnOnce() -> Value) -> Vec<Mut<'a>> {
+        match (self, parent) {
+            (Node::CompoundChild(name), Mut::Compound(c)) => {
+                vec![Mut::from_value(
+                    c.entry(name.clone()).or_insert_with(create),
+                )]
+            }
+            (Node::MatchObject(name, pattern), Mut::Compound(c)) => {
+                if !c.contains_key(name.as_str()) {
+                    c.insert(name.clone(), pattern.clone());
+                }
+
+                self.get_mut(Mut::Compound(c))
+            }
+            (Node::AllElements, mut parent) => {
+                if parent.len() == Some(0) {
+                    if let Some(value) = parent.convert_element(&create()) {
+                        parent.modify_elements(|elems| elems.push(value));
+                    }
+                }
+
+                parent.into_elements()
+            }
+            (Node::MatchElement(pattern), Mut::List(list)) => {
+                let any_match = match list {
+                    List::Compound(l) => l.iter().any(|c| compound_matches(pattern, c)),
+                    _ => false,
+                };
+
+                if !any_match {
+                    let mut parent = Mut::List(list);
+                    let pattern = Value::Compound(pattern.clone());
+
+                    if let Some(value) = parent.convert_element(&pattern) {
+                        parent.modify_elements(|elems| elems.push(value));
+                    }
+
+                    return self.get_mut(parent);
+                }
+
+                self.get_mut(Mut::List(list))
+            }
+            (_, parent) => self.get_mut(parent),
+        }
+    }
+
+    /// Returns the value created for a missing parent of this node.
+    fn preferred_parent(&self) -> Value {
+        match self {
+            Node::MatchRootObject(_) | Node::CompoundChild(_) | Node::MatchObject(..) => {
+                Value::Compound(Compound::new())
+            }
+            Node::IndexedElement(_) | Node::AllElements | Node::MatchElement(_) => {
+                Value::List(List::Byte(vec![]))
+            }
+        }
+    }
+
+    /// Replaces the values this node selects in `parent`. Returns the number
+    /// of values which changed.
+    fn set(&self, mut parent: Mut, value: &Value) -> usize {
+        match self {
+            Node::MatchRootObject(_) => 0,
+            Node::CompoundChild(name) => match parent {
+                Mut::Compound(c) if c.get(name.as_str()) != Some(value) => {
+                    c.insert(name.clone(), value.clone());
+                    1
+                }
+                _ => 0,
+            },
+            Node::MatchObject(name, pattern) => match parent {
+                Mut::Compound(c) => match c.get(name.as_str()) {
+                    Some(child @ Value::Compound(child_compound))
+                        if compound_matches(pattern, child_compound) && child != value =>
+                    {
+                        c.insert(name.clone(), value.clone());
+                        1
+                    }
+                    _ => 0,
+                },
+                _ => 0,
+            },
+            Node::IndexedElement(index) => {
+                let Some(value) = parent.convert_element(value) else {
+                    return 0;
+                };
+
+                parent
+                    .modify_elements(|elems| match resolve_index(*index, elems.len()) {
+                        Some(i) if elems[i] != value => {
+                            elems[i] = value;
+                            1
+                        }
+                        _ => 0,
+                    })
+                    .unwrap_or(0)
+            }
+            Node::AllElements => {
+                // Lists are cleared before being refilled, so any type is allowed.
+                let value = match parent {
+                    Mut::List(_) => value.clone(),
+                    _ => match parent.convert_element(value) {
+                        Some(value) => value,
+                        None => return 0,
+                    },
+                };
+
+                parent
+                    .modify_elements(|elems| {
+                        if elems.is_empty() {
+                            elems.push(value);
+                            return 1;
+                        }
+
+                        let changed = elems.iter().filter(|&e| *e != value).count();
+                        elems.fill(value);
+                        changed
+                    })
+                    .unwrap_or(0)
+            }
+            Node::MatchElement(pattern) => match (parent, value) {
+                (Mut::List(List::Compound(list)), Value::Compound(value)) => {
+                    let mut changed = 0;
+
+                    for elem in list {
+                        if compound_matches(pattern, elem) && elem != value {
+                            *elem = value.clone();
+                            changed += 1;
+                        }
+                    }
+
+                    changed
+                }
+                _ => 0,
+            },
+        }
+    }
+
+    /// Removes the values this node selects from `parent`. Returns the number
+    /// of values removed.
+    fn remove(&self, mut parent: Mut) -> usize {
+        match self {
+            Node::MatchRootObject(_) => 0,
+            Node::CompoundChild(name) => match parent {
+                Mut::Compound(c) => c.remove(name.as_str()).is_some() as usize,
+                _ => 0,
+            },
+            Node::MatchObject(name, pattern) => match parent {
+                Mut::Compound(c) => match c.get(name.as_str()) {
+                    Some(Value::Compound(child)) if compound_matches(pattern, child) => {
+                        c.remove(name.as_str());
+                        1
+                    }
+                    _ => 0,
+                },
+                _ => 0,
+            },
+            Node::IndexedElement(index) => parent
+                .modify_elements(|elems| match resolve_index(*index, elems.len()) {
+                    Some(i) => {
+                        elems.remove(i);
+                        1
+                    }
+                    None => 0,
+                })
+                .unwrap_or(0),
+            Node::AllElements => parent
+                .modify_elements(|elems| mem::take(elems).len())
+                .unwrap_or(0),
+            Node::MatchElement(pattern) => match parent {
+                Mut::List(List::Compound(list)) => {
+                    let len = list.len();
+                    list.retain(|c| !compound_matches(pattern, c));
+                    len - list.len()
+                }
+                _ => 0,
+            },
+        }
+    }
+}
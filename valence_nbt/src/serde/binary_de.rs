@@ -0,0 +1,448 @@
+use std::borrow::Cow;
+
+use ::serde::de::{
+    self, DeserializeSeed, EnumAccess, IgnoredAny, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use ::serde::{forward_to_deserialize_any, Deserialize, Deserializer};
+
+use super::{array_name, VALUE_NAME};
+use crate::decode::{check_len, min_size};
+use crate::from_binary_slice::DecodeState;
+use crate::tag::Tag;
+use crate::{Error, Result};
+
+/// Decodes uncompressed NBT binary data from the provided slice into a `T`
+/// without building an intermediate [`Compound`](crate::Compound).
+///
+/// Strings which do not need to be converted from modified UTF-8 are
+/// borrowed from the input when `T` allows it. The string returned is the
+/// name of the root compound.
+pub fn from_binary_slice<'de, T>(slice: &mut &'de [u8]) -> Result<(T, String)>
+where
+    T: Deserialize<'de>,
+{
+    let mut state = DecodeState::new(slice);
+
+    let root_tag = state.read_tag()?;
+    if root_tag != Tag::Compound {
+        return Err(Error::new_owned(format!(
+            "expected root tag for compound (got {root_tag})",
+        )));
+    }
+
+    let root_name = state.read_str()?.into_owned();
+    let root = T::deserialize(PayloadDeserializer::new(&mut state, Tag::Compound))?;
+
+    debug_assert_eq!(state.depth.current(), 0);
+
+    Ok((root, root_name))
+}
+
+/// Reads the length of a list or array, ensuring it does not exceed the
+/// remainder of the input.
+fn read_len(state: &mut DecodeState, desc: &str, elem_tag: Tag) -> Result<i32> {
+    let len = state.read_int()?;
+    check_len(desc, len, min_size(elem_tag), state.remaining())?;
+    Ok(len)
+}
+
+/// Deserializes the payload of a value whose tag has already been read.
+struct PayloadDeserializer<'s, 'a, 'de> {
+    state: &'s mut DecodeState<'a, 'de>,
+    tag: Tag,
+    /// Whether arrays should be presented as single-entry maps for
+    /// [`Value`](crate::Value)'s `Deserialize` implementation.
+    as_value: bool,
+}
+
+impl<'s, 'a, 'de> PayloadDeserializer<'s, 'a, 'de> {
+    fn new(state: &'s mut DecodeState<'a, 'de>, tag: Tag) -> Self {
+        Self {
+            state,
+            tag,
+            as_value: false,
+        }
+    }
+
+    fn visit_seq<V: Visitor<'de>>(self, elem_tag: Tag, len: i32, visitor: V) -> Result<V::Value> {
+        self.state.check_depth(|state| {
+            let mut seq = SeqDeserializer {
+                state,
+                elem_tag,
+                remaining: len,
+            };
+
+            let value = visitor.visit_seq(&mut seq)?;
+
+            if seq.remaining != 0 {
+                return Err(de::Error::invalid_length(
+                    len as usize,
+                    &"fewer elements in sequence",
+                ));
+            }
+
+            Ok(value)
+        })
+    }
+}
+
+impl<'s, 'a, 'de> Deserializer<'de> for PayloadDeserializer<'s, 'a, 'de> {
+    type Error = Error;
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct identifier
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.tag {
+            Tag::End => unreachable!("illegal TAG_End argument"),
+            Tag::Byte => visitor.visit_i8(self.state.read_byte()?),
+            Tag::Short => visitor.visit_i16(self.state.read_short()?),
+            Tag::Int => visitor.visit_i32(self.state.read_int()?),
+            Tag::Long => visitor.visit_i64(self.state.read_long()?),
+            Tag::Float => visitor.visit_f32(self.state.read_float()?),
+            Tag::Double => visitor.visit_f64(self.state.read_double()?),
+            Tag::String => match self.state.read_str()? {
+                Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+                Cow::Owned(s) => visitor.visit_string(s),
+            },
+            Tag::List => {
+                let elem_tag = self.state.read_tag()?;
+                let len = read_len(self.state, "list", elem_tag)?;
+
+                if elem_tag == Tag::End && len != 0 {
+                    return Err(Error::new_owned(format!(
+                        "TAG_End list with nonzero length of {len}"
+                    )));
+                }
+
+                self.visit_seq(elem_tag, len, visitor)
+            }
+            Tag::Compound => self.state.check_depth(|state| {
+                let mut map = MapDeserializer {
+                    state,
+                    tag: None,
+                    done: false,
+                };
+                let value = visitor.visit_map(&mut map)?;
+
+                // Skip entries the visitor did not ask for.
+                while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {}
+
+                Ok(value)
+            }),
+            tag @ (Tag::ByteArray | Tag::IntArray | Tag::LongArray) => {
+                let elem_tag = match tag {
+                    Tag::ByteArray => Tag::Byte,
+                    Tag::IntArray => Tag::Int,
+                    _ => Tag::Long,
+                };
+
+                if self.as_value {
+                    visitor.visit_map(ArrayMapDeserializer {
+                        name: array_name(tag),
+                        payload: Some(PayloadDeserializer::new(self.state, tag)),
+                    })
+                } else {
+                    let len = read_len(self.state, &tag.to_string(), elem_tag)?;
+                    self.visit_seq(elem_tag, len, visitor)
+                }
+            }
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.tag {
+            Tag::Byte => visitor.visit_bool(self.state.read_byte()? != 0),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.tag {
+            Tag::Byte => visitor.visit_u8(self.state.read_byte()? as u8),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.tag {
+            Tag::Short => visitor.visit_u16(self.state.read_short()? as u16),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.tag {
+            Tag::Int => visitor.visit_u32(self.state.read_int()? as u32),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.tag {
+            Tag::Long => visitor.visit_u64(self.state.read_long()? as u64),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.tag {
+            Tag::ByteArray => {
+                let len = read_len(self.state, "byte array", Tag::Byte)? as usize;
+                let (bytes, rest) = self.state.slice.split_at(len);
+                *self.state.slice = rest;
+
+                visitor.visit_borrowed_bytes(bytes)
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        // Absent compound fields are the only representation of `None`.
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(Self {
+            as_value: name == VALUE_NAME,
+            ..self
+        })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self.tag {
+            Tag::String => visitor.visit_enum(self.state.read_str()?.into_deserializer()),
+            Tag::Compound => self
+                .state
+                .check_depth(|state| visitor.visit_enum(EnumDeserializer { state })),
+            tag => Err(Error::new_owned(format!(
+                "expected string or compound for enum (got {tag})"
+            ))),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_any(visitor)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+/// Deserializes the elements of a list or array.
+struct SeqDeserializer<'s, 'a, 'de> {
+    state: &'s mut DecodeState<'a, 'de>,
+    elem_tag: Tag,
+    remaining: i32,
+}
+
+impl<'de> SeqAccess<'de> for SeqDeserializer<'_, '_, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+
+        seed.deserialize(PayloadDeserializer::new(self.state, self.elem_tag))
+            .map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining as usize)
+    }
+}
+
+/// Deserializes the entries of a compound.
+struct MapDeserializer<'s, 'a, 'de> {
+    state: &'s mut DecodeState<'a, 'de>,
+    /// The tag of the entry whose key was just read.
+    tag: Option<Tag>,
+    /// Whether the end tag has been read.
+    done: bool,
+}
+
+impl<'de> MapAccess<'de> for MapDeserializer<'_, '_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        if self.tag.is_some() {
+            // The previous value was never read.
+            let tag = self.tag.take().unwrap();
+            IgnoredAny::deserialize(PayloadDeserializer::new(self.state, tag))?;
+        }
+
+        if self.done {
+            return Ok(None);
+        }
+
+        let tag = self.state.read_tag()?;
+        if tag == Tag::End {
+            self.done = true;
+            return Ok(None);
+        }
+
+        let key = seed.deserialize(StrDeserializer(self.state.read_str()?))?;
+        self.tag = Some(tag);
+
+        Ok(Some(key))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let tag = self
+            .tag
+            .take()
+            .ok_or_else(|| Error::new_static("compound value requested before key"))?;
+
+        seed.deserialize(PayloadDeserializer::new(self.state, tag))
+    }
+}
+
+/// Presents an array as a map with a single entry keyed by the array's name.
+struct ArrayMapDeserializer<'s, 'a, 'de> {
+    name: Option<&'static str>,
+    payload: Option<PayloadDeserializer<'s, 'a, 'de>>,
+}
+
+impl<'de> MapAccess<'de> for ArrayMapDeserializer<'_, '_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.name.take() {
+            Some(name) => seed.deserialize(name.into_deserializer()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let payload = self
+            .payload
+            .take()
+            .ok_or_else(|| Error::new_static("array value requested twice"))?;
+
+        seed.deserialize(payload)
+    }
+}
+
+/// Deserializes an enum variant stored as a compound with a single entry.
+struct EnumDeserializer<'s, 'a, 'de> {
+    state: &'s mut DecodeState<'a, 'de>,
+}
+
+impl<'s, 'a, 'de> EnumAccess<'de> for EnumDeserializer<'s, 'a, 'de> {
+    type Error = Error;
+    type Variant = VariantDeserializer<'s, 'a, 'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant)> {
+        let tag = self.state.read_tag()?;
+        if tag == Tag::End {
+            return Err(Error::new_static(
+                "expected enum variant (got empty compound)",
+            ));
+        }
+
+        let variant = seed.deserialize(StrDeserializer(self.state.read_str()?))?;
+
+        Ok((
+            variant,
+            VariantDeserializer {
+                state: self.state,
+                tag,
+            },
+        ))
+    }
+}
+
+struct VariantDeserializer<'s, 'a, 'de> {
+    state: &'s mut DecodeState<'a, 'de>,
+    tag: Tag,
+}
+
+impl<'de> VariantDeserializer<'_, '_, 'de> {
+    fn deserialize_with<T>(
+        self,
+        f: impl FnOnce(PayloadDeserializer<'_, '_, 'de>) -> Result<T>,
+    ) -> Result<T> {
+        let value = f(PayloadDeserializer::new(self.state, self.tag))?;
+
+        match self.state.read_tag()? {
+            Tag::End => Ok(value),
+            _ => Err(Error::new_static(
+                "enum variant compound must have exactly one entry",
+            )),
+        }
+    }
+}
+
+impl<'de> VariantAccess<'de> for VariantDeserializer<'_, '_, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        self.deserialize_with(|de| IgnoredAny::deserialize(de).map(|_| ()))
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        self.deserialize_with(|de| seed.deserialize(de))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_with(|de| de.deserialize_seq(visitor))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_with(|de| de.deserialize_map(visitor))
+    }
+}
+
+/// Deserializes compound keys and enum variant names, borrowing from the input
+/// when possible.
+struct StrDeserializer<'de>(Cow<'de, str>);
+
+impl<'de> Deserializer<'de> for StrDeserializer<'de> {
+    type Error = Error;
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.0 {
+            Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+            Cow::Owned(s) => visitor.visit_string(s),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+}
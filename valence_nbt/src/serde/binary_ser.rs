@@ -0,0 +1,884 @@
+use std::io::Write;
+
+use ::serde::ser::{
+    SerializeMap, SerializeSeq, SerializeStruct, SerializeStructVariant, SerializeTuple,
+    SerializeTupleStruct, SerializeTupleVariant,
+};
+use ::serde::{Serialize, Serializer};
+
+use super::array_tag;
+use super::ser::KeySerializer;
+use crate::tag::Tag;
+use crate::to_binary_writer::EncodeState;
+use crate::{Error, Result};
+
+/// Encodes a `T` as uncompressed NBT binary data to the provided writer
+/// without building an intermediate [`Compound`](crate::Compound).
+///
+/// `T` must serialize to a compound, such as a struct or a map. The root
+/// compound is given the name `root_name`.
+pub fn to_binary_writer<W, T>(writer: W, value: &T, root_name: &str) -> Result<()>
+where
+    W: Write,
+    T: Serialize + ?Sized,
+{
+    if tag_of(value)? != Some(Tag::Compound) {
+        return Err(Error::new_static("root value must serialize to a compound"));
+    }
+
+    let mut state = EncodeState { writer };
+
+    state.write_tag(Tag::Compound)?;
+    state.write_string(root_name)?;
+    value.serialize(PayloadSerializer { state: &mut state })
+}
+
+/// Determines the tag a value is encoded with, or `None` for `Option::None`.
+fn tag_of<T: Serialize + ?Sized>(value: &T) -> Result<Option<Tag>> {
+    value.serialize(TagSerializer)
+}
+
+fn list_len(len: Option<usize>) -> Result<i32> {
+    let len = len.ok_or_else(|| Error::new_static("sequence length must be known"))?;
+
+    len.try_into().map_err(|_| {
+        Error::new_owned(format!(
+            "sequence of length {len} exceeds maximum of i32::MAX"
+        ))
+    })
+}
+
+/// Serializes the payload of a value, which excludes the tag and name.
+struct PayloadSerializer<'a, W> {
+    state: &'a mut EncodeState<W>,
+}
+
+impl<'a, W: Write> Serializer for PayloadSerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = ListSerializer<'a, W>;
+    type SerializeTuple = ListSerializer<'a, W>;
+    type SerializeTupleStruct = ListSerializer<'a, W>;
+    type SerializeTupleVariant = ListSerializer<'a, W>;
+    type SerializeMap = CompoundSerializer<'a, W>;
+    type SerializeStruct = CompoundSerializer<'a, W>;
+    type SerializeStructVariant = CompoundSerializer<'a, W>;
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        self.state.write_byte(v as i8)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.state.write_byte(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.state.write_short(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.state.write_int(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        self.state.write_long(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.state.write_byte(v as i8)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.state.write_short(v as i16)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.state.write_int(v as i32)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        self.state.write_long(v as i64)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        self.state.write_float(v)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        self.state.write_double(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<()> {
+        self.state.write_string(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<()> {
+        self.state.write_string(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        self.state.write_int(list_len(Some(v.len()))?)?;
+        Ok(self.state.writer.write_all(v)?)
+    }
+
+    fn serialize_none(self) -> Result<()> {
+        Err(Error::new_static(
+            "cannot serialize `None` outside of a compound",
+        ))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Err(Error::new_static("cannot serialize unit"))
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<()> {
+        Err(Error::new_owned(format!(
+            "cannot serialize unit struct `{name}`"
+        )))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        self.state.write_string(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        match array_tag(name) {
+            Some(tag) => value.serialize(ArraySerializer {
+                state: self.state,
+                tag,
+            }),
+            None => value.serialize(self),
+        }
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        let mut compound = CompoundSerializer {
+            state: self.state,
+            key: None,
+            close_variant: false,
+        };
+
+        compound.write_field(variant, value)?;
+        SerializeStruct::end(compound)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(ListSerializer {
+            state: self.state,
+            len: list_len(len)?,
+            count: 0,
+            elem_tag: None,
+            close_variant: false,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        self.state.write_tag(Tag::List)?;
+        self.state.write_string(variant)?;
+
+        Ok(ListSerializer {
+            state: self.state,
+            len: list_len(Some(len))?,
+            count: 0,
+            elem_tag: None,
+            close_variant: true,
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(CompoundSerializer {
+            state: self.state,
+            key: None,
+            close_variant: false,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        self.state.write_tag(Tag::Compound)?;
+        self.state.write_string(variant)?;
+
+        Ok(CompoundSerializer {
+            state: self.state,
+            key: None,
+            close_variant: true,
+        })
+    }
+}
+
+struct ListSerializer<'a, W> {
+    state: &'a mut EncodeState<W>,
+    len: i32,
+    count: i32,
+    /// The tag of the first element. The list header is written once it is
+    /// known.
+    elem_tag: Option<Tag>,
+    /// Whether the list is wrapped in a compound for an enum variant.
+    close_variant: bool,
+}
+
+impl<W: Write> ListSerializer<'_, W> {
+    fn write_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let tag =
+            tag_of(value)?.ok_or_else(|| Error::new_static("cannot serialize `None` in a list"))?;
+
+        match self.elem_tag {
+            None => {
+                self.state.write_tag(tag)?;
+                self.state.write_int(self.len)?;
+                self.elem_tag = Some(tag);
+            }
+            Some(elem_tag) if elem_tag != tag => {
+                return Err(Error::new_owned(format!(
+                    "cannot insert {tag} into list of {elem_tag}"
+                )))
+            }
+            Some(_) => {}
+        }
+
+        if self.count == self.len {
+            return Err(Error::new_static(
+                "sequence has more elements than its length",
+            ));
+        }
+        self.count += 1;
+
+        value.serialize(PayloadSerializer { state: self.state })
+    }
+
+    fn finish(self) -> Result<()> {
+        if self.count != self.len {
+            return Err(Error::new_static(
+                "sequence has fewer elements than its length",
+            ));
+        }
+
+        if self.elem_tag.is_none() {
+            // Empty lists have the end tag as their element type, like in vanilla.
+            self.state.write_tag(Tag::End)?;
+            self.state.write_int(0)?;
+        }
+
+        if self.close_variant {
+            self.state.write_tag(Tag::End)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write> SerializeSeq for ListSerializer<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.write_element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl<W: Write> SerializeTuple for ListSerializer<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.write_element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl<W: Write> SerializeTupleStruct for ListSerializer<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.write_element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl<W: Write> SerializeTupleVariant for ListSerializer<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.write_element(value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+struct CompoundSerializer<'a, W> {
+    state: &'a mut EncodeState<W>,
+    /// The key of the entry currently being serialized.
+    key: Option<String>,
+    /// Whether the compound is wrapped in another compound for an enum
+    /// variant.
+    close_variant: bool,
+}
+
+impl<W: Write> CompoundSerializer<'_, W> {
+    fn write_field<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<()> {
+        if let Some(tag) = tag_of(value)? {
+            self.state.write_tag(tag)?;
+            self.state.write_string(key)?;
+            value.serialize(PayloadSerializer { state: self.state })?;
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> Result<()> {
+        self.state.write_tag(Tag::End)?;
+
+        if self.close_variant {
+            self.state.write_tag(Tag::End)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write> SerializeMap for CompoundSerializer<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        self.key = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::new_static("map value serialized before key"))?;
+
+        self.write_field(&key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl<W: Write> SerializeStruct for CompoundSerializer<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.write_field(key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+impl<W: Write> SerializeStructVariant for CompoundSerializer<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.write_field(key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.finish()
+    }
+}
+
+/// Serializes the payload of a byte, int or long array from a sequence of
+/// integers.
+struct ArraySerializer<'a, W> {
+    state: &'a mut EncodeState<W>,
+    tag: Tag,
+}
+
+impl<'a, W: Write> ArraySerializer<'a, W> {
+    fn elem_tag(&self) -> Tag {
+        match self.tag {
+            Tag::ByteArray => Tag::Byte,
+            Tag::IntArray => Tag::Int,
+            _ => Tag::Long,
+        }
+    }
+}
+
+impl<'a, W: Write> Serializer for ArraySerializer<'a, W> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = ::serde::ser::Impossible<(), Error>;
+    type SerializeTupleVariant = ::serde::ser::Impossible<(), Error>;
+    type SerializeMap = ::serde::ser::Impossible<(), Error>;
+    type SerializeStruct = ::serde::ser::Impossible<(), Error>;
+    type SerializeStructVariant = ::serde::ser::Impossible<(), Error>;
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        if self.tag != Tag::ByteArray {
+            return Err(Error::new_owned(format!(
+                "cannot serialize bytes as {}",
+                self.tag
+            )));
+        }
+
+        PayloadSerializer { state: self.state }.serialize_bytes(v)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self> {
+        self.state.write_int(list_len(len)?)?;
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self> {
+        self.serialize_seq(Some(len))
+    }
+
+    unsupported! {
+        "arrays must be serialized from sequences of integers";
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_none(),
+        serialize_unit(),
+        serialize_unit_struct(&'static str),
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<()> {
+        Err(Error::new_static(
+            "arrays must be serialized from sequences of integers",
+        ))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<()> {
+        Err(Error::new_static(
+            "arrays must be serialized from sequences of integers",
+        ))
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<()> {
+        Err(Error::new_static(
+            "arrays must be serialized from sequences of integers",
+        ))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(Error::new_static(
+            "arrays must be serialized from sequences of integers",
+        ))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(Error::new_static(
+            "arrays must be serialized from sequences of integers",
+        ))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(Error::new_static(
+            "arrays must be serialized from sequences of integers",
+        ))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(Error::new_static(
+            "arrays must be serialized from sequences of integers",
+        ))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(Error::new_static(
+            "arrays must be serialized from sequences of integers",
+        ))
+    }
+}
+
+impl<W: Write> SerializeSeq for ArraySerializer<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let elem_tag = self.elem_tag();
+
+        if tag_of(value)? != Some(elem_tag) {
+            return Err(Error::new_owned(format!(
+                "{} elements must be {elem_tag}s",
+                self.tag
+            )));
+        }
+
+        value.serialize(PayloadSerializer { state: self.state })
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<W: Write> SerializeTuple for ArraySerializer<'_, W> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Determines the tag a value will be encoded with without encoding it.
+struct TagSerializer;
+
+/// Discards the elements of a list or compound and returns its tag.
+struct TagOnly(Tag);
+
+impl Serializer for TagSerializer {
+    type Ok = Option<Tag>;
+    type Error = Error;
+    type SerializeSeq = TagOnly;
+    type SerializeTuple = TagOnly;
+    type SerializeTupleStruct = TagOnly;
+    type SerializeTupleVariant = TagOnly;
+    type SerializeMap = TagOnly;
+    type SerializeStruct = TagOnly;
+    type SerializeStructVariant = TagOnly;
+
+    fn serialize_bool(self, _v: bool) -> Result<Self::Ok> {
+        Ok(Some(Tag::Byte))
+    }
+
+    fn serialize_i8(self, _v: i8) -> Result<Self::Ok> {
+        Ok(Some(Tag::Byte))
+    }
+
+    fn serialize_i16(self, _v: i16) -> Result<Self::Ok> {
+        Ok(Some(Tag::Short))
+    }
+
+    fn serialize_i32(self, _v: i32) -> Result<Self::Ok> {
+        Ok(Some(Tag::Int))
+    }
+
+    fn serialize_i64(self, _v: i64) -> Result<Self::Ok> {
+        Ok(Some(Tag::Long))
+    }
+
+    fn serialize_u8(self, _v: u8) -> Result<Self::Ok> {
+        Ok(Some(Tag::Byte))
+    }
+
+    fn serialize_u16(self, _v: u16) -> Result<Self::Ok> {
+        Ok(Some(Tag::Short))
+    }
+
+    fn serialize_u32(self, _v: u32) -> Result<Self::Ok> {
+        Ok(Some(Tag::Int))
+    }
+
+    fn serialize_u64(self, _v: u64) -> Result<Self::Ok> {
+        Ok(Some(Tag::Long))
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Self::Ok> {
+        Ok(Some(Tag::Float))
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Self::Ok> {
+        Ok(Some(Tag::Double))
+    }
+
+    fn serialize_char(self, _v: char) -> Result<Self::Ok> {
+        Ok(Some(Tag::String))
+    }
+
+    fn serialize_str(self, _v: &str) -> Result<Self::Ok> {
+        Ok(Some(Tag::String))
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok> {
+        Ok(Some(Tag::ByteArray))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        Err(Error::new_static("cannot serialize unit"))
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Self::Ok> {
+        Err(Error::new_owned(format!(
+            "cannot serialize unit struct `{name}`"
+        )))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<Self::Ok> {
+        Ok(Some(Tag::String))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok> {
+        match array_tag(name) {
+            Some(tag) => Ok(Some(tag)),
+            None => value.serialize(self),
+        }
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok> {
+        Ok(Some(Tag::Compound))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(TagOnly(Tag::List))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Ok(TagOnly(Tag::List))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Ok(TagOnly(Tag::List))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Ok(TagOnly(Tag::Compound))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(TagOnly(Tag::Compound))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Ok(TagOnly(Tag::Compound))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Ok(TagOnly(Tag::Compound))
+    }
+}
+
+macro_rules! impl_tag_only {
+    ($($trait:ident::$method:ident),* $(,)?) => {
+        $(
+            impl $trait for TagOnly {
+                type Ok = Option<Tag>;
+                type Error = Error;
+
+                fn $method<T: Serialize + ?Sized>(&mut self, _value: &T) -> Result<()> {
+                    Ok(())
+                }
+
+                fn end(self) -> Result<Self::Ok> {
+                    Ok(Some(self.0))
+                }
+            }
+        )*
+    };
+}
+
+impl_tag_only! {
+    SerializeSeq::serialize_element,
+    SerializeTuple::serialize_element,
+    SerializeTupleStruct::serialize_field,
+    SerializeTupleVariant::serialize_field,
+}
+
+impl SerializeMap for TagOnly {
+    type Ok = Option<Tag>;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, _key: &T) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, _value: &T) -> Result<()> {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(self.0))
+    }
+}
+
+impl SerializeStruct for TagOnly {
+    type Ok = Option<Tag>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        _value: &T,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(self.0))
+    }
+}
+
+impl SerializeStructVariant for TagOnly {
+    type Ok = Option<Tag>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        _value: &T,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(Some(self.0))
+    }
+}
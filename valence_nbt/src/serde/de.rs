@@ -0,0 +1,245 @@
+use std::iter;
+
+use ::serde::de::value::{MapDeserializer, SeqDeserializer};
+use ::serde::de::{
+    DeserializeOwned, DeserializeSeed, EnumAccess, IgnoredAny, IntoDeserializer, VariantAccess,
+    Visitor,
+};
+use ::serde::{forward_to_deserialize_any, Deserialize, Deserializer};
+
+use super::{array_name, VALUE_NAME};
+use crate::tag::Tag;
+use crate::{Compound, Error, List, Result, Value};
+
+/// Converts a [`Value`] into a `T`.
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T> {
+    T::deserialize(ValueDeserializer::new(value))
+}
+
+struct ValueDeserializer {
+    value: Value,
+    /// Whether arrays should be presented as single-entry maps for
+    /// [`Value`]'s `Deserialize` implementation.
+    as_value: bool,
+}
+
+impl ValueDeserializer {
+    fn new(value: Value) -> Self {
+        Self {
+            value,
+            as_value: false,
+        }
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for ValueDeserializer {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+fn visit_seq<'de, V, I>(values: I, visitor: V) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+    I: IntoIterator,
+    I::Item: Into<Value>,
+{
+    let mut seq =
+        SeqDeserializer::new(values.into_iter().map(|v| ValueDeserializer::new(v.into())));
+    let value = visitor.visit_seq(&mut seq)?;
+    seq.end()?;
+
+    Ok(value)
+}
+
+fn visit_compound<'de, V: Visitor<'de>>(compound: Compound, visitor: V) -> Result<V::Value> {
+    let mut map = MapDeserializer::new(
+        compound
+            .into_iter()
+            .map(|(k, v)| (k, ValueDeserializer::new(v))),
+    );
+    let value = visitor.visit_map(&mut map)?;
+    map.end()?;
+
+    Ok(value)
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer {
+    type Error = Error;
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.as_value {
+            if let Some(name) = array_name(Tag::element_type(&self.value)) {
+                let array = match self.value {
+                    Value::ByteArray(v) => Value::List(List::Byte(v)),
+                    Value::IntArray(v) => Value::List(List::Int(v)),
+                    Value::LongArray(v) => Value::List(List::Long(v)),
+                    _ => unreachable!(),
+                };
+
+                let mut map =
+                    MapDeserializer::new(iter::once((name, ValueDeserializer::new(array))));
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+
+                return Ok(value);
+            }
+        }
+
+        match self.value {
+            Value::Byte(v) => visitor.visit_i8(v),
+            Value::Short(v) => visitor.visit_i16(v),
+            Value::Int(v) => visitor.visit_i32(v),
+            Value::Long(v) => visitor.visit_i64(v),
+            Value::Float(v) => visitor.visit_f32(v),
+            Value::Double(v) => visitor.visit_f64(v),
+            Value::ByteArray(v) => visit_seq(v, visitor),
+            Value::String(v) => visitor.visit_string(v),
+            Value::List(v) => visit_seq(v.into_values(), visitor),
+            Value::Compound(v) => visit_compound(v, visitor),
+            Value::IntArray(v) => visit_seq(v, visitor),
+            Value::LongArray(v) => visit_seq(v, visitor),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::Byte(v) => visitor.visit_bool(v != 0),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::Byte(v) => visitor.visit_u8(v as u8),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::Short(v) => visitor.visit_u16(v as u16),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::Int(v) => visitor.visit_u32(v as u32),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::Long(v) => visitor.visit_u64(v as u64),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            Value::ByteArray(v) => visitor.visit_byte_buf(v.into_iter().map(|b| b as u8).collect()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        // Absent compound fields are the only representation of `None`.
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(Self {
+            as_value: name == VALUE_NAME,
+            ..self
+        })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self.value {
+            Value::String(s) => visitor.visit_enum(s.into_deserializer()),
+            Value::Compound(c) => {
+                let mut entries = c.into_iter();
+
+                match (entries.next(), entries.next()) {
+                    (Some((variant, value)), None) => {
+                        visitor.visit_enum(EnumDeserializer { variant, value })
+                    }
+                    _ => Err(Error::new_static(
+                        "enum variant compound must have exactly one entry",
+                    )),
+                }
+            }
+            v => Err(Error::new_owned(format!(
+                "expected string or compound for enum (got {})",
+                Tag::element_type(&v)
+            ))),
+        }
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+/// Deserializes an enum variant stored as a compound with a single entry.
+struct EnumDeserializer {
+    variant: String,
+    value: Value,
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer {
+    type Error = Error;
+    type Variant = ValueDeserializer;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self::Variant)> {
+        let variant =
+            seed.deserialize(IntoDeserializer::<Error>::into_deserializer(self.variant))?;
+        Ok((variant, ValueDeserializer::new(self.value)))
+    }
+}
+
+impl<'de> VariantAccess<'de> for ValueDeserializer {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        IgnoredAny::deserialize(self).map(|_| ())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_map(visitor)
+    }
+}
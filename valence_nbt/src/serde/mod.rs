@@ -0,0 +1,390 @@
+//! Conversion between NBT and types implementing [serde]'s `Serialize` and
+//! `Deserialize` traits.
+//!
+//! This module is only available with the `serde` feature enabled.
+//!
+//! Types are mapped to NBT as follows:
+//!
+//! - `bool` is a byte. Unsigned integers are stored in the signed NBT type of
+//!   the same size, so `u8` is a byte with `255_u8` stored as `-1_i8`.
+//! - Strings, chars and unit enum variants are strings.
+//! - Sequences and tuples are lists. Lists must be homogeneous and their length
+//!   must be known up front.
+//! - Structs and maps with string keys are compounds. Fields set to `None` are
+//!   omitted.
+//! - Other enum variants are compounds with a single field named after the
+//!   variant.
+//!
+//! Sequences are lists by default. Use the [`byte_array`], [`int_array`] or
+//! [`long_array`] modules with `#[serde(with = "...")]` to encode them as
+//! arrays instead. Byte slices serialized with `serialize_bytes` are always
+//! byte arrays.
+//!
+//! # Examples
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//! use valence_nbt::serde::to_value;
+//! use valence_nbt::{compound, Value};
+//!
+//! #[derive(Serialize, Deserialize, PartialEq, Debug)]
+//! struct Player {
+//!     name: String,
+//!     health: f32,
+//!     #[serde(with = "valence_nbt::serde::int_array")]
+//!     uuid: [i32; 4],
+//! }
+//!
+//! let player = Player {
+//!     name: "Steve".into(),
+//!     health: 20.0,
+//!     uuid: [1, 2, 3, 4],
+//! };
+//!
+//! let expected = compound! {
+//!     "name" => "Steve",
+//!     "health" => 20.0_f32,
+//!     "uuid" => vec![1, 2, 3, 4],
+//! };
+//!
+//! assert_eq!(
+//!     to_value(&player).unwrap(),
+//!     Value::Compound(expected.clone())
+//! );
+//!
+//! let mut buf = vec![];
+//! valence_nbt::serde::to_binary_writer(&mut buf, &player, "").unwrap();
+//!
+//! let (decoded, _) = valence_nbt::from_binary_slice(&mut buf.as_slice()).unwrap();
+//! assert_eq!(decoded, expected);
+//!
+//! let (player2, _) =
+//!     valence_nbt::serde::from_binary_slice::<Player>(&mut buf.as_slice()).unwrap();
+//! assert_eq!(player, player2);
+//! ```
+//!
+//! [serde]: https://serde.rs
+
+use std::fmt;
+
+use ::serde::de::{MapAccess, SeqAccess, Visitor};
+use ::serde::ser::{SerializeMap, SerializeSeq};
+use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+pub use self::binary_de::from_binary_slice;
+pub use self::binary_ser::to_binary_writer;
+pub use self::de::from_value;
+pub use self::ser::to_value;
+use crate::tag::Tag;
+use crate::{Compound, Error, List, Value};
+
+/// Implements `Serializer` methods which always fail with the given message.
+macro_rules! unsupported {
+    ($msg:expr; $($method:ident($($arg:ty),*)),* $(,)?) => {
+        $(
+            fn $method(self, $(_: $arg),*) -> $crate::Result<Self::Ok> {
+                Err($crate::Error::new_static($msg))
+            }
+        )*
+    };
+}
+
+mod binary_de;
+mod binary_ser;
+mod de;
+mod ser;
+
+/// Newtype struct names used to request array encodings from the serializers
+/// in this module.
+const BYTE_ARRAY_NAME: &str = "__valence_nbt_byte_array";
+const INT_ARRAY_NAME: &str = "__valence_nbt_int_array";
+const LONG_ARRAY_NAME: &str = "__valence_nbt_long_array";
+
+/// Newtype struct name used by [`Value`]'s `Deserialize` implementation. The
+/// deserializers in this module present arrays as a single-entry map keyed by
+/// the array name when they see it, so that arrays are not mistaken for lists.
+const VALUE_NAME: &str = "__valence_nbt_value";
+
+/// Returns the array tag requested by a newtype struct name, if any.
+fn array_tag(name: &str) -> Option<Tag> {
+    match name {
+        BYTE_ARRAY_NAME => Some(Tag::ByteArray),
+        INT_ARRAY_NAME => Some(Tag::IntArray),
+        LONG_ARRAY_NAME => Some(Tag::LongArray),
+        _ => None,
+    }
+}
+
+/// Returns the newtype struct name for an array tag.
+fn array_name(tag: Tag) -> Option<&'static str> {
+    match tag {
+        Tag::ByteArray => Some(BYTE_ARRAY_NAME),
+        Tag::IntArray => Some(INT_ARRAY_NAME),
+        Tag::LongArray => Some(LONG_ARRAY_NAME),
+        _ => None,
+    }
+}
+
+macro_rules! array_module {
+    ($module:ident, $name:ident, $desc:literal) => {
+        #[doc = concat!("Encodes a sequence as an NBT ", $desc, " instead of a list.")]
+        ///
+        /// Use with `#[serde(with = "...")]`. Deserialization accepts any
+        /// sequence, so this is only needed for serialization.
+        pub mod $module {
+            use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+            /// Serializes the sequence as an array.
+            pub fn serialize<T, S>(array: &T, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                T: Serialize + ?Sized,
+                S: Serializer,
+            {
+                serializer.serialize_newtype_struct(super::$name, array)
+            }
+
+            /// Deserializes the sequence normally.
+            pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+            where
+                T: Deserialize<'de>,
+                D: Deserializer<'de>,
+            {
+                T::deserialize(deserializer)
+            }
+        }
+    };
+}
+
+array_module!(byte_array, BYTE_ARRAY_NAME, "byte array");
+array_module!(int_array, INT_ARRAY_NAME, "int array");
+array_module!(long_array, LONG_ARRAY_NAME, "long array");
+
+impl ::serde::ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::new_owned(msg.to_string())
+    }
+}
+
+impl ::serde::de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::new_owned(msg.to_string())
+    }
+}
+
+/// Serializes an array as a newtype struct so the array type is preserved.
+struct Array<'a, T>(&'static str, &'a [T]);
+
+impl<T: Serialize> Serialize for Array<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(self.0, self.1)
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Byte(v) => serializer.serialize_i8(*v),
+            Value::Short(v) => serializer.serialize_i16(*v),
+            Value::Int(v) => serializer.serialize_i32(*v),
+            Value::Long(v) => serializer.serialize_i64(*v),
+            Value::Float(v) => serializer.serialize_f32(*v),
+            Value::Double(v) => serializer.serialize_f64(*v),
+            Value::ByteArray(v) => Array(BYTE_ARRAY_NAME, v).serialize(serializer),
+            Value::String(v) => serializer.serialize_str(v),
+            Value::List(v) => v.serialize(serializer),
+            Value::Compound(v) => v.serialize(serializer),
+            Value::IntArray(v) => Array(INT_ARRAY_NAME, v).serialize(serializer),
+            Value::LongArray(v) => Array(LONG_ARRAY_NAME, v).serialize(serializer),
+        }
+    }
+}
+
+impl Serialize for List {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        fn arrays<S: Serializer, T: Serialize>(
+            serializer: S,
+            name: &'static str,
+            arrays: &[Vec<T>],
+        ) -> Result<S::Ok, S::Error> {
+            let mut seq = serializer.serialize_seq(Some(arrays.len()))?;
+            for array in arrays {
+                seq.serialize_element(&Array(name, array))?;
+            }
+            seq.end()
+        }
+
+        match self {
+            List::Byte(l) => l.serialize(serializer),
+            List::Short(l) => l.serialize(serializer),
+            List::Int(l) => l.serialize(serializer),
+            List::Long(l) => l.serialize(serializer),
+            List::Float(l) => l.serialize(serializer),
+            List::Double(l) => l.serialize(serializer),
+            List::ByteArray(l) => arrays(serializer, BYTE_ARRAY_NAME, l),
+            List::String(l) => l.serialize(serializer),
+            List::List(l) => l.serialize(serializer),
+            List::Compound(l) => l.serialize(serializer),
+            List::IntArray(l) => arrays(serializer, INT_ARRAY_NAME, l),
+            List::LongArray(l) => arrays(serializer, LONG_ARRAY_NAME, l),
+        }
+    }
+}
+
+impl Serialize for Compound {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (k, v) in self {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_newtype_struct(VALUE_NAME, ValueVisitor)
+    }
+}
+
+impl<'de> Deserialize<'de> for List {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::List(l) => Ok(l),
+            v => Err(::serde::de::Error::custom(format!(
+                "expected list (got {})",
+                Tag::element_type(&v)
+            ))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Compound {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Value::deserialize(deserializer)? {
+            Value::Compound(c) => Ok(c),
+            v => Err(::serde::de::Error::custom(format!(
+                "expected compound (got {})",
+                Tag::element_type(&v)
+            ))),
+        }
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a valid NBT value")
+    }
+
+    fn visit_bool<E: ::serde::de::Error>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Byte(v as i8))
+    }
+
+    fn visit_i8<E: ::serde::de::Error>(self, v: i8) -> Result<Value, E> {
+        Ok(Value::Byte(v))
+    }
+
+    fn visit_i16<E: ::serde::de::Error>(self, v: i16) -> Result<Value, E> {
+        Ok(Value::Short(v))
+    }
+
+    fn visit_i32<E: ::serde::de::Error>(self, v: i32) -> Result<Value, E> {
+        Ok(Value::Int(v))
+    }
+
+    fn visit_i64<E: ::serde::de::Error>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Long(v))
+    }
+
+    fn visit_u8<E: ::serde::de::Error>(self, v: u8) -> Result<Value, E> {
+        Ok(Value::Byte(v as i8))
+    }
+
+    fn visit_u16<E: ::serde::de::Error>(self, v: u16) -> Result<Value, E> {
+        Ok(Value::Short(v as i16))
+    }
+
+    fn visit_u32<E: ::serde::de::Error>(self, v: u32) -> Result<Value, E> {
+        Ok(Value::Int(v as i32))
+    }
+
+    fn visit_u64<E: ::serde::de::Error>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::Long(v as i64))
+    }
+
+    fn visit_f32<E: ::serde::de::Error>(self, v: f32) -> Result<Value, E> {
+        Ok(Value::Float(v))
+    }
+
+    fn visit_f64<E: ::serde::de::Error>(self, v: f64) -> Result<Value, E> {
+        Ok(Value::Double(v))
+    }
+
+    fn visit_str<E: ::serde::de::Error>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_owned()))
+    }
+
+    fn visit_string<E: ::serde::de::Error>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_bytes<E: ::serde::de::Error>(self, v: &[u8]) -> Result<Value, E> {
+        Ok(Value::ByteArray(v.iter().map(|&b| b as i8).collect()))
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut values: Vec<Value> = vec![];
+
+        while let Some(v) = seq.next_element()? {
+            if let Some(first) = values.first() {
+                if Tag::element_type(first) != Tag::element_type(&v) {
+                    return Err(::serde::de::Error::custom(format!(
+                        "cannot insert {} into list of {}",
+                        Tag::element_type(&v),
+                        Tag::element_type(first)
+                    )));
+                }
+            }
+
+            values.push(v);
+        }
+
+        Ok(Value::List(List::from_values(values)))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let Some(first_key) = map.next_key::<String>()? else {
+            return Ok(Value::Compound(Compound::new()));
+        };
+
+        match first_key.as_str() {
+            BYTE_ARRAY_NAME => return Ok(Value::ByteArray(map.next_value()?)),
+            INT_ARRAY_NAME => return Ok(Value::IntArray(map.next_value()?)),
+            LONG_ARRAY_NAME => return Ok(Value::LongArray(map.next_value()?)),
+            _ => {}
+        }
+
+        let mut compound = Compound::new();
+        compound.insert(first_key, map.next_value::<Value>()?);
+
+        while let Some((k, v)) = map.next_entry::<String, Value>()? {
+            compound.insert(k, v);
+        }
+
+        Ok(Value::Compound(compound))
+    }
+}
@@ -0,0 +1,526 @@
+//! Stringified NBT (SNBT), the textual NBT format used by commands, data packs
+//! and debugging output.
+//!
+//! The syntax follows the vanilla parser. Numbers use typed suffixes (`1b`,
+//! `2s`, `3L`, `4.5f`, `6.7d`), arrays are written as `[B;1b,2b]`, `[I;1,2]`
+//! and `[L;1L,2L]`, and strings and compound keys may be unquoted if they only
+//! contain the characters `0-9 A-Z a-z _ - . +`.
+
+use std::fmt;
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::decode::MAX_DEPTH;
+use crate::tag::Tag;
+use crate::{Compound, Error, List, Result, Value};
+
+/// Parses a value from its SNBT representation.
+///
+/// # Examples
+///
+/// ```
+/// use valence_nbt::{compound, from_snbt_str, List, Value};
+///
+/// let value = from_snbt_str("{a:1b,b:[I;1,2],c:[3.5f],'d e':\"foo\"}").unwrap();
+///
+/// assert_eq!(
+///     value,
+///     Value::Compound(compound! {
+///         "a" => 1_i8,
+///         "b" => vec![1, 2],
+///         "c" => List::Float(vec![3.5]),
+///         "d e" => "foo",
+///     })
+/// );
+/// ```
+pub fn from_snbt_str(snbt: &str) -> Result<Value> {
+    let mut reader = SnbtReader {
+        input: snbt,
+        pos: 0,
+        depth: 0,
+    };
+
+    let value = reader.read_value()?;
+
+    reader.skip_whitespace();
+    if reader.pos != snbt.len() {
+        return Err(reader.error("trailing data"));
+    }
+
+    Ok(value)
+}
+
+/// Converts a value to its SNBT representation.
+///
+/// The output is compact and can be parsed back with [`from_snbt_str`].
+///
+/// # Examples
+///
+/// ```
+/// use valence_nbt::{compound, to_snbt_string, Value};
+///
+/// let c = compound! {
+///     "byte" => 5_i8,
+///     "string" => "hello",
+/// };
+///
+/// assert_eq!(
+///     to_snbt_string(&Value::Compound(c)),
+///     r#"{byte:5b,string:"hello"}"#
+/// );
+/// ```
+pub fn to_snbt_string(value: &Value) -> String {
+    value.to_string()
+}
+
+impl fmt::Display for Value {
+    /// Writes the SNBT representation of this value.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Byte(b) => write!(f, "{b}b"),
+            Value::Short(s) => write!(f, "{s}s"),
+            Value::Int(i) => write!(f, "{i}"),
+            Value::Long(l) => write!(f, "{l}L"),
+            Value::Float(v) => write!(f, "{v:?}f"),
+            Value::Double(d) => write!(f, "{d:?}d"),
+            Value::ByteArray(ba) => write_array(f, "B;", ba, |f, b| write!(f, "{b}B")),
+            Value::String(s) => write_quoted(f, s),
+            Value::List(l) => l.fmt(f),
+            Value::Compound(c) => c.fmt(f),
+            Value::IntArray(ia) => write_array(f, "I;", ia, |f, i| write!(f, "{i}")),
+            Value::LongArray(la) => write_array(f, "L;", la, |f, l| write!(f, "{l}L")),
+        }
+    }
+}
+
+impl fmt::Display for List {
+    /// Writes the SNBT representation of this list.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            List::Byte(l) => write_array(f, "", l, |f, b| write!(f, "{b}b")),
+            List::Short(l) => write_array(f, "", l, |f, s| write!(f, "{s}s")),
+            List::Int(l) => write_array(f, "", l, |f, i| write!(f, "{i}")),
+            List::Long(l) => write_array(f, "", l, |f, l| write!(f, "{l}L")),
+            List::Float(l) => write_array(f, "", l, |f, v| write!(f, "{v:?}f")),
+            List::Double(l) => write_array(f, "", l, |f, d| write!(f, "{d:?}d")),
+            List::ByteArray(l) => write_array(f, "", l, |f, ba| {
+                write_array(f, "B;", ba, |f, b| write!(f, "{b}B"))
+            }),
+            List::String(l) => write_array(f, "", l, |f, s| write_quoted(f, s)),
+            List::List(l) => write_array(f, "", l, |f, l| l.fmt(f)),
+            List::Compound(l) => write_array(f, "", l, |f, c| c.fmt(f)),
+            List::IntArray(l) => write_array(f, "", l, |f, ia| {
+                write_array(f, "I;", ia, |f, i| write!(f, "{i}"))
+            }),
+            List::LongArray(l) => write_array(f, "", l, |f, la| {
+                write_array(f, "L;", la, |f, l| write!(f, "{l}L"))
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Compound {
+    /// Writes the SNBT representation of this compound.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('{')?;
+
+        for (i, (k, v)) in self.iter().enumerate() {
+            if i != 0 {
+                f.write_char(',')?;
+            }
+
+            if !k.is_empty() && k.chars().all(is_unquoted_char) {
+                f.write_str(k)?;
+            } else {
+                write_quoted(f, k)?;
+            }
+
+            write!(f, ":{v}")?;
+        }
+
+        f.write_char('}')
+    }
+}
+
+impl FromStr for Value {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        from_snbt_str(s)
+    }
+}
+
+impl FromStr for Compound {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match from_snbt_str(s)? {
+            Value::Compound(c) => Ok(c),
+            v => Err(Error::new_owned(format!(
+                "expected compound (got {})",
+                Tag::element_type(&v)
+            ))),
+        }
+    }
+}
+
+fn write_array<T>(
+    f: &mut fmt::Formatter,
+    prefix: &str,
+    elems: &[T],
+    mut write_elem: impl FnMut(&mut fmt::Formatter, &T) -> fmt::Result,
+) -> fmt::Result {
+    write!(f, "[{prefix}")?;
+
+    for (i, elem) in elems.iter().enumerate() {
+        if i != 0 {
+            f.write_char(',')?;
+        }
+        write_elem(f, elem)?;
+    }
+
+    f.write_char(']')
+}
+
+/// Writes a quoted and escaped string. Double quotes are used unless the
+/// string contains double quotes but no single quotes.
+pub(crate) fn write_quoted(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    let quote = if s.contains('"') && !s.contains('\'') {
+        '\''
+    } else {
+        '"'
+    };
+
+    f.write_char(quote)?;
+
+    for c in s.chars() {
+        if c == quote || c == '\\' {
+            f.write_char('\\')?;
+        }
+        f.write_char(c)?;
+    }
+
+    f.write_char(quote)
+}
+
+pub(crate) fn is_unquoted_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+}
+
+pub(crate) struct SnbtReader<'a> {
+    pub(crate) input: &'a str,
+    /// Byte offset of the next character.
+    pub(crate) pos: usize,
+    /// Current recursion depth.
+    pub(crate) depth: usize,
+}
+
+impl<'a> SnbtReader<'a> {
+    pub(crate) fn error(&self, msg: &str) -> Error {
+        Error::new_owned(format!("{msg} at position {}", self.pos))
+    }
+
+    pub(crate) fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        self.skip_whitespace();
+
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{c}'")))
+        }
+    }
+
+    /// Consumes a `,` if one follows. Returns whether there may be another
+    /// element.
+    fn element_separator(&mut self) -> bool {
+        self.skip_whitespace();
+
+        if self.peek() == Some(',') {
+            self.pos += 1;
+            self.skip_whitespace();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn check_depth<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error("reached maximum recursion depth"));
+        }
+
+        self.depth += 1;
+        let res = f(self);
+        self.depth -= 1;
+        res
+    }
+
+    fn read_value(&mut self) -> Result<Value> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('{') => self.check_depth(|r| Ok(r.read_compound()?.into())),
+            Some('[') => self.check_depth(|r| r.read_list_or_array()),
+            Some('"' | '\'') => Ok(self.read_quoted_string()?.into()),
+            Some(_) => {
+                let start = self.pos;
+                let s = self.read_unquoted_string();
+
+                if s.is_empty() {
+                    self.pos = start;
+                    return Err(self.error("expected value"));
+                }
+
+                Ok(parse_unquoted(s))
+            }
+            None => Err(self.error("expected value")),
+        }
+    }
+
+    fn read_unquoted_string(&mut self) -> &'a str {
+        let rest = &self.input[self.pos..];
+        let len = rest.find(|c| !is_unquoted_char(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    pub(crate) fn read_quoted_string(&mut self) -> Result<String> {
+        let Some(quote) = self.peek() else {
+            return Err(self.error("expected quoted string"));
+        };
+        self.pos += 1;
+
+        let mut s = String::new();
+        let mut escaped = false;
+
+        for c in self.input[self.pos..].chars() {
+            self.pos += c.len_utf8();
+
+            if escaped {
+                if c != quote && c != '\\' {
+                    return Err(self.error(&format!("invalid escape sequence '\\{c}'")));
+                }
+                s.push(c);
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == quote {
+                return Ok(s);
+            } else {
+                s.push(c);
+            }
+        }
+
+        Err(self.error("unclosed quoted string"))
+    }
+
+    fn read_key(&mut self) -> Result<String> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some('"' | '\'') => self.read_quoted_string(),
+            _ => match self.read_unquoted_string() {
+                "" => Err(self.error("expected key")),
+                key => Ok(key.to_owned()),
+            },
+        }
+    }
+
+    pub(crate) fn read_compound(&mut self) -> Result<Compound> {
+        self.expect('{')?;
+        self.skip_whitespace();
+
+        let mut compound = Compound::new();
+
+        while self.peek() != Some('}') {
+            let key = self.read_key()?;
+            self.expect(':')?;
+            let value = self.read_value()?;
+            compound.insert(key, value);
+
+            if !self.element_separator() {
+                break;
+            }
+        }
+
+        self.expect('}')?;
+
+        Ok(compound)
+    }
+
+    fn read_list_or_array(&mut self) -> Result<Value> {
+        let rest = &self.input[self.pos..];
+
+        if let [b'[', kind @ (b'B' | b'I' | b'L'), b';', ..] = rest.as_bytes() {
+            let kind = *kind;
+            self.pos += 3;
+            return self.read_array(kind);
+        }
+
+        self.expect('[')?;
+        self.skip_whitespace();
+
+        let mut values = vec![];
+        while self.peek() != Some(']') {
+            let start = self.pos;
+            let value = self.read_value()?;
+
+            if let Some(first) = values.first() {
+                if Tag::element_type(first) != Tag::element_type(&value) {
+                    self.pos = start;
+                    return Err(self.error(&format!(
+                        "cannot insert {} into list of {}",
+                        Tag::element_type(&value),
+                        Tag::element_type(first)
+                    )));
+                }
+            }
+
+            values.push(value);
+
+            if !self.element_separator() {
+                break;
+            }
+        }
+
+        self.expect(']')?;
+
+        Ok(Value::List(List::from_values(values)))
+    }
+
+    /// Assumes the `[B;`, `[I;` or `[L;` prefix has already been read.
+    fn read_array(&mut self, kind: u8) -> Result<Value> {
+        let mut bytes = vec![];
+        let mut ints = vec![];
+        let mut longs = vec![];
+
+        self.skip_whitespace();
+
+        while self.peek() != Some(']') {
+            let start = self.pos;
+
+            match (kind, self.read_value()?) {
+                (b'B', Value::Byte(b)) => bytes.push(b),
+                (b'I', Value::Int(i)) => ints.push(i),
+                (b'L', Value::Long(l)) => longs.push(l),
+                (_, v) => {
+                    self.pos = start;
+                    return Err(self.error(&format!(
+                        "cannot insert {} into {} array",
+                        Tag::element_type(&v),
+                        match kind {
+                            b'B' => "byte",
+                            b'I' => "int",
+                            _ => "long",
+                        }
+                    )));
+                }
+            }
+
+            if !self.element_separator() {
+                break;
+            }
+        }
+
+        self.expect(']')?;
+
+        Ok(match kind {
+            b'B' => Value::ByteArray(bytes),
+            b'I' => Value::IntArray(ints),
+            _ => Value::LongArray(longs),
+        })
+    }
+}
+
+/// Determines the type of an unquoted token. Tokens which do not look like
+/// numbers or overflow their type are strings.
+fn parse_unquoted(s: &str) -> Value {
+    if s.eq_ignore_ascii_case("true") {
+        return Value::Byte(1);
+    }
+
+    if s.eq_ignore_ascii_case("false") {
+        return Value::Byte(0);
+    }
+
+    if let Some(last) = s.chars().last() {
+        let body = &s[..s.len() - 1];
+
+        let value = match last.to_ascii_lowercase() {
+            'b' if is_integer(body) => body.parse().ok().map(Value::Byte),
+            's' if is_integer(body) => body.parse().ok().map(Value::Short),
+            'l' if is_integer(body) => body.parse().ok().map(Value::Long),
+            'f' if is_decimal(body, false) => body.parse().ok().map(Value::Float),
+            'd' if is_decimal(body, false) => body.parse().ok().map(Value::Double),
+            _ => None,
+        };
+
+        if let Some(value) = value {
+            return value;
+        }
+    }
+
+    if is_integer(s) {
+        if let Ok(i) = s.parse() {
+            return Value::Int(i);
+        }
+    }
+
+    if is_decimal(s, true) {
+        if let Ok(d) = s.parse() {
+            return Value::Double(d);
+        }
+    }
+
+    Value::String(s.to_owned())
+}
+
+/// Matches `[-+]?(0|[1-9][0-9]*)`.
+fn is_integer(s: &str) -> bool {
+    let digits = s.strip_prefix(['-', '+']).unwrap_or(s);
+
+    match digits.as_bytes() {
+        [b'0'] => true,
+        [b'1'..=b'9', rest @ ..] => rest.iter().all(u8::is_ascii_digit),
+        _ => false,
+    }
+}
+
+/// Matches `[-+]?([0-9]+[.]?|[0-9]*[.][0-9]+)(e[-+]?[0-9]+)?`. If
+/// `require_point` is set, the decimal point is mandatory.
+fn is_decimal(s: &str, require_point: bool) -> bool {
+    let s = s.strip_prefix(['-', '+']).unwrap_or(s);
+
+    let (mantissa, exponent) = match s.find(['e', 'E']) {
+        Some(i) => (&s[..i], Some(&s[i + 1..])),
+        None => (s, None),
+    };
+
+    if let Some(exp) = exponent {
+        let exp = exp.strip_prefix(['-', '+']).unwrap_or(exp);
+        if exp.is_empty() || !exp.bytes().all(|b| b.is_ascii_digit()) {
+            return false;
+        }
+    }
+
+    match mantissa.split_once('.') {
+        Some((int, frac)) => {
+            int.bytes().all(|b| b.is_ascii_digit())
+                && frac.bytes().all(|b| b.is_ascii_digit())
+                && !(int.is_empty() && frac.is_empty())
+        }
+        None => {
+            !require_point && !mantissa.is_empty() && mantissa.bytes().all(|b| b.is_ascii_digit())
+        }
+    }
+}
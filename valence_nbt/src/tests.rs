@@ -1,5 +1,11 @@
+use std::borrow::Cow;
+
 use crate::tag::Tag;
-use crate::{compound, from_binary_slice, to_binary_writer, Compound, List, Value};
+use crate::{
+    compound, from_binary_reader, from_binary_reader_with_limits, from_binary_slice,
+    from_binary_slice_ref, from_snbt_str, to_binary_writer, to_snbt_string, Compound, DecodeLimits,
+    List, NbtPath, Value, ValueRef,
+};
 
 const ROOT_NAME: &str = "The root name‽";
 
@@ -19,6 +25,177 @@ fn round_trip() {
     assert_eq!(compound, decoded);
 }
 
+#[test]
+fn reader_round_trip() {
+    let mut buf = Vec::new();
+
+    let compound = example_compound();
+
+    to_binary_writer(&mut buf, &compound, ROOT_NAME).unwrap();
+
+    // Trailing data is left unread.
+    buf.extend_from_slice(&[1, 2, 3]);
+
+    let mut reader = buf.as_slice();
+    let (decoded, root_name) = from_binary_reader(&mut reader).unwrap();
+
+    assert_eq!(root_name, ROOT_NAME);
+    assert_eq!(compound, decoded);
+    assert_eq!(reader, [1, 2, 3]);
+}
+
+#[test]
+fn reader_limits() {
+    let mut buf = Vec::new();
+    to_binary_writer(&mut buf, &example_compound(), ROOT_NAME).unwrap();
+
+    let exact = DecodeLimits {
+        max_bytes: buf.len() as u64,
+        ..Default::default()
+    };
+    assert!(from_binary_reader_with_limits(buf.as_slice(), exact).is_ok());
+
+    let too_small = DecodeLimits {
+        max_bytes: buf.len() as u64 - 1,
+        ..Default::default()
+    };
+    assert!(from_binary_reader_with_limits(buf.as_slice(), too_small).is_err());
+
+    let shallow = DecodeLimits {
+        max_depth: 1,
+        ..Default::default()
+    };
+    assert!(from_binary_reader_with_limits(buf.as_slice(), shallow).is_err());
+
+    // Huge array lengths are rejected before anything is allocated.
+    let huge_array = [10, 0, 0, 11, 0, 0, 0x7f, 0xff, 0xff, 0xff];
+    assert!(from_binary_reader(huge_array.as_slice()).is_err());
+
+    // Without a size limit, huge arrays fail once the input runs out.
+    let huge_array = [10, 0, 0, 7, 0, 0, 0x7f, 0xff, 0xff, 0xff];
+    assert!(
+        from_binary_reader_with_limits(huge_array.as_slice(), DecodeLimits::UNLIMITED).is_err()
+    );
+
+    // Truncated input.
+    assert!(from_binary_reader(&buf[..buf.len() - 1]).is_err());
+}
+
+#[test]
+fn slice_ref_round_trip() {
+    let mut buf = Vec::new();
+
+    let compound = example_compound();
+
+    to_binary_writer(&mut buf, &compound, ROOT_NAME).unwrap();
+    buf.extend_from_slice(&[1, 2, 3]);
+
+    let mut slice = buf.as_slice();
+    let (root, root_name) = from_binary_slice_ref(&mut slice).unwrap();
+
+    assert_eq!(root_name.to_str().unwrap(), ROOT_NAME);
+    assert_eq!(root.to_compound().unwrap(), compound);
+    assert_eq!(slice, [1, 2, 3]);
+
+    assert_eq!(root.get("byte"), Some(ValueRef::Byte(123)));
+    assert_eq!(root.get("missing"), None);
+
+    let Some(ValueRef::String(s)) = root.get("string") else {
+        panic!("expected string")
+    };
+    assert!(matches!(s.to_str().unwrap(), Cow::Borrowed("aé日")));
+
+    let Some(ValueRef::List(list)) = root.get("list_of_int") else {
+        panic!("expected list")
+    };
+    assert_eq!(list.len(), 3);
+    assert_eq!(list.get(1), Some(ValueRef::Int(-7)));
+    assert_eq!(list.get(3), None);
+
+    let Some(ValueRef::List(list)) = root.get("list_of_compound") else {
+        panic!("expected list")
+    };
+    let Some(ValueRef::Compound(inner)) = list.get(2) else {
+        panic!("expected compound")
+    };
+    assert_eq!(inner.get("long"), Some(ValueRef::Long(i64::MAX)));
+
+    let Some(ValueRef::LongArray(array)) = root.get("long_array") else {
+        panic!("expected long array")
+    };
+    assert_eq!(array.len(), 3);
+    assert_eq!(array.get(2), Some(789));
+    assert_eq!(array.iter().rev().collect::<Vec<_>>(), [789, 456, 123]);
+
+    // Names which differ between UTF-8 and modified UTF-8.
+    let c = compound! { "a\0b" => 1, "😀" => 2 };
+    buf.clear();
+    to_binary_writer(&mut buf, &c, "").unwrap();
+
+    let (root, _) = from_binary_slice_ref(&mut buf.as_slice()).unwrap();
+    assert_eq!(root.get("a\0b"), Some(ValueRef::Int(1)));
+    assert_eq!(root.get("😀"), Some(ValueRef::Int(2)));
+}
+
+#[test]
+fn slice_ref_errors() {
+    let mut buf = Vec::new();
+    to_binary_writer(&mut buf, &example_compound(), ROOT_NAME).unwrap();
+
+    // Every truncation of the input is rejected up front.
+    for len in 0..buf.len() {
+        assert!(from_binary_slice_ref(&mut &buf[..len]).is_err());
+    }
+
+    let huge_array = [10, 0, 0, 12, 0, 0, 0x7f, 0xff, 0xff, 0xff];
+    assert!(from_binary_slice_ref(&mut huge_array.as_slice()).is_err());
+
+    let negative_list = [10, 0, 0, 9, 0, 0, 1, 0xff, 0xff, 0xff, 0xff, 0];
+    assert!(from_binary_slice_ref(&mut negative_list.as_slice()).is_err());
+
+    // Invalid strings are only detected once they are decoded.
+    let bad_string = [10, 0, 0, 8, 0, 0, 0, 1, 0xff, 0];
+    let (root, _) = from_binary_slice_ref(&mut bad_string.as_slice()).unwrap();
+    assert!(root.to_compound().is_err());
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn compressed_round_trip() {
+    use crate::compression::{from_compressed_reader, to_compressed_writer, Compression};
+
+    let compound = example_compound();
+
+    for compression in [Compression::Gzip, Compression::Zlib, Compression::None] {
+        assert_eq!(
+            Compression::from_region_id(compression.region_id()),
+            Some(compression)
+        );
+
+        let mut buf = Vec::new();
+        to_compressed_writer(&mut buf, &compound, ROOT_NAME, compression).unwrap();
+
+        let (decoded, root_name) =
+            from_compressed_reader(buf.as_slice(), compression, DecodeLimits::default()).unwrap();
+
+        assert_eq!(root_name, ROOT_NAME);
+        assert_eq!(compound, decoded);
+    }
+
+    // The size limit applies to the decompressed data.
+    let big = compound! {
+        "zeros" => vec![0_i64; 1024 * 1024],
+    };
+
+    let mut buf = Vec::new();
+    to_compressed_writer(&mut buf, &big, "", Compression::Gzip).unwrap();
+    assert!(buf.len() < 64 * 1024);
+
+    assert!(
+        from_compressed_reader(buf.as_slice(), Compression::Gzip, DecodeLimits::default()).is_err()
+    );
+}
+
 #[test]
 fn check_min_sizes() {
     fn check(min_val: Value, expected_size: usize) {
@@ -66,6 +243,14 @@ fn deeply_nested_compound_decode() {
 
     // Should not overflow the stack
     let _ = from_binary_slice(&mut buf.as_slice());
+    let _ = from_binary_slice_ref(&mut buf.as_slice());
+
+    // Depth limits above the maximum are clamped.
+    let deep = DecodeLimits {
+        max_depth: usize::MAX,
+        ..DecodeLimits::UNLIMITED
+    };
+    assert!(from_binary_reader_with_limits(buf.as_slice(), deep).is_err());
 }
 
 #[test]
@@ -85,6 +270,7 @@ fn deeply_nested_list_decode() {
 
     // Should not overflow the stack
     let _ = from_binary_slice(&mut buf.as_slice());
+    let _ = from_binary_slice_ref(&mut buf.as_slice());
 }
 
 #[test]
@@ -142,3 +328,375 @@ fn example_compound() -> Compound {
         "long_array" => vec![123_i64, 456, 789],
     }
 }
+
+#[test]
+fn snbt_round_trip() {
+    let c = compound! {
+        "byte" => -5_i8,
+        "short" => 300_i16,
+        "int" => i32::MIN,
+        "long" => i64::MAX,
+        "float" => 1e10_f32,
+        "double" => -0.5_f64,
+        "byte_array" => vec![0_i8, 2, 3],
+        "int_array" => vec![5, -9],
+        "long_array" => Vec::<i64>::new(),
+        "string" => "aé日 \"quoted\"",
+        "needs quotes" => "",
+        "list_of_list" => List::List(vec![List::Int(vec![1]), List::String(vec!["2".into()])]),
+        "list_of_compound" => List::Compound(vec![compound!(), compound!("a" => 1)]),
+        "empty_list" => List::Byte(vec![]),
+    };
+
+    let snbt = to_snbt_string(&Value::Compound(c.clone()));
+
+    assert_eq!(from_snbt_str(&snbt).unwrap(), Value::Compound(c));
+}
+
+#[test]
+fn snbt_vanilla_syntax() {
+    let value = from_snbt_str(
+        r#" { a : 1B , b:2s,c:3L,d:4.5F,e:6d,f:7.,g:true,h:1e3,i:foo,j:'it\'s',k:[B; 1b,2b,],l:99999999999} "#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        value,
+        Value::Compound(compound! {
+            "a" => 1_i8,
+            "b" => 2_i16,
+            "c" => 3_i64,
+            "d" => 4.5_f32,
+            "e" => 6_f64,
+            "f" => 7_f64,
+            "g" => 1_i8,
+            "h" => "1e3",
+            "i" => "foo",
+            "j" => "it's",
+            "k" => vec![1_i8, 2],
+            "l" => "99999999999",
+        })
+    );
+
+    assert!(from_snbt_str("[1,2b]").is_err());
+    assert!(from_snbt_str("[I;1,2b]").is_err());
+    assert!(from_snbt_str("{a:1} extra").is_err());
+    assert!(from_snbt_str("{a:'\\n'}").is_err());
+    assert!(from_snbt_str(&"[".repeat(10_000)).is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_value_round_trip() {
+    use crate::serde::{from_value, to_value};
+
+    let compound = example_compound();
+
+    let value = to_value(&compound).unwrap();
+    assert_eq!(value, Value::Compound(compound.clone()));
+    assert_eq!(from_value::<Compound>(value).unwrap(), compound);
+
+    let mut buf = Vec::new();
+    crate::serde::to_binary_writer(&mut buf, &compound, ROOT_NAME).unwrap();
+
+    let mut expected = Vec::new();
+    to_binary_writer(&mut expected, &compound, ROOT_NAME).unwrap();
+    assert_eq!(buf, expected);
+
+    let (decoded, root_name) =
+        crate::serde::from_binary_slice::<Compound>(&mut buf.as_slice()).unwrap();
+
+    assert_eq!(root_name, ROOT_NAME);
+    assert_eq!(decoded, compound);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_derive() {
+    use serde::{Deserialize, Serialize};
+
+    use crate::serde::{from_value, to_value};
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Item<'a> {
+        id: &'a str,
+        count: u8,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        tag: Option<Compound>,
+        enchanted: bool,
+        kind: Kind,
+        #[serde(with = "crate::serde::long_array")]
+        bits: Vec<i64>,
+        lore: Vec<String>,
+    }
+
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    enum Kind {
+        Plain,
+        Potion { effect: String, duration: i32 },
+        Durability(u16),
+    }
+
+    let items = [
+        Item {
+            id: "minecraft:stick",
+            count: 200,
+            tag: None,
+            enchanted: false,
+            kind: Kind::Plain,
+            bits: vec![],
+            lore: vec![],
+        },
+        Item {
+            id: "minecraft:potion",
+            count: 1,
+            tag: Some(compound! { "CustomPotionColor" => 0xff00ff }),
+            enchanted: true,
+            kind: Kind::Potion {
+                effect: "speed".into(),
+                duration: 600,
+            },
+            bits: vec![1, -1],
+            lore: vec!["Fast".into(), "Très".into()],
+        },
+        Item {
+            id: "minecraft:iron_sword",
+            count: 1,
+            tag: None,
+            enchanted: false,
+            kind: Kind::Durability(250),
+            bits: vec![i64::MAX],
+            lore: vec!["Sharp".into()],
+        },
+    ];
+
+    for item in &items {
+        let mut buf = Vec::new();
+        crate::serde::to_binary_writer(&mut buf, item, "").unwrap();
+
+        // The binary encoding matches the encoding of the equivalent value.
+        let (compound, _) = from_binary_slice(&mut buf.as_slice()).unwrap();
+        assert_eq!(Value::Compound(compound.clone()), to_value(item).unwrap());
+
+        let (decoded, _) = crate::serde::from_binary_slice::<Item>(&mut buf.as_slice()).unwrap();
+        assert_eq!(&decoded, item);
+
+        // Strings are owned when converting from a value, so use an owned type.
+        let kind: Kind = from_value(compound.get("kind").unwrap().clone()).unwrap();
+        assert_eq!(kind, item.kind);
+    }
+
+    let value = to_value(&items[1]).unwrap();
+    let Value::Compound(c) = &value else {
+        panic!("expected compound");
+    };
+    assert_eq!(c.get("count"), Some(&Value::Byte(1)));
+    assert_eq!(c.get("enchanted"), Some(&Value::Byte(1)));
+    assert_eq!(c.get("bits"), Some(&Value::LongArray(vec![1, -1])));
+
+    // 200_u8 is stored as a negative byte.
+    let Value::Compound(c) = to_value(&items[0]).unwrap() else {
+        panic!("expected compound");
+    };
+    assert_eq!(c.get("count"), Some(&Value::Byte(-56)));
+    assert!(!c.contains_key("tag"));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_errors() {
+    use crate::serde::to_value;
+
+    // Heterogeneous lists cannot be represented.
+    #[derive(serde::Serialize)]
+    #[serde(untagged)]
+    enum Either {
+        Int(i32),
+        Str(&'static str),
+    }
+
+    assert!(to_value(&[Either::Int(1), Either::Str("a")]).is_err());
+    assert!(to_value(&None::<i32>).is_err());
+    assert!(to_value(&()).is_err());
+
+    let mut buf = Vec::new();
+    assert!(crate::serde::to_binary_writer(&mut buf, &5_i32, "").is_err());
+
+    let mut map = std::collections::BTreeMap::new();
+    map.insert(1, 2);
+    assert!(to_value(&map).is_err());
+}
+
+#[test]
+fn nbt_path_parse() {
+    for s in [
+        "foo",
+        "foo.bar[0]",
+        "foo[-1][]",
+        "Items[{Slot:1b}].tag",
+        "{id:\"minecraft:pig\"}.Passengers[0]",
+        "\"quoted key\".bar{a:1}",
+    ] {
+        let path: NbtPath = s.parse().unwrap();
+        assert_eq!(path.to_string(), s);
+    }
+
+    for s in [
+        "", "foo.", ".foo", "foo..bar", "foo[", "foo[x]", "a.{b:1}", "foo bar",
+    ] {
+        assert!(s.parse::<NbtPath>().is_err(), "{s:?}");
+    }
+}
+
+#[test]
+fn nbt_path_get() {
+    let root = compound! {
+        "display" => compound! {
+            "Lore" => List::String(vec!["a".into(), "b".into(), "c".into()]),
+        },
+        "Items" => List::Compound(vec![
+            compound! { "Slot" => 0_i8, "id" => "stone" },
+            compound! { "Slot" => 1_i8, "id" => "dirt" },
+            compound! { "Slot" => 2_i8, "id" => "dirt" },
+        ]),
+        "ints" => vec![1, 2, 3],
+    };
+
+    let get = |path: &str| path.parse::<NbtPath>().unwrap().get(&root);
+
+    assert_eq!(get("display.Lore[0]").unwrap(), [Value::from("a")]);
+    assert_eq!(get("display.Lore[-1]").unwrap(), [Value::from("c")]);
+    assert_eq!(get("display.Lore[]").unwrap().len(), 3);
+    assert_eq!(get("ints[1]").unwrap(), [Value::Int(2)]);
+    assert_eq!(get("Items[{Slot:1b}].id").unwrap(), [Value::from("dirt")]);
+    assert_eq!(
+        get("Items[{id:\"dirt\"}].Slot").unwrap(),
+        [1_i8.into(), 2_i8.into()]
+    );
+    assert_eq!(
+        get("display{Lore:[\"b\"]}.Lore[1]").unwrap(),
+        [Value::from("b")]
+    );
+    assert_eq!(get("{ints:[I;1,2,3]}.ints[0]").unwrap(), [Value::Int(1)]);
+
+    assert!(get("display.Lore[3]").is_err());
+    assert!(get("display{Lore:[\"d\"]}").is_err());
+    assert!(get("Items[{Slot:3b}]").is_err());
+    assert!(get("missing.Lore").is_err());
+}
+
+#[test]
+fn nbt_path_set_remove_merge() {
+    let mut root = Compound::new();
+
+    let path = |s: &str| s.parse::<NbtPath>().unwrap();
+
+    // Missing parents are created.
+    assert_eq!(path("a.b.c").set(&mut root, 5.into()).unwrap(), 1);
+    assert_eq!(path("a.b.c").set(&mut root, 5.into()).unwrap(), 0);
+    assert_eq!(
+        path("list[{id:1}].count").set(&mut root, 2.into()).unwrap(),
+        1
+    );
+    assert_eq!(
+        root,
+        compound! {
+            "a" => compound! { "b" => compound! { "c" => 5 } },
+            "list" => List::Compound(vec![compound! { "id" => 1, "count" => 2 }]),
+        }
+    );
+
+    // Indexed elements must have the type of the list.
+    assert_eq!(
+        path("a.l")
+            .set(&mut root, List::Int(vec![1, 2]).into())
+            .unwrap(),
+        1
+    );
+    assert_eq!(path("a.l[0]").set(&mut root, "x".into()).unwrap(), 0);
+    assert_eq!(path("a.l[-1]").set(&mut root, 7.into()).unwrap(), 1);
+    assert_eq!(
+        path("a.l").get(&root).unwrap(),
+        [List::Int(vec![1, 7]).into()]
+    );
+
+    // Setting every element may change the type of a list.
+    assert_eq!(path("a.l[]").set(&mut root, "x".into()).unwrap(), 2);
+    assert_eq!(
+        path("a.l").get(&root).unwrap(),
+        [List::String(vec!["x".into(), "x".into()]).into()]
+    );
+
+    // Arrays convert numbers to their element type.
+    assert_eq!(
+        path("a.arr").set(&mut root, vec![1_i64, 2].into()).unwrap(),
+        1
+    );
+    assert_eq!(path("a.arr[0]").set(&mut root, 3_i8.into()).unwrap(), 1);
+    assert_eq!(path("a.arr").get(&root).unwrap(), [vec![3_i64, 2].into()]);
+
+    assert_eq!(path("a.b.c.d").set(&mut root, 1.into()).unwrap(), 0);
+    assert!(path("a.b.c[0].d").set(&mut root, 1.into()).is_err());
+
+    assert_eq!(
+        path("list[{id:1}]")
+            .merge(&mut root, &compound! { "count" => 3, "extra" => 1_i8 })
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        path("list[0]").get(&root).unwrap(),
+        [compound! { "id" => 1, "count" => 3, "extra" => 1_i8 }.into()]
+    );
+    assert!(path("a.b.c").merge(&mut root, &Compound::new()).is_err());
+
+    // Failed merges leave the compound unchanged.
+    let mut mixed = compound! {
+        "l" => List::Compound(vec![
+            compound! { "b" => compound! {} },
+            compound! { "b" => 1 },
+        ]),
+    };
+    let before = mixed.clone();
+    let source = compound! { "x" => 1 };
+    assert!(path("l[].b").merge(&mut mixed, &source).is_err());
+    assert!(path("new[0]").merge(&mut mixed, &source).is_err());
+    assert_eq!(mixed, before);
+
+    assert_eq!(path("a.arr[]").remove(&mut root), 2);
+    assert_eq!(path("list[{id:2}]").remove(&mut root), 0);
+    assert_eq!(path("list[{id:1}]").remove(&mut root), 1);
+    assert_eq!(path("a.b").remove(&mut root), 1);
+    assert_eq!(path("missing.b").remove(&mut root), 0);
+    assert_eq!(
+        root,
+        compound! {
+            "a" => compound! {
+                "l" => List::String(vec!["x".into(), "x".into()]),
+                "arr" => Vec::<i64>::new(),
+            },
+            "list" => List::Compound(vec![]),
+        }
+    );
+}
+
+#[test]
+fn compound_merge() {
+    let mut c = compound! {
+        "a" => compound! { "x" => 1, "y" => 2 },
+        "b" => 1,
+    };
+
+    c.merge(compound! {
+        "a" => compound! { "y" => 3, "z" => 4 },
+        "b" => "replaced",
+    });
+
+    assert_eq!(
+        c,
+        compound! {
+            "a" => compound! { "x" => 1, "y" => 3, "z" => 4 },
+            "b" => "replaced",
+        }
+    );
+}
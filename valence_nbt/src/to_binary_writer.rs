@@ -74,12 +74,12 @@ pub(crate) fn encoded_len(compound: &Compound, root_name: &str) -> usize {
     1 + string_len(root_name) + compound_len(compound)
 }
 
-struct EncodeState<W> {
-    writer: W,
+pub(crate) struct EncodeState<W> {
+    pub(crate) writer: W,
 }
 
 impl<W: Write> EncodeState<W> {
-    fn write_tag(&mut self, tag: Tag) -> Result<()> {
+    pub(crate) fn write_tag(&mut self, tag: Tag) -> Result<()> {
         Ok(self.writer.write_u8(tag as u8)?)
     }
 
@@ -100,27 +100,27 @@ impl<W: Write> EncodeState<W> {
         }
     }
 
-    fn write_byte(&mut self, byte: i8) -> Result<()> {
+    pub(crate) fn write_byte(&mut self, byte: i8) -> Result<()> {
         Ok(self.writer.write_i8(byte)?)
     }
 
-    fn write_short(&mut self, short: i16) -> Result<()> {
+    pub(crate) fn write_short(&mut self, short: i16) -> Result<()> {
         Ok(self.writer.write_i16::<BigEndian>(short)?)
     }
 
-    fn write_int(&mut self, int: i32) -> Result<()> {
+    pub(crate) fn write_int(&mut self, int: i32) -> Result<()> {
         Ok(self.writer.write_i32::<BigEndian>(int)?)
     }
 
-    fn write_long(&mut self, long: i64) -> Result<()> {
+    pub(crate) fn write_long(&mut self, long: i64) -> Result<()> {
         Ok(self.writer.write_i64::<BigEndian>(long)?)
     }
 
-    fn write_float(&mut self, float: f32) -> Result<()> {
+    pub(crate) fn write_float(&mut self, float: f32) -> Result<()> {
         Ok(self.writer.write_f32::<BigEndian>(float)?)
     }
 
-    fn write_double(&mut self, double: f64) -> Result<()> {
+    pub(crate) fn write_double(&mut self, double: f64) -> Result<()> {
         Ok(self.writer.write_f64::<BigEndian>(double)?)
     }
 
@@ -138,7 +138,7 @@ impl<W: Write> EncodeState<W> {
         Ok(self.writer.write_all(bytes.as_bytes())?)
     }
 
-    fn write_string(&mut self, s: &str) -> Result<()> {
+    pub(crate) fn write_string(&mut self, s: &str) -> Result<()> {
         let len = modified_utf8::encoded_len(s);
 
         match len.try_into() {
@@ -70,6 +70,62 @@ impl List {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Converts values with identical tags to a list. An empty `Vec` becomes
+    /// an empty list of bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the values do not all have the same tag.
+    pub(crate) fn from_values(values: Vec<Value>) -> Self {
+        macro_rules! collect {
+            ($variant:ident) => {
+                List::$variant(
+                    values
+                        .into_iter()
+                        .map(|v| match v {
+                            Value::$variant(v) => v,
+                            _ => panic!("heterogeneous list"),
+                        })
+                        .collect(),
+                )
+            };
+        }
+
+        match values.first() {
+            None => List::Byte(vec![]),
+            Some(Value::Byte(_)) => collect!(Byte),
+            Some(Value::Short(_)) => collect!(Short),
+            Some(Value::Int(_)) => collect!(Int),
+            Some(Value::Long(_)) => collect!(Long),
+            Some(Value::Float(_)) => collect!(Float),
+            Some(Value::Double(_)) => collect!(Double),
+            Some(Value::ByteArray(_)) => collect!(ByteArray),
+            Some(Value::String(_)) => collect!(String),
+            Some(Value::List(_)) => collect!(List),
+            Some(Value::Compound(_)) => collect!(Compound),
+            Some(Value::IntArray(_)) => collect!(IntArray),
+            Some(Value::LongArray(_)) => collect!(LongArray),
+        }
+    }
+
+    /// Converts this list into its elements.
+    pub(crate) fn into_values(self) -> Vec<Value> {
+        match self {
+            List::Byte(l) => l.into_iter().map(Value::Byte).collect(),
+            List::Short(l) => l.into_iter().map(Value::Short).collect(),
+            List::Int(l) => l.into_iter().map(Value::Int).collect(),
+            List::Long(l) => l.into_iter().map(Value::Long).collect(),
+            List::Float(l) => l.into_iter().map(Value::Float).collect(),
+            List::Double(l) => l.into_iter().map(Value::Double).collect(),
+            List::ByteArray(l) => l.into_iter().map(Value::ByteArray).collect(),
+            List::String(l) => l.into_iter().map(Value::String).collect(),
+            List::List(l) => l.into_iter().map(Value::List).collect(),
+            List::Compound(l) => l.into_iter().map(Value::Compound).collect(),
+            List::IntArray(l) => l.into_iter().map(Value::IntArray).collect(),
+            List::LongArray(l) => l.into_iter().map(Value::LongArray).collect(),
+        }
+    }
 }
 
 impl From<i8> for Value {
@@ -0,0 +1,629 @@
+//! Borrowed views of NBT binary data.
+//!
+//! [`from_binary_slice_ref`] checks that its input is well-formed without
+//! allocating and returns a [`CompoundRef`] which borrows from the input.
+//! Values are decoded lazily as they are accessed and strings stay in their
+//! encoded form until [`StrRef::to_str`] is called. This is much cheaper than
+//! [`from_binary_slice`](crate::from_binary_slice) when only a few fields of a
+//! large compound are needed.
+//!
+//! # Examples
+//!
+//! ```
+//! use valence_nbt::value_ref::ValueRef;
+//! use valence_nbt::{compound, from_binary_slice_ref, to_binary_writer, List};
+//!
+//! let c = compound! {
+//!     "DataVersion" => 3120,
+//!     "sections" => List::Compound(vec![compound! { "Y" => -4_i8 }]),
+//!     "Status" => "full",
+//! };
+//!
+//! let mut buf = vec![];
+//! to_binary_writer(&mut buf, &c, "").unwrap();
+//!
+//! let (root, _) = from_binary_slice_ref(&mut buf.as_slice()).unwrap();
+//!
+//! assert!(matches!(root.get("DataVersion"), Some(ValueRef::Int(3120))));
+//!
+//! let Some(ValueRef::String(status)) = root.get("Status") else {
+//!     panic!()
+//! };
+//! assert_eq!(status.to_str().unwrap(), "full");
+//!
+//! let Some(ValueRef::List(sections)) = root.get("sections") else {
+//!     panic!()
+//! };
+//! assert_eq!(sections.len(), 1);
+//! ```
+
+use std::borrow::Cow;
+use std::fmt;
+use std::iter::FusedIterator;
+
+use byteorder::{BigEndian, ByteOrder};
+use cesu8::Cesu8DecodingError;
+use zerocopy::LayoutVerified;
+
+use crate::decode::{check_len, min_size, tag_from_byte};
+use crate::from_binary_slice::DecodeState;
+use crate::tag::Tag;
+use crate::{modified_utf8, Compound, Error, List, Result, Value};
+
+/// Checks that the provided slice begins with well-formed uncompressed NBT
+/// binary data and returns a view of it without allocating. The slice is
+/// advanced past the data.
+///
+/// The [`StrRef`] returned is the name of the root compound.
+pub fn from_binary_slice_ref<'a>(slice: &mut &'a [u8]) -> Result<(CompoundRef<'a>, StrRef<'a>)> {
+    let mut state = DecodeState::new(slice);
+
+    let root_tag = state.read_tag()?;
+    if root_tag != Tag::Compound {
+        return Err(Error::new_owned(format!(
+            "expected root tag for compound (got {root_tag})",
+        )));
+    }
+
+    let root_name = StrRef {
+        bytes: read_str_bytes(&mut state)?,
+    };
+
+    let start = *state.slice;
+    skip_payload(&mut state, Tag::Compound)?;
+    let len = start.len() - state.slice.len();
+
+    debug_assert_eq!(state.depth.current(), 0);
+
+    Ok((
+        CompoundRef {
+            bytes: &start[..len],
+        },
+        root_name,
+    ))
+}
+
+/// Returns the size of an encoded value with the given tag if it is always
+/// the same.
+fn fixed_size(tag: Tag) -> Option<usize> {
+    match tag {
+        Tag::Byte => Some(1),
+        Tag::Short => Some(2),
+        Tag::Int | Tag::Float => Some(4),
+        Tag::Long | Tag::Double => Some(8),
+        _ => None,
+    }
+}
+
+fn take<'a>(state: &mut DecodeState<'_, 'a>, len: usize, desc: &str) -> Result<&'a [u8]> {
+    if len > state.slice.len() {
+        return Err(Error::new_owned(format!(
+            "{desc} exceeds remainder of input"
+        )));
+    }
+
+    let (left, right) = state.slice.split_at(len);
+    *state.slice = right;
+    Ok(left)
+}
+
+fn read_len(state: &mut DecodeState, desc: &str, elem_tag: Tag) -> Result<usize> {
+    let len = state.read_int()?;
+    check_len(desc, len, min_size(elem_tag), state.remaining())
+}
+
+fn read_str_bytes<'a>(state: &mut DecodeState<'_, 'a>) -> Result<&'a [u8]> {
+    let len = state.read_short()? as u16;
+    take(state, len.into(), "string")
+}
+
+/// Advances past the payload of a value with the given tag, checking that it
+/// is well-formed. Strings are not checked for valid modified UTF-8.
+fn skip_payload(state: &mut DecodeState, tag: Tag) -> Result<()> {
+    if let Some(size) = fixed_size(tag) {
+        take(state, size, tag.name())?;
+        return Ok(());
+    }
+
+    match tag {
+        Tag::ByteArray => {
+            let len = read_len(state, "byte array", Tag::Byte)?;
+            take(state, len, "byte array")?;
+        }
+        Tag::String => {
+            read_str_bytes(state)?;
+        }
+        Tag::List => state.check_depth(|st| {
+            let elem_tag = st.read_tag()?;
+            let len = read_len(st, "list", elem_tag)?;
+
+            if elem_tag == Tag::End {
+                if len != 0 {
+                    return Err(Error::new_owned(format!(
+                        "TAG_End list with nonzero length of {len}"
+                    )));
+                }
+            } else if let Some(size) = fixed_size(elem_tag) {
+                let total = len
+                    .checked_mul(size)
+                    .ok_or_else(|| Error::new_static("list length overflow"))?;
+
+                take(st, total, "list")?;
+            } else {
+                for _ in 0..len {
+                    skip_payload(st, elem_tag)?;
+                }
+            }
+
+            Ok(())
+        })?,
+        Tag::Compound => state.check_depth(|st| loop {
+            let tag = st.read_tag()?;
+            if tag == Tag::End {
+                return Ok(());
+            }
+
+            read_str_bytes(st)?;
+            skip_payload(st, tag)?;
+        })?,
+        Tag::IntArray => {
+            let len = read_len(state, "int array", Tag::Int)?;
+            take(state, len * 4, "int array")?;
+        }
+        Tag::LongArray => {
+            let len = read_len(state, "long array", Tag::Long)?;
+            take(state, len * 8, "long array")?;
+        }
+        _ => unreachable!("illegal tag {tag}"),
+    }
+
+    Ok(())
+}
+
+/// Reads the payload of a value from input which has already been checked by
+/// [`skip_payload`].
+fn read_checked<'a>(slice: &mut &'a [u8], tag: Tag) -> ValueRef<'a> {
+    let mut state = DecodeState::new(slice);
+    let start = *state.slice;
+
+    // The input is known to be well-formed, so none of these can fail.
+    match tag {
+        Tag::Byte => ValueRef::Byte(state.read_byte().unwrap()),
+        Tag::Short => ValueRef::Short(state.read_short().unwrap()),
+        Tag::Int => ValueRef::Int(state.read_int().unwrap()),
+        Tag::Long => ValueRef::Long(state.read_long().unwrap()),
+        Tag::Float => ValueRef::Float(state.read_float().unwrap()),
+        Tag::Double => ValueRef::Double(state.read_double().unwrap()),
+        Tag::ByteArray => {
+            let len = state.read_int().unwrap() as usize;
+            let bytes = take(&mut state, len, "byte array").unwrap();
+
+            ValueRef::ByteArray(
+                LayoutVerified::<_, [i8]>::new_slice_unaligned(bytes)
+                    .unwrap()
+                    .into_slice(),
+            )
+        }
+        Tag::String => ValueRef::String(StrRef {
+            bytes: read_str_bytes(&mut state).unwrap(),
+        }),
+        Tag::List => {
+            skip_payload(&mut state, Tag::List).unwrap();
+            let bytes = &start[..start.len() - state.slice.len()];
+
+            ValueRef::List(ListRef {
+                elem_tag: read_checked_tag(bytes[0]),
+                len: BigEndian::read_i32(&bytes[1..5]) as usize,
+                bytes: &bytes[5..],
+            })
+        }
+        Tag::Compound => {
+            skip_payload(&mut state, Tag::Compound).unwrap();
+
+            ValueRef::Compound(CompoundRef {
+                bytes: &start[..start.len() - state.slice.len()],
+            })
+        }
+        Tag::IntArray => {
+            let len = state.read_int().unwrap() as usize;
+
+            ValueRef::IntArray(IntArrayRef {
+                bytes: take(&mut state, len * 4, "int array").unwrap(),
+            })
+        }
+        Tag::LongArray => {
+            let len = state.read_int().unwrap() as usize;
+
+            ValueRef::LongArray(LongArrayRef {
+                bytes: take(&mut state, len * 8, "long array").unwrap(),
+            })
+        }
+        Tag::End => unreachable!("illegal TAG_End argument"),
+    }
+}
+
+fn read_checked_tag(byte: u8) -> Tag {
+    tag_from_byte(byte).unwrap()
+}
+
+/// A borrowed view of an NBT value.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ValueRef<'a> {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(&'a [i8]),
+    String(StrRef<'a>),
+    List(ListRef<'a>),
+    Compound(CompoundRef<'a>),
+    IntArray(IntArrayRef<'a>),
+    LongArray(LongArrayRef<'a>),
+}
+
+impl ValueRef<'_> {
+    /// Decodes this value into an owned [`Value`].
+    ///
+    /// Returns an error if a string is not valid modified UTF-8.
+    pub fn to_value(&self) -> Result<Value> {
+        Ok(match self {
+            ValueRef::Byte(v) => Value::Byte(*v),
+            ValueRef::Short(v) => Value::Short(*v),
+            ValueRef::Int(v) => Value::Int(*v),
+            ValueRef::Long(v) => Value::Long(*v),
+            ValueRef::Float(v) => Value::Float(*v),
+            ValueRef::Double(v) => Value::Double(*v),
+            ValueRef::ByteArray(v) => Value::ByteArray(v.to_vec()),
+            ValueRef::String(v) => Value::String(v.to_str()?.into_owned()),
+            ValueRef::List(v) => Value::List(v.to_list()?),
+            ValueRef::Compound(v) => Value::Compound(v.to_compound()?),
+            ValueRef::IntArray(v) => Value::IntArray(v.to_vec()),
+            ValueRef::LongArray(v) => Value::LongArray(v.to_vec()),
+        })
+    }
+}
+
+/// A string in its encoded modified UTF-8 form.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StrRef<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> StrRef<'a> {
+    /// Returns the encoded bytes of this string.
+    pub fn as_modified_utf8(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Decodes this string. The string is borrowed if its encoding is also
+    /// valid UTF-8.
+    pub fn to_str(&self) -> Result<Cow<'a, str>> {
+        cesu8::from_java_cesu8(self.bytes).map_err(|Cesu8DecodingError| {
+            Error::new_static("could not convert CESU-8 data to UTF-8")
+        })
+    }
+
+    /// Returns whether this string is equal to `s` without decoding it.
+    pub fn eq_str(&self, s: &str) -> bool {
+        // Modified UTF-8 only differs from UTF-8 in how it encodes the null
+        // character and characters outside the BMP.
+        if s.bytes().all(|b| b != 0 && b < 0xf0) {
+            return self.bytes == s.as_bytes();
+        }
+
+        let mut buf = Vec::with_capacity(self.bytes.len());
+        modified_utf8::write_modified_utf8(&mut buf, s).unwrap();
+        self.bytes == buf
+    }
+}
+
+impl fmt::Debug for StrRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_str() {
+            Ok(s) => s.fmt(f),
+            Err(_) => write!(f, "StrRef({:?})", self.bytes),
+        }
+    }
+}
+
+/// A borrowed view of an NBT list.
+#[derive(Clone, Copy, PartialEq)]
+pub struct ListRef<'a> {
+    elem_tag: Tag,
+    len: usize,
+    /// The encoded elements.
+    bytes: &'a [u8],
+}
+
+impl<'a> ListRef<'a> {
+    /// Returns the length of this list.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if this list has no elements. `false` otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the element at `idx`, or `None` if it is out of bounds.
+    ///
+    /// This takes constant time for lists of numbers and linear time
+    /// otherwise.
+    pub fn get(&self, idx: usize) -> Option<ValueRef<'a>> {
+        if idx >= self.len {
+            return None;
+        }
+
+        match fixed_size(self.elem_tag) {
+            Some(size) => {
+                let mut slice = &self.bytes[idx * size..];
+                Some(read_checked(&mut slice, self.elem_tag))
+            }
+            None => self.iter().nth(idx),
+        }
+    }
+
+    /// Returns an iterator over the elements of this list.
+    pub fn iter(&self) -> ListIter<'a> {
+        ListIter {
+            elem_tag: self.elem_tag,
+            remaining: self.len,
+            bytes: self.bytes,
+        }
+    }
+
+    /// Decodes this list into an owned [`List`].
+    ///
+    /// Returns an error if a string is not valid modified UTF-8.
+    pub fn to_list(&self) -> Result<List> {
+        macro_rules! collect {
+            ($variant:ident, $pat:ident => $expr:expr) => {
+                List::$variant(
+                    self.iter()
+                        .map(|v| match v {
+                            ValueRef::$variant($pat) => Ok($expr),
+                            _ => unreachable!("heterogeneous list"),
+                        })
+                        .collect::<Result<_>>()?,
+                )
+            };
+        }
+
+        Ok(match self.elem_tag {
+            Tag::End => List::Byte(vec![]),
+            Tag::Byte => collect!(Byte, v => v),
+            Tag::Short => collect!(Short, v => v),
+            Tag::Int => collect!(Int, v => v),
+            Tag::Long => collect!(Long, v => v),
+            Tag::Float => collect!(Float, v => v),
+            Tag::Double => collect!(Double, v => v),
+            Tag::ByteArray => collect!(ByteArray, v => v.to_vec()),
+            Tag::String => collect!(String, v => v.to_str()?.into_owned()),
+            Tag::List => collect!(List, v => v.to_list()?),
+            Tag::Compound => collect!(Compound, v => v.to_compound()?),
+            Tag::IntArray => collect!(IntArray, v => v.to_vec()),
+            Tag::LongArray => collect!(LongArray, v => v.to_vec()),
+        })
+    }
+}
+
+impl fmt::Debug for ListRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a> IntoIterator for ListRef<'a> {
+    type Item = ValueRef<'a>;
+    type IntoIter = ListIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the elements of a [`ListRef`].
+#[derive(Clone, Debug)]
+pub struct ListIter<'a> {
+    elem_tag: Tag,
+    remaining: usize,
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for ListIter<'a> {
+    type Item = ValueRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        self.remaining -= 1;
+        Some(read_checked(&mut self.bytes, self.elem_tag))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for ListIter<'_> {}
+
+impl FusedIterator for ListIter<'_> {}
+
+/// A borrowed view of an NBT compound.
+#[derive(Clone, Copy, PartialEq)]
+pub struct CompoundRef<'a> {
+    /// The encoded entries, including the terminating end tag.
+    bytes: &'a [u8],
+}
+
+impl<'a> CompoundRef<'a> {
+    /// Returns the value of the entry named `name`, or `None` if there is no
+    /// such entry.
+    ///
+    /// This takes linear time in the size of the compound.
+    pub fn get(&self, name: &str) -> Option<ValueRef<'a>> {
+        self.iter()
+            .find_map(|(k, v)| if k.eq_str(name) { Some(v) } else { None })
+    }
+
+    /// Returns `true` if there is an entry named `name`.
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Returns `true` if this compound has no entries. `false` otherwise.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.first() == Some(&0)
+    }
+
+    /// Returns an iterator over the entries of this compound in the order
+    /// they are encoded.
+    pub fn iter(&self) -> CompoundIter<'a> {
+        CompoundIter { bytes: self.bytes }
+    }
+
+    /// Decodes this compound into an owned [`Compound`].
+    ///
+    /// Returns an error if a string is not valid modified UTF-8.
+    pub fn to_compound(&self) -> Result<Compound> {
+        self.iter()
+            .map(|(k, v)| Ok((k.to_str()?.into_owned(), v.to_value()?)))
+            .collect()
+    }
+}
+
+impl fmt::Debug for CompoundRef<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a> IntoIterator for CompoundRef<'a> {
+    type Item = (StrRef<'a>, ValueRef<'a>);
+    type IntoIter = CompoundIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the entries of a [`CompoundRef`].
+#[derive(Clone, Debug)]
+pub struct CompoundIter<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for CompoundIter<'a> {
+    type Item = (StrRef<'a>, ValueRef<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let tag = read_checked_tag(*self.bytes.first()?);
+        if tag == Tag::End {
+            return None;
+        }
+
+        let name_len = BigEndian::read_u16(&self.bytes[1..3]) as usize;
+        let name = StrRef {
+            bytes: &self.bytes[3..3 + name_len],
+        };
+
+        self.bytes = &self.bytes[3 + name_len..];
+        let value = read_checked(&mut self.bytes, tag);
+
+        Some((name, value))
+    }
+}
+
+impl FusedIterator for CompoundIter<'_> {}
+
+macro_rules! array_ref {
+    ($array:ident, $iter:ident, $elem:ty, $size:literal, $read:ident, $desc:literal) => {
+        #[doc = concat!("A borrowed view of an NBT ", $desc, ".")]
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        pub struct $array<'a> {
+            /// The big-endian encoded elements.
+            bytes: &'a [u8],
+        }
+
+        impl<'a> $array<'a> {
+            /// Returns the length of this array.
+            pub fn len(&self) -> usize {
+                self.bytes.len() / $size
+            }
+
+            /// Returns `true` if this array has no elements. `false` otherwise.
+            pub fn is_empty(&self) -> bool {
+                self.bytes.is_empty()
+            }
+
+            /// Returns the element at `idx`, or `None` if it is out of bounds.
+            pub fn get(&self, idx: usize) -> Option<$elem> {
+                let bytes = self.bytes.get(idx * $size..(idx + 1) * $size)?;
+                Some(BigEndian::$read(bytes))
+            }
+
+            /// Returns an iterator over the elements of this array.
+            pub fn iter(&self) -> $iter<'a> {
+                $iter {
+                    chunks: self.bytes.chunks_exact($size),
+                }
+            }
+
+            /// Decodes this array into a `Vec`.
+            pub fn to_vec(&self) -> Vec<$elem> {
+                self.iter().collect()
+            }
+        }
+
+        impl fmt::Debug for $array<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_list().entries(self.iter()).finish()
+            }
+        }
+
+        impl<'a> IntoIterator for $array<'a> {
+            type Item = $elem;
+            type IntoIter = $iter<'a>;
+
+            fn into_iter(self) -> Self::IntoIter {
+                self.iter()
+            }
+        }
+
+        #[doc = concat!("An iterator over the elements of a [`", stringify!($array), "`].")]
+        #[derive(Clone, Debug)]
+        pub struct $iter<'a> {
+            chunks: std::slice::ChunksExact<'a, u8>,
+        }
+
+        impl Iterator for $iter<'_> {
+            type Item = $elem;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                self.chunks.next().map(BigEndian::$read)
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.chunks.size_hint()
+            }
+        }
+
+        impl DoubleEndedIterator for $iter<'_> {
+            fn next_back(&mut self) -> Option<Self::Item> {
+                self.chunks.next_back().map(BigEndian::$read)
+            }
+        }
+
+        impl ExactSizeIterator for $iter<'_> {}
+
+        impl FusedIterator for $iter<'_> {}
+    };
+}
+
+array_ref!(IntArrayRef, IntArrayIter, i32, 4, read_i32, "int array");
+array_ref!(LongArrayRef, LongArrayIter, i64, 8, read_i64, "long array");
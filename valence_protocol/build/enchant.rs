@@ -64,6 +64,17 @@ pub fn build() -> anyhow::Result<TokenStream> {
         })
         .collect::<TokenStream>();
 
+    let enchantmentkind_from_str_arms = enchants
+        .iter()
+        .map(|enchant| {
+            let rustified_name = ident(enchant.name.to_pascal_case());
+            let name = &enchant.name;
+            quote! {
+                #name => Some(Self::#rustified_name),
+            }
+        })
+        .collect::<TokenStream>();
+
     let enchantmentkind_translations = enchants
         .iter()
         .map(|enchant| {
@@ -179,6 +190,17 @@ pub fn build() -> anyhow::Result<TokenStream> {
                 }
             }
 
+            /// Constructs an `EnchantmentKind` from the name the game uses.
+            ///
+            /// Returns `None` if the name is invalid.
+            #[allow(clippy::should_implement_trait)]
+            pub fn from_str(name: &str) -> Option<Self> {
+                match name {
+                    #enchantmentkind_from_str_arms
+                    _ => None
+                }
+            }
+
             /// Returns the minimum enchantment level officially supported by Minecraft.
             pub const fn min_level(self) -> i16 {
                 match self{
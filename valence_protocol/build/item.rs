@@ -15,6 +15,9 @@ struct Item {
     max_durability: u16,
     enchantability: u8,
     fireproof: bool,
+    rarity: String,
+    recipe_remainder: Option<String>,
+    equipment_slot: Option<String>,
     food: Option<FoodComponent>,
 }
 
@@ -171,6 +174,46 @@ pub fn build() -> anyhow::Result<TokenStream> {
         })
         .collect::<TokenStream>();
 
+    let item_kind_to_rarity_arms = items
+        .iter()
+        .filter(|item| item.rarity != "common")
+        .map(|item| {
+            let name = ident(item.name.to_pascal_case());
+            let rarity = ident(item.rarity.to_pascal_case());
+
+            quote! {
+                Self::#name => Rarity::#rarity,
+            }
+        })
+        .collect::<TokenStream>();
+
+    let item_kind_to_recipe_remainder_arms = items
+        .iter()
+        .filter_map(|item| {
+            let name = ident(item.name.to_pascal_case());
+            let remainder = ident(item.recipe_remainder.as_ref()?.to_pascal_case());
+
+            Some(quote! {
+                Self::#name => Some(Self::#remainder),
+            })
+        })
+        .collect::<TokenStream>();
+
+    let item_kind_to_equipment_slot_arms = items
+        .iter()
+        .filter_map(|item| {
+            let name = ident(item.name.to_pascal_case());
+            let slot = match item.equipment_slot.as_deref()? {
+                "offhand" => ident("OffHand"),
+                slot => ident(slot.to_pascal_case()),
+            };
+
+            Some(quote! {
+                Self::#name => AttributeSlot::#slot,
+            })
+        })
+        .collect::<TokenStream>();
+
     Ok(quote! {
         /// Represents an item from the game
         #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
@@ -191,6 +234,28 @@ pub fn build() -> anyhow::Result<TokenStream> {
             pub snack: bool,
         }
 
+        /// The rarity of an item, which determines the color of its name.
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+        pub enum Rarity {
+            Common,
+            Uncommon,
+            Rare,
+            Epic,
+        }
+
+        impl Rarity {
+            /// Returns the color used to display the name of items with this
+            /// rarity.
+            pub const fn color(self) -> Color {
+                match self {
+                    Self::Common => Color::WHITE,
+                    Self::Uncommon => Color::YELLOW,
+                    Self::Rare => Color::AQUA,
+                    Self::Epic => Color::LIGHT_PURPLE,
+                }
+            }
+        }
+
         impl ItemKind {
             /// Constructs a item kind from a raw item ID.
             ///
@@ -280,6 +345,38 @@ pub fn build() -> anyhow::Result<TokenStream> {
                 }
             }
 
+            /// Returns the rarity of the item kind.
+            ///
+            /// Items which are enchanted have their rarity raised by one level
+            /// by the client, which is not reflected here.
+            pub const fn rarity(self) -> Rarity {
+                match self {
+                    #item_kind_to_rarity_arms
+                    _ => Rarity::Common,
+                }
+            }
+
+            /// Returns the item left behind in the crafting grid when this
+            /// item is used as an ingredient, such as the bucket of a milk
+            /// bucket.
+            pub const fn recipe_remainder(self) -> Option<ItemKind> {
+                match self {
+                    #item_kind_to_recipe_remainder_arms
+                    _ => None,
+                }
+            }
+
+            /// Returns the equipment slot this item kind is worn in, such as
+            /// [`AttributeSlot::Head`] for helmets.
+            ///
+            /// Items which are not worn return [`AttributeSlot::MainHand`].
+            pub const fn equipment_slot(self) -> AttributeSlot {
+                match self {
+                    #item_kind_to_equipment_slot_arms
+                    _ => AttributeSlot::MainHand,
+                }
+            }
+
             /// Constructs an item kind from a block kind.
             ///
             /// [`ItemKind::Air`] is used to indicate the absence of an item.
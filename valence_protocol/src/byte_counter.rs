@@ -1,5 +1,7 @@
 use std::io;
 
+/// An [`io::Write`] implementation which discards the bytes written to it
+/// while counting them.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Debug)]
 pub struct ByteCounter(pub usize);
 
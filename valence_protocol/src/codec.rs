@@ -222,7 +222,7 @@ impl PacketDecoder {
         };
 
         ensure!(
-            packet_len <= MAX_PACKET_SIZE,
+            (0..=MAX_PACKET_SIZE).contains(&packet_len),
             "packet length of {packet_len} is out of bounds"
         );
 
@@ -278,11 +278,24 @@ impl PacketDecoder {
         Ok(Some(packet))
     }
 
+    /// Returns `true` if an entire packet has been queued and can be decoded
+    /// with [`PacketDecoder::try_next_packet`].
+    ///
+    /// An error is returned if the length of the next packet is out of
+    /// bounds, so callers waiting for a whole packet do not buffer data
+    /// indefinitely.
     pub fn has_next_packet(&self) -> Result<bool> {
         let mut r = &self.buf[self.cursor..];
 
         match VarInt::decode_partial(&mut r) {
-            Ok(_) => Ok(true),
+            Ok(len) => {
+                ensure!(
+                    (0..=MAX_PACKET_SIZE).contains(&len),
+                    "packet length of {len} is out of bounds"
+                );
+
+                Ok(r.len() >= len as usize)
+            }
             Err(VarIntDecodeError::Incomplete) => Ok(false),
             Err(VarIntDecodeError::TooLarge) => bail!("malformed packet length VarInt"),
         }
@@ -442,4 +455,48 @@ mod tests {
             .unwrap()
             .check("third");
     }
+
+    #[test]
+    fn has_next_packet_waits_for_whole_packet() {
+        let mut enc = PacketEncoder::new();
+        enc.append_packet(&TestPacket::new("first")).unwrap();
+        let bytes = enc.take();
+
+        let mut dec = PacketDecoder::new();
+        assert!(!dec.has_next_packet().unwrap());
+
+        dec.queue_slice(&bytes[..bytes.len() - 1]);
+        assert!(!dec.has_next_packet().unwrap());
+
+        dec.queue_slice(&bytes[bytes.len() - 1..]);
+        assert!(dec.has_next_packet().unwrap());
+        dec.try_next_packet::<TestPacket>()
+            .unwrap()
+            .unwrap()
+            .check("first");
+    }
+
+    #[test]
+    fn packet_length_bounds() {
+        for len in [-1, MAX_PACKET_SIZE + 1] {
+            let mut buf = vec![];
+            VarInt(len).encode(&mut buf).unwrap();
+
+            let mut dec = PacketDecoder::new();
+            dec.queue_slice(&buf);
+
+            assert!(dec.has_next_packet().is_err());
+            assert!(dec.try_next_packet::<TestPacket>().is_err());
+        }
+
+        let mut buf = vec![];
+        VarInt(MAX_PACKET_SIZE).encode(&mut buf).unwrap();
+
+        let mut dec = PacketDecoder::new();
+        dec.queue_slice(&buf);
+
+        // The largest length is accepted, but the packet is incomplete.
+        assert!(!dec.has_next_packet().unwrap());
+        assert!(dec.try_next_packet::<TestPacket>().unwrap().is_none());
+    }
 }
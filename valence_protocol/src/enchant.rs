@@ -3,3 +3,17 @@
 // associated const fn functions of `EnchantmentKind` to access details about an
 // enchantment type. enchantment specific functions
 include!(concat!(env!("OUT_DIR"), "/enchant.rs"));
+
+/// An enchantment applied to an item, as stored in the `Enchantments` tag of
+/// an [`ItemStack`](crate::ItemStack).
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Enchantment {
+    pub kind: EnchantmentKind,
+    pub level: i16,
+}
+
+impl Enchantment {
+    pub const fn new(kind: EnchantmentKind, level: i16) -> Self {
+        Self { kind, level }
+    }
+}
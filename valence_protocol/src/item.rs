@@ -3,8 +3,13 @@ use std::io::Write;
 use anyhow::{ensure, Context};
 use valence_nbt::Compound;
 
+use crate::text::Color;
 use crate::{BlockKind, Decode, Encode, Result, VarInt};
 
+mod nbt;
+
+pub use nbt::{AttributeModifier, AttributeOperation, AttributeSlot, HideFlags, SkullOwner};
+
 include!(concat!(env!("OUT_DIR"), "/item.rs"));
 
 #[derive(Clone, PartialEq, Debug)]
@@ -141,4 +146,88 @@ mod tests {
         stack.set_count(201);
         assert_eq!(stack.count, STACK_MAX);
     }
+
+    #[test]
+    fn item_properties() {
+        assert_eq!(ItemKind::EnderPearl.max_stack(), 16);
+        assert_eq!(ItemKind::DiamondSword.max_stack(), 1);
+        assert_eq!(ItemKind::DiamondSword.max_durability(), 1561);
+        assert!(ItemKind::NetheriteIngot.fireproof());
+        assert!(ItemKind::Apple.food_component().is_some());
+        assert_eq!(ItemKind::Stone.rarity(), Rarity::Common);
+        assert_eq!(ItemKind::Elytra.rarity(), Rarity::Uncommon);
+        assert_eq!(ItemKind::Beacon.rarity(), Rarity::Rare);
+        assert_eq!(ItemKind::EnchantedGoldenApple.rarity(), Rarity::Epic);
+        assert_eq!(
+            ItemKind::MilkBucket.recipe_remainder(),
+            Some(ItemKind::Bucket)
+        );
+        assert_eq!(
+            ItemKind::HoneyBottle.recipe_remainder(),
+            Some(ItemKind::GlassBottle)
+        );
+        assert_eq!(ItemKind::Wheat.recipe_remainder(), None);
+        assert_eq!(ItemKind::TurtleHelmet.equipment_slot(), AttributeSlot::Head);
+        assert_eq!(
+            ItemKind::CarvedPumpkin.equipment_slot(),
+            AttributeSlot::Head
+        );
+        assert_eq!(ItemKind::Elytra.equipment_slot(), AttributeSlot::Chest);
+        assert_eq!(ItemKind::IronLeggings.equipment_slot(), AttributeSlot::Legs);
+        assert_eq!(ItemKind::GoldenBoots.equipment_slot(), AttributeSlot::Feet);
+        assert_eq!(ItemKind::Shield.equipment_slot(), AttributeSlot::OffHand);
+        assert_eq!(ItemKind::Stone.equipment_slot(), AttributeSlot::MainHand);
+    }
+
+    #[test]
+    fn item_nbt_round_trip() {
+        use crate::enchant::{Enchantment, EnchantmentKind};
+        use crate::Text;
+
+        let mut stack = ItemStack::new(ItemKind::DiamondSword, 1, None)
+            .with_display_name("Excalibur")
+            .with_lore(["line one", "line two"])
+            .with_enchantment(EnchantmentKind::Sharpness, 5);
+
+        stack.add_enchantment(EnchantmentKind::Sharpness, 3);
+        stack.set_damage(10);
+        stack.set_unbreakable(true);
+        stack.set_custom_model_data(Some(42));
+        stack.set_hide_flags(HideFlags::new().with_enchantments(true));
+
+        let owner = SkullOwner {
+            uuid: Some(crate::Uuid::from_u128(
+                0x0123_4567_89ab_cdef_0123_4567_89ab_cdef,
+            )),
+            name: Some("Notch".into()),
+            properties: vec![],
+        };
+        stack.set_skull_owner(Some(owner.clone()));
+
+        assert_eq!(stack.display_name(), Some("Excalibur".into()));
+        assert_eq!(
+            stack.lore(),
+            vec![Text::from("line one"), "line two".into()]
+        );
+        assert_eq!(
+            stack.enchantments(),
+            vec![Enchantment::new(EnchantmentKind::Sharpness, 3)]
+        );
+        assert_eq!(stack.damage(), 10);
+        assert!(stack.unbreakable());
+        assert_eq!(stack.custom_model_data(), Some(42));
+        assert!(stack.hide_flags().enchantments());
+        assert_eq!(stack.skull_owner(), Some(owner));
+
+        stack.set_display_name(None::<Text>);
+        stack.set_lore(Vec::<Text>::new());
+        stack.set_enchantments([]);
+        stack.set_damage(0);
+        stack.set_unbreakable(false);
+        stack.set_custom_model_data(None);
+        stack.set_hide_flags(HideFlags::new());
+        stack.set_skull_owner(None);
+
+        assert_eq!(stack.nbt, None);
+    }
 }
@@ -0,0 +1,559 @@
+//! Typed access to the common tags stored in an item stack's NBT.
+//!
+//! Every accessor reads from and writes to [`ItemStack::nbt`] directly, so
+//! tags that are not covered here are left untouched. Setters that are given
+//! `None` remove the tag, and the compound is dropped entirely once it becomes
+//! empty.
+
+use bitfield_struct::bitfield;
+use uuid::Uuid;
+use valence_nbt::{compound, Compound, List, Value};
+
+use crate::enchant::{Enchantment, EnchantmentKind};
+use crate::types::SignedPropertyOwned;
+use crate::{Ident, ItemStack, Text};
+
+/// Controls which parts of an item's tooltip are hidden from the client.
+#[bitfield(u8)]
+#[derive(PartialEq, Eq)]
+pub struct HideFlags {
+    pub enchantments: bool,
+    pub attribute_modifiers: bool,
+    pub unbreakable: bool,
+    pub can_destroy: bool,
+    pub can_place_on: bool,
+    /// Potion effects, book and firework information, and similar.
+    pub other: bool,
+    pub dyed: bool,
+    _pad: bool,
+}
+
+/// The owner of a player head, stored in the `SkullOwner` tag.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct SkullOwner {
+    pub uuid: Option<Uuid>,
+    pub name: Option<String>,
+    /// Profile properties such as `textures`.
+    pub properties: Vec<SignedPropertyOwned>,
+}
+
+/// An entry of the `AttributeModifiers` tag.
+#[derive(Clone, PartialEq, Debug)]
+pub struct AttributeModifier {
+    /// The attribute being modified, e.g. `generic.attack_damage`.
+    pub attribute: Ident<String>,
+    pub name: String,
+    pub uuid: Uuid,
+    pub amount: f64,
+    pub operation: AttributeOperation,
+    /// The slot the item must be in for the modifier to apply. `None` means
+    /// any slot.
+    pub slot: Option<AttributeSlot>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AttributeOperation {
+    Add,
+    MultiplyBase,
+    MultiplyTotal,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AttributeSlot {
+    MainHand,
+    OffHand,
+    Feet,
+    Legs,
+    Chest,
+    Head,
+}
+
+impl AttributeOperation {
+    fn from_raw(raw: i32) -> Option<Self> {
+        match raw {
+            0 => Some(Self::Add),
+            1 => Some(Self::MultiplyBase),
+            2 => Some(Self::MultiplyTotal),
+            _ => None,
+        }
+    }
+
+    fn to_raw(self) -> i32 {
+        self as i32
+    }
+}
+
+impl AttributeSlot {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "mainhand" => Some(Self::MainHand),
+            "offhand" => Some(Self::OffHand),
+            "feet" => Some(Self::Feet),
+            "legs" => Some(Self::Legs),
+            "chest" => Some(Self::Chest),
+            "head" => Some(Self::Head),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::MainHand => "mainhand",
+            Self::OffHand => "offhand",
+            Self::Feet => "feet",
+            Self::Legs => "legs",
+            Self::Chest => "chest",
+            Self::Head => "head",
+        }
+    }
+}
+
+impl ItemStack {
+    /// Gets the custom name of this item from the `display.Name` tag.
+    ///
+    /// Returns `None` if the tag is absent or is not valid JSON text.
+    pub fn display_name(&self) -> Option<Text> {
+        match self.display()?.get("Name")? {
+            Value::String(json) => serde_json::from_str(json).ok(),
+            _ => None,
+        }
+    }
+
+    /// Sets or removes the custom name of this item.
+    pub fn set_display_name(&mut self, name: Option<impl Into<Text>>) {
+        let name = name.map(|n| Value::String(text_to_json(&n.into())));
+        self.set_display_tag("Name", name);
+    }
+
+    /// Builder-style variant of [`Self::set_display_name`].
+    #[must_use]
+    pub fn with_display_name(mut self, name: impl Into<Text>) -> Self {
+        self.set_display_name(Some(name));
+        self
+    }
+
+    /// Gets the lines of lore from the `display.Lore` tag. Lines that are not
+    /// valid JSON text are skipped.
+    pub fn lore(&self) -> Vec<Text> {
+        match self.display().and_then(|d| d.get("Lore")) {
+            Some(Value::List(List::String(lines))) => lines
+                .iter()
+                .filter_map(|json| serde_json::from_str(json).ok())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Replaces the lore of this item. An empty iterator removes the tag.
+    pub fn set_lore<I, T>(&mut self, lore: I)
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Text>,
+    {
+        let lines: Vec<_> = lore
+            .into_iter()
+            .map(|line| text_to_json(&line.into()))
+            .collect();
+
+        let lore = (!lines.is_empty()).then(|| List::String(lines).into());
+        self.set_display_tag("Lore", lore);
+    }
+
+    /// Builder-style variant of [`Self::set_lore`].
+    #[must_use]
+    pub fn with_lore<I, T>(mut self, lore: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Text>,
+    {
+        self.set_lore(lore);
+        self
+    }
+
+    /// Gets the enchantments on this item from the `Enchantments` tag.
+    /// Entries with an unknown enchantment are skipped.
+    pub fn enchantments(&self) -> Vec<Enchantment> {
+        match self.tag("Enchantments") {
+            Some(Value::List(List::Compound(list))) => {
+                list.iter().filter_map(enchantment_from_nbt).collect()
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Replaces the enchantments on this item. An empty iterator removes the
+    /// tag.
+    pub fn set_enchantments(&mut self, enchantments: impl IntoIterator<Item = Enchantment>) {
+        let list: Vec<_> = enchantments.into_iter().map(enchantment_to_nbt).collect();
+
+        let list = (!list.is_empty()).then(|| List::Compound(list).into());
+        self.set_tag("Enchantments", list);
+    }
+
+    /// Adds an enchantment to this item, replacing the level of an existing
+    /// enchantment of the same kind.
+    pub fn add_enchantment(&mut self, kind: EnchantmentKind, level: i16) {
+        let mut enchantments = self.enchantments();
+
+        match enchantments.iter_mut().find(|e| e.kind == kind) {
+            Some(e) => e.level = level,
+            None => enchantments.push(Enchantment::new(kind, level)),
+        }
+
+        self.set_enchantments(enchantments);
+    }
+
+    /// Builder-style variant of [`Self::add_enchantment`].
+    #[must_use]
+    pub fn with_enchantment(mut self, kind: EnchantmentKind, level: i16) -> Self {
+        self.add_enchantment(kind, level);
+        self
+    }
+
+    /// Gets the amount of durability this item has lost from the `Damage`
+    /// tag. Returns `0` if the tag is absent.
+    pub fn damage(&self) -> i32 {
+        match self.tag("Damage") {
+            Some(&Value::Int(damage)) => damage,
+            _ => 0,
+        }
+    }
+
+    /// Sets the amount of durability this item has lost. A damage of `0`
+    /// removes the tag.
+    pub fn set_damage(&mut self, damage: i32) {
+        self.set_tag("Damage", (damage != 0).then_some(Value::Int(damage)));
+    }
+
+    /// Returns whether this item has the `Unbreakable` tag set.
+    pub fn unbreakable(&self) -> bool {
+        matches!(self.tag("Unbreakable"), Some(&Value::Byte(b)) if b != 0)
+    }
+
+    /// Sets or removes the `Unbreakable` tag.
+    pub fn set_unbreakable(&mut self, unbreakable: bool) {
+        self.set_tag("Unbreakable", unbreakable.then_some(Value::Byte(1)));
+    }
+
+    /// Gets the `CustomModelData` tag used by resource packs to select a
+    /// model.
+    pub fn custom_model_data(&self) -> Option<i32> {
+        match self.tag("CustomModelData") {
+            Some(&Value::Int(data)) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Sets or removes the `CustomModelData` tag.
+    pub fn set_custom_model_data(&mut self, data: Option<i32>) {
+        self.set_tag("CustomModelData", data.map(Value::Int));
+    }
+
+    /// Gets the tooltip sections hidden by the `HideFlags` tag.
+    pub fn hide_flags(&self) -> HideFlags {
+        match self.tag("HideFlags") {
+            Some(&Value::Int(flags)) => HideFlags::from(flags as u8),
+            _ => HideFlags::new(),
+        }
+    }
+
+    /// Sets the tooltip sections to hide. Hiding nothing removes the tag.
+    pub fn set_hide_flags(&mut self, flags: HideFlags) {
+        let flags = u8::from(flags);
+        self.set_tag(
+            "HideFlags",
+            (flags != 0).then_some(Value::Int(flags as i32)),
+        );
+    }
+
+    /// Gets the owner of this player head from the `SkullOwner` tag, which
+    /// may be either a bare player name or a full profile.
+    pub fn skull_owner(&self) -> Option<SkullOwner> {
+        match self.tag("SkullOwner")? {
+            Value::String(name) => Some(SkullOwner {
+                name: Some(name.clone()),
+                ..Default::default()
+            }),
+            Value::Compound(owner) => Some(skull_owner_from_nbt(owner)),
+            _ => None,
+        }
+    }
+
+    /// Sets or removes the owner of this player head.
+    pub fn set_skull_owner(&mut self, owner: Option<SkullOwner>) {
+        self.set_tag("SkullOwner", owner.map(|o| skull_owner_to_nbt(o).into()));
+    }
+
+    /// Gets the attribute modifiers of this item from the `AttributeModifiers`
+    /// tag. Malformed entries are skipped.
+    pub fn attribute_modifiers(&self) -> Vec<AttributeModifier> {
+        match self.tag("AttributeModifiers") {
+            Some(Value::List(List::Compound(list))) => list
+                .iter()
+                .filter_map(attribute_modifier_from_nbt)
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Replaces the attribute modifiers of this item. An empty iterator
+    /// removes the tag.
+    pub fn set_attribute_modifiers(
+        &mut self,
+        modifiers: impl IntoIterator<Item = AttributeModifier>,
+    ) {
+        let list: Vec<_> = modifiers
+            .into_iter()
+            .map(attribute_modifier_to_nbt)
+            .collect();
+
+        let list = (!list.is_empty()).then(|| List::Compound(list).into());
+        self.set_tag("AttributeModifiers", list);
+    }
+
+    fn tag(&self, key: &str) -> Option<&Value> {
+        self.nbt.as_ref()?.get(key)
+    }
+
+    fn set_tag(&mut self, key: &str, value: Option<Value>) {
+        match value {
+            Some(value) => {
+                self.nbt
+                    .get_or_insert_with(Compound::new)
+                    .insert(key, value);
+            }
+            None => {
+                if let Some(nbt) = &mut self.nbt {
+                    nbt.remove(key);
+                    if nbt.is_empty() {
+                        self.nbt = None;
+                    }
+                }
+            }
+        }
+    }
+
+    fn display(&self) -> Option<&Compound> {
+        match self.tag("display")? {
+            Value::Compound(display) => Some(display),
+            _ => None,
+        }
+    }
+
+    fn set_display_tag(&mut self, key: &str, value: Option<Value>) {
+        let mut display = match self.tag("display") {
+            Some(Value::Compound(display)) => display.clone(),
+            _ => Compound::new(),
+        };
+
+        match value {
+            Some(value) => {
+                display.insert(key, value);
+            }
+            None => {
+                display.remove(key);
+            }
+        }
+
+        self.set_tag("display", (!display.is_empty()).then(|| display.into()));
+    }
+}
+
+fn text_to_json(text: &Text) -> String {
+    serde_json::to_string(text).expect("failed to serialize text")
+}
+
+fn enchantment_from_nbt(nbt: &Compound) -> Option<Enchantment> {
+    let Some(Value::String(id)) = nbt.get("id") else {
+        return None;
+    };
+
+    let id = Ident::new(id.as_str()).ok()?;
+    if id.namespace() != "minecraft" {
+        return None;
+    }
+
+    let level = match *nbt.get("lvl")? {
+        Value::Short(lvl) => lvl,
+        Value::Int(lvl) => lvl.try_into().ok()?,
+        Value::Byte(lvl) => lvl.into(),
+        _ => return None,
+    };
+
+    Some(Enchantment::new(
+        EnchantmentKind::from_str(id.path())?,
+        level,
+    ))
+}
+
+fn enchantment_to_nbt(enchantment: Enchantment) -> Compound {
+    compound! {
+        "id" => format!("minecraft:{}", enchantment.kind.name()),
+        "lvl" => enchantment.level,
+    }
+}
+
+fn skull_owner_from_nbt(nbt: &Compound) -> SkullOwner {
+    let uuid = match nbt.get("Id") {
+        Some(Value::IntArray(ints)) => uuid_from_ints(ints),
+        _ => None,
+    };
+
+    let name = match nbt.get("Name") {
+        Some(Value::String(name)) => Some(name.clone()),
+        _ => None,
+    };
+
+    let mut properties = vec![];
+
+    if let Some(Value::Compound(props)) = nbt.get("Properties") {
+        for (name, value) in props {
+            let Value::List(List::Compound(entries)) = value else {
+                continue;
+            };
+
+            for entry in entries {
+                let Some(Value::String(value)) = entry.get("Value") else {
+                    continue;
+                };
+
+                let signature = match entry.get("Signature") {
+                    Some(Value::String(sig)) => Some(sig.clone()),
+                    _ => None,
+                };
+
+                properties.push(SignedPropertyOwned {
+                    name: name.clone(),
+                    value: value.clone(),
+                    signature,
+                });
+            }
+        }
+    }
+
+    SkullOwner {
+        uuid,
+        name,
+        properties,
+    }
+}
+
+fn skull_owner_to_nbt(owner: SkullOwner) -> Compound {
+    let mut nbt = Compound::new();
+
+    if let Some(uuid) = owner.uuid {
+        nbt.insert("Id", uuid_to_ints(uuid));
+    }
+
+    if let Some(name) = owner.name {
+        nbt.insert("Name", name);
+    }
+
+    if !owner.properties.is_empty() {
+        let mut props = Compound::new();
+
+        for prop in owner.properties {
+            let mut entry = compound! {
+                "Value" => prop.value,
+            };
+
+            if let Some(sig) = prop.signature {
+                entry.insert("Signature", sig);
+            }
+
+            match props
+                .entry(prop.name)
+                .or_insert_with(|| List::Compound(vec![]))
+            {
+                Value::List(List::Compound(entries)) => entries.push(entry),
+                _ => unreachable!(),
+            }
+        }
+
+        nbt.insert("Properties", props);
+    }
+
+    nbt
+}
+
+fn attribute_modifier_from_nbt(nbt: &Compound) -> Option<AttributeModifier> {
+    let Some(Value::String(attribute)) = nbt.get("AttributeName") else {
+        return None;
+    };
+
+    let name = match nbt.get("Name") {
+        Some(Value::String(name)) => name.clone(),
+        _ => String::new(),
+    };
+
+    let uuid = match nbt.get("UUID")? {
+        Value::IntArray(ints) => uuid_from_ints(ints)?,
+        _ => return None,
+    };
+
+    let amount = match *nbt.get("Amount")? {
+        Value::Double(amount) => amount,
+        Value::Float(amount) => amount.into(),
+        _ => return None,
+    };
+
+    let operation = match nbt.get("Operation") {
+        Some(&Value::Int(op)) => AttributeOperation::from_raw(op)?,
+        None => AttributeOperation::Add,
+        _ => return None,
+    };
+
+    let slot = match nbt.get("Slot") {
+        Some(Value::String(slot)) => Some(AttributeSlot::from_name(slot)?),
+        _ => None,
+    };
+
+    Some(AttributeModifier {
+        attribute: Ident::new(attribute.clone()).ok()?,
+        name,
+        uuid,
+        amount,
+        operation,
+        slot,
+    })
+}
+
+fn attribute_modifier_to_nbt(modifier: AttributeModifier) -> Compound {
+    let mut nbt = compound! {
+        "AttributeName" => modifier.attribute.into_inner(),
+        "Name" => modifier.name,
+        "UUID" => uuid_to_ints(modifier.uuid),
+        "Amount" => modifier.amount,
+        "Operation" => modifier.operation.to_raw(),
+    };
+
+    if let Some(slot) = modifier.slot {
+        nbt.insert("Slot", slot.name());
+    }
+
+    nbt
+}
+
+/// UUIDs are stored in NBT as four ints, most significant first.
+fn uuid_from_ints(ints: &[i32]) -> Option<Uuid> {
+    let [a, b, c, d] = *ints else {
+        return None;
+    };
+
+    Some(Uuid::from_u128(
+        (a as u32 as u128) << 96
+            | (b as u32 as u128) << 64
+            | (c as u32 as u128) << 32
+            | d as u32 as u128,
+    ))
+}
+
+fn uuid_to_ints(uuid: Uuid) -> Vec<i32> {
+    let n = uuid.as_u128();
+    vec![
+        (n >> 96) as i32,
+        (n >> 64) as i32,
+        (n >> 32) as i32,
+        n as i32,
+    ]
+}
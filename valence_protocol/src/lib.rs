@@ -75,20 +75,22 @@ pub use anyhow::{Error, Result};
 pub use block::{BlockFace, BlockKind, BlockState};
 pub use block_pos::BlockPos;
 pub use byte_angle::ByteAngle;
+pub use byte_counter::ByteCounter;
 pub use cache::{Cached, EncodedBuf};
 pub use codec::{PacketDecoder, PacketEncoder};
 pub use ident::Ident;
-pub use item::{ItemKind, ItemStack};
+pub use item::{
+    AttributeModifier, AttributeOperation, AttributeSlot, HideFlags, ItemKind, ItemStack, Rarity,
+    SkullOwner,
+};
 pub use raw_bytes::RawBytes;
 pub use text::{Text, TextFormat};
 pub use username::Username;
-pub use uuid::Uuid;
+pub use uuid::{self, Uuid};
 pub use valence_derive::{Decode, Encode, Packet};
+pub use valence_nbt as nbt;
 pub use var_int::VarInt;
 pub use var_long::VarLong;
-pub use {uuid, valence_nbt as nbt};
-
-use crate::byte_counter::ByteCounter;
 
 /// The Minecraft protocol version this library currently targets.
 pub const PROTOCOL_VERSION: i32 = 760;
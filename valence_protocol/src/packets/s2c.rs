@@ -10,8 +10,8 @@ use crate::raw_bytes::RawBytes;
 use crate::text::Text;
 use crate::types::{
     AttributeProperty, BossBarAction, ChunkDataBlockEntity, DeathLocation, Difficulty, GameMode,
-    GameStateChangeReason, PlayerInfoAddPlayer, SignedProperty, SoundCategory,
-    SyncPlayerPosLookFlags,
+    GameStateChangeReason, PlayerInfoAddPlayer, Recipe, SignedProperty, SoundCategory,
+    SyncPlayerPosLookFlags, UpdateRecipeBookAction,
 };
 use crate::username::Username;
 use crate::var_int::VarInt;
@@ -185,6 +185,13 @@ pub mod play {
         pub reset: bool,
     }
 
+    #[derive(Copy, Clone, Debug, Encode, Decode, Packet)]
+    #[packet_id = 0x10]
+    pub struct CloseContainerS2c {
+        /// Ignored by notchian clients.
+        pub window_id: u8,
+    }
+
     #[derive(Clone, Debug, Encode, Decode, Packet)]
     #[packet_id = 0x11]
     pub struct SetContainerContent {
@@ -392,6 +399,13 @@ pub mod play {
         pub window_title: Text,
     }
 
+    #[derive(Copy, Clone, Debug, Encode, Decode, Packet)]
+    #[packet_id = 0x30]
+    pub struct PlaceGhostRecipe<'a> {
+        pub window_id: i8,
+        pub recipe: Ident<&'a str>,
+    }
+
     #[derive(Clone, Debug, Encode, Decode, Packet)]
     #[packet_id = 0x33]
     pub struct PlayerChatMessage<'a> {
@@ -429,6 +443,12 @@ pub mod play {
         pub dismount_vehicle: bool,
     }
 
+    #[derive(Clone, Debug, Encode, Decode, Packet)]
+    #[packet_id = 0x3a]
+    pub struct UpdateRecipeBook<'a> {
+        pub action: UpdateRecipeBookAction<'a>,
+    }
+
     #[derive(Clone, PartialEq, Debug, Encode, Decode, Packet)]
     #[packet_id = 0x3b]
     pub struct RemoveEntities {
@@ -629,6 +649,12 @@ pub mod play {
         pub properties: Vec<AttributeProperty<'a>>,
     }
 
+    #[derive(Clone, Debug, Encode, Decode, Packet)]
+    #[packet_id = 0x6a]
+    pub struct UpdateRecipes<'a> {
+        pub recipes: Vec<Recipe<'a>>,
+    }
+
     packet_enum! {
         #[derive(Clone, Debug)]
         S2cPlayPacket<'a> {
@@ -643,6 +669,7 @@ pub mod play {
             BossBar,
             SetDifficulty,
             ClearTitles,
+            CloseContainerS2c,
             SetContainerContent,
             SetContainerProperty,
             SetContainerSlot,
@@ -662,10 +689,12 @@ pub mod play {
             UpdateEntityPositionAndRotation,
             UpdateEntityRotation,
             OpenScreen,
+            PlaceGhostRecipe<'a>,
             PlayerChatMessage<'a>,
             CombatDeath,
             PlayerInfo<'a>,
             SynchronizePlayerPosition,
+            UpdateRecipeBook<'a>,
             RemoveEntities,
             ResourcePackS2c<'a>,
             Respawn<'a>,
@@ -690,6 +719,7 @@ pub mod play {
             SetTabListHeaderAndFooter,
             TeleportEntity,
             UpdateAttributes<'a>,
+            UpdateRecipes<'a>,
         }
     }
 }
@@ -10,6 +10,12 @@ use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use crate::byte_counter::ByteCounter;
 use crate::{Decode, Encode, Ident, Result, VarInt};
 
+mod legacy;
+mod markup;
+mod translation;
+
+pub use translation::Translations;
+
 /// Represents formatted text in Minecraft's JSON text format.
 ///
 /// Text is used in various places such as chat, window titles,
@@ -336,134 +342,142 @@ impl Text {
     /// writer.
     pub fn write_string(&self, mut w: impl fmt::Write) -> fmt::Result {
         fn write_string_inner(this: &Text, w: &mut impl fmt::Write) -> fmt::Result {
-            match &this.0.content {
-                TextContent::Text { text } => w.write_str(text.as_ref())?,
-                TextContent::Translate { translate, with } => {
-                    w.write_str(translate.as_ref())?;
-
-                    if !with.is_empty() {
-                        w.write_char('[')?;
-                        for (i, slot) in with.iter().enumerate() {
-                            if i > 0 {
-                                w.write_str(", ")?;
-                            }
-                            w.write_char(char::from_digit((i + 1) as u32, 10).unwrap_or('?'))?;
-                            w.write_char('=')?;
-                            write_string_inner(slot, w)?;
-                        }
-                        w.write_char(']')?;
-                    }
-                }
-                TextContent::ScoreboardValue { score } => {
-                    let ScoreboardValueContent {
-                        name,
-                        objective,
-                        value,
-                    } = score;
-
-                    write!(w, "scoreboard_value[name={name}, objective={objective}")?;
-
-                    if let Some(value) = value {
-                        if !value.is_empty() {
-                            w.write_str(", value=")?;
-                            w.write_str(value)?;
-                        }
-                    }
+            this.write_content(w)?;
 
-                    w.write_char(']')?;
-                }
-                TextContent::EntityNames {
-                    selector,
-                    separator,
-                } => {
-                    write!(w, "entity_names[selector={selector}")?;
-
-                    if let Some(separator) = separator {
-                        if !separator.is_empty() {
-                            w.write_str(", separator={separator}")?;
+            for child in &this.0.extra {
+                write_string_inner(child, w)?;
+            }
+
+            Ok(())
+        }
+
+        write_string_inner(self, &mut w)
+    }
+
+    /// Writes the string representation of this text object's content to
+    /// the provided writer, excluding its children.
+    fn write_content(&self, w: &mut dyn fmt::Write) -> fmt::Result {
+        match &self.0.content {
+            TextContent::Text { text } => w.write_str(text.as_ref())?,
+            TextContent::Translate { translate, with } => {
+                w.write_str(translate.as_ref())?;
+
+                if !with.is_empty() {
+                    w.write_char('[')?;
+                    for (i, slot) in with.iter().enumerate() {
+                        if i > 0 {
+                            w.write_str(", ")?;
                         }
+                        w.write_char(char::from_digit((i + 1) as u32, 10).unwrap_or('?'))?;
+                        w.write_char('=')?;
+                        slot.write_string(&mut *w)?;
                     }
-
                     w.write_char(']')?;
                 }
-                TextContent::Keybind { keybind } => write!(w, "keybind[{keybind}]")?,
-                TextContent::BlockNbt {
-                    block,
-                    nbt,
-                    interpret,
-                    separator,
-                } => {
-                    write!(w, "block_nbt[nbt={nbt}")?;
-
-                    if let Some(interpret) = interpret {
-                        write!(w, ", interpret={interpret}")?;
+            }
+            TextContent::ScoreboardValue { score } => {
+                let ScoreboardValueContent {
+                    name,
+                    objective,
+                    value,
+                } = score;
+
+                write!(w, "scoreboard_value[name={name}, objective={objective}")?;
+
+                if let Some(value) = value {
+                    if !value.is_empty() {
+                        w.write_str(", value=")?;
+                        w.write_str(value)?;
                     }
+                }
 
-                    if let Some(separator) = separator {
-                        if !separator.is_empty() {
-                            write!(w, "separator={separator}")?;
-                        }
+                w.write_char(']')?;
+            }
+            TextContent::EntityNames {
+                selector,
+                separator,
+            } => {
+                write!(w, "entity_names[selector={selector}")?;
+
+                if let Some(separator) = separator {
+                    if !separator.is_empty() {
+                        w.write_str(", separator={separator}")?;
                     }
+                }
 
-                    write!(w, "block={block}")?;
+                w.write_char(']')?;
+            }
+            TextContent::Keybind { keybind } => write!(w, "keybind[{keybind}]")?,
+            TextContent::BlockNbt {
+                block,
+                nbt,
+                interpret,
+                separator,
+            } => {
+                write!(w, "block_nbt[nbt={nbt}")?;
 
-                    w.write_char(']')?;
+                if let Some(interpret) = interpret {
+                    write!(w, ", interpret={interpret}")?;
                 }
-                TextContent::EntityNbt {
-                    entity,
-                    nbt,
-                    interpret,
-                    separator,
-                } => {
-                    write!(w, "entity_nbt[nbt={nbt}")?;
-
-                    if let Some(interpret) = interpret {
-                        write!(w, ", interpret={interpret}")?;
-                    }
 
-                    if let Some(separator) = separator {
-                        if !separator.is_empty() {
-                            write!(w, "separator={separator}")?;
-                        }
+                if let Some(separator) = separator {
+                    if !separator.is_empty() {
+                        write!(w, "separator={separator}")?;
                     }
+                }
 
-                    write!(w, ", entity={entity}")?;
+                write!(w, "block={block}")?;
 
-                    w.write_char(']')?;
+                w.write_char(']')?;
+            }
+            TextContent::EntityNbt {
+                entity,
+                nbt,
+                interpret,
+                separator,
+            } => {
+                write!(w, "entity_nbt[nbt={nbt}")?;
+
+                if let Some(interpret) = interpret {
+                    write!(w, ", interpret={interpret}")?;
                 }
-                TextContent::StorageNbt {
-                    storage,
-                    nbt,
-                    interpret,
-                    separator,
-                } => {
-                    write!(w, "storage_nbt[nbt={nbt}")?;
-
-                    if let Some(interpret) = interpret {
-                        write!(w, ", interpret={interpret}")?;
-                    }
 
-                    if let Some(separator) = separator {
-                        if !separator.is_empty() {
-                            write!(w, "separator=")?;
-                            write_string_inner(separator, w)?;
-                        }
+                if let Some(separator) = separator {
+                    if !separator.is_empty() {
+                        write!(w, "separator={separator}")?;
                     }
+                }
 
-                    write!(w, ", storage={storage}")?;
+                write!(w, ", entity={entity}")?;
 
-                    w.write_char(']')?;
-                }
+                w.write_char(']')?;
             }
+            TextContent::StorageNbt {
+                storage,
+                nbt,
+                interpret,
+                separator,
+            } => {
+                write!(w, "storage_nbt[nbt={nbt}")?;
 
-            for child in &this.0.extra {
-                write_string_inner(child, w)?;
-            }
+                if let Some(interpret) = interpret {
+                    write!(w, ", interpret={interpret}")?;
+                }
 
-            Ok(())
+                if let Some(separator) = separator {
+                    if !separator.is_empty() {
+                        write!(w, "separator=")?;
+                        separator.write_string(&mut *w)?;
+                    }
+                }
+
+                write!(w, ", storage={storage}")?;
+
+                w.write_char(']')?;
+            }
         }
 
-        write_string_inner(self, &mut w)
+        Ok(())
     }
 
     /// Returns `true` if the text contains no characters. Returns `false`
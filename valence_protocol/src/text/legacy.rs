@@ -0,0 +1,384 @@
+//! Conversion between [`Text`] and strings containing [legacy formatting
+//! codes].
+//!
+//! [legacy formatting codes]: https://minecraft.fandom.com/wiki/Formatting_codes
+
+use std::fmt;
+
+use super::{Color, Text, TextContent, TextFormat};
+
+/// The colors which can be selected with a legacy formatting code.
+const COLOR_CODES: [(char, Color); 16] = [
+    ('0', Color::BLACK),
+    ('1', Color::DARK_BLUE),
+    ('2', Color::DARK_GREEN),
+    ('3', Color::DARK_AQUA),
+    ('4', Color::DARK_RED),
+    ('5', Color::DARK_PURPLE),
+    ('6', Color::GOLD),
+    ('7', Color::GRAY),
+    ('8', Color::DARK_GRAY),
+    ('9', Color::BLUE),
+    ('a', Color::GREEN),
+    ('b', Color::AQUA),
+    ('c', Color::RED),
+    ('d', Color::LIGHT_PURPLE),
+    ('e', Color::YELLOW),
+    ('f', Color::WHITE),
+];
+
+/// The subset of a text's style which legacy formatting codes can express.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+struct LegacyStyle {
+    color: Option<Color>,
+    obfuscated: bool,
+    bold: bool,
+    strikethrough: bool,
+    underlined: bool,
+    italic: bool,
+}
+
+impl LegacyStyle {
+    /// Returns the style after the formatting code `code` is applied, or
+    /// `None` if the code is not recognized. Hex colors of the form
+    /// `§x§r§r§g§g§b§b` consume the remaining digits from `rest`.
+    fn apply_code(
+        self,
+        code: char,
+        rest: &mut std::str::Chars,
+        code_char: char,
+    ) -> Option<LegacyStyle> {
+        let code = code.to_ascii_lowercase();
+
+        if let Some(&(_, color)) = COLOR_CODES.iter().find(|(c, _)| *c == code) {
+            // Colors reset any formatting that came before them.
+            return Some(LegacyStyle {
+                color: Some(color),
+                ..Default::default()
+            });
+        }
+
+        let mut style = self;
+
+        match code {
+            'k' => style.obfuscated = true,
+            'l' => style.bold = true,
+            'm' => style.strikethrough = true,
+            'n' => style.underlined = true,
+            'o' => style.italic = true,
+            'r' => style = LegacyStyle::default(),
+            'x' => {
+                let mut rgb = 0;
+                for _ in 0..6 {
+                    if rest.next()? != code_char {
+                        return None;
+                    }
+                    rgb = rgb << 4 | rest.next()?.to_digit(16)?;
+                }
+
+                return Some(LegacyStyle {
+                    color: Some(Color::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)),
+                    ..Default::default()
+                });
+            }
+            _ => return None,
+        }
+
+        Some(style)
+    }
+
+    fn apply(self, mut text: Text) -> Text {
+        if let Some(color) = self.color {
+            text = text.color(color);
+        }
+
+        if self.obfuscated {
+            text = text.obfuscated();
+        }
+        if self.bold {
+            text = text.bold();
+        }
+        if self.strikethrough {
+            text = text.strikethrough();
+        }
+        if self.underlined {
+            text = text.underlined();
+        }
+        if self.italic {
+            text = text.italic();
+        }
+
+        text
+    }
+
+    /// Returns the formatting flags of this style in the order their codes
+    /// are written.
+    fn flags(self) -> [(char, bool); 5] {
+        [
+            ('k', self.obfuscated),
+            ('l', self.bold),
+            ('m', self.strikethrough),
+            ('n', self.underlined),
+            ('o', self.italic),
+        ]
+    }
+
+    /// Writes the codes needed to switch from the `prev` style to this one.
+    fn write_codes(
+        self,
+        prev: LegacyStyle,
+        code_char: char,
+        w: &mut impl fmt::Write,
+    ) -> fmt::Result {
+        let flags_kept = prev
+            .flags()
+            .iter()
+            .zip(self.flags())
+            .all(|(&(_, was_set), (_, is_set))| !was_set || is_set);
+
+        if self.color == prev.color && flags_kept {
+            for ((code, was_set), (_, is_set)) in prev.flags().into_iter().zip(self.flags()) {
+                if is_set && !was_set {
+                    w.write_char(code_char)?;
+                    w.write_char(code)?;
+                }
+            }
+
+            return Ok(());
+        }
+
+        // Switching colors (or resetting) clears all formatting, so every
+        // flag needs to be written again afterwards.
+        let color_code = match self.color {
+            Some(color) => COLOR_CODES
+                .iter()
+                .find(|(_, c)| *c == color)
+                .map_or('r', |(code, _)| *code),
+            None => 'r',
+        };
+
+        w.write_char(code_char)?;
+        w.write_char(color_code)?;
+
+        for (code, is_set) in self.flags() {
+            if is_set {
+                w.write_char(code_char)?;
+                w.write_char(code)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the color selectable with a legacy formatting code which is
+/// closest to `color`.
+fn nearest_legacy_color(color: Color) -> Color {
+    let dist = |c: Color| {
+        let dr = c.r as i32 - color.r as i32;
+        let dg = c.g as i32 - color.g as i32;
+        let db = c.b as i32 - color.b as i32;
+        dr * dr + dg * dg + db * db
+    };
+
+    COLOR_CODES
+        .iter()
+        .map(|&(_, c)| c)
+        .min_by_key(|&c| dist(c))
+        .unwrap()
+}
+
+impl Text {
+    /// Parses a string containing legacy formatting codes prefixed with
+    /// `code_char`. This is usually `'§'`, though `'&'` is a common
+    /// alternative for strings typed by users.
+    ///
+    /// Unrecognized codes are kept as literal text. Hex colors in the
+    /// `§x§r§r§g§g§b§b` form are supported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use valence_protocol::text::{Color, Text, TextFormat};
+    ///
+    /// let txt = Text::from_legacy("&cRed &lbold&r plain", '&');
+    ///
+    /// assert_eq!(
+    ///     txt,
+    ///     Text::default() + "Red ".color(Color::RED) + "bold".color(Color::RED).bold() + " plain"
+    /// );
+    /// ```
+    pub fn from_legacy(s: &str, code_char: char) -> Text {
+        let mut segments = vec![];
+        let mut style = LegacyStyle::default();
+        let mut buf = String::new();
+
+        let mut chars = s.chars();
+
+        while let Some(c) = chars.next() {
+            if c == code_char {
+                let mut rest = chars.clone();
+
+                if let Some(new_style) = rest
+                    .next()
+                    .and_then(|code| style.apply_code(code, &mut rest, code_char))
+                {
+                    if new_style != style && !buf.is_empty() {
+                        segments.push(style.apply(Text::text(std::mem::take(&mut buf))));
+                    }
+
+                    style = new_style;
+                    chars = rest;
+                    continue;
+                }
+            }
+
+            buf.push(c);
+        }
+
+        if !buf.is_empty() {
+            segments.push(style.apply(Text::text(buf)));
+        }
+
+        if segments.len() == 1 {
+            segments.pop().unwrap()
+        } else {
+            let mut txt = Text::default();
+            txt.0.extra = segments;
+            txt
+        }
+    }
+
+    /// Writes this text object to the provided writer as a string containing
+    /// legacy formatting codes prefixed with `code_char`.
+    ///
+    /// Formatting which legacy codes cannot express, such as click events,
+    /// is dropped. Colors are replaced with the closest of the sixteen colors
+    /// available.
+    pub fn write_legacy(&self, mut w: impl fmt::Write, code_char: char) -> fmt::Result {
+        fn write_legacy_inner(
+            this: &Text,
+            parent: LegacyStyle,
+            current: &mut LegacyStyle,
+            code_char: char,
+            w: &mut impl fmt::Write,
+        ) -> fmt::Result {
+            let style = LegacyStyle {
+                color: this.0.color.map(nearest_legacy_color).or(parent.color),
+                obfuscated: this.0.obfuscated.unwrap_or(parent.obfuscated),
+                bold: this.0.bold.unwrap_or(parent.bold),
+                strikethrough: this.0.strikethrough.unwrap_or(parent.strikethrough),
+                underlined: this.0.underlined.unwrap_or(parent.underlined),
+                italic: this.0.italic.unwrap_or(parent.italic),
+            };
+
+            let is_empty = match &this.0.content {
+                TextContent::Text { text } => text.is_empty(),
+                _ => false,
+            };
+
+            if !is_empty {
+                if style != *current {
+                    style.write_codes(*current, code_char, w)?;
+                    *current = style;
+                }
+
+                this.write_content(w)?;
+            }
+
+            for child in &this.0.extra {
+                write_legacy_inner(child, style, current, code_char, w)?;
+            }
+
+            Ok(())
+        }
+
+        write_legacy_inner(
+            self,
+            LegacyStyle::default(),
+            &mut LegacyStyle::default(),
+            code_char,
+            &mut w,
+        )
+    }
+
+    /// Converts this text object to a string containing legacy formatting
+    /// codes prefixed with `code_char`. See [`Text::write_legacy`] for
+    /// details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use valence_protocol::text::{Color, Text, TextFormat};
+    ///
+    /// let txt = "Gold ".color(Color::GOLD) + "italic".italic();
+    ///
+    /// assert_eq!(txt.to_legacy_string('§'), "§6Gold §oitalic");
+    /// ```
+    pub fn to_legacy_string(&self, code_char: char) -> String {
+        let mut s = String::new();
+        let _ = self.write_legacy(&mut s, code_char);
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_parse() {
+        assert_eq!(Text::from_legacy("", '§'), Text::default());
+        assert_eq!(Text::from_legacy("plain", '§'), Text::text("plain"));
+        assert_eq!(Text::from_legacy("§cred", '§'), "red".color(Color::RED));
+
+        // Colors reset formatting, but other codes accumulate.
+        assert_eq!(
+            Text::from_legacy("§l§obold italic§9blue", '§'),
+            Text::default() + "bold italic".bold().italic() + "blue".color(Color::BLUE)
+        );
+
+        // Unknown codes and a trailing code char are kept.
+        assert_eq!(Text::from_legacy("§z100%§", '§'), Text::text("§z100%§"));
+
+        // Codes are case insensitive.
+        assert_eq!(Text::from_legacy("&Ahi", '&'), "hi".color(Color::GREEN));
+
+        assert_eq!(
+            Text::from_legacy("§x§1§2§3§a§B§chex", '§'),
+            "hex".color(Color::new(0x12, 0x3a, 0xbc))
+        );
+
+        // An incomplete hex color is kept, but the digits are still codes.
+        assert_eq!(
+            Text::from_legacy("§x§1§2oops", '§'),
+            Text::default() + "§x" + "oops".color(Color::DARK_GREEN)
+        );
+    }
+
+    #[test]
+    fn legacy_write() {
+        let txt = "a".color(Color::RED).bold()
+            + "b"
+            + "c".italic()
+            + "d".not_bold()
+            + Text::translate("key", []).color(Color::WHITE);
+
+        assert_eq!(txt.to_legacy_string('&'), "&c&lab&oc&cd&f&lkey");
+
+        // Colors without a formatting code are approximated.
+        assert_eq!(
+            "x".color(Color::new(250, 80, 90)).to_legacy_string('§'),
+            "§cx"
+        );
+
+        // Empty components do not produce codes.
+        assert_eq!(
+            (Text::default() + "".bold() + "x").to_legacy_string('§'),
+            "x"
+        );
+
+        let s = "§6§oSecond §r§kEntry";
+        assert_eq!(Text::from_legacy(s, '§').to_legacy_string('§'), s);
+    }
+}
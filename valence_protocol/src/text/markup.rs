@@ -0,0 +1,424 @@
+//! Parsing [`Text`] from a tag-based markup similar to [MiniMessage].
+//!
+//! [MiniMessage]: https://docs.advntr.dev/minimessage/format.html
+
+use std::borrow::Cow;
+
+use super::{color_from_str, ClickEvent, Color, HoverEvent, Text};
+
+/// The formatting applied to a run of text by the tags enclosing it.
+#[derive(Clone, PartialEq, Default, Debug)]
+struct Style {
+    color: Option<Color>,
+    font: Option<Cow<'static, str>>,
+    bold: Option<bool>,
+    italic: Option<bool>,
+    underlined: Option<bool>,
+    strikethrough: Option<bool>,
+    obfuscated: Option<bool>,
+    insertion: Option<Cow<'static, str>>,
+    click_event: Option<ClickEvent>,
+    hover_event: Option<HoverEvent>,
+}
+
+impl Style {
+    fn apply(self, text: impl Into<Cow<'static, str>>) -> Text {
+        let mut txt = Text::text(text);
+
+        txt.0.color = self.color;
+        txt.0.font = self.font;
+        txt.0.bold = self.bold;
+        txt.0.italic = self.italic;
+        txt.0.underlined = self.underlined;
+        txt.0.strikethrough = self.strikethrough;
+        txt.0.obfuscated = self.obfuscated;
+        txt.0.insertion = self.insertion;
+        txt.0.click_event = self.click_event;
+        txt.0.hover_event = self.hover_event;
+
+        txt
+    }
+}
+
+/// What a tag does once it has been recognized.
+enum TagAction {
+    /// Opens a tag with the given canonical name which applies a style until
+    /// it is closed.
+    Open(&'static str, Style),
+    /// Closes the innermost open tag with the given canonical name, or the
+    /// innermost tag of any name if `None`.
+    Close(Option<&'static str>),
+    /// Closes all open tags.
+    Reset,
+    /// Inserts literal text.
+    Insert(&'static str),
+}
+
+/// Returns the canonical name of a decoration tag.
+fn decoration_name(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "bold" | "b" => "bold",
+        "italic" | "i" | "em" => "italic",
+        "underlined" | "u" => "underlined",
+        "strikethrough" | "st" => "strikethrough",
+        "obfuscated" | "obf" => "obfuscated",
+        _ => return None,
+    })
+}
+
+/// Returns the canonical name of a tag which can be closed.
+fn closable_name(name: &str) -> Option<&'static str> {
+    if let Some(name) = decoration_name(name.strip_prefix('!').unwrap_or(name)) {
+        return Some(name);
+    }
+
+    Some(match name {
+        "color" | "colour" | "c" => "color",
+        "click" => "click",
+        "hover" => "hover",
+        "insert" | "insertion" => "insert",
+        "font" => "font",
+        _ if color_from_str(name).is_some() => "color",
+        _ => return None,
+    })
+}
+
+/// Interprets the arguments of a tag, returning `None` if it is not
+/// recognized or malformed.
+fn parse_tag(args: &[String], style: &Style) -> Option<TagAction> {
+    let (name, args) = args.split_first()?;
+    let name = name.to_ascii_lowercase();
+
+    if let Some(name) = name.strip_prefix('/') {
+        if name.is_empty() {
+            return Some(TagAction::Close(None));
+        }
+
+        return closable_name(name).map(|name| TagAction::Close(Some(name)));
+    }
+
+    let mut style = style.clone();
+
+    // Arguments after the action of click and hover tags are joined back
+    // together so that unquoted values may contain colons.
+    let rest = || (args.len() > 1).then(|| args[1..].join(":"));
+
+    let (negated, decoration) = match name.strip_prefix('!') {
+        Some(name) => (true, name),
+        None => (false, name.as_str()),
+    };
+
+    if let Some(decoration) = decoration_name(decoration) {
+        let flag = match decoration {
+            "bold" => &mut style.bold,
+            "italic" => &mut style.italic,
+            "underlined" => &mut style.underlined,
+            "strikethrough" => &mut style.strikethrough,
+            _ => &mut style.obfuscated,
+        };
+
+        *flag = Some(!negated);
+        return Some(TagAction::Open(decoration, style));
+    }
+
+    if negated {
+        return None;
+    }
+
+    let canonical = closable_name(&name);
+
+    match name.as_str() {
+        "reset" => return Some(TagAction::Reset),
+        "newline" | "br" => return Some(TagAction::Insert("\n")),
+        "color" | "colour" | "c" => match args {
+            [color] => style.color = Some(color_from_str(&color.to_ascii_lowercase())?),
+            _ => return None,
+        },
+        "click" => {
+            let action = args.first()?.to_ascii_lowercase();
+            let value = rest()?;
+
+            style.click_event = Some(match action.as_str() {
+                "open_url" => ClickEvent::OpenUrl(value.into()),
+                "run_command" => ClickEvent::RunCommand(value.into()),
+                "suggest_command" => ClickEvent::SuggestCommand(value.into()),
+                "change_page" => ClickEvent::ChangePage(value.parse().ok()?),
+                "copy_to_clipboard" => ClickEvent::CopyToClipboard(value.into()),
+                _ => return None,
+            });
+        }
+        "hover" => {
+            if !args.first()?.eq_ignore_ascii_case("show_text") {
+                return None;
+            }
+
+            style.hover_event = Some(HoverEvent::ShowText(Text::from_markup(&rest()?)));
+        }
+        "insert" | "insertion" => match args {
+            [value] => style.insertion = Some(value.clone().into()),
+            _ => return None,
+        },
+        "font" if !args.is_empty() => style.font = Some(args.join(":").into()),
+        _ => match args {
+            [] => style.color = Some(color_from_str(&name)?),
+            _ => return None,
+        },
+    }
+
+    Some(TagAction::Open(canonical?, style))
+}
+
+/// Reads the tag at the start of `s`, which begins with `<`. Returns the
+/// tag's colon separated arguments with quotes removed and the length of the
+/// tag in bytes.
+fn read_tag(s: &str) -> Option<(Vec<String>, usize)> {
+    let mut args = vec![];
+    let mut arg = String::new();
+    let mut quote = None;
+
+    let mut chars = s.char_indices().skip(1);
+
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(q), '\\') => match chars.next() {
+                Some((_, c)) if c == q || c == '\\' => arg.push(c),
+                Some((_, c)) => {
+                    arg.push('\\');
+                    arg.push(c);
+                }
+                None => return None,
+            },
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => arg.push(c),
+            (None, '\'' | '"') if arg.is_empty() => quote = Some(c),
+            (None, ':') => args.push(std::mem::take(&mut arg)),
+            (None, '>') => {
+                args.push(arg);
+                return Some((args, i + 1));
+            }
+            (None, '<') => return None,
+            (None, c) => arg.push(c),
+        }
+    }
+
+    None
+}
+
+impl Text {
+    /// Parses a string of text marked up with tags, in a format similar to
+    /// [MiniMessage].
+    ///
+    /// The supported tags are:
+    /// - Colors: `<red>`, `<#ff5555>` or `<color:red>`.
+    /// - Decorations: `<bold>`, `<italic>`, `<underlined>`, `<strikethrough>`
+    ///   and `<obfuscated>`, or their short forms `<b>`, `<i>`, `<u>`, `<st>`
+    ///   and `<obf>`. Prefixing a decoration with `!` disables it.
+    /// - `<click:action:value>`, where `action` is one of `open_url`,
+    ///   `run_command`, `suggest_command`, `change_page` or
+    ///   `copy_to_clipboard`.
+    /// - `<hover:show_text:'markup'>`, where the hover text is itself parsed as
+    ///   markup.
+    /// - `<insert:text>` and `<font:name>`.
+    /// - `<reset>` closes all open tags and `<newline>` inserts a line break.
+    ///
+    /// Tags are closed with `</name>`, which also closes any tags opened
+    /// after it, or with `</>` which closes the last tag opened. Arguments
+    /// containing `:` or `>` can be wrapped in single or double quotes.
+    ///
+    /// Unrecognized or malformed tags are kept as literal text. A literal `<`
+    /// can also be written as `\<`.
+    ///
+    /// [MiniMessage]: https://docs.advntr.dev/minimessage/format.html
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use valence_protocol::text::{Color, Text, TextFormat};
+    ///
+    /// let txt = Text::from_markup("<red>Hello, <bold>world</bold>!</red>");
+    ///
+    /// assert_eq!(
+    ///     txt,
+    ///     Text::default()
+    ///         + "Hello, ".color(Color::RED)
+    ///         + "world".color(Color::RED).bold()
+    ///         + "!".color(Color::RED)
+    /// );
+    /// ```
+    pub fn from_markup(s: &str) -> Text {
+        let mut segments: Vec<(String, Style)> = vec![];
+        let mut stack: Vec<(&'static str, Style)> = vec![];
+        let mut buf = String::new();
+
+        let current_style =
+            |stack: &[(&str, Style)]| stack.last().map(|(_, s)| s.clone()).unwrap_or_default();
+
+        let mut flush = |buf: &mut String, style: Style| {
+            if buf.is_empty() {
+                return;
+            }
+
+            match segments.last_mut() {
+                Some((text, last)) if *last == style => text.push_str(buf),
+                _ => segments.push((buf.clone(), style)),
+            }
+
+            buf.clear();
+        };
+
+        let mut rest = s;
+
+        while let Some(c) = rest.chars().next() {
+            match c {
+                '\\' if rest[1..].starts_with(['<', '\\']) => {
+                    buf.push_str(&rest[1..2]);
+                    rest = &rest[2..];
+                    continue;
+                }
+                '<' => {
+                    let action = read_tag(rest).and_then(|(args, len)| {
+                        let action = parse_tag(&args, &current_style(&stack))?;
+
+                        // Closing tags which were never opened are kept as text.
+                        if let TagAction::Close(name) = action {
+                            if !stack
+                                .iter()
+                                .any(|(n, _)| name.is_none() || name == Some(*n))
+                            {
+                                return None;
+                            }
+                        }
+
+                        Some((action, len))
+                    });
+
+                    if let Some((action, len)) = action {
+                        match action {
+                            TagAction::Insert(text) => buf.push_str(text),
+                            TagAction::Open(name, style) => {
+                                flush(&mut buf, current_style(&stack));
+                                stack.push((name, style));
+                            }
+                            TagAction::Close(name) => {
+                                flush(&mut buf, current_style(&stack));
+
+                                let idx = stack
+                                    .iter()
+                                    .rposition(|(n, _)| name.is_none() || name == Some(*n))
+                                    .unwrap();
+
+                                stack.truncate(idx);
+                            }
+                            TagAction::Reset => {
+                                flush(&mut buf, current_style(&stack));
+                                stack.clear();
+                            }
+                        }
+
+                        rest = &rest[len..];
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+
+            buf.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+
+        flush(&mut buf, current_style(&stack));
+
+        let mut segments: Vec<_> = segments
+            .into_iter()
+            .map(|(text, style)| style.apply(text))
+            .collect();
+
+        if segments.len() == 1 {
+            segments.pop().unwrap()
+        } else {
+            let mut txt = Text::default();
+            txt.0.extra = segments;
+            txt
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::TextFormat;
+
+    #[test]
+    fn markup_styles() {
+        assert_eq!(Text::from_markup(""), Text::default());
+        assert_eq!(Text::from_markup("plain"), Text::text("plain"));
+        assert_eq!(
+            Text::from_markup("<#123abc>hex"),
+            "hex".color(Color::new(0x12, 0x3a, 0xbc))
+        );
+        assert_eq!(
+            Text::from_markup("<color:gold>gold</color>"),
+            "gold".color(Color::GOLD)
+        );
+
+        // Closing a tag closes those opened after it.
+        assert_eq!(
+            Text::from_markup("<b>a<i>b<u>c</b>d"),
+            Text::default()
+                + "a".bold()
+                + "b".bold().italic()
+                + "c".bold().italic().underlined()
+                + "d"
+        );
+
+        assert_eq!(
+            Text::from_markup("<bold>a<!bold>b</>c<reset>d"),
+            Text::default() + "a".bold() + "b".not_bold() + "c".bold() + "d"
+        );
+
+        assert_eq!(
+            Text::from_markup("one<newline>two<br>three"),
+            Text::text("one\ntwo\nthree")
+        );
+    }
+
+    #[test]
+    fn markup_events() {
+        assert_eq!(
+            Text::from_markup("<click:run_command:/tp @s 0 64 0>Home"),
+            "Home".on_click_run_command("/tp @s 0 64 0")
+        );
+        assert_eq!(
+            Text::from_markup("<click:open_url:https://example.com>link"),
+            "link".on_click_open_url("https://example.com")
+        );
+        assert_eq!(
+            Text::from_markup("<click:change_page:3>next"),
+            "next".on_click_change_page(3)
+        );
+        assert_eq!(
+            Text::from_markup("<hover:show_text:'<red>Careful: \\'hot\\''>Lava"),
+            "Lava".on_hover_show_text("Careful: 'hot'".color(Color::RED))
+        );
+        assert_eq!(Text::from_markup("<insert:\"a>b\">x"), "x".insertion("a>b"));
+    }
+
+    #[test]
+    fn markup_literal() {
+        for s in [
+            "<unknown>",
+            "1 < 2 > 0",
+            "</red>",
+            "<click:teleport:x>",
+            "<click:change_page:x>",
+            "<color:nope>",
+            "<red",
+            "<!red>",
+        ] {
+            assert_eq!(Text::from_markup(s), Text::text(s), "{s}");
+        }
+
+        assert_eq!(Text::from_markup("\\<red>\\\\"), Text::text("<red>\\"));
+        assert_eq!(Text::from_markup("a\\b"), Text::text("a\\b"));
+    }
+}
@@ -0,0 +1,344 @@
+//! Rendering [`Text`] with translation keys resolved on the server.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use super::{Color, Text, TextContent};
+use crate::Result;
+
+/// A table of translation keys and their format strings, such as the
+/// contents of a vanilla `en_us.json` language file.
+///
+/// Format strings may contain `%s` to insert the next argument, `%1$s` to
+/// insert an argument by its position, and `%%` for a literal `%`.
+///
+/// # Examples
+///
+/// ```
+/// use valence_protocol::text::{Text, Translations};
+///
+/// let translations =
+///     Translations::from_json_str(r#"{"death.attack.generic": "%1$s died"}"#).unwrap();
+///
+/// let txt = Text::translate("death.attack.generic", ["Steve".into()]);
+///
+/// assert_eq!(txt.to_translated_string(&translations), "Steve died");
+/// ```
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Translations {
+    map: HashMap<String, String>,
+}
+
+impl Translations {
+    /// Creates an empty translation table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a translation table from a JSON object mapping translation
+    /// keys to format strings.
+    pub fn from_json_str(json: &str) -> Result<Self> {
+        let mut this = Self::new();
+        this.extend_from_json_str(json)?;
+        Ok(this)
+    }
+
+    /// Adds the translations in a JSON object mapping translation keys to
+    /// format strings, replacing any existing translations with the same
+    /// keys.
+    pub fn extend_from_json_str(&mut self, json: &str) -> Result<()> {
+        let map: HashMap<String, String> = serde_json::from_str(json)?;
+        self.map.extend(map);
+        Ok(())
+    }
+
+    /// Inserts a translation, returning the previous format string for the
+    /// key if there was one.
+    pub fn insert(&mut self, key: impl Into<String>, format: impl Into<String>) -> Option<String> {
+        self.map.insert(key.into(), format.into())
+    }
+
+    /// Gets the format string for a translation key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.map.get(key).map(|s| s.as_str())
+    }
+
+    /// Returns the number of translations in the table.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns `true` if the table contains no translations.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for Translations {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        Self {
+            map: iter
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        }
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> Extend<(K, V)> for Translations {
+    fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter: T) {
+        self.map
+            .extend(iter.into_iter().map(|(k, v)| (k.into(), v.into())));
+    }
+}
+
+/// The formatting of a rendered piece of text which can be shown on a
+/// terminal.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+struct AnsiStyle {
+    color: Option<Color>,
+    bold: bool,
+    italic: bool,
+    underlined: bool,
+    strikethrough: bool,
+}
+
+impl AnsiStyle {
+    fn inherit(self, text: &Text) -> Self {
+        Self {
+            color: text.0.color.or(self.color),
+            bold: text.0.bold.unwrap_or(self.bold),
+            italic: text.0.italic.unwrap_or(self.italic),
+            underlined: text.0.underlined.unwrap_or(self.underlined),
+            strikethrough: text.0.strikethrough.unwrap_or(self.strikethrough),
+        }
+    }
+
+    /// Writes the escape sequences which switch the terminal from the `prev`
+    /// style to this one.
+    fn write_escapes(self, prev: AnsiStyle, w: &mut impl fmt::Write) -> fmt::Result {
+        if prev != AnsiStyle::default() {
+            w.write_str("\x1b[0m")?;
+        }
+
+        if let Some(Color { r, g, b }) = self.color {
+            write!(w, "\x1b[38;2;{r};{g};{b}m")?;
+        }
+
+        for (code, is_set) in [
+            (1, self.bold),
+            (3, self.italic),
+            (4, self.underlined),
+            (9, self.strikethrough),
+        ] {
+            if is_set {
+                write!(w, "\x1b[{code}m")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Calls `f` with each piece of `text` after translation along with its
+/// style.
+fn render(
+    text: &Text,
+    translations: &Translations,
+    parent: AnsiStyle,
+    f: &mut impl FnMut(&str, AnsiStyle) -> fmt::Result,
+) -> fmt::Result {
+    let style = parent.inherit(text);
+
+    match &text.0.content {
+        TextContent::Text { text } => f(text, style)?,
+        TextContent::Translate { translate, with } => match translations.get(translate) {
+            Some(format) => render_format(format, with, translations, style, f)?,
+            None => f(translate, style)?,
+        },
+        _ => {
+            let mut s = String::new();
+            text.write_content(&mut s)?;
+            f(&s, style)?;
+        }
+    }
+
+    for child in &text.0.extra {
+        render(child, translations, style, f)?;
+    }
+
+    Ok(())
+}
+
+/// Renders a translation format string. Malformed or unsupported format
+/// specifiers are written literally, and specifiers referring to missing
+/// arguments are skipped.
+fn render_format(
+    format: &str,
+    args: &[Text],
+    translations: &Translations,
+    style: AnsiStyle,
+    f: &mut impl FnMut(&str, AnsiStyle) -> fmt::Result,
+) -> fmt::Result {
+    let mut rest = format;
+    let mut next_arg = 0;
+
+    while let Some(idx) = rest.find('%') {
+        f(&rest[..idx], style)?;
+        rest = &rest[idx + 1..];
+
+        if let Some(after) = rest.strip_prefix('%') {
+            f("%", style)?;
+            rest = after;
+            continue;
+        }
+
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+
+        let (arg_idx, after) = if digits > 0 && rest[digits..].starts_with("$s") {
+            match rest[..digits].parse::<usize>() {
+                Ok(n) if n > 0 => (n - 1, &rest[digits + 2..]),
+                _ => {
+                    f("%", style)?;
+                    continue;
+                }
+            }
+        } else if let Some(after) = rest.strip_prefix('s') {
+            next_arg += 1;
+            (next_arg - 1, after)
+        } else {
+            f("%", style)?;
+            continue;
+        };
+
+        if let Some(arg) = args.get(arg_idx) {
+            render(arg, translations, style, f)?;
+        }
+
+        rest = after;
+    }
+
+    f(rest, style)
+}
+
+impl Text {
+    /// Writes the string representation of this text object to the provided
+    /// writer, using `translations` to resolve translation keys.
+    ///
+    /// Keys missing from the table are written as the key itself, like the
+    /// vanilla client does.
+    pub fn write_translated(
+        &self,
+        mut w: impl fmt::Write,
+        translations: &Translations,
+    ) -> fmt::Result {
+        render(self, translations, AnsiStyle::default(), &mut |s, _| {
+            w.write_str(s)
+        })
+    }
+
+    /// Converts this text object to a string, using `translations` to
+    /// resolve translation keys. See [`Text::write_translated`] for details.
+    pub fn to_translated_string(&self, translations: &Translations) -> String {
+        let mut s = String::new();
+        let _ = self.write_translated(&mut s, translations);
+        s
+    }
+
+    /// Like [`Text::write_translated`], but colors and decorations are
+    /// written as ANSI escape sequences for display on a terminal.
+    ///
+    /// Obfuscated text is written normally.
+    pub fn write_ansi(&self, mut w: impl fmt::Write, translations: &Translations) -> fmt::Result {
+        let mut current = AnsiStyle::default();
+
+        render(self, translations, AnsiStyle::default(), &mut |s, style| {
+            if s.is_empty() {
+                return Ok(());
+            }
+
+            if style != current {
+                style.write_escapes(current, &mut w)?;
+                current = style;
+            }
+
+            w.write_str(s)
+        })?;
+
+        if current != AnsiStyle::default() {
+            w.write_str("\x1b[0m")?;
+        }
+
+        Ok(())
+    }
+
+    /// Converts this text object to a string with ANSI escape sequences. See
+    /// [`Text::write_ansi`] for details.
+    pub fn to_ansi_string(&self, translations: &Translations) -> String {
+        let mut s = String::new();
+        let _ = self.write_ansi(&mut s, translations);
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text::TextFormat;
+
+    fn translations() -> Translations {
+        Translations::from_json_str(
+            r#"{
+                "death.attack.generic": "%1$s died",
+                "death.attack.player": "%1$s was slain by %2$s",
+                "chat.type.text": "<%s> %s",
+                "swapped": "%2$s then %1$s",
+                "percent": "100%% of %s",
+                "broken": "%d %5$s %"
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn translated_string() {
+        let t = translations();
+
+        let txt = Text::translate("death.attack.player", ["Alex".into(), "Steve".into()]);
+        assert_eq!(txt.to_translated_string(&t), "Alex was slain by Steve");
+
+        let txt = Text::translate("chat.type.text", ["Steve".into(), "hi".into()]) + "!";
+        assert_eq!(txt.to_translated_string(&t), "<Steve> hi!");
+
+        let txt = Text::translate("swapped", ["a".into(), "b".into()]);
+        assert_eq!(txt.to_translated_string(&t), "b then a");
+
+        let txt = Text::translate("percent", ["x".into()]);
+        assert_eq!(txt.to_translated_string(&t), "100% of x");
+
+        let txt = Text::translate("broken", []);
+        assert_eq!(txt.to_translated_string(&t), "%d  %");
+
+        // Arguments are translated too, and unknown keys are left as is.
+        let txt = Text::translate(
+            "death.attack.generic",
+            [Text::translate("entity.unknown", [])],
+        );
+        assert_eq!(txt.to_translated_string(&t), "entity.unknown died");
+    }
+
+    #[test]
+    fn ansi_string() {
+        let t = translations();
+
+        let txt = "plain ".into_text()
+            + Text::translate("death.attack.generic", ["Steve".bold()]).color(Color::RED);
+
+        assert_eq!(
+            txt.to_ansi_string(&t),
+            "plain \x1b[38;2;255;85;85m\x1b[1mSteve\x1b[0m\x1b[38;2;255;85;85m died\x1b[0m"
+        );
+
+        assert_eq!(Text::text("no style").to_ansi_string(&t), "no style");
+    }
+}
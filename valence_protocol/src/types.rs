@@ -1,11 +1,14 @@
 //! Miscellaneous type definitions used in packets.
 
+use std::io::Write;
+
+use anyhow::{bail, ensure};
 use bitfield_struct::bitfield;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use valence_nbt::Compound;
 
-use crate::{BlockPos, Decode, Encode, Ident, Text, VarInt};
+use crate::{BlockPos, Decode, Encode, Ident, ItemStack, Text, VarInt};
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Encode, Decode)]
 pub enum HandshakeNextState {
@@ -363,3 +366,442 @@ pub struct SyncPlayerPosLookFlags {
     #[bits(3)]
     _pad: u8,
 }
+
+/// A recipe sent to clients in the
+/// [`UpdateRecipes`](crate::packets::s2c::play::UpdateRecipes) packet.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Recipe<'a> {
+    CraftingShapeless {
+        recipe_id: Ident<&'a str>,
+        group: &'a str,
+        ingredients: Vec<Ingredient>,
+        result: Option<ItemStack>,
+    },
+    CraftingShaped {
+        recipe_id: Ident<&'a str>,
+        width: VarInt,
+        height: VarInt,
+        group: &'a str,
+        /// The `width * height` ingredients of the pattern in row-major order.
+        ingredients: Vec<Ingredient>,
+        result: Option<ItemStack>,
+    },
+    /// Recipes with hardcoded logic on the client, such as dyeing armor.
+    CraftingSpecial {
+        /// The type of the recipe, e.g. `crafting_special_armordye`.
+        kind: Ident<&'a str>,
+        recipe_id: Ident<&'a str>,
+    },
+    Cooking {
+        kind: CookingRecipeKind,
+        recipe_id: Ident<&'a str>,
+        group: &'a str,
+        ingredient: Ingredient,
+        result: Option<ItemStack>,
+        experience: f32,
+        cooking_time: VarInt,
+    },
+    Stonecutting {
+        recipe_id: Ident<&'a str>,
+        group: &'a str,
+        ingredient: Ingredient,
+        result: Option<ItemStack>,
+    },
+    Smithing {
+        recipe_id: Ident<&'a str>,
+        base: Ingredient,
+        addition: Ingredient,
+        result: Option<ItemStack>,
+    },
+}
+
+/// The item stacks accepted by one slot of a recipe. An empty ingredient
+/// matches an empty slot.
+pub type Ingredient = Vec<Option<ItemStack>>;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CookingRecipeKind {
+    Smelting,
+    Blasting,
+    Smoking,
+    CampfireCooking,
+}
+
+impl CookingRecipeKind {
+    /// Returns the recipe type identifier without the namespace.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Smelting => "smelting",
+            Self::Blasting => "blasting",
+            Self::Smoking => "smoking",
+            Self::CampfireCooking => "campfire_cooking",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "smelting" => Some(Self::Smelting),
+            "blasting" => Some(Self::Blasting),
+            "smoking" => Some(Self::Smoking),
+            "campfire_cooking" => Some(Self::CampfireCooking),
+            _ => None,
+        }
+    }
+}
+
+impl Recipe<'_> {
+    /// Returns the identifier of this recipe's type.
+    fn recipe_type(&self) -> &str {
+        match self {
+            Recipe::CraftingShapeless { .. } => "minecraft:crafting_shapeless",
+            Recipe::CraftingShaped { .. } => "minecraft:crafting_shaped",
+            Recipe::CraftingSpecial { kind, .. } => kind.as_str(),
+            Recipe::Cooking { kind, .. } => match kind {
+                CookingRecipeKind::Smelting => "minecraft:smelting",
+                CookingRecipeKind::Blasting => "minecraft:blasting",
+                CookingRecipeKind::Smoking => "minecraft:smoking",
+                CookingRecipeKind::CampfireCooking => "minecraft:campfire_cooking",
+            },
+            Recipe::Stonecutting { .. } => "minecraft:stonecutting",
+            Recipe::Smithing { .. } => "minecraft:smithing",
+        }
+    }
+}
+
+impl Encode for Recipe<'_> {
+    fn encode(&self, mut w: impl Write) -> anyhow::Result<()> {
+        self.recipe_type().encode(&mut w)?;
+
+        match self {
+            Recipe::CraftingShapeless {
+                recipe_id,
+                group,
+                ingredients,
+                result,
+            } => {
+                recipe_id.encode(&mut w)?;
+                group.encode(&mut w)?;
+                ingredients.encode(&mut w)?;
+                result.encode(w)
+            }
+            Recipe::CraftingShaped {
+                recipe_id,
+                width,
+                height,
+                group,
+                ingredients,
+                result,
+            } => {
+                ensure!(
+                    (width.0 * height.0) as usize == ingredients.len(),
+                    "width and height of shaped recipe do not match the number of ingredients"
+                );
+                recipe_id.encode(&mut w)?;
+                width.encode(&mut w)?;
+                height.encode(&mut w)?;
+                group.encode(&mut w)?;
+                for ingredient in ingredients {
+                    ingredient.encode(&mut w)?;
+                }
+                result.encode(w)
+            }
+            Recipe::CraftingSpecial { recipe_id, .. } => recipe_id.encode(w),
+            Recipe::Cooking {
+                recipe_id,
+                group,
+                ingredient,
+                result,
+                experience,
+                cooking_time,
+                ..
+            } => {
+                recipe_id.encode(&mut w)?;
+                group.encode(&mut w)?;
+                ingredient.encode(&mut w)?;
+                result.encode(&mut w)?;
+                experience.encode(&mut w)?;
+                cooking_time.encode(w)
+            }
+            Recipe::Stonecutting {
+                recipe_id,
+                group,
+                ingredient,
+                result,
+            } => {
+                recipe_id.encode(&mut w)?;
+                group.encode(&mut w)?;
+                ingredient.encode(&mut w)?;
+                result.encode(w)
+            }
+            Recipe::Smithing {
+                recipe_id,
+                base,
+                addition,
+                result,
+            } => {
+                recipe_id.encode(&mut w)?;
+                base.encode(&mut w)?;
+                addition.encode(&mut w)?;
+                result.encode(w)
+            }
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        self.recipe_type().encoded_len()
+            + match self {
+                Recipe::CraftingShapeless {
+                    recipe_id,
+                    group,
+                    ingredients,
+                    result,
+                } => {
+                    recipe_id.encoded_len()
+                        + group.encoded_len()
+                        + ingredients.encoded_len()
+                        + result.encoded_len()
+                }
+                Recipe::CraftingShaped {
+                    recipe_id,
+                    width,
+                    height,
+                    group,
+                    ingredients,
+                    result,
+                } => {
+                    recipe_id.encoded_len()
+                        + width.encoded_len()
+                        + height.encoded_len()
+                        + group.encoded_len()
+                        + ingredients.iter().map(|i| i.encoded_len()).sum::<usize>()
+                        + result.encoded_len()
+                }
+                Recipe::CraftingSpecial { recipe_id, .. } => recipe_id.encoded_len(),
+                Recipe::Cooking {
+                    recipe_id,
+                    group,
+                    ingredient,
+                    result,
+                    experience,
+                    cooking_time,
+                    ..
+                } => {
+                    recipe_id.encoded_len()
+                        + group.encoded_len()
+                        + ingredient.encoded_len()
+                        + result.encoded_len()
+                        + experience.encoded_len()
+                        + cooking_time.encoded_len()
+                }
+                Recipe::Stonecutting {
+                    recipe_id,
+                    group,
+                    ingredient,
+                    result,
+                } => {
+                    recipe_id.encoded_len()
+                        + group.encoded_len()
+                        + ingredient.encoded_len()
+                        + result.encoded_len()
+                }
+                Recipe::Smithing {
+                    recipe_id,
+                    base,
+                    addition,
+                    result,
+                } => {
+                    recipe_id.encoded_len()
+                        + base.encoded_len()
+                        + addition.encoded_len()
+                        + result.encoded_len()
+                }
+            }
+    }
+}
+
+impl<'a> Decode<'a> for Recipe<'a> {
+    fn decode(r: &mut &'a [u8]) -> anyhow::Result<Self> {
+        let kind = Ident::<&str>::decode(r)?;
+
+        if kind.namespace() != "minecraft" {
+            bail!("unknown recipe type \"{kind}\"");
+        }
+
+        Ok(match kind.path() {
+            "crafting_shapeless" => Recipe::CraftingShapeless {
+                recipe_id: Decode::decode(r)?,
+                group: Decode::decode(r)?,
+                ingredients: Decode::decode(r)?,
+                result: Decode::decode(r)?,
+            },
+            "crafting_shaped" => {
+                let recipe_id = Decode::decode(r)?;
+                let width = VarInt::decode(r)?;
+                let height = VarInt::decode(r)?;
+                let group = Decode::decode(r)?;
+
+                ensure!(
+                    width.0 >= 0 && height.0 >= 0,
+                    "negative shaped recipe dimensions"
+                );
+
+                let mut ingredients = vec![];
+                for _ in 0..width.0 * height.0 {
+                    ingredients.push(Ingredient::decode(r)?);
+                }
+
+                Recipe::CraftingShaped {
+                    recipe_id,
+                    width,
+                    height,
+                    group,
+                    ingredients,
+                    result: Decode::decode(r)?,
+                }
+            }
+            "stonecutting" => Recipe::Stonecutting {
+                recipe_id: Decode::decode(r)?,
+                group: Decode::decode(r)?,
+                ingredient: Decode::decode(r)?,
+                result: Decode::decode(r)?,
+            },
+            "smithing" => Recipe::Smithing {
+                recipe_id: Decode::decode(r)?,
+                base: Decode::decode(r)?,
+                addition: Decode::decode(r)?,
+                result: Decode::decode(r)?,
+            },
+            path if path.starts_with("crafting_special_") => Recipe::CraftingSpecial {
+                kind,
+                recipe_id: Decode::decode(r)?,
+            },
+            path => match CookingRecipeKind::from_name(path) {
+                Some(kind) => Recipe::Cooking {
+                    kind,
+                    recipe_id: Decode::decode(r)?,
+                    group: Decode::decode(r)?,
+                    ingredient: Decode::decode(r)?,
+                    result: Decode::decode(r)?,
+                    experience: Decode::decode(r)?,
+                    cooking_time: Decode::decode(r)?,
+                },
+                None => bail!("unknown recipe type \"{kind}\""),
+            },
+        })
+    }
+}
+
+/// The open and filter state of each recipe book.
+#[derive(Copy, Clone, PartialEq, Eq, Default, Debug, Encode, Decode)]
+pub struct RecipeBookSettings {
+    pub crafting_book_open: bool,
+    pub crafting_filter_active: bool,
+    pub smelting_book_open: bool,
+    pub smelting_filter_active: bool,
+    pub blast_furnace_book_open: bool,
+    pub blast_furnace_filter_active: bool,
+    pub smoker_book_open: bool,
+    pub smoker_filter_active: bool,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum UpdateRecipeBookAction<'a> {
+    /// Replaces the client's recipe book.
+    Init {
+        settings: RecipeBookSettings,
+        /// The recipes which are unlocked.
+        recipe_ids: Vec<Ident<&'a str>>,
+        /// The unlocked recipes to highlight as new.
+        highlighted_recipe_ids: Vec<Ident<&'a str>>,
+    },
+    /// Unlocks recipes and highlights them as new.
+    Add {
+        settings: RecipeBookSettings,
+        recipe_ids: Vec<Ident<&'a str>>,
+    },
+    /// Locks recipes.
+    Remove {
+        settings: RecipeBookSettings,
+        recipe_ids: Vec<Ident<&'a str>>,
+    },
+}
+
+impl Encode for UpdateRecipeBookAction<'_> {
+    fn encode(&self, mut w: impl Write) -> anyhow::Result<()> {
+        match self {
+            Self::Init {
+                settings,
+                recipe_ids,
+                highlighted_recipe_ids,
+            } => {
+                VarInt(0).encode(&mut w)?;
+                settings.encode(&mut w)?;
+                recipe_ids.encode(&mut w)?;
+                highlighted_recipe_ids.encode(w)
+            }
+            Self::Add {
+                settings,
+                recipe_ids,
+            } => {
+                VarInt(1).encode(&mut w)?;
+                settings.encode(&mut w)?;
+                recipe_ids.encode(w)
+            }
+            Self::Remove {
+                settings,
+                recipe_ids,
+            } => {
+                VarInt(2).encode(&mut w)?;
+                settings.encode(&mut w)?;
+                recipe_ids.encode(w)
+            }
+        }
+    }
+
+    fn encoded_len(&self) -> usize {
+        match self {
+            Self::Init {
+                settings,
+                recipe_ids,
+                highlighted_recipe_ids,
+            } => {
+                1 + settings.encoded_len()
+                    + recipe_ids.encoded_len()
+                    + highlighted_recipe_ids.encoded_len()
+            }
+            Self::Add {
+                settings,
+                recipe_ids,
+            }
+            | Self::Remove {
+                settings,
+                recipe_ids,
+            } => 1 + settings.encoded_len() + recipe_ids.encoded_len(),
+        }
+    }
+}
+
+impl<'a> Decode<'a> for UpdateRecipeBookAction<'a> {
+    fn decode(r: &mut &'a [u8]) -> anyhow::Result<Self> {
+        let action = VarInt::decode(r)?.0;
+        let settings = RecipeBookSettings::decode(r)?;
+        let recipe_ids = Decode::decode(r)?;
+
+        Ok(match action {
+            0 => Self::Init {
+                settings,
+                recipe_ids,
+                highlighted_recipe_ids: Decode::decode(r)?,
+            },
+            1 => Self::Add {
+                settings,
+                recipe_ids,
+            },
+            2 => Self::Remove {
+                settings,
+                recipe_ids,
+            },
+            n => bail!("invalid recipe book action {n}"),
+        })
+    }
+}